@@ -1,5 +1,6 @@
 use clap::{Parser, Subcommand};
 use drunken_monkey::hang::{arc::ArcHangMethod, eth::EthHangMethod, noc::NocHangMethod};
+use luwen_if::ChipSelector;
 
 #[derive(Subcommand, Debug, Clone)]
 pub enum HangType {
@@ -22,4 +23,7 @@ pub enum HangType {
 pub struct CliOptions {
     #[command(subcommand)]
     pub ty: HangType,
+    /// Only hang the selected chips (bdf:, board:, eth: or if:), defaults to all of them.
+    #[arg(long = "chip", global = true)]
+    pub chips: Vec<ChipSelector>,
 }
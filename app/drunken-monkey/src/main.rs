@@ -5,7 +5,7 @@ mod clap;
 fn main() {
     let args = <clap::CliOptions as ::clap::Parser>::parse();
 
-    let chips = luwen_ref::detect_chips().unwrap();
+    let chips = luwen_ref::detect_selected_chips(args.chips).unwrap();
 
     match args.ty {
         clap::HangType::Arc { method } => {
@@ -2,7 +2,7 @@
 File: prometheus-exporter/src/main.rs
 
 Description:
-- Collects card health and software version information from WH/GS chips.
+- Collects card health and software version information from GS/WH/BH chips.
 - Exposes this data as a Prometheus endpoint.
 
 Details:
@@ -11,6 +11,14 @@ Details:
 and exports them as an endpoint.
 - You can test this code by running `curl localhost:8080/metrics` while the
 service is running.  Note that you may need to change the port.
+- PCIe link state and AER error counters of the device and the bridges above it
+are read from sysfs (`--sysfs-root`).
+- Wormhole ethernet ports are exported with their link state, firmware heartbeat
+and error counters, labeled by port number.
+- Chips whose pci link was lost are reopened in place first. Chips are
+re-detected whenever one of them stops responding or the set of pci devices
+changes (i.e. after a reset or hot-unplug), boards that could not be found
+again are reported with `tt_smi_up` set to 0.
 
 Limitations:
 - Not tested on Galaxy systems.
@@ -18,9 +26,14 @@ Limitations:
 */
 
 use clap::Parser;
-use luwen_if::chip::Telemetry;
-use luwen_if::{ChipImpl, DeviceInfo};
+use luwen_if::chip::{Chip, InitStatus, Telemetry, TelemetryTags, Wormhole};
+use luwen_if::{
+    ChipDetectOptions, ChipImpl, ChipSelector, ConnectionState, DeviceInfo, UninitChip,
+};
+use num_traits::FromPrimitive;
 use prometheus::{register_gauge_vec, GaugeVec, Opts};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
 use std::thread;
 use std::time::Duration;
 
@@ -39,10 +52,20 @@ struct CommandLineArguments {
     /// Ignore GS cards
     #[arg(short, long, default_value_t = false)]
     no_grayskull: bool,
+
+    /// Only export the selected chips (bdf:, board:, eth: or if:), defaults to all of them
+    #[arg(long = "chip")]
+    chips: Vec<ChipSelector>,
+
+    /// Where sysfs is mounted
+    #[arg(long, default_value = luwen_if::SYSFS_ROOT)]
+    sysfs_root: PathBuf,
 }
 
 /// Encapsulates prometheus metrics for all boards in a host.
 struct Metrics {
+    up: GaugeVec,                  // 1 if the board responded during the last update
+    health: GaugeVec,              // 1 if the component finished init without error
     sw_info: GaugeVec,             // FW versions, dates
     aiclk: GaugeVec,               // AI clock, MHz
     axiclk: GaugeVec,              // AXI clock, MHz
@@ -55,6 +78,15 @@ struct Metrics {
     inlet_temperature: GaugeVec,   // Celsius
     power: GaugeVec,               // Watts
     current: GaugeVec,             // Amps
+    fan_speed: GaugeVec,           // Percent
+    throttler: GaugeVec,           // Raw throttler bits
+    throttler_bit: GaugeVec,       // One series per throttler bit
+    faults: GaugeVec,              // Raw fault bits
+    fault_bit: GaugeVec,           // One series per fault bit
+    telemetry_tag: GaugeVec,       // Raw value of every telemetry tag (BH only)
+    eth_link: GaugeVec,            // 1 if the port is connected to a chip which can route
+    eth_link_up: GaugeVec,         // 1 if the port trained, routing or not
+    eth_heartbeat: GaugeVec,       // Raw erisc heartbeat, stops moving if the firmware hangs
     pci_bus: GaugeVec,             // B in Bus, Device, Function (BDF)
     pci_device: GaugeVec,          // D in BDF
     pci_function: GaugeVec,        // F in BDF
@@ -65,12 +97,20 @@ struct Metrics {
     pci_cur_link_gen: GaugeVec,    // Current PCIe link generation
     pci_max_link_width: GaugeVec,  // Maximum PCIe link width
     pci_max_link_gen: GaugeVec,    // Maximum PCIe link generation
+    pci_link_degraded: GaugeVec,   // 1 if the link trained below its maximum
+    pci_aer: GaugeVec,             // AER error totals by severity
+    pci_link_down: GaugeVec,       // Surprise link downs, including upstream bridges
+    pci_retrain: GaugeVec,         // Link retrains, including upstream bridges
 }
 
 macro_rules! register_gauge_vec_with_board_id {
     ($name:expr, $desc:expr) => {{
+        register_gauge_vec_with_board_id!($name, $desc, &[])
+    }};
+    ($name:expr, $desc:expr, $labels:expr) => {{
         let opts = Opts::new($name, $desc).namespace("tt").subsystem("smi");
-        register_gauge_vec!(opts, &["board_id"]).unwrap()
+        let labels: &[&str] = $labels;
+        register_gauge_vec!(opts, &[&["board_id"], labels].concat()).unwrap()
     }};
 }
 
@@ -83,6 +123,15 @@ macro_rules! set_with_board_id {
 impl Metrics {
     pub fn new() -> Metrics {
         Metrics {
+            up: register_gauge_vec_with_board_id!(
+                "up",
+                "1 if the board responded to the last query"
+            ),
+            health: register_gauge_vec_with_board_id!(
+                "health",
+                "1 if the component initialized without error",
+                &["component"]
+            ),
             sw_info: {
                 let opts = Opts::new("sw_info", "Always 1; labeled with software versions")
                     .namespace("tt")
@@ -128,6 +177,39 @@ impl Metrics {
             ),
             power: register_gauge_vec_with_board_id!("power", "Core Power (W)"),
             current: register_gauge_vec_with_board_id!("current", "Core Current (A)"),
+            fan_speed: register_gauge_vec_with_board_id!("fan_speed", "Fan Speed (%)"),
+            throttler: register_gauge_vec_with_board_id!("throttler", "Throttler bits"),
+            throttler_bit: register_gauge_vec_with_board_id!(
+                "throttler_bit",
+                "1 if the throttler bit is set",
+                &["bit"]
+            ),
+            faults: register_gauge_vec_with_board_id!("faults", "Fault bits"),
+            fault_bit: register_gauge_vec_with_board_id!(
+                "fault_bit",
+                "1 if the fault bit is set",
+                &["bit"]
+            ),
+            telemetry_tag: register_gauge_vec_with_board_id!(
+                "telemetry_tag",
+                "Raw telemetry value",
+                &["tag"]
+            ),
+            eth_link: register_gauge_vec_with_board_id!(
+                "eth_link",
+                "1 if the ethernet port is connected to a chip that it can route to",
+                &["port", "remote"]
+            ),
+            eth_link_up: register_gauge_vec_with_board_id!(
+                "eth_link_up",
+                "1 if the ethernet port trained",
+                &["port"]
+            ),
+            eth_heartbeat: register_gauge_vec_with_board_id!(
+                "eth_heartbeat",
+                "Ethernet firmware heartbeat",
+                &["port"]
+            ),
             pci_bus: register_gauge_vec_with_board_id!("pci_bus", "pci.bus"),
             pci_device: register_gauge_vec_with_board_id!("pci_device", "pci.device"),
             pci_function: register_gauge_vec_with_board_id!("pci_function", "pci.function"),
@@ -150,119 +232,370 @@ impl Metrics {
                 "Max PCIe width"
             ),
             pci_max_link_gen: register_gauge_vec_with_board_id!("max_pci_link_gen", "Max PCIe gen"),
+            pci_link_degraded: register_gauge_vec_with_board_id!(
+                "pci_link_degraded",
+                "1 if the PCIe link trained below its max width or gen"
+            ),
+            pci_aer: register_gauge_vec_with_board_id!(
+                "pci_aer_errors",
+                "PCIe AER error count",
+                &["severity"]
+            ),
+            pci_link_down: register_gauge_vec_with_board_id!(
+                "pci_link_down_events",
+                "PCIe surprise link down events seen by the device or its upstream bridges"
+            ),
+            pci_retrain: register_gauge_vec_with_board_id!(
+                "pci_retrain_events",
+                "PCIe link retrain events seen by the device or its upstream bridges"
+            ),
         }
     }
 
-    /// Call with telemetry for any board.
-    pub fn update(&self, device_info: &Option<DeviceInfo>, telemetry: &Telemetry) {
-        let mut board_id = telemetry.board_serial_number_hex();
+    pub fn set_up(&self, board_id: &str, up: bool) {
+        set_with_board_id!(self.up, board_id, up as u8);
+    }
 
-        match device_info {
-            // This chip is connected via PCIe.
-            Some(info) => {
-                board_id.push_str("_pcie");
-
-                // PCI BDF (bus/device/function)
-                set_with_board_id!(self.pci_bus, &board_id, info.bus);
-                set_with_board_id!(self.pci_device, &board_id, info.slot);
-                set_with_board_id!(self.pci_function, &board_id, info.function);
-
-                // PCI link info
-                set_with_board_id!(
-                    self.pci_cur_link_width,
-                    &board_id,
-                    info.pcie_current_link_width()
-                );
-                set_with_board_id!(
-                    self.pci_cur_link_gen,
-                    &board_id,
-                    info.pcie_current_link_gen()
-                );
-                set_with_board_id!(
-                    self.pci_max_link_width,
-                    &board_id,
-                    info.pcie_max_link_width()
-                );
-                set_with_board_id!(self.pci_max_link_gen, &board_id, info.pcie_max_link_gen());
-
-                // Luwen uses `device_id` to describe N in /dev/tenstorrent/N in
-                // some places, interface_id elsewhere.  Here we're concerned
-                // with the PCI device & vendor IDs, that is, the 1e52:401e in
-                // e.g. 01:00.0 Processing accelerators: Device 1e52:401e
-                //                                              ^^^^ ^^^^
-                set_with_board_id!(self.pci_device_id, &board_id, info.device_id);
-                set_with_board_id!(self.pci_vendor_id, &board_id, info.vendor);
-
-                // The N in /dev/tenstorrent/N
-                set_with_board_id!(self.tt_interface_id, &board_id, info.interface_id);
+    /// Call with the result of chip detection.
+    pub fn update_health(&self, board_id: &str, status: Option<&InitStatus>) {
+        let components = match status {
+            Some(status) => [
+                ("comms", status.comms_status.ok()),
+                ("arc", !status.arc_status.has_error()),
+                ("dram", !status.dram_status.has_error()),
+                ("eth", !status.eth_status.has_error()),
+                ("cpu", !status.cpu_status.has_error()),
+            ],
+            // The chip initialized without any problems
+            None => [
+                ("comms", true),
+                ("arc", true),
+                ("dram", true),
+                ("eth", true),
+                ("cpu", true),
+            ],
+        };
+
+        for (component, healthy) in components {
+            self.health
+                .with_label_values(&[board_id, component])
+                .set(healthy as u8 as f64);
+        }
+    }
+
+    /// Call with the ethernet links reported by a chip.
+    pub fn update_eth_links(&self, board_id: &str, chip: &Chip) {
+        let Ok(neighbours) = chip.get_neighbouring_chips() else {
+            return;
+        };
+
+        let port_of = |(x, y): (u8, u8)| {
+            chip.as_wh()
+                .and_then(|wh| {
+                    wh.eth_locations
+                        .iter()
+                        .position(|core| core.x == x && core.y == y)
+                })
+                .map(|port| port.to_string())
+                .unwrap_or_else(|| format!("{x}-{y}"))
+        };
+
+        for neighbour in neighbours {
+            self.eth_link
+                .with_label_values(&[
+                    board_id,
+                    &port_of(neighbour.local_noc_addr),
+                    &neighbour.eth_addr.to_string(),
+                ])
+                .set(neighbour.routing_enabled as u8 as f64);
+        }
+    }
+
+    /// Call with a wormhole to export the link state and heartbeat of each ethernet port.
+    pub fn update_eth_ports(&self, board_id: &str, wh: &Wormhole) {
+        let Ok(ports) = wh.eth_port_status(None) else {
+            return;
+        };
+
+        for port in ports.iter().filter(|port| port.enabled) {
+            let labels = [board_id, &port.port.to_string()];
+            if let Some(link) = port.link {
+                self.eth_link_up
+                    .with_label_values(&labels)
+                    .set(link.is_up() as u8 as f64);
             }
-            // Currently, only PCIe-connected chips have a DeviceInfo.
-            None => {
-                board_id.push_str("_remote");
+            if let Some(heartbeat) = port.heartbeat {
+                self.eth_heartbeat
+                    .with_label_values(&labels)
+                    .set(heartbeat as f64);
+            }
+        }
+    }
+
+    /// Call with the raw telemetry table of a blackhole.
+    pub fn update_telemetry_tags(&self, board_id: &str, tags: &[(u32, u32)]) {
+        for (tag, value) in tags.iter().copied() {
+            let name = TelemetryTags::from_u32(tag)
+                .map(|tag| tag.name().to_string())
+                .unwrap_or_else(|| format!("tag_{tag}"));
+            self.telemetry_tag
+                .with_label_values(&[board_id, &name])
+                .set(value as f64);
+        }
+    }
+
+    /// Call with a pci connected chip to export its link and error state.
+    pub fn update_pcie(&self, board_id: &str, info: &DeviceInfo, sysfs_root: &Path) {
+        // The device may have gone away, in which case there is nothing to report
+        let Ok(health) = info.pcie_health_from(sysfs_root) else {
+            return;
+        };
+
+        let link = &health.link;
+        set_with_board_id!(self.pci_cur_link_width, board_id, link.current_width);
+        set_with_board_id!(self.pci_cur_link_gen, board_id, link.current_gen);
+        set_with_board_id!(self.pci_max_link_width, board_id, link.max_width);
+        set_with_board_id!(self.pci_max_link_gen, board_id, link.max_gen);
+        set_with_board_id!(self.pci_link_degraded, board_id, link.is_degraded() as u8);
+
+        let aer = [
+            ("correctable", &health.aer.correctable),
+            ("nonfatal", &health.aer.nonfatal),
+            ("fatal", &health.aer.fatal),
+        ];
+        for (severity, counters) in aer {
+            if let Some(counters) = counters {
+                self.pci_aer
+                    .with_label_values(&[board_id, severity])
+                    .set(counters.total as f64);
             }
         }
+        set_with_board_id!(self.pci_link_down, board_id, health.link_down_events());
+        set_with_board_id!(self.pci_retrain, board_id, health.retrain_events());
+    }
+
+    /// Call with telemetry for any board.
+    pub fn update(&self, board_id: &str, device_info: &Option<DeviceInfo>, telemetry: &Telemetry) {
+        if let Some(info) = device_info {
+            // PCI BDF (bus/device/function)
+            set_with_board_id!(self.pci_bus, board_id, info.bus);
+            set_with_board_id!(self.pci_device, board_id, info.slot);
+            set_with_board_id!(self.pci_function, board_id, info.function);
+
+            // Luwen uses `device_id` to describe N in /dev/tenstorrent/N in
+            // some places, interface_id elsewhere.  Here we're concerned
+            // with the PCI device & vendor IDs, that is, the 1e52:401e in
+            // e.g. 01:00.0 Processing accelerators: Device 1e52:401e
+            //                                              ^^^^ ^^^^
+            set_with_board_id!(self.pci_device_id, board_id, info.device_id);
+            set_with_board_id!(self.pci_vendor_id, board_id, info.vendor);
+
+            // The N in /dev/tenstorrent/N
+            set_with_board_id!(self.tt_interface_id, board_id, info.interface_id);
+        }
 
         let fw_date = telemetry.firmware_date();
         let arc_fw_ver = telemetry.arc_fw_version();
         let eth_fw_ver = telemetry.eth_fw_version();
         let board_type = telemetry.board_type();
         self.sw_info
-            .with_label_values(&[&board_id, &fw_date, &arc_fw_ver, &eth_fw_ver, &board_type])
+            .with_label_values(&[board_id, &fw_date, &arc_fw_ver, &eth_fw_ver, board_type])
             .set(1.0);
 
-        set_with_board_id!(&self.aiclk, &board_id, telemetry.ai_clk());
-        set_with_board_id!(&self.axiclk, &board_id, telemetry.axi_clk());
-        set_with_board_id!(&self.arcclk, &board_id, telemetry.arc_clk());
-        set_with_board_id!(&self.voltage, &board_id, telemetry.voltage());
+        set_with_board_id!(&self.aiclk, board_id, telemetry.ai_clk());
+        set_with_board_id!(&self.axiclk, board_id, telemetry.axi_clk());
+        set_with_board_id!(&self.arcclk, board_id, telemetry.arc_clk());
+        set_with_board_id!(&self.voltage, board_id, telemetry.voltage());
         set_with_board_id!(
             &self.asic_temperature,
-            &board_id,
+            board_id,
             telemetry.asic_temperature()
         );
         set_with_board_id!(
             &self.vreg_temperature,
-            &board_id,
+            board_id,
             telemetry.vreg_temperature()
         );
         set_with_board_id!(
             &self.inlet_temperature,
-            &board_id,
+            board_id,
             telemetry.inlet_temperature()
         );
         set_with_board_id!(
             &self.outlet_temperature1,
-            &board_id,
+            board_id,
             telemetry.outlet_temperature1()
         );
         set_with_board_id!(
             &self.outlet_temperature2,
-            &board_id,
+            board_id,
             telemetry.outlet_temperature2()
         );
-        set_with_board_id!(&self.power, &board_id, telemetry.power());
-        set_with_board_id!(&self.current, &board_id, telemetry.current());
+        set_with_board_id!(&self.power, board_id, telemetry.power());
+        set_with_board_id!(&self.current, board_id, telemetry.current());
+        set_with_board_id!(&self.fan_speed, board_id, telemetry.fan_speed);
+        set_with_board_id!(&self.throttler, board_id, telemetry.throttler);
+        set_with_board_id!(&self.faults, board_id, telemetry.faults);
+        for bit in 0..32 {
+            let bit_label = bit.to_string();
+            self.throttler_bit
+                .with_label_values(&[board_id, &bit_label])
+                .set(((telemetry.throttler >> bit) & 0x1) as f64);
+            self.fault_bit
+                .with_label_values(&[board_id, &bit_label])
+                .set(((telemetry.faults >> bit) & 0x1) as f64);
+        }
+    }
+}
+
+/// A chip found during detection along with the label we report it under.
+struct MonitoredChip {
+    chip: Chip,
+    status: Option<InitStatus>,
+    board_id: Option<String>,
+}
+
+impl MonitoredChip {
+    fn new(chip: UninitChip) -> Self {
+        let status = chip.status().cloned();
+        MonitoredChip {
+            chip: chip.upgrade(),
+            status,
+            board_id: None,
+        }
+    }
+
+    /// Label the chip by its board id, falling back to how it is connected if the board id
+    /// can't be read.
+    fn label(&self, device_info: &Option<DeviceInfo>, telemetry: Option<&Telemetry>) -> String {
+        let connection = match device_info {
+            // This chip is connected via PCIe.
+            Some(_) => "pcie",
+            // Currently, only PCIe-connected chips have a DeviceInfo.
+            None => "remote",
+        };
+
+        match (telemetry, device_info) {
+            (Some(telemetry), _) => {
+                format!("{}_{connection}", telemetry.board_serial_number_hex())
+            }
+            (None, Some(info)) => format!("interface{}_{connection}", info.interface_id),
+            (None, None) => {
+                let coord = self
+                    .chip
+                    .as_wh()
+                    .and_then(|wh| wh.get_local_chip_coord().ok())
+                    .map(|coord| coord.to_string())
+                    .unwrap_or_else(|| "unknown".to_string());
+                format!("{coord}_{connection}")
+            }
+        }
+    }
+}
+
+fn detect(no_grayskull: bool, selectors: &[ChipSelector]) -> Vec<MonitoredChip> {
+    let options = ChipDetectOptions::default().chip_selectors(selectors.to_vec());
+    let chips = match luwen_ref::detect_chips_options(options) {
+        Ok(chips) => chips,
+        Err(err) => {
+            eprintln!("Failed to detect chips: {err}");
+            return Vec::new();
+        }
+    };
+
+    chips
+        .into_iter()
+        .map(MonitoredChip::new)
+        .filter(|chip| !(no_grayskull && chip.chip.as_gs().is_some()))
+        .collect()
+}
+
+/// Query every chip; returns false if any of them did not respond.
+fn update(metrics: &Metrics, chips: &mut [MonitoredChip], sysfs_root: &Path) -> bool {
+    let mut all_up = true;
+    for chip in chips.iter_mut() {
+        // Reopening the device is much cheaper than detecting every chip again,
+        // if it doesn't work the chip won't respond and we fall back to that.
+        if chip.chip.connection_state() != ConnectionState::Connected {
+            if let Err(err) = chip.chip.reopen() {
+                eprintln!("Failed to reopen chip: {err}");
+            }
+        }
+
+        let device_info = chip.chip.get_device_info().ok().flatten();
+        let telemetry = chip.chip.get_telemetry().ok();
+
+        let board_id = match &chip.board_id {
+            Some(board_id) => board_id.clone(),
+            None => {
+                let label = chip.label(&device_info, telemetry.as_ref());
+                // Only hold on to the label once it is based on the board id
+                if telemetry.is_some() {
+                    chip.board_id = Some(label.clone());
+                }
+                label
+            }
+        };
+
+        metrics.update_health(&board_id, chip.status.as_ref());
+        if let Some(info) = &device_info {
+            metrics.update_pcie(&board_id, info, sysfs_root);
+        }
+
+        let Some(telemetry) = telemetry else {
+            metrics.set_up(&board_id, false);
+            all_up = false;
+            continue;
+        };
+
+        metrics.set_up(&board_id, true);
+        metrics.update(&board_id, &device_info, &telemetry);
+        metrics.update_eth_links(&board_id, &chip.chip);
+        if let Some(wh) = chip.chip.as_wh() {
+            metrics.update_eth_ports(&board_id, wh);
+        }
+        if let Some(bh) = chip.chip.as_bh() {
+            if let Ok(tags) = bh.get_telemetry_tags() {
+                metrics.update_telemetry_tags(&board_id, &tags);
+            }
+        }
     }
+
+    all_up
 }
 
 fn main() {
     let args = CommandLineArguments::parse();
     let interval_ms = args.interval;
-    let chips = luwen_ref::detect_chips().unwrap();
 
     let worker = thread::spawn(move || {
         let metrics = Metrics::new();
 
+        let mut interfaces = luwen_ref::PciDevice::scan();
+        let mut chips = detect(args.no_grayskull, &args.chips);
+        let mut known_boards = HashSet::new();
+
         loop {
-            for chip in &chips {
-                if args.no_grayskull && chip.as_wh().is_none() {
-                    continue;
-                }
-                let device_info = chip.get_device_info().unwrap();
-                let telemetry = chip.get_telemetry().unwrap();
-                metrics.update(&device_info, &telemetry);
+            let all_up = update(&metrics, &mut chips, &args.sysfs_root);
+
+            let current = chips
+                .iter()
+                .filter_map(|chip| chip.board_id.clone())
+                .collect::<HashSet<_>>();
+            // Boards which we have seen before but can no longer find
+            for board_id in known_boards.difference(&current) {
+                metrics.set_up(board_id, false);
             }
+            known_boards.extend(current);
 
             thread::sleep(Duration::from_millis(interval_ms as u64));
+
+            // After a reset or hotplug the old chip handles are no longer usable
+            let new_interfaces = luwen_ref::PciDevice::scan();
+            if !all_up || new_interfaces != interfaces {
+                interfaces = new_interfaces;
+                chips = detect(args.no_grayskull, &args.chips);
+            }
         }
     });
 
@@ -1,141 +1,117 @@
-use std::collections::HashSet;
-
-use luwen_if::ChipImpl;
-
-mod blackhole;
-mod wormhole;
-
-trait Reset {
-    fn reset(&mut self);
-    fn wait(&mut self) -> bool;
-    fn restore(&mut self);
+use std::time::Duration;
+
+use clap::Parser;
+use luwen_if::ChipSelector;
+use luwen_ref::reset::ResetOptions;
+
+#[derive(Parser)]
+pub struct CmdArgs {
+    /// Chips to reset (bdf:0000:41:00.0, board:<serial>[/<asic>], eth:<rack_x>,<rack_y>,<shelf_x>,<shelf_y>
+    /// or if:<id>, a bare number is an interface id), defaults to all of them.
+    /// Remote chips are reset through the interface they are reached by.
+    chips: Vec<ChipSelector>,
+    /// Don't retrain the pcie link before the reset.
+    #[arg(long)]
+    no_link_reset: bool,
+    /// Only reset the chips directly connected over pci.
+    #[arg(long)]
+    local_only: bool,
+    /// Seconds to wait for the chips to come out of reset.
+    #[arg(long, default_value_t = 2.0)]
+    timeout: f64,
+    /// Don't rerun chip detection after the reset.
+    #[arg(long)]
+    no_verify: bool,
 }
 
-/// Returns true if the link was okay if false link was not good
-fn link_reset(interface: usize) -> bool {
-    let fd = if let Ok(fd) = std::fs::OpenOptions::new()
-        .read(true)
-        .write(true)
-        .open(format!("/dev/tenstorrent/{}", interface))
-    {
-        fd
-    } else {
-        return false;
-    };
+fn main() {
+    let args = CmdArgs::parse();
 
-    let mut reset_device = ttkmd_if::ioctl::ResetDevice {
-        input: ttkmd_if::ioctl::ResetDeviceIn {
-            flags: ttkmd_if::ioctl::RESET_DEVICE_RESET_PCIE_LINK,
-            ..Default::default()
-        },
+    let options = ResetOptions {
+        link_reset: !args.no_link_reset,
+        reset_remote: !args.local_only,
+        timeout: Duration::from_secs_f64(args.timeout),
+        verify: !args.no_verify,
         ..Default::default()
     };
-    if unsafe {
-        ttkmd_if::ioctl::reset_device(std::os::fd::AsRawFd::as_raw_fd(&fd), &mut reset_device)
-    }
-    .is_err()
-    {
-        return false;
-    }
 
-    reset_device.output.result == 0
-}
-
-fn main() {
-    let mut trackers = Vec::new();
-    // The interfaces that we expect to be restored post reset...
-    let interfaces = luwen_ref::PciDevice::scan();
-    println!("Found {} chips to reset", interfaces.len());
-    for interface in interfaces.iter().copied() {
-        // Just try to reset the link... if it fails then we should still try stuff
-        link_reset(interface);
-        if let Ok(device) = ttkmd_if::PciDevice::open(interface) {
-            let tracker = match device.arch {
-                luwen_core::Arch::Grayskull => continue,
-                luwen_core::Arch::Wormhole => {
-                    Box::new(wormhole::ResetTracker::init(interface)) as Box<dyn Reset>
-                }
-                luwen_core::Arch::Blackhole => {
-                    Box::new(blackhole::ResetTracker::init(interface)) as Box<dyn Reset>
-                }
-                luwen_core::Arch::Unknown(_) => todo!(),
-            };
-            trackers.push((tracker, false));
+    let interfaces = match luwen_ref::resolve_interfaces(&args.chips) {
+        Ok(interfaces) => interfaces,
+        Err(err) => {
+            eprintln!("Failed to find the chips to reset: {err}");
+            std::process::exit(1);
         }
-    }
-
-    println!("Resetting chips");
-    for (tracker, _completed) in trackers.iter_mut() {
-        tracker.reset();
-    }
-
-    println!("Waiting for reset to complete");
-    let start = std::time::Instant::now();
+    };
+    println!("Found {} chips to reset", interfaces.len());
 
-    while start.elapsed().as_secs() < 2 {
-        let mut all_done = true;
-        for (tracker, completed) in trackers.iter_mut() {
-            if !*completed {
-                all_done = false;
-                if tracker.wait() {
-                    *completed = true;
-                }
-            }
+    let report = match luwen_ref::reset::reset_interfaces(&interfaces, &options) {
+        Ok(report) => report,
+        Err(err) => {
+            eprintln!("Failed to reset chips: {err}");
+            std::process::exit(1);
         }
+    };
 
-        if all_done {
-            break;
-        }
+    for remote in &report.remote {
+        let hops = remote
+            .hops
+            .map(|h| h.to_string())
+            .unwrap_or_else(|| "?".to_string());
+        println!(
+            "Remote chip {:?} ({hops} hops): {}",
+            remote.coord, remote.status
+        );
     }
 
-    let mut failed_reset = Vec::new();
-    for (interface, (mut tracker, completed)) in trackers.into_iter().enumerate() {
-        if !completed {
-            failed_reset.push(interface);
+    for interface in &report.interfaces {
+        let arch = interface
+            .arch
+            .map(|a| format!("{a:?}"))
+            .unwrap_or_else(|| "unknown".to_string());
+        println!(
+            "Interface {} ({arch}): {}",
+            interface.interface, interface.status
+        );
+        if let Some(Err(err)) = &interface.link {
+            println!("\tlink reset failed: {err}");
         }
-        tracker.restore();
-    }
-
-    let mut reinit_interfaces = HashSet::new();
-
-    let chips = luwen_ref::detect_chips_fallible().unwrap();
-    for chip in chips {
-        let chip = chip
-            .init(&mut |_| Ok::<(), std::convert::Infallible>(()))
-            .map_err(Into::<luwen_if::error::PlatformError>::into)
-            .unwrap();
-        if let Ok(Some(info)) = chip.get_device_info() {
-            reinit_interfaces.insert(info.interface_id as usize);
+        if let Some(Err(err)) = &interface.restore {
+            println!("\trestore failed: {err}");
         }
     }
 
-    if !failed_reset.is_empty() {
-        println!("Failed to reset chips {failed_reset:?}");
+    match &report.verification {
+        Some(Ok(verification)) => {
+            if !verification.missing_interfaces.is_empty() {
+                println!(
+                    "Interfaces that vanished: {:?}",
+                    verification.missing_interfaces
+                );
+            }
+            if !verification.new_interfaces.is_empty() {
+                println!(
+                    "Interfaces that appeared: {:?}",
+                    verification.new_interfaces
+                );
+            }
+            if !verification.uninitialized_interfaces.is_empty() {
+                println!(
+                    "Interfaces that did not initialize: {:?}",
+                    verification.uninitialized_interfaces
+                );
+            }
+            if !verification.missing_remote.is_empty() {
+                println!(
+                    "Remote chips that vanished: {:?}",
+                    verification.missing_remote
+                );
+            }
+        }
+        Some(Err(err)) => println!("Failed to redetect chips: {err}"),
+        None => {}
     }
 
-    let interfaces = HashSet::from_iter(interfaces);
-    let mut new_interfaces = reinit_interfaces
-        .difference(&interfaces)
-        .copied()
-        .collect::<Vec<_>>();
-    new_interfaces.sort();
-    let mut not_found_interfaces = interfaces
-        .difference(&reinit_interfaces)
-        .copied()
-        .collect::<Vec<_>>();
-    not_found_interfaces.sort();
-
-    if !new_interfaces.is_empty() || !not_found_interfaces.is_empty() {
-        if !new_interfaces.is_empty() && !not_found_interfaces.is_empty() {
-            panic!("We have had interfaces both appearing and disappearing (interfaces that appeared: {new_interfaces:?}; interfaces that vanished: {not_found_interfaces:?})");
-        } else if !new_interfaces.is_empty() {
-            panic!(
-                "Some new interfaces appeared?!? (interfaces that appeared: {new_interfaces:?})"
-            );
-        } else if !not_found_interfaces.is_empty() {
-            panic!(
-                "Some interfaces disappeared (interfaces that vanished: {not_found_interfaces:?})"
-            );
-        }
+    if !report.is_ok() {
+        std::process::exit(1);
     }
 }
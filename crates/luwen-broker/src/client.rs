@@ -0,0 +1,631 @@
+// SPDX-FileCopyrightText: © 2024 Tenstorrent Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use std::{
+    convert::Infallible,
+    io::{BufReader, BufWriter, Read, Write},
+    net::{TcpStream, ToSocketAddrs},
+    os::unix::net::UnixStream,
+    path::Path,
+    sync::{Arc, Mutex},
+};
+
+use luwen_if::{
+    chip::{
+        ArcMsgOptions, Chip, ChipComms, ChipInitResult, ChipInterface, Harvesting, HlComms,
+        InitError, InitStatus, NeighbouringChip, Telemetry,
+    },
+    error::{ChipIdentity, PlatformError},
+    ArcMsgOk, CallbackStorage, ChipDetectOptions, ChipImpl, DeviceInfo, FnAxi, FnDriver, FnNoc,
+    FnOptions, FnRemote, UninitChip,
+};
+
+use crate::{
+    error::BrokerError,
+    protocol::{read_message, write_message, DeviceDesc, Request, Response, PROTOCOL_VERSION},
+};
+
+struct Connection {
+    reader: BufReader<Box<dyn Read + Send>>,
+    writer: BufWriter<Box<dyn Write + Send>>,
+}
+
+/// A connection to a broker, requests from different threads are sent one at a time.
+pub struct BrokerClient {
+    connection: Mutex<Connection>,
+}
+
+impl BrokerClient {
+    pub fn connect(path: impl AsRef<Path>) -> Result<Arc<Self>, BrokerError> {
+        let stream = UnixStream::connect(path)?;
+        Self::handshake(Box::new(stream.try_clone()?), Box::new(stream))
+    }
+
+    pub fn connect_tcp(addr: impl ToSocketAddrs) -> Result<Arc<Self>, BrokerError> {
+        let stream = TcpStream::connect(addr)?;
+        stream.set_nodelay(true)?;
+        Self::handshake(Box::new(stream.try_clone()?), Box::new(stream))
+    }
+
+    fn handshake(
+        reader: Box<dyn Read + Send>,
+        writer: Box<dyn Write + Send>,
+    ) -> Result<Arc<Self>, BrokerError> {
+        let client = Self {
+            connection: Mutex::new(Connection {
+                reader: BufReader::new(reader),
+                writer: BufWriter::new(writer),
+            }),
+        };
+
+        match client.request(&Request::Hello {
+            version: PROTOCOL_VERSION,
+        })? {
+            Response::Hello { version } if version == PROTOCOL_VERSION => Ok(Arc::new(client)),
+            Response::Hello { version } => Err(BrokerError::VersionMismatch {
+                server: version,
+                client: PROTOCOL_VERSION,
+            }),
+            response => Err(unexpected(response)),
+        }
+    }
+
+    /// Send a single request and wait for its response, errors raised by the device are
+    /// returned as [`BrokerError::Remote`].
+    pub fn request(&self, request: &Request) -> Result<Response, BrokerError> {
+        let mut connection = self.connection.lock().unwrap();
+
+        write_message(&mut connection.writer, request)?;
+        connection.writer.flush()?;
+
+        match read_message(&mut connection.reader)? {
+            Some(Response::Error(message)) => Err(BrokerError::Remote(message)),
+            Some(response) => Ok(response),
+            None => Err(BrokerError::Disconnected),
+        }
+    }
+
+    pub fn devices(&self) -> Result<Vec<DeviceDesc>, BrokerError> {
+        match self.request(&Request::ListDevices)? {
+            Response::Devices(devices) => Ok(devices),
+            response => Err(unexpected(response)),
+        }
+    }
+
+    /// The [`ChipInterface`](luwen_if::chip::ChipInterface) of a device served by the broker.
+    pub fn interface(self: &Arc<Self>, device: u32) -> CallbackStorage<BrokerInterface> {
+        CallbackStorage::new(
+            broker_callback,
+            BrokerInterface {
+                client: self.clone(),
+                device,
+            },
+        )
+    }
+
+    /// Open every device served by the broker, these can be passed on to
+    /// [`luwen_if::detect_chips`] in the place of the pci chips. ARC messages to the default
+    /// mailbox are sent as [`Request::ArcMsg`], so the broker can hand a forced fan back to the
+    /// firmware once this client disconnects.
+    pub fn open_chips(self: &Arc<Self>) -> Result<Vec<Chip>, PlatformError> {
+        let devices = self
+            .devices()
+            .map_err(|err| PlatformError::from(err.to_string()))?;
+
+        let mut chips = Vec::with_capacity(devices.len());
+        for (device, desc) in devices.into_iter().enumerate() {
+            let device = device as u32;
+            let chip = BrokerChip {
+                chip: Chip::open(desc.arch.into(), self.interface(device))?,
+                client: self.clone(),
+                device,
+            };
+            chips.push(Chip::from(Box::new(chip) as Box<dyn ChipImpl>));
+        }
+
+        Ok(chips)
+    }
+
+    /// Find all chips reachable through the broker, the same as detecting them locally.
+    pub fn detect_chips(
+        self: &Arc<Self>,
+        options: ChipDetectOptions,
+    ) -> Result<Vec<UninitChip>, PlatformError> {
+        luwen_if::detect_chips(
+            self.open_chips()?,
+            &mut |_| Ok::<(), Infallible>(()),
+            options,
+        )
+        .map_err(|err| match err {
+            InitError::PlatformError(err) => err,
+            InitError::CallbackError(_) => unreachable!(),
+        })
+    }
+
+    /// Send an ARC message through the broker, which keeps other clients off the device until
+    /// the message has completed. The message always goes to the default mailbox.
+    pub fn arc_msg(&self, device: u32, options: &ArcMsgOptions) -> Result<ArcMsgOk, BrokerError> {
+        let (arg0, arg1) = options.msg.args();
+        let request = Request::ArcMsg {
+            device,
+            msg: options.msg.msg_code(),
+            arg0,
+            arg1,
+            wait_for_done: options.wait_for_done,
+            timeout_ms: options.timeout.as_millis() as u64,
+        };
+
+        match self.request(&request)? {
+            Response::ArcMsg { rc, arg } => Ok(ArcMsgOk::Ok { rc, arg }),
+            Response::ArcMsgNoWait => Ok(ArcMsgOk::OkNoWait),
+            response => Err(unexpected(response)),
+        }
+    }
+
+    pub fn telemetry(&self, device: u32) -> Result<Telemetry, BrokerError> {
+        match self.request(&Request::Telemetry { device })? {
+            Response::Telemetry(telemetry) => Ok(*telemetry),
+            response => Err(unexpected(response)),
+        }
+    }
+
+    fn device_info(&self, device: u32) -> Result<Option<DeviceInfo>, BrokerError> {
+        match self.request(&Request::DeviceInfo { device })? {
+            Response::DeviceInfo(info) => Ok(info),
+            response => Err(unexpected(response)),
+        }
+    }
+
+    fn read(&self, request: &Request, data: &mut [u8]) -> Result<(), BrokerError> {
+        match self.request(request)? {
+            Response::Data(value) if value.len() == data.len() => {
+                data.copy_from_slice(&value);
+                Ok(())
+            }
+            response => Err(unexpected(response)),
+        }
+    }
+
+    fn write(&self, request: &Request) -> Result<(), BrokerError> {
+        match self.request(request)? {
+            Response::Done => Ok(()),
+            response => Err(unexpected(response)),
+        }
+    }
+}
+
+fn unexpected(response: Response) -> BrokerError {
+    BrokerError::UnexpectedResponse(format!("{response:?}"))
+}
+
+/// A chip opened through the broker, which sends its ARC messages through the broker instead
+/// of writing the mailbox itself.
+struct BrokerChip {
+    chip: Chip,
+    client: Arc<BrokerClient>,
+    device: u32,
+}
+
+impl HlComms for BrokerChip {
+    fn comms_obj(&self) -> (&dyn ChipComms, &dyn ChipInterface) {
+        self.chip.comms_obj()
+    }
+
+    fn chip_identity(&self) -> ChipIdentity {
+        self.chip.chip_identity()
+    }
+}
+
+impl ChipImpl for BrokerChip {
+    fn update_init_state(
+        &mut self,
+        status: &mut InitStatus,
+    ) -> Result<ChipInitResult, PlatformError> {
+        self.chip.update_init_state(status)
+    }
+
+    fn get_arch(&self) -> luwen_core::Arch {
+        self.chip.get_arch()
+    }
+
+    fn get_telemetry(&self) -> Result<Telemetry, PlatformError> {
+        self.chip.get_telemetry()
+    }
+
+    fn arc_msg(&self, msg: ArcMsgOptions) -> Result<ArcMsgOk, PlatformError> {
+        // The broker only sends to the default mailbox
+        if msg.use_second_mailbox || msg.addrs.is_some() {
+            return self.chip.arc_msg(msg);
+        }
+
+        self.client
+            .arc_msg(self.device, &msg)
+            .map_err(|err| PlatformError::from(err.to_string()))
+    }
+
+    fn harvesting(&self) -> Result<Harvesting, PlatformError> {
+        self.chip.harvesting()
+    }
+
+    fn get_neighbouring_chips(&self) -> Result<Vec<NeighbouringChip>, PlatformError> {
+        self.chip.get_neighbouring_chips()
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self.chip.as_any()
+    }
+
+    fn get_device_info(&self) -> Result<Option<DeviceInfo>, PlatformError> {
+        self.chip.get_device_info()
+    }
+}
+
+#[derive(Clone)]
+pub struct BrokerInterface {
+    client: Arc<BrokerClient>,
+    device: u32,
+}
+
+pub fn broker_callback(
+    ud: &BrokerInterface,
+    op: FnOptions,
+) -> Result<(), Box<dyn std::error::Error>> {
+    Ok(broker_callback_inner(ud, op)?)
+}
+
+fn broker_callback_inner(ud: &BrokerInterface, op: FnOptions) -> Result<(), BrokerError> {
+    let BrokerInterface { client, device } = ud;
+    let device = *device;
+
+    match op {
+        FnOptions::Driver(op) => match op {
+            FnDriver::DeviceInfo(info) => {
+                let value = client.device_info(device)?;
+                unsafe { *info = value };
+            }
+            FnDriver::EthRoutes { routes, len } => {
+                let routes = unsafe { std::slice::from_raw_parts(routes, len) }.to_vec();
+                client.write(&Request::SetEthRoutes { device, routes })?;
+            }
+            // The broker owns the connection to the device, so it's always connected from here
+            FnDriver::ConnectionState(_) => {}
+            FnDriver::Reopen => {
+                return Err(BrokerError::Remote(
+                    "Devices can't be reopened through the broker".to_string(),
+                ))
+            }
+            FnDriver::Acquire => client.write(&Request::Acquire { device })?,
+            FnDriver::Release => client.write(&Request::Release { device })?,
+        },
+        FnOptions::Axi(op) => match op {
+            FnAxi::Read { addr, data, len } => {
+                let data = unsafe { std::slice::from_raw_parts_mut(data, len as usize) };
+                client.read(&Request::AxiRead { device, addr, len }, data)?;
+            }
+            FnAxi::Write { addr, data, len } => {
+                let data = unsafe { std::slice::from_raw_parts(data, len as usize) }.to_vec();
+                client.write(&Request::AxiWrite { device, addr, data })?;
+            }
+        },
+        FnOptions::Noc(op) => match op {
+            FnNoc::Read {
+                noc_id,
+                x,
+                y,
+                addr,
+                data,
+                len,
+            } => {
+                let data = unsafe { std::slice::from_raw_parts_mut(data, len as usize) };
+                let request = Request::NocRead {
+                    device,
+                    noc_id,
+                    x: x as u8,
+                    y: y as u8,
+                    addr,
+                    len: len as u32,
+                };
+                client.read(&request, data)?;
+            }
+            FnNoc::Write {
+                noc_id,
+                x,
+                y,
+                addr,
+                data,
+                len,
+            } => {
+                let data = unsafe { std::slice::from_raw_parts(data, len as usize) }.to_vec();
+                client.write(&Request::NocWrite {
+                    device,
+                    noc_id,
+                    x: x as u8,
+                    y: y as u8,
+                    addr,
+                    data,
+                })?;
+            }
+            FnNoc::Broadcast {
+                noc_id,
+                addr,
+                data,
+                len,
+            } => {
+                let data = unsafe { std::slice::from_raw_parts(data, len as usize) }.to_vec();
+                client.write(&Request::NocBroadcast {
+                    device,
+                    noc_id,
+                    addr,
+                    data,
+                })?;
+            }
+        },
+        FnOptions::Eth(FnRemote { addr: eth_addr, rw }) => match rw {
+            FnNoc::Read {
+                noc_id,
+                x,
+                y,
+                addr,
+                data,
+                len,
+            } => {
+                let data = unsafe { std::slice::from_raw_parts_mut(data, len as usize) };
+                let request = Request::EthNocRead {
+                    device,
+                    eth_addr,
+                    noc_id,
+                    x: x as u8,
+                    y: y as u8,
+                    addr,
+                    len: len as u32,
+                };
+                client.read(&request, data)?;
+            }
+            FnNoc::Write {
+                noc_id,
+                x,
+                y,
+                addr,
+                data,
+                len,
+            } => {
+                let data = unsafe { std::slice::from_raw_parts(data, len as usize) }.to_vec();
+                client.write(&Request::EthNocWrite {
+                    device,
+                    eth_addr,
+                    noc_id,
+                    x: x as u8,
+                    y: y as u8,
+                    addr,
+                    data,
+                })?;
+            }
+            FnNoc::Broadcast {
+                noc_id,
+                addr,
+                data,
+                len,
+            } => {
+                let data = unsafe { std::slice::from_raw_parts(data, len as usize) }.to_vec();
+                client.write(&Request::EthNocBroadcast {
+                    device,
+                    eth_addr,
+                    noc_id,
+                    addr,
+                    data,
+                })?;
+            }
+        },
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use luwen_core::Arch;
+    use luwen_if::{
+        chip::{communication::fault_injection::MemoryInterface, ChipInterface},
+        EthAddr,
+    };
+
+    use super::*;
+    use crate::server::{Broker, BrokerDevice};
+
+    /// A socket path which is unique to this test.
+    fn socket_path() -> std::path::PathBuf {
+        static COUNT: AtomicUsize = AtomicUsize::new(0);
+        std::env::temp_dir().join(format!(
+            "luwen-broker-test-{}-{}.sock",
+            std::process::id(),
+            COUNT.fetch_add(1, Ordering::Relaxed)
+        ))
+    }
+
+    /// Serve two simulated devices from a socket which is unique to this test.
+    fn serve() -> std::path::PathBuf {
+        let path = socket_path();
+
+        let mut memory = MemoryInterface::new();
+        memory.device_info = Some(DeviceInfo {
+            interface_id: 3,
+            domain: 0,
+            bus: 0x41,
+            slot: 0,
+            function: 0,
+            vendor: 0x1e52,
+            device_id: 0x401e,
+            board_id: 0,
+            bar_size: 0,
+        });
+        let broker = Broker::new(vec![
+            BrokerDevice::new(Arch::Wormhole, Arc::new(memory)),
+            BrokerDevice::new(Arch::Blackhole, Arc::new(MemoryInterface::new())),
+        ]);
+
+        let listener = Broker::bind_unix(&path).unwrap();
+        std::thread::spawn(move || broker.serve_unix(listener));
+
+        path
+    }
+
+    #[test]
+    fn end_to_end() {
+        let path = serve();
+        let client = BrokerClient::connect(&path).unwrap();
+
+        let devices = client.devices().unwrap();
+        assert_eq!(devices.len(), 2);
+        assert_eq!(Arch::from(devices[0].arch), Arch::Wormhole);
+        assert_eq!(devices[0].info.as_ref().unwrap().interface_id, 3);
+        assert!(devices[1].info.is_none());
+
+        let wh = client.interface(0);
+        let bh = client.interface(1);
+        assert_eq!(wh.get_device_info().unwrap().unwrap().bus, 0x41);
+
+        wh.axi_write(0x1ff30060, &[1, 2, 3, 4]).unwrap();
+        wh.noc_write(0, 9, 0, 0x100, &[5, 6]).unwrap();
+        let eth_addr = EthAddr {
+            shelf_x: 1,
+            shelf_y: 0,
+            rack_x: 0,
+            rack_y: 0,
+        };
+        wh.eth_noc_write(eth_addr, 0, 9, 0, 0x100, &[7]).unwrap();
+
+        let mut data = [0; 4];
+        wh.axi_read(0x1ff30060, &mut data).unwrap();
+        assert_eq!(data, [1, 2, 3, 4]);
+        let mut data = [0; 2];
+        wh.noc_read(0, 9, 0, 0x100, &mut data).unwrap();
+        assert_eq!(data, [5, 6]);
+        let mut data = [0; 1];
+        wh.eth_noc_read(eth_addr, 0, 9, 0, 0x100, &mut data)
+            .unwrap();
+        assert_eq!(data, [7]);
+
+        // Devices don't share memory
+        let mut data = [0xff; 4];
+        bh.axi_read(0x1ff30060, &mut data).unwrap();
+        assert_eq!(data, [0; 4]);
+
+        // A second client sees the same devices
+        let other = BrokerClient::connect(&path).unwrap();
+        let mut data = [0; 4];
+        other.interface(0).axi_read(0x1ff30060, &mut data).unwrap();
+        assert_eq!(data, [1, 2, 3, 4]);
+
+        // Errors are returned to the client without dropping the connection
+        let err = client.interface(2).axi_write(0, &[0]).unwrap_err();
+        assert!(err.to_string().contains("no device 2"), "{err}");
+        assert_eq!(client.devices().unwrap().len(), 2);
+
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn concurrent_clients() {
+        let path = serve();
+
+        let threads = (0..4u32)
+            .map(|index| {
+                let path = path.clone();
+                std::thread::spawn(move || {
+                    let client = BrokerClient::connect(&path).unwrap();
+                    let interface = client.interface(1);
+                    for value in 0..100u32 {
+                        let addr = index * 4;
+                        interface.axi_write(addr, &value.to_le_bytes()).unwrap();
+                        let mut data = [0; 4];
+                        interface.axi_read(addr, &mut data).unwrap();
+                        assert_eq!(u32::from_le_bytes(data), value);
+                    }
+                })
+            })
+            .collect::<Vec<_>>();
+
+        for thread in threads {
+            thread.join().unwrap();
+        }
+
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn acquire() {
+        let path = serve();
+        let owner = BrokerClient::connect(&path).unwrap();
+        owner.interface(1).acquire().unwrap();
+        owner.interface(1).axi_write(0, &[1, 0, 0, 0]).unwrap();
+
+        let (sender, receiver) = std::sync::mpsc::channel();
+        let other_path = path.clone();
+        let thread = std::thread::spawn(move || {
+            let other = BrokerClient::connect(&other_path).unwrap();
+            let mut data = [0; 4];
+            other.interface(1).axi_read(0, &mut data).unwrap();
+            sender.send(data).unwrap();
+        });
+
+        // Other clients wait until the device is released
+        assert!(receiver
+            .recv_timeout(std::time::Duration::from_millis(100))
+            .is_err());
+        // but can still list the devices
+        let other = BrokerClient::connect(&path).unwrap();
+        assert_eq!(other.devices().unwrap().len(), 2);
+        drop(other);
+        owner.interface(1).axi_write(0, &[2, 0, 0, 0]).unwrap();
+        owner.interface(1).release().unwrap();
+        assert_eq!(
+            receiver
+                .recv_timeout(std::time::Duration::from_secs(5))
+                .unwrap(),
+            [2, 0, 0, 0]
+        );
+        thread.join().unwrap();
+
+        // Only the client which acquired a device can release it
+        let other = BrokerClient::connect(&path).unwrap();
+        assert!(other.interface(1).release().is_err());
+
+        // A client which goes away doesn't keep the device
+        owner.interface(0).acquire().unwrap();
+        drop(owner);
+        other.interface(0).axi_write(0, &[3]).unwrap();
+
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn bind_keeps_other_files() {
+        let path = socket_path();
+        std::fs::write(&path, "not a socket").unwrap();
+
+        assert!(Broker::bind_unix(&path).is_err());
+        assert_eq!(std::fs::read(&path).unwrap(), b"not a socket");
+
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn version_mismatch() {
+        let path = serve();
+        let mut stream = UnixStream::connect(&path).unwrap();
+
+        write_message(&mut stream, &Request::Hello { version: 0 }).unwrap();
+        assert!(matches!(
+            read_message(&mut stream).unwrap(),
+            Some(Response::Hello {
+                version: PROTOCOL_VERSION
+            })
+        ));
+        // The broker hangs up on clients it can't talk to
+        assert!(read_message::<Response>(&mut stream).unwrap().is_none());
+
+        std::fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,29 @@
+// SPDX-FileCopyrightText: © 2024 Tenstorrent Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum BrokerError {
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+
+    #[error("Could not encode or decode a broker message: {0}")]
+    Encoding(#[from] bincode::Error),
+
+    #[error("Broker message of {0} bytes is too large")]
+    MessageTooLarge(u64),
+
+    #[error("The broker speaks protocol version {server}, but this client speaks {client}")]
+    VersionMismatch { server: u32, client: u32 },
+
+    #[error("The broker closed the connection")]
+    Disconnected,
+
+    #[error("Unexpected response from the broker: {0}")]
+    UnexpectedResponse(String),
+
+    /// The request reached the device but failed there.
+    #[error("{0}")]
+    Remote(String),
+}
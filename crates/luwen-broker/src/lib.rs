@@ -0,0 +1,22 @@
+// SPDX-FileCopyrightText: © 2024 Tenstorrent Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Shares chips between processes.
+//!
+//! The broker is the only process which opens the devices, it serves the raw chip accesses,
+//! ARC messages and telemetry of any number of clients over a unix socket (or tcp for lab
+//! debugging) and keeps their accesses to a device from interleaving. On the client side
+//! [`BrokerClient::interface`] is a regular `ChipInterface`, so the chips returned by
+//! [`BrokerClient::open_chips`] can be passed to `detect_chips` like local ones.
+//!
+//! A fan speed forced by a client is handed back to the firmware when that client disconnects.
+
+mod client;
+pub mod error;
+pub mod protocol;
+mod server;
+
+pub use client::{broker_callback, BrokerClient, BrokerInterface};
+pub use error::BrokerError;
+pub use protocol::{DeviceDesc, DEFAULT_SOCKET, PROTOCOL_VERSION};
+pub use server::{Broker, BrokerDevice, SharedInterface};
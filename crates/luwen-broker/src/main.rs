@@ -0,0 +1,99 @@
+// SPDX-FileCopyrightText: © 2024 Tenstorrent Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use std::{net::SocketAddr, path::PathBuf, sync::Arc};
+
+use clap::Parser;
+use luwen_broker::{Broker, BrokerDevice, DEFAULT_SOCKET};
+use luwen_core::Arch;
+use luwen_if::{
+    chip::communication::fault_injection::MemoryInterface, CallbackStorage, DeviceInfo,
+};
+
+#[derive(Parser)]
+#[command(about = "Own the tenstorrent devices and serve chip access to other processes")]
+struct Args {
+    /// Unix socket to listen on
+    #[arg(long, default_value = DEFAULT_SOCKET)]
+    socket: PathBuf,
+
+    /// Also listen for tcp connections on this address, they are not authenticated
+    #[arg(long)]
+    tcp: Option<SocketAddr>,
+
+    /// Serve this many simulated wormhole chips backed by memory instead of the pci devices
+    #[arg(long)]
+    simulate: Option<u32>,
+}
+
+fn pci_devices() -> Vec<BrokerDevice> {
+    let mut devices = Vec::new();
+    for interface in luwen_ref::PciDevice::scan() {
+        let device = match luwen_ref::ExtendedPciDevice::open(interface) {
+            Ok(device) => device,
+            Err(err) => {
+                eprintln!("Skipping /dev/tenstorrent/{interface}: {err}");
+                continue;
+            }
+        };
+
+        let arch = device.borrow().device.arch;
+        devices.push(BrokerDevice::new(
+            arch,
+            Arc::new(CallbackStorage::new(luwen_ref::comms_callback, device)),
+        ));
+    }
+
+    devices
+}
+
+fn simulated_devices(count: u32) -> Vec<BrokerDevice> {
+    (0..count)
+        .map(|interface_id| {
+            let mut memory = MemoryInterface::new();
+            memory.device_info = Some(DeviceInfo {
+                interface_id,
+                domain: 0,
+                bus: interface_id as u16,
+                slot: 0,
+                function: 0,
+                vendor: 0x1e52,
+                device_id: 0x401e,
+                board_id: 0,
+                bar_size: 0,
+            });
+            BrokerDevice::new(Arch::Wormhole, Arc::new(memory))
+        })
+        .collect()
+}
+
+fn main() {
+    let args = Args::parse();
+
+    let devices = match args.simulate {
+        Some(count) => simulated_devices(count),
+        None => pci_devices(),
+    };
+    println!(
+        "Serving {} devices on {}",
+        devices.len(),
+        args.socket.display()
+    );
+    let broker = Broker::new(devices);
+
+    if let Some(addr) = args.tcp {
+        let listener = std::net::TcpListener::bind(addr).expect("Could not bind the tcp address");
+        let broker = broker.clone();
+        std::thread::spawn(move || {
+            if let Err(err) = broker.serve_tcp(listener) {
+                eprintln!("Stopped serving tcp clients: {err}");
+            }
+        });
+    }
+
+    let listener = Broker::bind_unix(&args.socket).expect("Could not bind the unix socket");
+    if let Err(err) = broker.serve_unix(listener) {
+        eprintln!("Stopped serving: {err}");
+        std::process::exit(1);
+    }
+}
@@ -0,0 +1,240 @@
+// SPDX-FileCopyrightText: © 2024 Tenstorrent Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! The messages exchanged between the broker and its clients.
+//!
+//! Every message is a little endian u32 length followed by that many bytes of bincode. A client
+//! starts with [`Request::Hello`] and then sends one request at a time, each is answered by
+//! exactly one [`Response`].
+
+use std::io::{Read, Write};
+
+use luwen_core::Arch;
+use luwen_if::{chip::NeighbouringChip, chip::Telemetry, DeviceInfo, EthAddr};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use crate::error::BrokerError;
+
+/// Bumped whenever a message changes shape.
+pub const PROTOCOL_VERSION: u32 = 2;
+
+/// Where the broker listens when no socket is given.
+pub const DEFAULT_SOCKET: &str = "/tmp/luwen-broker.sock";
+
+/// Larger messages are rejected rather than allocated.
+pub const MAX_MESSAGE_LEN: u32 = 64 * 1024 * 1024;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum WireArch {
+    Grayskull,
+    Wormhole,
+    Blackhole,
+    Unknown(u16),
+}
+
+impl From<Arch> for WireArch {
+    fn from(value: Arch) -> Self {
+        match value {
+            Arch::Grayskull => WireArch::Grayskull,
+            Arch::Wormhole => WireArch::Wormhole,
+            Arch::Blackhole => WireArch::Blackhole,
+            Arch::Unknown(id) => WireArch::Unknown(id),
+        }
+    }
+}
+
+impl From<WireArch> for Arch {
+    fn from(value: WireArch) -> Self {
+        match value {
+            WireArch::Grayskull => Arch::Grayskull,
+            WireArch::Wormhole => Arch::Wormhole,
+            WireArch::Blackhole => Arch::Blackhole,
+            WireArch::Unknown(id) => Arch::Unknown(id),
+        }
+    }
+}
+
+/// A device served by the broker.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct DeviceDesc {
+    pub arch: WireArch,
+    pub info: Option<DeviceInfo>,
+}
+
+/// `device` is the index into the list returned for [`Request::ListDevices`].
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub enum Request {
+    Hello {
+        version: u32,
+    },
+    ListDevices,
+    DeviceInfo {
+        device: u32,
+    },
+    SetEthRoutes {
+        device: u32,
+        routes: Vec<NeighbouringChip>,
+    },
+    AxiRead {
+        device: u32,
+        addr: u32,
+        len: u32,
+    },
+    AxiWrite {
+        device: u32,
+        addr: u32,
+        data: Vec<u8>,
+    },
+    NocRead {
+        device: u32,
+        noc_id: u8,
+        x: u8,
+        y: u8,
+        addr: u64,
+        len: u32,
+    },
+    NocWrite {
+        device: u32,
+        noc_id: u8,
+        x: u8,
+        y: u8,
+        addr: u64,
+        data: Vec<u8>,
+    },
+    NocBroadcast {
+        device: u32,
+        noc_id: u8,
+        addr: u64,
+        data: Vec<u8>,
+    },
+    EthNocRead {
+        device: u32,
+        eth_addr: EthAddr,
+        noc_id: u8,
+        x: u8,
+        y: u8,
+        addr: u64,
+        len: u32,
+    },
+    EthNocWrite {
+        device: u32,
+        eth_addr: EthAddr,
+        noc_id: u8,
+        x: u8,
+        y: u8,
+        addr: u64,
+        data: Vec<u8>,
+    },
+    EthNocBroadcast {
+        device: u32,
+        eth_addr: EthAddr,
+        noc_id: u8,
+        addr: u64,
+        data: Vec<u8>,
+    },
+    /// Send a raw message to the ARC mailbox, the broker holds the device for the whole exchange.
+    ArcMsg {
+        device: u32,
+        msg: u16,
+        arg0: u16,
+        arg1: u16,
+        wait_for_done: bool,
+        timeout_ms: u64,
+    },
+    Telemetry {
+        device: u32,
+    },
+    /// Keep other clients off the device until the matching [`Request::Release`], for exchanges
+    /// made of several accesses. Devices are released when their client disconnects.
+    Acquire {
+        device: u32,
+    },
+    Release {
+        device: u32,
+    },
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub enum Response {
+    Hello { version: u32 },
+    Devices(Vec<DeviceDesc>),
+    DeviceInfo(Option<DeviceInfo>),
+    Data(Vec<u8>),
+    Done,
+    ArcMsg { rc: u32, arg: u32 },
+    ArcMsgNoWait,
+    Telemetry(Box<Telemetry>),
+    Error(String),
+}
+
+/// Send a single message, the caller is responsible for flushing buffered streams.
+pub fn write_message(stream: &mut impl Write, message: &impl Serialize) -> Result<(), BrokerError> {
+    let data = bincode::serialize(message)?;
+    let len = u32::try_from(data.len())
+        .ok()
+        .filter(|len| *len <= MAX_MESSAGE_LEN)
+        .ok_or(BrokerError::MessageTooLarge(data.len() as u64))?;
+
+    stream.write_all(&len.to_le_bytes())?;
+    stream.write_all(&data)?;
+
+    Ok(())
+}
+
+/// Read a single message, returns None if the stream was closed cleanly between messages.
+pub fn read_message<T: DeserializeOwned>(stream: &mut impl Read) -> Result<Option<T>, BrokerError> {
+    let mut len = [0u8; 4];
+    match stream.read_exact(&mut len) {
+        Ok(()) => {}
+        Err(err) if err.kind() == std::io::ErrorKind::UnexpectedEof => return Ok(None),
+        Err(err) => return Err(err.into()),
+    }
+
+    let len = u32::from_le_bytes(len);
+    if len > MAX_MESSAGE_LEN {
+        return Err(BrokerError::MessageTooLarge(len as u64));
+    }
+
+    let mut data = vec![0; len as usize];
+    stream.read_exact(&mut data)?;
+
+    Ok(Some(bincode::deserialize(&data)?))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn roundtrip() {
+        let mut buffer = Vec::new();
+        write_message(
+            &mut buffer,
+            &Request::AxiWrite {
+                device: 1,
+                addr: 0x1ff30060,
+                data: vec![1, 2, 3, 4],
+            },
+        )
+        .unwrap();
+        write_message(&mut buffer, &Request::ListDevices).unwrap();
+
+        let mut stream = buffer.as_slice();
+        assert!(matches!(
+            read_message(&mut stream).unwrap(),
+            Some(Request::AxiWrite { device: 1, addr: 0x1ff30060, data }) if data == [1, 2, 3, 4]
+        ));
+        assert!(matches!(
+            read_message(&mut stream).unwrap(),
+            Some(Request::ListDevices)
+        ));
+        assert!(read_message::<Request>(&mut stream).unwrap().is_none());
+
+        // A length beyond the limit is rejected before anything is allocated
+        let mut stream = &(MAX_MESSAGE_LEN + 1).to_le_bytes()[..];
+        assert!(matches!(
+            read_message::<Request>(&mut stream),
+            Err(BrokerError::MessageTooLarge(_))
+        ));
+    }
+}
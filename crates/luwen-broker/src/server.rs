@@ -0,0 +1,573 @@
+// SPDX-FileCopyrightText: © 2024 Tenstorrent Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use std::{
+    collections::BTreeSet,
+    io::{BufReader, BufWriter, Read, Write},
+    net::TcpListener,
+    os::unix::{fs::FileTypeExt, net::UnixListener},
+    path::Path,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Condvar, Mutex, MutexGuard,
+    },
+    time::Duration,
+};
+
+use luwen_core::Arch;
+use luwen_if::{
+    chip::{ArcMsgOptions, Chip, ChipInterface},
+    ArcMsg, ArcMsgOk, CallbackStorage, ChipImpl, FnAxi, FnDriver, FnNoc, FnOptions, FnRemote,
+    TypedArcMsg,
+};
+
+use crate::{
+    error::BrokerError,
+    protocol::{
+        read_message, write_message, DeviceDesc, Request, Response, MAX_MESSAGE_LEN,
+        PROTOCOL_VERSION,
+    },
+};
+
+pub type SharedInterface = Arc<dyn ChipInterface + Send + Sync>;
+
+/// A device owned by the broker.
+pub struct BrokerDevice {
+    arch: Arch,
+    interface: SharedInterface,
+    /// Read once up front so that listing the devices never waits for a client which acquired one.
+    desc: DeviceDesc,
+    /// Locked for every request so that clients can't interleave accesses to the same device,
+    /// the chip is only opened once an ARC message or telemetry is requested.
+    chip: Mutex<Option<Chip>>,
+    /// The client which acquired the device and how many times it did so.
+    owner: Mutex<Option<(u64, usize)>>,
+    released: Condvar,
+}
+
+impl BrokerDevice {
+    pub fn new(arch: Arch, interface: SharedInterface) -> Self {
+        let desc = DeviceDesc {
+            arch: arch.into(),
+            info: interface.get_device_info().ok().flatten(),
+        };
+
+        Self {
+            arch,
+            interface,
+            desc,
+            chip: Mutex::new(None),
+            owner: Mutex::new(None),
+            released: Condvar::new(),
+        }
+    }
+
+    /// Lock the device for a single request, waiting while another client has acquired it.
+    fn lock(&self, client: u64) -> MutexGuard<'_, Option<Chip>> {
+        // The owner stays locked until we have the chip, so that a client which acquires the
+        // device in the meantime only starts once this request is done
+        let _owner = self
+            .released
+            .wait_while(
+                self.owner.lock().unwrap(),
+                |owner| matches!(owner, Some((id, _)) if *id != client),
+            )
+            .unwrap();
+
+        self.chip.lock().unwrap()
+    }
+
+    fn acquire(&self, client: u64) {
+        let mut owner = self
+            .released
+            .wait_while(
+                self.owner.lock().unwrap(),
+                |owner| matches!(owner, Some((id, _)) if *id != client),
+            )
+            .unwrap();
+
+        let count = owner.map_or(0, |(_, count)| count);
+        *owner = Some((client, count + 1));
+    }
+
+    /// Undo one [`BrokerDevice::acquire`], or all of them if the client went away.
+    fn release(&self, client: u64, all: bool) -> Result<(), Box<dyn std::error::Error>> {
+        let mut owner = self.owner.lock().unwrap();
+        match *owner {
+            Some((owner_client, count)) if owner_client == client => {
+                *owner = (count > 1 && !all).then_some((client, count - 1));
+            }
+            _ => return Err("The device was not acquired by this client".into()),
+        }
+        self.released.notify_all();
+
+        Ok(())
+    }
+
+    fn open_chip(&self) -> Result<Chip, Box<dyn std::error::Error>> {
+        if let Arch::Unknown(id) = self.arch {
+            return Err(format!("Can't open a chip with the unknown arch {id:x}").into());
+        }
+
+        Ok(Chip::open(
+            self.arch,
+            CallbackStorage::new(forward_callback, self.interface.clone()),
+        )?)
+    }
+}
+
+/// Serves the accesses of any number of clients to a fixed set of devices.
+pub struct Broker {
+    devices: Vec<BrokerDevice>,
+    next_client: AtomicU64,
+}
+
+impl Broker {
+    pub fn new(devices: Vec<BrokerDevice>) -> Arc<Self> {
+        Arc::new(Self {
+            devices,
+            next_client: AtomicU64::new(0),
+        })
+    }
+
+    /// Bind `path`, replacing the socket left behind by a broker which didn't shut down cleanly.
+    /// Anything at `path` which isn't a socket is left alone.
+    pub fn bind_unix(path: impl AsRef<Path>) -> Result<UnixListener, BrokerError> {
+        let path = path.as_ref();
+        let is_socket = std::fs::symlink_metadata(path)
+            .map(|metadata| metadata.file_type().is_socket())
+            .unwrap_or(false);
+        if is_socket && std::os::unix::net::UnixStream::connect(path).is_err() {
+            let _ = std::fs::remove_file(path);
+        }
+
+        Ok(UnixListener::bind(path)?)
+    }
+
+    /// Accept clients until the listener fails, every client is served from its own thread.
+    pub fn serve_unix(self: &Arc<Self>, listener: UnixListener) -> Result<(), BrokerError> {
+        for stream in listener.incoming() {
+            let stream = stream?;
+            let reader = stream.try_clone()?;
+            self.spawn_client(reader, stream);
+        }
+
+        Ok(())
+    }
+
+    /// Like [`Broker::serve_unix`], there is no authentication so this should only be exposed
+    /// on a trusted network.
+    pub fn serve_tcp(self: &Arc<Self>, listener: TcpListener) -> Result<(), BrokerError> {
+        for stream in listener.incoming() {
+            let stream = stream?;
+            stream.set_nodelay(true)?;
+            let reader = stream.try_clone()?;
+            self.spawn_client(reader, stream);
+        }
+
+        Ok(())
+    }
+
+    fn spawn_client(
+        self: &Arc<Self>,
+        reader: impl Read + Send + 'static,
+        writer: impl Write + Send + 'static,
+    ) {
+        let broker = self.clone();
+        std::thread::spawn(move || {
+            if let Err(err) = broker.handle_connection(reader, writer) {
+                tracing::warn!("Dropped broker client: {err}");
+            }
+        });
+    }
+
+    /// Serve a single client until it disconnects.
+    pub fn handle_connection(
+        &self,
+        reader: impl Read,
+        writer: impl Write,
+    ) -> Result<(), BrokerError> {
+        let mut reader = BufReader::new(reader);
+        let mut writer = BufWriter::new(writer);
+
+        match read_message(&mut reader)? {
+            Some(Request::Hello { version }) => {
+                write_message(
+                    &mut writer,
+                    &Response::Hello {
+                        version: PROTOCOL_VERSION,
+                    },
+                )?;
+                writer.flush()?;
+
+                if version != PROTOCOL_VERSION {
+                    return Err(BrokerError::VersionMismatch {
+                        server: PROTOCOL_VERSION,
+                        client: version,
+                    });
+                }
+            }
+            Some(request) => {
+                return Err(BrokerError::UnexpectedResponse(format!(
+                    "expected a hello, got {request:?}"
+                )))
+            }
+            None => return Ok(()),
+        }
+
+        let client = self.next_client.fetch_add(1, Ordering::Relaxed);
+
+        // Fans forced by this client go back to the firmware curve once it disconnects, so a
+        // fan controller which crashed can't leave a fan pinned
+        let mut forced_fans = BTreeSet::new();
+        let result = self.serve_requests(client, &mut reader, &mut writer, &mut forced_fans);
+        for device in &self.devices {
+            let _ = device.release(client, true);
+        }
+        for device in forced_fans {
+            if let Err(err) = self.restore_fan_control(client, device) {
+                tracing::warn!("Failed to restore automatic fan control of device {device}: {err}");
+            }
+        }
+
+        result
+    }
+
+    fn serve_requests(
+        &self,
+        client: u64,
+        reader: &mut impl Read,
+        writer: &mut impl Write,
+        forced_fans: &mut BTreeSet<u32>,
+    ) -> Result<(), BrokerError> {
+        let force_fan_speed = TypedArcMsg::ForceFanSpeed { speed: 0 }.msg_code();
+
+        while let Some(request) = read_message(reader)? {
+            if let Request::ArcMsg {
+                device,
+                msg,
+                arg0,
+                arg1,
+                ..
+            } = request
+            {
+                if msg & 0xFF == force_fan_speed {
+                    if (arg0, arg1) == (0xFFFF, 0xFFFF) {
+                        forced_fans.remove(&device);
+                    } else {
+                        forced_fans.insert(device);
+                    }
+                }
+            }
+
+            let response = self
+                .handle(client, request)
+                .unwrap_or_else(|err| Response::Error(err.to_string()));
+            write_message(writer, &response)?;
+            writer.flush()?;
+        }
+
+        Ok(())
+    }
+
+    fn restore_fan_control(
+        &self,
+        client: u64,
+        device: u32,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let device = self.device(device)?;
+        let mut chip = device.lock(client);
+        if chip.is_none() {
+            *chip = Some(device.open_chip()?);
+        }
+
+        Ok(chip.as_ref().unwrap().fan().clear_forced_speed()?)
+    }
+
+    fn device(&self, device: u32) -> Result<&BrokerDevice, Box<dyn std::error::Error>> {
+        self.devices
+            .get(device as usize)
+            .ok_or_else(|| format!("The broker has no device {device}").into())
+    }
+
+    fn handle(
+        &self,
+        client: u64,
+        request: Request,
+    ) -> Result<Response, Box<dyn std::error::Error>> {
+        let read_buffer = |len: u32| -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+            if len > MAX_MESSAGE_LEN {
+                return Err(format!("Read of {len} bytes is too large").into());
+            }
+            Ok(vec![0; len as usize])
+        };
+
+        let response = match request {
+            Request::Hello { .. } => return Err("Already connected".into()),
+            Request::ListDevices => Response::Devices(
+                self.devices
+                    .iter()
+                    .map(|device| device.desc.clone())
+                    .collect(),
+            ),
+            Request::DeviceInfo { device } => {
+                let device = self.device(device)?;
+                let _lock = device.lock(client);
+                Response::DeviceInfo(device.interface.get_device_info()?)
+            }
+            Request::SetEthRoutes { device, routes } => {
+                let device = self.device(device)?;
+                let _lock = device.lock(client);
+                device.interface.set_eth_routes(&routes)?;
+                Response::Done
+            }
+            Request::AxiRead { device, addr, len } => {
+                let device = self.device(device)?;
+                let mut data = read_buffer(len)?;
+                let _lock = device.lock(client);
+                device.interface.axi_read(addr, &mut data)?;
+                Response::Data(data)
+            }
+            Request::AxiWrite { device, addr, data } => {
+                let device = self.device(device)?;
+                let _lock = device.lock(client);
+                device.interface.axi_write(addr, &data)?;
+                Response::Done
+            }
+            Request::NocRead {
+                device,
+                noc_id,
+                x,
+                y,
+                addr,
+                len,
+            } => {
+                let device = self.device(device)?;
+                let mut data = read_buffer(len)?;
+                let _lock = device.lock(client);
+                device.interface.noc_read(noc_id, x, y, addr, &mut data)?;
+                Response::Data(data)
+            }
+            Request::NocWrite {
+                device,
+                noc_id,
+                x,
+                y,
+                addr,
+                data,
+            } => {
+                let device = self.device(device)?;
+                let _lock = device.lock(client);
+                device.interface.noc_write(noc_id, x, y, addr, &data)?;
+                Response::Done
+            }
+            Request::NocBroadcast {
+                device,
+                noc_id,
+                addr,
+                data,
+            } => {
+                let device = self.device(device)?;
+                let _lock = device.lock(client);
+                device.interface.noc_broadcast(noc_id, addr, &data)?;
+                Response::Done
+            }
+            Request::EthNocRead {
+                device,
+                eth_addr,
+                noc_id,
+                x,
+                y,
+                addr,
+                len,
+            } => {
+                let device = self.device(device)?;
+                let mut data = read_buffer(len)?;
+                let _lock = device.lock(client);
+                device
+                    .interface
+                    .eth_noc_read(eth_addr, noc_id, x, y, addr, &mut data)?;
+                Response::Data(data)
+            }
+            Request::EthNocWrite {
+                device,
+                eth_addr,
+                noc_id,
+                x,
+                y,
+                addr,
+                data,
+            } => {
+                let device = self.device(device)?;
+                let _lock = device.lock(client);
+                device
+                    .interface
+                    .eth_noc_write(eth_addr, noc_id, x, y, addr, &data)?;
+                Response::Done
+            }
+            Request::EthNocBroadcast {
+                device,
+                eth_addr,
+                noc_id,
+                addr,
+                data,
+            } => {
+                let device = self.device(device)?;
+                let _lock = device.lock(client);
+                device
+                    .interface
+                    .eth_noc_broadcast(eth_addr, noc_id, addr, &data)?;
+                Response::Done
+            }
+            Request::ArcMsg {
+                device,
+                msg,
+                arg0,
+                arg1,
+                wait_for_done,
+                timeout_ms,
+            } => {
+                let device = self.device(device)?;
+                let mut chip = device.lock(client);
+                if chip.is_none() {
+                    *chip = Some(device.open_chip()?);
+                }
+                let chip = chip.as_ref().unwrap();
+
+                match chip.arc_msg(ArcMsgOptions {
+                    msg: ArcMsg::Raw { msg, arg0, arg1 },
+                    wait_for_done,
+                    timeout: Duration::from_millis(timeout_ms),
+                    ..Default::default()
+                })? {
+                    ArcMsgOk::Ok { rc, arg } => Response::ArcMsg { rc, arg },
+                    ArcMsgOk::OkNoWait => Response::ArcMsgNoWait,
+                }
+            }
+            Request::Telemetry { device } => {
+                let device = self.device(device)?;
+                let mut chip = device.lock(client);
+                if chip.is_none() {
+                    *chip = Some(device.open_chip()?);
+                }
+                let chip = chip.as_ref().unwrap();
+
+                Response::Telemetry(Box::new(chip.get_telemetry()?))
+            }
+            Request::Acquire { device } => {
+                self.device(device)?.acquire(client);
+                Response::Done
+            }
+            Request::Release { device } => {
+                self.device(device)?.release(client, false)?;
+                Response::Done
+            }
+        };
+
+        Ok(response)
+    }
+}
+
+/// Lets a [`Chip`] be opened on top of an interface the broker already owns.
+fn forward_callback(
+    interface: &SharedInterface,
+    op: FnOptions,
+) -> Result<(), Box<dyn std::error::Error>> {
+    match op {
+        FnOptions::Driver(op) => match op {
+            FnDriver::DeviceInfo(info) => {
+                let value = interface.get_device_info()?;
+                unsafe { *info = value };
+            }
+            FnDriver::ConnectionState(state) => {
+                let value = interface.connection_state();
+                unsafe { *state = value };
+            }
+            FnDriver::Reopen => interface.reopen()?,
+            FnDriver::Acquire => interface.acquire()?,
+            FnDriver::Release => interface.release()?,
+            FnDriver::EthRoutes { routes, len } => {
+                let routes = unsafe { std::slice::from_raw_parts(routes, len) };
+                interface.set_eth_routes(routes)?;
+            }
+        },
+        FnOptions::Axi(op) => match op {
+            FnAxi::Read { addr, data, len } => {
+                let data = unsafe { std::slice::from_raw_parts_mut(data, len as usize) };
+                interface.axi_read(addr, data)?;
+            }
+            FnAxi::Write { addr, data, len } => {
+                let data = unsafe { std::slice::from_raw_parts(data, len as usize) };
+                interface.axi_write(addr, data)?;
+            }
+        },
+        FnOptions::Noc(op) => match op {
+            FnNoc::Read {
+                noc_id,
+                x,
+                y,
+                addr,
+                data,
+                len,
+            } => {
+                let data = unsafe { std::slice::from_raw_parts_mut(data, len as usize) };
+                interface.noc_read(noc_id, x as u8, y as u8, addr, data)?;
+            }
+            FnNoc::Write {
+                noc_id,
+                x,
+                y,
+                addr,
+                data,
+                len,
+            } => {
+                let data = unsafe { std::slice::from_raw_parts(data, len as usize) };
+                interface.noc_write(noc_id, x as u8, y as u8, addr, data)?;
+            }
+            FnNoc::Broadcast {
+                noc_id,
+                addr,
+                data,
+                len,
+            } => {
+                let data = unsafe { std::slice::from_raw_parts(data, len as usize) };
+                interface.noc_broadcast(noc_id, addr, data)?;
+            }
+        },
+        FnOptions::Eth(FnRemote { addr: eth_addr, rw }) => match rw {
+            FnNoc::Read {
+                noc_id,
+                x,
+                y,
+                addr,
+                data,
+                len,
+            } => {
+                let data = unsafe { std::slice::from_raw_parts_mut(data, len as usize) };
+                interface.eth_noc_read(eth_addr, noc_id, x as u8, y as u8, addr, data)?;
+            }
+            FnNoc::Write {
+                noc_id,
+                x,
+                y,
+                addr,
+                data,
+                len,
+            } => {
+                let data = unsafe { std::slice::from_raw_parts(data, len as usize) };
+                interface.eth_noc_write(eth_addr, noc_id, x as u8, y as u8, addr, data)?;
+            }
+            FnNoc::Broadcast {
+                noc_id,
+                addr,
+                data,
+                len,
+            } => {
+                let data = unsafe { std::slice::from_raw_parts(data, len as usize) };
+                interface.eth_noc_broadcast(eth_addr, noc_id, addr, data)?;
+            }
+        },
+    }
+
+    Ok(())
+}
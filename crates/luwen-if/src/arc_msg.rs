@@ -4,7 +4,7 @@
 use thiserror::Error;
 
 use crate::{
-    chip::{AxiError, HlComms},
+    chip::{poll, AxiError, HlComms, PollOptions, WaitError},
     error::PlatformError,
 };
 
@@ -46,6 +46,8 @@ pub enum TypedArcMsg {
     ResetSafeClks { arg: u32 },
     ToggleTensixReset { arg: u32 },
     DeassertRiscVReset,
+    ForceAiclk { mhz: u32 },
+    ForceFanSpeed { speed: u32 },
     GetAiclk,
     TriggerReset,
     GetHarvesting,
@@ -79,7 +81,9 @@ impl TypedArcMsg {
             TypedArcMsg::DeassertRiscVReset => 0xba,
             TypedArcMsg::ResetSafeClks { .. } => 0xbb,
             TypedArcMsg::ToggleTensixReset { .. } => 0xaf,
+            TypedArcMsg::ForceAiclk { .. } => 0x33,
             TypedArcMsg::GetAiclk => 0x34,
+            TypedArcMsg::ForceFanSpeed { .. } => 0xAC,
             TypedArcMsg::SetArcState { state } => match state {
                 ArcState::A0 => 0xA0,
                 ArcState::A1 => 0xA1,
@@ -117,6 +121,8 @@ impl ArcMsg {
                 TypedArcMsg::Test { arg }
                 | TypedArcMsg::ResetSafeClks { arg }
                 | TypedArcMsg::ToggleTensixReset { arg }
+                | TypedArcMsg::ForceAiclk { mhz: arg }
+                | TypedArcMsg::ForceFanSpeed { speed: arg }
                 | TypedArcMsg::SpiRead { addr: arg } => {
                     ((arg & 0xFFFF) as u16, ((arg >> 16) & 0xFFFF) as u16)
                 }
@@ -146,6 +152,7 @@ impl ArcMsg {
         let msg = 0xFF & msg;
         let msg = match msg {
             0x11 => TypedArcMsg::Nop,
+            0x33 => TypedArcMsg::ForceAiclk { mhz: arg },
             0x34 => TypedArcMsg::GetAiclk,
             0x56 => TypedArcMsg::TriggerReset,
             0xbb => TypedArcMsg::ResetSafeClks { arg },
@@ -169,6 +176,7 @@ impl ArcMsg {
             0xA5 => TypedArcMsg::SetArcState {
                 state: ArcState::A5,
             },
+            0xAC => TypedArcMsg::ForceFanSpeed { speed: arg },
             0xB9 => TypedArcMsg::FwVersion(match arg {
                 0 => FwType::ArcL2,
                 1 => FwType::FwBundle,
@@ -188,8 +196,8 @@ impl ArcMsg {
 pub enum ArcMsgProtocolError {
     #[error("Message {0} not recognized")]
     MsgNotRecognized(u16),
-    #[error("Timed out while waiting {0:?} for ARC to respond")]
-    Timeout(std::time::Duration),
+    #[error("ARC did not respond: {0}")]
+    Timeout(Box<WaitError>),
     #[error("ARC is asleep")]
     ArcAsleep,
     #[error("Failed to trigger FW interrupt")]
@@ -283,23 +291,28 @@ pub fn arc_msg<T: HlComms>(
     }
 
     if wait_for_done {
-        let start = std::time::Instant::now();
-        loop {
-            let status = comms.axi_read32(addrs.scratch_base + (msg_reg * 4))?;
-            if (status & 0xFFFF) as u16 == code & 0xFF {
-                let exit_code = (status >> 16) & 0xFFFF;
-                let arg = comms.axi_read32(addrs.scratch_base + (return_reg * 4))?;
-
-                return Ok(ArcMsgOk::Ok { rc: exit_code, arg });
-            } else if status == MSG_ERROR_REPLY {
-                Err(ArcMsgProtocolError::MsgNotRecognized(code).into_error())?;
-            }
+        let options = PollOptions::new(timeout).backoff(
+            std::time::Duration::from_micros(10),
+            std::time::Duration::from_millis(1),
+        );
+        let status = poll(
+            format_args!("ARC to respond to message {code:#x}"),
+            &options,
+            || -> Result<u32, PlatformError> {
+                let status = comms.axi_read32(addrs.scratch_base + (msg_reg * 4))?;
+                if status == MSG_ERROR_REPLY {
+                    Err(ArcMsgProtocolError::MsgNotRecognized(code).into_error())?;
+                }
+                Ok(status)
+            },
+            |status| (status & 0xFFFF) as u16 == code & 0xFF,
+        )?
+        .map_err(|err| ArcMsgProtocolError::Timeout(Box::new(err)).into_error())?;
 
-            std::thread::sleep(std::time::Duration::from_millis(1));
-            if start.elapsed() > timeout {
-                Err(ArcMsgProtocolError::Timeout(timeout).into_error())?;
-            }
-        }
+        let exit_code = (status >> 16) & 0xFFFF;
+        let arg = comms.axi_read32(addrs.scratch_base + (return_reg * 4))?;
+
+        return Ok(ArcMsgOk::Ok { rc: exit_code, arg });
     }
 
     Ok(ArcMsgOk::OkNoWait)
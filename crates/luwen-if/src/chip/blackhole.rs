@@ -5,22 +5,29 @@ use num_traits::cast::FromPrimitive;
 
 use std::sync::Arc;
 
+use tracing::field::Empty;
+
 use crate::{
     arc_msg::ArcMsgOk,
     chip::{
-        communication::{chip_comms::ChipComms, chip_interface::ChipInterface},
+        communication::{
+            chip_comms::ChipComms,
+            chip_interface::{ChipInterface, ExclusiveAccess},
+        },
         hl_comms::HlCommsInterface,
     },
-    error::{BtWrapper, PlatformError},
+    error::{BtWrapper, ChipIdentity, PlatformError},
     ChipImpl,
 };
 
 use super::{
+    communication::instrumented::traced,
     eth_addr::EthAddr,
     hl_comms::HlComms,
     init::status::{ComponentStatusInfo, InitOptions, WaitStatus},
     remote::EthAddresses,
-    ArcMsgOptions, AxiData, ChipInitResult, CommsStatus, InitStatus, NeighbouringChip,
+    ArcMsgOptions, AxiData, ChipInitResult, CommsStatus, Harvesting, InitStatus, NeighbouringChip,
+    PollOptions,
 };
 
 pub mod boot_fs;
@@ -54,6 +61,8 @@ pub struct Blackhole {
     pub arc_if: Arc<dyn ChipComms + Send + Sync>,
 
     pub message_queue: once_cell::sync::OnceCell<message::MessageQueue<8>>,
+    board_id: once_cell::sync::OnceCell<u64>,
+    harvesting: once_cell::sync::OnceCell<Harvesting>,
 
     pub eth_locations: [EthCore; 14],
     pub eth_addrs: EthAddresses,
@@ -67,12 +76,24 @@ impl HlComms for Blackhole {
     fn comms_obj(&self) -> (&dyn ChipComms, &dyn ChipInterface) {
         (self.arc_if.as_ref(), self.chip_if.as_ref())
     }
+
+    fn chip_identity(&self) -> ChipIdentity {
+        ChipIdentity {
+            arch: Some(luwen_core::Arch::Blackhole),
+            board_id: self.board_id.get().copied(),
+            ..ChipIdentity::from_comms(self.arc_if.as_ref(), self.chip_if.as_ref())
+        }
+    }
 }
 
 impl HlComms for &Blackhole {
     fn comms_obj(&self) -> (&dyn ChipComms, &dyn ChipInterface) {
         (self.arc_if.as_ref(), self.chip_if.as_ref())
     }
+
+    fn chip_identity(&self) -> ChipIdentity {
+        Blackhole::chip_identity(self)
+    }
 }
 
 #[derive(Clone, Copy, Debug)]
@@ -114,6 +135,8 @@ impl Blackhole {
             chip_if: Arc::new(chip_if),
 
             message_queue: once_cell::sync::OnceCell::new(),
+            board_id: once_cell::sync::OnceCell::new(),
+            harvesting: once_cell::sync::OnceCell::new(),
 
             eth_addrs: EthAddresses::default(),
 
@@ -214,14 +237,30 @@ impl Blackhole {
         self.chip_if.as_any().downcast_ref::<T>()
     }
 
+    fn boot_status_hw_ready(boot_status_0: u32) -> bool {
+        ((boot_status_0 >> 1) & 0x3) == 2
+    }
+
     pub fn hw_ready(&self) -> bool {
         if let Ok(boot_status_0) = self.axi_read32(self.scratch_ram_base.addr + (4 * 2)) {
-            ((boot_status_0 >> 1) & 0x3) == 2
+            Self::boot_status_hw_ready(boot_status_0)
         } else {
             false
         }
     }
 
+    /// Wait for the firmware to report that the hardware has finished initializing,
+    /// for example after a reset.
+    pub fn wait_for_hw_ready(&self, options: &PollOptions) -> Result<(), PlatformError> {
+        self.wait_until(
+            self.scratch_ram_base.addr + (4 * 2),
+            Self::boot_status_hw_ready,
+            options,
+        )?;
+
+        Ok(())
+    }
+
     pub fn check_arc_msg_safe(&self) -> bool {
         if !self.hw_ready() {
             return false;
@@ -241,6 +280,8 @@ impl Blackhole {
         data: &[u32],
         timeout: Option<std::time::Duration>,
     ) -> Result<(u8, u16, [u32; 7]), PlatformError> {
+        // Other users of a shared device must not touch the queue until we have the reply
+        let _exclusive = ExclusiveAccess::acquire(self.comms_obj().1)?;
         if !self.check_arc_msg_safe() {
             return Err(PlatformError::ArcNotReady(
                 crate::error::ArcReadyError::BootIncomplete,
@@ -324,27 +365,37 @@ impl Blackhole {
     }
 
     pub fn spi_write(&self, mut addr: u32, value: &[u8]) -> Result<(), Box<dyn std::error::Error>> {
-        let buffer = self.get_spi_buffer()?;
-
-        for chunk in value.chunks(buffer.size as usize) {
-            self.axi_write(buffer.addr as u64, chunk)?;
-            let (status, _, _) = self.bh_arc_msg(
-                0x1A,
-                Some(0 << 8),
-                &[addr, chunk.len() as u32, buffer.addr],
-                None,
-            )?;
-
-            std::thread::sleep(std::time::Duration::from_millis(100));
+        let span = tracing::debug_span!(
+            "spi_write",
+            chip = %self.chip_identity(),
+            addr,
+            len = value.len(),
+            duration_us = Empty,
+            error = Empty
+        );
+        traced(span, || {
+            let buffer = self.get_spi_buffer()?;
+
+            for chunk in value.chunks(buffer.size as usize) {
+                self.axi_write(buffer.addr as u64, chunk)?;
+                let (status, _, _) = self.bh_arc_msg(
+                    0x1A,
+                    Some(0 << 8),
+                    &[addr, chunk.len() as u32, buffer.addr],
+                    None,
+                )?;
+
+                std::thread::sleep(std::time::Duration::from_millis(100));
+
+                if status != 0 {
+                    return Err("Failed to write to SPI".into());
+                }
 
-            if status != 0 {
-                return Err("Failed to write to SPI".into());
+                addr += chunk.len() as u32;
             }
 
-            addr += chunk.len() as u32;
-        }
-
-        Ok(())
+            Ok(())
+        })
     }
 
     pub fn spi_read(
@@ -352,26 +403,98 @@ impl Blackhole {
         mut addr: u32,
         value: &mut [u8],
     ) -> Result<(), Box<dyn std::error::Error>> {
-        let buffer = self.get_spi_buffer()?;
+        let span = tracing::debug_span!(
+            "spi_read",
+            chip = %self.chip_identity(),
+            addr,
+            len = value.len(),
+            duration_us = Empty,
+            error = Empty
+        );
+        traced(span, || {
+            let buffer = self.get_spi_buffer()?;
+
+            for chunk in value.chunks_mut(buffer.size as usize) {
+                let (status, _, _) = self.bh_arc_msg(
+                    0x19,
+                    Some(0 << 8),
+                    &[addr, chunk.len() as u32, buffer.addr],
+                    None,
+                )?;
+
+                if status != 0 {
+                    return Err("Failed to read from SPI".into());
+                }
 
-        for chunk in value.chunks_mut(buffer.size as usize) {
-            let (status, _, _) = self.bh_arc_msg(
-                0x19,
-                Some(0 << 8),
-                &[addr, chunk.len() as u32, buffer.addr],
-                None,
-            )?;
+                self.axi_read(buffer.addr as u64, chunk)?;
 
-            if status != 0 {
-                return Err("Failed to read from SPI".into());
+                addr += chunk.len() as u32;
             }
 
-            self.axi_read(buffer.addr as u64, chunk)?;
+            Ok(())
+        })
+    }
+
+    /// Read the raw telemetry table as (tag, value) pairs, in the order that they are reported
+    /// by the firmware. Tags which are unknown to luwen are included.
+    pub fn get_telemetry_tags(&self) -> Result<Vec<(u32, u32)>, PlatformError> {
+        // Get chip telemetry and device data
+        // Read telemetry data block address from scratch ram
+        // Then read and parse telemetry data
+
+        // Get address of telem struct from scratch ram
+        let mut scratch_reg_13_value = [0u8; 4];
+        self.axi_read_field(&self.telemetry_struct_addr, &mut scratch_reg_13_value)?;
+        let telem_struct_addr = u32::from_le_bytes(scratch_reg_13_value);
+        // Check if the address is within CSM memory. Otherwise, it must be invalid
+        if telem_struct_addr < 0x10000000 || telem_struct_addr > 0x1007FFFF {
+            return Err(PlatformError::Generic(
+                format!(
+                    "Invalid Telemetry struct address: 0x{:08x}",
+                    telem_struct_addr
+                ),
+                BtWrapper::capture(),
+            ));
+        }
+
+        if telem_struct_addr == 0 {
+            return Err(PlatformError::ArcNotReady(
+                crate::error::ArcReadyError::BootIncomplete,
+                BtWrapper::capture(),
+            ));
+        }
+
+        // Read the data block from the address in sctrach 13
+        // Parse out the version and entry count before reading the data block
+        let _version = self.axi_read32(telem_struct_addr as u64)?;
+        let entry_count = self.axi_read32(telem_struct_addr as u64 + 4)?;
+
+        // TODO: Implement version check and data block parsing based on version
+        // For now, assume version 1 and parse data block as is
+        // let version = u32::from_le_bytes(version);
 
-            addr += chunk.len() as u32;
+        // Get telemetry tags data block and telemetry data data block
+        let mut telemetry_tags_data_block: Vec<u8> = vec![0u8; (entry_count + 1) as usize * 4];
+        let mut telem_data_block: Vec<u8> = vec![0u8; (entry_count + 1) as usize * 4];
+
+        self.axi_read(
+            (telem_struct_addr + 8) as u64,
+            &mut telemetry_tags_data_block,
+        )?;
+        self.axi_read(
+            (telem_struct_addr + 8 + entry_count * 4) as u64,
+            &mut telem_data_block,
+        )?;
+
+        let mut tags = Vec::with_capacity(entry_count as usize);
+        for i in 0..entry_count as u8 {
+            let entry = u32_from_slice(&telemetry_tags_data_block, i);
+            let tag = entry & 0xFF;
+            let offset = (entry >> 16) & 0xFF;
+            tags.push((tag, u32_from_slice(&telem_data_block, offset as u8)));
         }
 
-        Ok(())
+        Ok(tags)
     }
 
     pub fn get_local_chip_coord(&self) -> Result<EthAddr, PlatformError> {
@@ -387,15 +510,24 @@ impl Blackhole {
         &self,
         tag_name: &str,
     ) -> Result<Option<(u32, boot_fs::TtBootFsFd)>, Box<dyn std::error::Error>> {
-        let reader = |addr: u32, size: usize| {
+        let reader = |addr: u32, size: usize| -> Result<_, Box<dyn std::error::Error>> {
+            let mut buf = vec![0; size];
+            self.spi_read(addr, &mut buf)?;
+            Ok(buf)
+        };
+        boot_fs::read_tag(reader, tag_name)
+    }
+
+    /// List all of the tables in the boot fs along with their spi address.
+    pub fn get_boot_fs_tables(
+        &self,
+    ) -> Result<Vec<(u32, boot_fs::TtBootFsFd)>, Box<dyn std::error::Error>> {
+        let reader = |addr: u32, size: usize| -> Result<_, Box<dyn std::error::Error>> {
             let mut buf = vec![0; size];
-            self.spi_read(addr, &mut buf).unwrap();
-            return buf;
+            self.spi_read(addr, &mut buf)?;
+            Ok(buf)
         };
-        return Ok(boot_fs::read_tag(
-            &reader as &dyn Fn(u32, usize) -> Vec<u8>,
-            &tag_name,
-        ));
+        boot_fs::read_all(reader)
     }
 
     fn remove_padding_proto_bin<'a>(
@@ -422,32 +554,39 @@ impl Blackhole {
         serde_json::from_str(&json_string).unwrap()
     }
 
-    pub fn decode_boot_fs_table(
+    /// Read the protobuf of a boot fs table with its padding removed.
+    fn read_boot_fs_table_bin(
         &self,
         tag_name: &str,
-    ) -> Result<HashMap<String, Value>, Box<dyn std::error::Error>> {
-        // Return the decoded boot fs table as a HashMap
+    ) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
         // Get the spi address and image size of the tag and read the proto bin
-        // Decode the proto bin and convert it to a HashMap
-        let spi_addr = self
+        let (_, fd) = self
             .get_boot_fs_tables_spi_read(tag_name)?
-            .unwrap()
-            .1
-            .spi_addr;
-        let image_size = unsafe {
-            self.get_boot_fs_tables_spi_read(tag_name)?
-                .unwrap()
-                .1
-                .flags
-                .f
-                .image_size()
-        };
+            .ok_or_else(|| format!("No {tag_name} table in the boot fs"))?;
+        let spi_addr = fd.spi_addr;
+        let image_size = fd.image_size();
         // declare as vec to allow non-const size
         let mut proto_bin = vec![0u8; image_size as usize];
         self.spi_read(spi_addr, &mut proto_bin)?;
-        let final_decode_map: HashMap<String, Value>;
         // remove padding
-        proto_bin = self.remove_padding_proto_bin(&proto_bin)?.to_vec();
+        Ok(self.remove_padding_proto_bin(&proto_bin)?.to_vec())
+    }
+
+    /// The firmware table the running firmware was configured with.
+    pub fn fw_table(&self) -> Result<spirom_tables::fw_table::FwTable, Box<dyn std::error::Error>> {
+        Ok(spirom_tables::fw_table::FwTable::decode(
+            &*self.read_boot_fs_table_bin("cmfwcfg")?,
+        )?)
+    }
+
+    pub fn decode_boot_fs_table(
+        &self,
+        tag_name: &str,
+    ) -> Result<HashMap<String, Value>, Box<dyn std::error::Error>> {
+        // Return the decoded boot fs table as a HashMap
+        // Decode the proto bin and convert it to a HashMap
+        let proto_bin = self.read_boot_fs_table_bin(tag_name)?;
+        let final_decode_map: HashMap<String, Value>;
 
         if tag_name == "cmfwcfg" || tag_name == "origcfg" {
             final_decode_map =
@@ -545,6 +684,18 @@ impl ChipImpl for Blackhole {
             }
         }
 
+        // Only ports which survived harvesting can be used
+        if !status.arc_status.is_waiting()
+            && !status.arc_status.has_error()
+            && !status.init_options.noc_safe
+        {
+            if let Ok(harvesting) = self.harvesting() {
+                for (port, core) in self.eth_locations.iter_mut().enumerate() {
+                    core.enabled = harvesting.eth_port_enabled(port);
+                }
+            }
+        }
+
         {
             let status = &mut status.dram_status;
             for s in status.wait_status.iter_mut() {
@@ -574,21 +725,36 @@ impl ChipImpl for Blackhole {
     }
 
     fn arc_msg(&self, msg: ArcMsgOptions) -> Result<ArcMsgOk, PlatformError> {
-        let code = msg.msg.msg_code();
-        let args = msg.msg.args();
-
-        let (_status, rc, response) = self.bh_arc_msg(
-            code as u8,
-            None,
-            &[args.0 as u32 | ((args.1 as u32) << 16)],
-            Some(msg.timeout),
-        )?;
-        Ok(ArcMsgOk::Ok {
-            rc: rc as u32,
-            arg: response[0],
+        let span = tracing::debug_span!(
+            "arc_msg",
+            chip = %self.chip_identity(),
+            msg = msg.msg.msg_code(),
+            duration_us = Empty,
+            error = Empty
+        );
+        traced(span, || {
+            let code = msg.msg.msg_code();
+            let args = msg.msg.args();
+
+            let (_status, rc, response) = self.bh_arc_msg(
+                code as u8,
+                None,
+                &[args.0 as u32 | ((args.1 as u32) << 16)],
+                Some(msg.timeout),
+            )?;
+            Ok(ArcMsgOk::Ok {
+                rc: rc as u32,
+                arg: response[0],
+            })
         })
     }
 
+    fn harvesting(&self) -> Result<Harvesting, PlatformError> {
+        self.harvesting
+            .get_or_try_init(|| Harvesting::read(self))
+            .cloned()
+    }
+
     fn get_neighbouring_chips(&self) -> Result<Vec<NeighbouringChip>, crate::error::PlatformError> {
         Ok(vec![])
     }
@@ -598,62 +764,11 @@ impl ChipImpl for Blackhole {
     }
 
     fn get_telemetry(&self) -> Result<super::Telemetry, PlatformError> {
-        // Get chip telemetry and device data
-        // Read telemetry data block address from scratch ram
-        // Then read and parse telemetry data
-
-        // Get address of telem struct from scratch ram
-        let mut scratch_reg_13_value = [0u8; 4];
-        self.axi_read_field(&self.telemetry_struct_addr, &mut scratch_reg_13_value)?;
-        let telem_struct_addr = u32::from_le_bytes(scratch_reg_13_value);
-        // Check if the address is within CSM memory. Otherwise, it must be invalid
-        if telem_struct_addr < 0x10000000 || telem_struct_addr > 0x1007FFFF {
-            return Err(PlatformError::Generic(
-                format!(
-                    "Invalid Telemetry struct address: 0x{:08x}",
-                    telem_struct_addr
-                ),
-                BtWrapper::capture(),
-            ));
-        }
-
-        if telem_struct_addr == 0 {
-            return Err(PlatformError::ArcNotReady(
-                crate::error::ArcReadyError::BootIncomplete,
-                BtWrapper::capture(),
-            ));
-        }
-
-        // Read the data block from the address in sctrach 13
-        // Parse out the version and entry count before reading the data block
-        let _version = self.axi_read32(telem_struct_addr as u64)?;
-        let entry_count = self.axi_read32(telem_struct_addr as u64 + 4)?;
-
-        // TODO: Implement version check and data block parsing based on version
-        // For now, assume version 1 and parse data block as is
-        // let version = u32::from_le_bytes(version);
-
-        // Get telemetry tags data block and telemetry data data block
-        let mut telemetry_tags_data_block: Vec<u8> = vec![0u8; (entry_count + 1) as usize * 4];
-        let mut telem_data_block: Vec<u8> = vec![0u8; (entry_count + 1) as usize * 4];
-
-        self.axi_read(
-            (telem_struct_addr + 8) as u64,
-            &mut telemetry_tags_data_block,
-        )?;
-        self.axi_read(
-            (telem_struct_addr + 8 + entry_count * 4) as u64,
-            &mut telem_data_block,
-        )?;
+        let tags = self.get_telemetry_tags()?;
 
         // Parse telemetry data
         let mut telemetry_data = super::Telemetry::default();
-        for i in 0..entry_count as u8 {
-            let entry = u32_from_slice(&telemetry_tags_data_block, i);
-            let tag = entry & 0xFF;
-            let offset = (entry >> 16) & 0xFF;
-            let data = u32_from_slice(&telem_data_block, offset as u8);
-            // print!("Tag: {} Data: {:#02x}\n", tag, data);
+        for (tag, data) in tags {
             if let Some(tag) = TelemetryTags::from_u32(tag) {
                 match tag {
                     TelemetryTags::BoardIdHigh => telemetry_data.board_id_high = data,
@@ -695,6 +810,8 @@ impl ChipImpl for Blackhole {
         }
         telemetry_data.board_id =
             (telemetry_data.board_id_high as u64) << 32 | telemetry_data.board_id_low as u64;
+        // Remembered so that later errors can report which board they came from
+        let _ = self.board_id.set(telemetry_data.board_id);
         Ok(telemetry_data)
     }
 
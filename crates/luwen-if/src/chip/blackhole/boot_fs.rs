@@ -94,6 +94,14 @@ impl TtBootFsFd {
             .unwrap_or(self.image_tag.len());
         String::from_utf8_lossy(&self.image_tag[..nul_pos]).to_string()
     }
+
+    pub fn image_size(&self) -> u32 {
+        unsafe { self.flags.f.image_size() }
+    }
+
+    pub fn executable(&self) -> bool {
+        unsafe { self.flags.f.executable() }
+    }
 }
 
 impl PartialEq for TtBootFsFd {
@@ -115,29 +123,53 @@ impl fmt::Debug for TtBootFsFd {
     }
 }
 
-pub fn read_fd(reader: impl Fn(u32, usize) -> Vec<u8>, addr: u32) -> Option<TtBootFsFd> {
-    let fd_bytes = reader(addr, mem::size_of::<TtBootFsFd>());
+pub fn read_fd<E>(
+    reader: impl Fn(u32, usize) -> Result<Vec<u8>, E>,
+    addr: u32,
+) -> Result<Option<TtBootFsFd>, E> {
+    let fd_bytes = reader(addr, mem::size_of::<TtBootFsFd>())?;
     if fd_bytes.len() == mem::size_of::<TtBootFsFd>() {
-        Some(unsafe {
+        Ok(Some(unsafe {
             mem::transmute::<[u8; mem::size_of::<TtBootFsFd>()], TtBootFsFd>(
                 fd_bytes.try_into().unwrap(),
             )
-        })
+        }))
     } else {
-        None
+        Ok(None)
     }
 }
 
-pub fn read_tag(reader: impl Fn(u32, usize) -> Vec<u8>, tag: &str) -> Option<(u32, TtBootFsFd)> {
+pub fn read_tag<E>(
+    reader: impl Fn(u32, usize) -> Result<Vec<u8>, E>,
+    tag: &str,
+) -> Result<Option<(u32, TtBootFsFd)>, E> {
     let mut curr_addr = 0;
-    loop {
-        let fd = read_fd(&reader, curr_addr).unwrap();
+    while let Some(fd) = read_fd(&reader, curr_addr)? {
         if unsafe { fd.flags.f.invalid() } {
-            return None;
+            break;
         }
         if fd.image_tag_str() == tag {
-            return Some((curr_addr, fd));
+            return Ok(Some((curr_addr, fd)));
         }
         curr_addr += mem::size_of::<TtBootFsFd>() as u32;
     }
+
+    Ok(None)
+}
+
+/// Read every descriptor in the boot fs, up to the first invalid entry.
+pub fn read_all<E>(
+    reader: impl Fn(u32, usize) -> Result<Vec<u8>, E>,
+) -> Result<Vec<(u32, TtBootFsFd)>, E> {
+    let mut fds = Vec::new();
+    let mut curr_addr = 0;
+    while let Some(fd) = read_fd(&reader, curr_addr)? {
+        if unsafe { fd.flags.f.invalid() } {
+            break;
+        }
+        fds.push((curr_addr, fd));
+        curr_addr += mem::size_of::<TtBootFsFd>() as u32;
+    }
+
+    Ok(fds)
 }
@@ -1,14 +1,14 @@
-use crate::{chip::AxiData, error::PlatformError};
+use crate::{
+    chip::{poll, AxiData, PollOptions, WaitError},
+    error::PlatformError,
+};
 
 use super::Blackhole;
 
 #[derive(Debug, thiserror::Error)]
 pub enum MessageError {
-    #[error("Timed out in {phase} after {}s", .timeout.as_secs_f32())]
-    Timeout {
-        phase: String,
-        timeout: std::time::Duration,
-    },
+    #[error("Message queue timeout: {0}")]
+    Timeout(Box<WaitError>),
     #[error("Selected out of range queue ({index} > {queue_count})")]
     QueueIndexOutOfRange { index: u32, queue_count: u32 },
 }
@@ -81,25 +81,17 @@ impl<const N: usize> MessageQueue<N> {
     ) -> Result<(), PlatformError> {
         let request_queue_wptr = self.qread32(chip, index, 0)?;
 
-        let start_time = std::time::Instant::now();
-        loop {
-            let request_queue_rptr = self.qread32(chip, index, 4)?;
-
-            // Check if the queue is full
-            if request_queue_rptr.abs_diff(request_queue_wptr) % (2 * self.queue_size)
-                != self.queue_size
-            {
-                break;
-            }
-
-            let elapsed = start_time.elapsed();
-            if elapsed > timeout {
-                return Err(MessageError::Timeout {
-                    phase: "push".to_string(),
-                    timeout: elapsed,
-                })?;
-            }
-        }
+        poll(
+            format_args!("space in message queue {index}"),
+            &PollOptions::new(timeout),
+            || self.qread32(chip, index, 4),
+            // The queue is full when the pointers are a whole queue apart
+            |request_queue_rptr| {
+                request_queue_rptr.abs_diff(request_queue_wptr) % (2 * self.queue_size)
+                    != self.queue_size
+            },
+        )?
+        .map_err(|err| MessageError::Timeout(Box::new(err)))?;
 
         let request_entry_offset =
             self.header_size + (request_queue_wptr % self.queue_size) * N as u32;
@@ -124,23 +116,13 @@ impl<const N: usize> MessageQueue<N> {
     ) -> Result<(), PlatformError> {
         let response_queue_rptr = self.qread32(&chip, index, 1)?;
 
-        let start_time = std::time::Instant::now();
-        loop {
-            let response_queue_wptr = self.qread32(&chip, index, 5)?;
-
-            // Break if there is some data in the queue
-            if response_queue_wptr != response_queue_rptr {
-                break;
-            }
-
-            let elapsed = start_time.elapsed();
-            if elapsed > timeout {
-                return Err(MessageError::Timeout {
-                    phase: "pop".to_string(),
-                    timeout: elapsed,
-                })?;
-            }
-        }
+        poll(
+            format_args!("a response in message queue {index}"),
+            &PollOptions::new(timeout),
+            || self.qread32(chip, index, 5),
+            |response_queue_wptr| *response_queue_wptr != response_queue_rptr,
+        )?
+        .map_err(|err| MessageError::Timeout(Box::new(err)))?;
 
         let response_entry_offset = self.header_size
             + (self.queue_size + (response_queue_rptr % self.queue_size)) * N as u32;
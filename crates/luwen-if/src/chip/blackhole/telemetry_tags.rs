@@ -1,6 +1,6 @@
 use num_derive::FromPrimitive;
 
-#[derive(FromPrimitive)]
+#[derive(FromPrimitive, Clone, Copy, Debug, PartialEq, Eq)]
 #[repr(u32)]
 pub enum TelemetryTags {
     BoardIdHigh = 1,
@@ -36,4 +36,53 @@ pub enum TelemetryTags {
     FanSpeed = 31,
     TimerHeartbeat = 32,
     TelemEnumCount = 33,
+    EnabledTensixCol = 34,
+    EnabledEth = 35,
+    EnabledGddr = 36,
+    EnabledL2Cpu = 37,
+}
+
+impl TelemetryTags {
+    /// Name of the tag in snake case, suitable for use as a label.
+    pub fn name(&self) -> &'static str {
+        match self {
+            TelemetryTags::BoardIdHigh => "board_id_high",
+            TelemetryTags::BoardIdLow => "board_id_low",
+            TelemetryTags::AsicId => "asic_id",
+            TelemetryTags::HarvestingState => "harvesting_state",
+            TelemetryTags::UpdateTelemSpeed => "update_telem_speed",
+            TelemetryTags::VCORE => "vcore",
+            TelemetryTags::TDP => "tdp",
+            TelemetryTags::TDC => "tdc",
+            TelemetryTags::VddLimits => "vdd_limits",
+            TelemetryTags::ThmLimits => "thm_limits",
+            TelemetryTags::AsicTemperature => "asic_temperature",
+            TelemetryTags::VregTemperature => "vreg_temperature",
+            TelemetryTags::BoardTemperature => "board_temperature",
+            TelemetryTags::AICLK => "aiclk",
+            TelemetryTags::AXICLK => "axiclk",
+            TelemetryTags::ARCCLK => "arcclk",
+            TelemetryTags::L2CPUCLK0 => "l2cpuclk0",
+            TelemetryTags::L2CPUCLK1 => "l2cpuclk1",
+            TelemetryTags::L2CPUCLK2 => "l2cpuclk2",
+            TelemetryTags::L2CPUCLK3 => "l2cpuclk3",
+            TelemetryTags::EthLiveStatus => "eth_live_status",
+            TelemetryTags::DdrStatus => "ddr_status",
+            TelemetryTags::DdrSpeed => "ddr_speed",
+            TelemetryTags::EthFwVersion => "eth_fw_version",
+            TelemetryTags::DdrFwVersion => "ddr_fw_version",
+            TelemetryTags::BmAppFwVersion => "bm_app_fw_version",
+            TelemetryTags::BmBlFwVersion => "bm_bl_fw_version",
+            TelemetryTags::FlashBundleVersion => "flash_bundle_version",
+            TelemetryTags::CmFwVersion => "cm_fw_version",
+            TelemetryTags::L2cpuFwVersion => "l2cpu_fw_version",
+            TelemetryTags::FanSpeed => "fan_speed",
+            TelemetryTags::TimerHeartbeat => "timer_heartbeat",
+            TelemetryTags::TelemEnumCount => "telem_enum_count",
+            TelemetryTags::EnabledTensixCol => "enabled_tensix_col",
+            TelemetryTags::EnabledEth => "enabled_eth",
+            TelemetryTags::EnabledGddr => "enabled_gddr",
+            TelemetryTags::EnabledL2Cpu => "enabled_l2cpu",
+        }
+    }
 }
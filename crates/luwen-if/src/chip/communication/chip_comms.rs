@@ -8,6 +8,7 @@ use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
 use super::chip_interface::ChipInterface;
+use crate::EthAddr;
 
 #[derive(Error, Debug)]
 pub enum AxiError {
@@ -68,6 +69,12 @@ pub fn load_axi_table(file: &str, _version: u32) -> MemorySlices {
 pub trait ChipComms {
     /// Translate a String path into the corresponding AXI address.
     fn axi_translate(&self, addr: &str) -> Result<AxiData, AxiError>;
+
+    /// The ethernet address of the chip if it is accessed over ethernet.
+    fn eth_addr(&self) -> Option<EthAddr> {
+        None
+    }
+
     /// Read and write to the NOC using AXI address gotten from `axi_translate`.
     fn axi_read(
         &self,
@@ -398,6 +405,10 @@ impl ChipComms for Arc<dyn ChipComms> {
         self.as_ref().axi_translate(addr)
     }
 
+    fn eth_addr(&self) -> Option<EthAddr> {
+        self.as_ref().eth_addr()
+    }
+
     fn axi_read(
         &self,
         chip_if: &dyn ChipInterface,
@@ -456,6 +467,10 @@ impl ChipComms for Arc<dyn ChipComms + Send + Sync> {
         self.as_ref().axi_translate(addr)
     }
 
+    fn eth_addr(&self) -> Option<EthAddr> {
+        self.as_ref().eth_addr()
+    }
+
     fn axi_read(
         &self,
         chip_if: &dyn ChipInterface,
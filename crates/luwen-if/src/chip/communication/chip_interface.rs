@@ -3,7 +3,7 @@
 
 use std::sync::Arc;
 
-use crate::{DeviceInfo, EthAddr};
+use crate::{chip::NeighbouringChip, ConnectionState, DeviceInfo, EthAddr};
 
 /// This trait is used to abstract the interface to the lowest level
 /// chip communication primatives. These primatives are defined to be a chip resource
@@ -15,6 +15,39 @@ pub trait ChipInterface: 'static {
     /// Access information about the underlying chip.
     fn get_device_info(&self) -> Result<Option<DeviceInfo>, Box<dyn std::error::Error>>;
 
+    /// Tell the implementation which ethernet links on this chip lead to other chips.
+    /// Implementations which support remote access may use this to spread traffic over all usable
+    /// ethernet cores, the default implementation ignores it.
+    fn set_eth_routes(
+        &self,
+        _routes: &[NeighbouringChip],
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        Ok(())
+    }
+
+    /// Whether the transport is still usable, implementations which can't lose their
+    /// connection are always connected.
+    fn connection_state(&self) -> ConnectionState {
+        ConnectionState::Connected
+    }
+
+    /// Reopen the underlying device after the connection was lost or the chip was reset.
+    fn reopen(&self) -> Result<(), Box<dyn std::error::Error>> {
+        Err("This interface does not support reopening the device".into())
+    }
+
+    /// Keep other users of a shared device off it until [`ChipInterface::release`] is called, so
+    /// that exchanges made of several accesses (i.e. ARC messages) are not interleaved.
+    /// Interfaces which are not shared don't need to do anything.
+    fn acquire(&self) -> Result<(), Box<dyn std::error::Error>> {
+        Ok(())
+    }
+
+    /// Let other users access the device again after [`ChipInterface::acquire`].
+    fn release(&self) -> Result<(), Box<dyn std::error::Error>> {
+        Ok(())
+    }
+
     /// Read and write to the noc endpoint on the chip in implementation
     /// this may take the form of a direct pci bar read/write or a noc read/write
     fn axi_read(&self, addr: u32, data: &mut [u8]) -> Result<(), Box<dyn std::error::Error>>;
@@ -79,6 +112,29 @@ impl ChipInterface for Arc<dyn ChipInterface + Send + Sync> {
         self.as_ref().get_device_info()
     }
 
+    fn set_eth_routes(
+        &self,
+        routes: &[NeighbouringChip],
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        self.as_ref().set_eth_routes(routes)
+    }
+
+    fn connection_state(&self) -> ConnectionState {
+        self.as_ref().connection_state()
+    }
+
+    fn reopen(&self) -> Result<(), Box<dyn std::error::Error>> {
+        self.as_ref().reopen()
+    }
+
+    fn acquire(&self) -> Result<(), Box<dyn std::error::Error>> {
+        self.as_ref().acquire()
+    }
+
+    fn release(&self) -> Result<(), Box<dyn std::error::Error>> {
+        self.as_ref().release()
+    }
+
     fn axi_read(&self, addr: u32, data: &mut [u8]) -> Result<(), Box<dyn std::error::Error>> {
         self.as_ref().axi_read(addr, data)
     }
@@ -160,6 +216,26 @@ impl ChipInterface for Arc<dyn ChipInterface + Send + Sync> {
     }
 }
 
+/// Holds a device acquired with [`ChipInterface::acquire`] and releases it when dropped.
+pub struct ExclusiveAccess<'a> {
+    interface: &'a dyn ChipInterface,
+}
+
+impl<'a> ExclusiveAccess<'a> {
+    pub fn acquire(interface: &'a dyn ChipInterface) -> Result<Self, Box<dyn std::error::Error>> {
+        interface.acquire()?;
+        Ok(Self { interface })
+    }
+}
+
+impl Drop for ExclusiveAccess<'_> {
+    fn drop(&mut self) {
+        if let Err(err) = self.interface.release() {
+            tracing::warn!("Failed to release the device: {err}");
+        }
+    }
+}
+
 pub struct NocInterface {
     pub noc_id: u8,
     pub x: u8,
@@ -173,6 +249,29 @@ impl ChipInterface for NocInterface {
         self.backing.get_device_info()
     }
 
+    fn set_eth_routes(
+        &self,
+        routes: &[NeighbouringChip],
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        self.backing.set_eth_routes(routes)
+    }
+
+    fn connection_state(&self) -> ConnectionState {
+        self.backing.connection_state()
+    }
+
+    fn reopen(&self) -> Result<(), Box<dyn std::error::Error>> {
+        self.backing.reopen()
+    }
+
+    fn acquire(&self) -> Result<(), Box<dyn std::error::Error>> {
+        self.backing.acquire()
+    }
+
+    fn release(&self) -> Result<(), Box<dyn std::error::Error>> {
+        self.backing.release()
+    }
+
     fn axi_read(&self, addr: u32, data: &mut [u8]) -> Result<(), Box<dyn std::error::Error>> {
         self.backing
             .noc_read(self.noc_id, self.x, self.y, addr as u64, data)
@@ -0,0 +1,891 @@
+// SPDX-FileCopyrightText: © 2024 Tenstorrent Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Tools for exercising error handling without having to hang real hardware.
+//!
+//! [`FaultInjector`] wraps any [`ChipInterface`] and corrupts, delays or fails the accesses
+//! matched by its [`FaultRule`]s. [`MemoryInterface`] is a trivial backing store which can be
+//! placed underneath it when no hardware is available.
+
+use std::{collections::HashMap, ops::Range, sync::Mutex, time::Duration};
+
+use thiserror::Error;
+
+use crate::{chip::NeighbouringChip, ConnectionState, DeviceInfo, EthAddr};
+
+use super::chip_interface::ChipInterface;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AccessKind {
+    /// An access through the axi (pci bar) interface.
+    Axi,
+    /// A noc access on the local chip.
+    Noc,
+    /// A noc access routed over ethernet.
+    Eth,
+}
+
+/// A single access passing through the [`FaultInjector`].
+#[derive(Clone, Copy, Debug)]
+pub struct Access {
+    pub kind: AccessKind,
+    pub write: bool,
+    pub eth_addr: Option<EthAddr>,
+    pub core: Option<(u8, u8)>,
+    pub addr: u64,
+    pub len: usize,
+}
+
+#[derive(Clone, Debug)]
+pub enum Fault {
+    /// Reads of the matched addresses return this word, writes are dropped.
+    /// Use 0xffffffff to emulate an endpoint that is no longer accessible.
+    Value(u32),
+    /// Xor the data read from or written to the matched addresses with this word.
+    FlipBits(u32),
+    /// Wait before performing the access.
+    Delay(Duration),
+    /// Wait and then fail the access without performing it.
+    Timeout(Duration),
+    /// Fail the access without performing it.
+    Error(String),
+}
+
+#[derive(Error, Debug)]
+pub enum InjectedFault {
+    #[error("{message} (injected on {access:?})")]
+    Error { message: String, access: Access },
+
+    #[error("Timeout after {timeout:?} (injected on {access:?})")]
+    Timeout { timeout: Duration, access: Access },
+}
+
+/// Describes which accesses a [`Fault`] should be applied to.
+///
+/// By default a rule matches every access, use the builder functions to narrow it down.
+#[derive(Clone, Debug)]
+pub struct FaultRule {
+    pub fault: Fault,
+    /// Only match this kind of access, or all kinds if None.
+    pub kind: Option<AccessKind>,
+    pub reads: bool,
+    pub writes: bool,
+    /// Match accesses which overlap this address range.
+    pub addrs: Range<u64>,
+    pub core: Option<(u8, u8)>,
+    pub eth_addr: Option<EthAddr>,
+    /// Let this many matching accesses through before the fault is applied.
+    pub skip: usize,
+    /// Stop applying the fault after it has been applied this many times.
+    pub count: Option<usize>,
+}
+
+impl FaultRule {
+    pub fn new(fault: Fault) -> Self {
+        FaultRule {
+            fault,
+            kind: None,
+            reads: true,
+            writes: true,
+            addrs: 0..u64::MAX,
+            core: None,
+            eth_addr: None,
+            skip: 0,
+            count: None,
+        }
+    }
+
+    pub fn kind(mut self, kind: AccessKind) -> Self {
+        self.kind = Some(kind);
+        self
+    }
+
+    pub fn reads_only(mut self) -> Self {
+        self.reads = true;
+        self.writes = false;
+        self
+    }
+
+    pub fn writes_only(mut self) -> Self {
+        self.reads = false;
+        self.writes = true;
+        self
+    }
+
+    pub fn addrs(mut self, addrs: Range<u64>) -> Self {
+        self.addrs = addrs;
+        self
+    }
+
+    /// Match the 32 bit word at `addr`.
+    pub fn addr(self, addr: u64) -> Self {
+        self.addrs(addr..addr + 4)
+    }
+
+    pub fn core(mut self, x: u8, y: u8) -> Self {
+        self.core = Some((x, y));
+        self
+    }
+
+    pub fn eth_addr(mut self, eth_addr: EthAddr) -> Self {
+        self.eth_addr = Some(eth_addr);
+        self
+    }
+
+    /// Only start failing after `accesses` matching accesses have succeeded.
+    pub fn after(mut self, accesses: usize) -> Self {
+        self.skip = accesses;
+        self
+    }
+
+    pub fn times(mut self, count: usize) -> Self {
+        self.count = Some(count);
+        self
+    }
+
+    fn matches(&self, access: &Access) -> bool {
+        let overlaps =
+            access.addr < self.addrs.end && self.addrs.start < access.addr + access.len as u64;
+
+        overlaps
+            && (self.kind.is_none() || self.kind == Some(access.kind))
+            && if access.write {
+                self.writes
+            } else {
+                self.reads
+            }
+            && (self.core.is_none() || self.core == access.core)
+            && (self.eth_addr.is_none() || self.eth_addr == access.eth_addr)
+    }
+
+    /// Apply a value fault to the bytes of `data` which fall inside of the matched range.
+    fn patch(&self, addr: u64, data: &mut [u8]) {
+        for (offset, byte) in data.iter_mut().enumerate() {
+            let addr = addr + offset as u64;
+            if !self.addrs.contains(&addr) {
+                continue;
+            }
+
+            let lane = (addr % 4) as usize;
+            match self.fault {
+                Fault::Value(value) => *byte = value.to_le_bytes()[lane],
+                Fault::FlipBits(mask) => *byte ^= mask.to_le_bytes()[lane],
+                _ => {}
+            }
+        }
+    }
+}
+
+struct RuleState {
+    rule: FaultRule,
+    matched: usize,
+    fired: usize,
+}
+
+/// A [`ChipInterface`] which injects faults into the accesses made to the wrapped interface.
+///
+/// Rules can be added and removed while the interface is in use, get a handle to the injector
+/// from a chip with `Wormhole::get_if::<FaultInjector>()`.
+pub struct FaultInjector {
+    pub backing: Box<dyn ChipInterface + Send + Sync>,
+    rules: Mutex<Vec<RuleState>>,
+}
+
+impl FaultInjector {
+    pub fn new(backing: impl ChipInterface + Send + Sync) -> Self {
+        FaultInjector {
+            backing: Box::new(backing),
+            rules: Mutex::new(Vec::new()),
+        }
+    }
+
+    pub fn with_rule(self, rule: FaultRule) -> Self {
+        self.add_rule(rule);
+        self
+    }
+
+    /// Returns the index of the rule, for use with [`FaultInjector::fired`].
+    pub fn add_rule(&self, rule: FaultRule) -> usize {
+        let mut rules = self.rules.lock().unwrap();
+        rules.push(RuleState {
+            rule,
+            matched: 0,
+            fired: 0,
+        });
+        rules.len() - 1
+    }
+
+    pub fn clear_rules(&self) {
+        self.rules.lock().unwrap().clear();
+    }
+
+    /// Number of times the rule at `index` has been applied.
+    pub fn fired(&self, index: usize) -> usize {
+        self.rules
+            .lock()
+            .unwrap()
+            .get(index)
+            .map_or(0, |state| state.fired)
+    }
+
+    fn active_rules(&self, access: &Access) -> Vec<FaultRule> {
+        let mut rules = self.rules.lock().unwrap();
+
+        let mut active = Vec::new();
+        for state in rules.iter_mut() {
+            if !state.rule.matches(access) {
+                continue;
+            }
+
+            state.matched += 1;
+            if state.matched <= state.rule.skip
+                || state.rule.count.is_some_and(|count| state.fired >= count)
+            {
+                continue;
+            }
+
+            state.fired += 1;
+            active.push(state.rule.clone());
+        }
+
+        active
+    }
+
+    /// Apply the faults which happen before the access, returns false if a write should be dropped.
+    fn before(&self, access: &Access, rules: &[FaultRule]) -> Result<bool, InjectedFault> {
+        let mut perform = true;
+        for rule in rules {
+            match &rule.fault {
+                Fault::Delay(delay) => std::thread::sleep(*delay),
+                Fault::Timeout(timeout) => {
+                    std::thread::sleep(*timeout);
+                    return Err(InjectedFault::Timeout {
+                        timeout: *timeout,
+                        access: *access,
+                    });
+                }
+                Fault::Error(message) => {
+                    return Err(InjectedFault::Error {
+                        message: message.clone(),
+                        access: *access,
+                    })
+                }
+                Fault::Value(_) => perform = !access.write,
+                Fault::FlipBits(_) => {}
+            }
+        }
+
+        Ok(perform)
+    }
+
+    fn read(
+        &self,
+        access: Access,
+        data: &mut [u8],
+        read: impl FnOnce(&mut [u8]) -> Result<(), Box<dyn std::error::Error>>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let rules = self.active_rules(&access);
+        self.before(&access, &rules)?;
+
+        read(data)?;
+
+        for rule in rules.iter() {
+            rule.patch(access.addr, data);
+        }
+
+        Ok(())
+    }
+
+    fn write(
+        &self,
+        access: Access,
+        data: &[u8],
+        write: impl FnOnce(&[u8]) -> Result<(), Box<dyn std::error::Error>>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let rules = self.active_rules(&access);
+        if !self.before(&access, &rules)? {
+            return Ok(());
+        }
+
+        if rules
+            .iter()
+            .any(|rule| matches!(rule.fault, Fault::FlipBits(_)))
+        {
+            let mut data = data.to_vec();
+            for rule in rules.iter() {
+                rule.patch(access.addr, &mut data);
+            }
+            write(&data)
+        } else {
+            write(data)
+        }
+    }
+}
+
+impl ChipInterface for FaultInjector {
+    fn get_device_info(&self) -> Result<Option<DeviceInfo>, Box<dyn std::error::Error>> {
+        self.backing.get_device_info()
+    }
+
+    fn set_eth_routes(
+        &self,
+        routes: &[NeighbouringChip],
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        self.backing.set_eth_routes(routes)
+    }
+
+    fn connection_state(&self) -> ConnectionState {
+        self.backing.connection_state()
+    }
+
+    fn reopen(&self) -> Result<(), Box<dyn std::error::Error>> {
+        self.backing.reopen()
+    }
+
+    fn acquire(&self) -> Result<(), Box<dyn std::error::Error>> {
+        self.backing.acquire()
+    }
+
+    fn release(&self) -> Result<(), Box<dyn std::error::Error>> {
+        self.backing.release()
+    }
+
+    fn axi_read(&self, addr: u32, data: &mut [u8]) -> Result<(), Box<dyn std::error::Error>> {
+        let access = Access {
+            kind: AccessKind::Axi,
+            write: false,
+            eth_addr: None,
+            core: None,
+            addr: addr as u64,
+            len: data.len(),
+        };
+        self.read(access, data, |data| self.backing.axi_read(addr, data))
+    }
+
+    fn axi_write(&self, addr: u32, data: &[u8]) -> Result<(), Box<dyn std::error::Error>> {
+        let access = Access {
+            kind: AccessKind::Axi,
+            write: true,
+            eth_addr: None,
+            core: None,
+            addr: addr as u64,
+            len: data.len(),
+        };
+        self.write(access, data, |data| self.backing.axi_write(addr, data))
+    }
+
+    fn noc_read(
+        &self,
+        noc_id: u8,
+        x: u8,
+        y: u8,
+        addr: u64,
+        data: &mut [u8],
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let access = Access {
+            kind: AccessKind::Noc,
+            write: false,
+            eth_addr: None,
+            core: Some((x, y)),
+            addr,
+            len: data.len(),
+        };
+        self.read(access, data, |data| {
+            self.backing.noc_read(noc_id, x, y, addr, data)
+        })
+    }
+
+    fn noc_write(
+        &self,
+        noc_id: u8,
+        x: u8,
+        y: u8,
+        addr: u64,
+        data: &[u8],
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let access = Access {
+            kind: AccessKind::Noc,
+            write: true,
+            eth_addr: None,
+            core: Some((x, y)),
+            addr,
+            len: data.len(),
+        };
+        self.write(access, data, |data| {
+            self.backing.noc_write(noc_id, x, y, addr, data)
+        })
+    }
+
+    fn noc_broadcast(
+        &self,
+        noc_id: u8,
+        addr: u64,
+        data: &[u8],
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let access = Access {
+            kind: AccessKind::Noc,
+            write: true,
+            eth_addr: None,
+            core: None,
+            addr,
+            len: data.len(),
+        };
+        self.write(access, data, |data| {
+            self.backing.noc_broadcast(noc_id, addr, data)
+        })
+    }
+
+    fn eth_noc_read(
+        &self,
+        eth_addr: EthAddr,
+        noc_id: u8,
+        x: u8,
+        y: u8,
+        addr: u64,
+        data: &mut [u8],
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let access = Access {
+            kind: AccessKind::Eth,
+            write: false,
+            eth_addr: Some(eth_addr),
+            core: Some((x, y)),
+            addr,
+            len: data.len(),
+        };
+        self.read(access, data, |data| {
+            self.backing
+                .eth_noc_read(eth_addr, noc_id, x, y, addr, data)
+        })
+    }
+
+    fn eth_noc_write(
+        &self,
+        eth_addr: EthAddr,
+        noc_id: u8,
+        x: u8,
+        y: u8,
+        addr: u64,
+        data: &[u8],
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let access = Access {
+            kind: AccessKind::Eth,
+            write: true,
+            eth_addr: Some(eth_addr),
+            core: Some((x, y)),
+            addr,
+            len: data.len(),
+        };
+        self.write(access, data, |data| {
+            self.backing
+                .eth_noc_write(eth_addr, noc_id, x, y, addr, data)
+        })
+    }
+
+    fn eth_noc_broadcast(
+        &self,
+        eth_addr: EthAddr,
+        noc_id: u8,
+        addr: u64,
+        data: &[u8],
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let access = Access {
+            kind: AccessKind::Eth,
+            write: true,
+            eth_addr: Some(eth_addr),
+            core: None,
+            addr,
+            len: data.len(),
+        };
+        self.write(access, data, |data| {
+            self.backing.eth_noc_broadcast(eth_addr, noc_id, addr, data)
+        })
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+}
+
+/// Which memory an address in the [`MemoryInterface`] belongs to.
+type MemoryKey = (Option<EthAddr>, Option<(u8, u8)>, u64);
+
+/// A [`ChipInterface`] backed by plain memory, unwritten addresses read back as 0.
+///
+/// Every noc endpoint (local or remote) gets its own address space, broadcasts are ignored.
+#[derive(Default)]
+pub struct MemoryInterface {
+    pub device_info: Option<DeviceInfo>,
+    memory: Mutex<HashMap<MemoryKey, u8>>,
+}
+
+impl MemoryInterface {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn read(&self, eth_addr: Option<EthAddr>, core: Option<(u8, u8)>, addr: u64, data: &mut [u8]) {
+        let memory = self.memory.lock().unwrap();
+        for (offset, byte) in data.iter_mut().enumerate() {
+            *byte = memory
+                .get(&(eth_addr, core, addr + offset as u64))
+                .copied()
+                .unwrap_or(0);
+        }
+    }
+
+    fn write(&self, eth_addr: Option<EthAddr>, core: Option<(u8, u8)>, addr: u64, data: &[u8]) {
+        let mut memory = self.memory.lock().unwrap();
+        for (offset, byte) in data.iter().enumerate() {
+            memory.insert((eth_addr, core, addr + offset as u64), *byte);
+        }
+    }
+}
+
+impl ChipInterface for MemoryInterface {
+    fn get_device_info(&self) -> Result<Option<DeviceInfo>, Box<dyn std::error::Error>> {
+        Ok(self.device_info.clone())
+    }
+
+    fn axi_read(&self, addr: u32, data: &mut [u8]) -> Result<(), Box<dyn std::error::Error>> {
+        self.read(None, None, addr as u64, data);
+        Ok(())
+    }
+
+    fn axi_write(&self, addr: u32, data: &[u8]) -> Result<(), Box<dyn std::error::Error>> {
+        self.write(None, None, addr as u64, data);
+        Ok(())
+    }
+
+    fn noc_read(
+        &self,
+        _noc_id: u8,
+        x: u8,
+        y: u8,
+        addr: u64,
+        data: &mut [u8],
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        self.read(None, Some((x, y)), addr, data);
+        Ok(())
+    }
+
+    fn noc_write(
+        &self,
+        _noc_id: u8,
+        x: u8,
+        y: u8,
+        addr: u64,
+        data: &[u8],
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        self.write(None, Some((x, y)), addr, data);
+        Ok(())
+    }
+
+    fn noc_broadcast(
+        &self,
+        _noc_id: u8,
+        _addr: u64,
+        _data: &[u8],
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        Ok(())
+    }
+
+    fn eth_noc_read(
+        &self,
+        eth_addr: EthAddr,
+        _noc_id: u8,
+        x: u8,
+        y: u8,
+        addr: u64,
+        data: &mut [u8],
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        self.read(Some(eth_addr), Some((x, y)), addr, data);
+        Ok(())
+    }
+
+    fn eth_noc_write(
+        &self,
+        eth_addr: EthAddr,
+        _noc_id: u8,
+        x: u8,
+        y: u8,
+        addr: u64,
+        data: &[u8],
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        self.write(Some(eth_addr), Some((x, y)), addr, data);
+        Ok(())
+    }
+
+    fn eth_noc_broadcast(
+        &self,
+        _eth_addr: EthAddr,
+        _noc_id: u8,
+        _addr: u64,
+        _data: &[u8],
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        Ok(())
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod test {
+    use crate::{
+        chip::{
+            communication::chip_comms::load_axi_table,
+            init::status::{ArcInitError, WaitStatus},
+            ArcIf, ChipComms, HlComms, HlCommsInterface, InitStatus, Wormhole,
+        },
+        error::{ArcReadyError, Operation, OperationKind, PlatformError},
+        ChipDetectOptions, ChipImpl,
+    };
+
+    use std::time::Instant;
+
+    use super::*;
+
+    const POST_CODE_INIT_DONE: u32 = 0xC0DE0001;
+
+    fn read32(chip_if: &dyn ChipInterface, addr: u32) -> u32 {
+        let mut value = [0; 4];
+        chip_if.axi_read(addr, &mut value).unwrap();
+        u32::from_le_bytes(value)
+    }
+
+    fn axi_addr(path: &str) -> u64 {
+        ArcIf {
+            axi_data: load_axi_table("wormhole-axi-pci.bin", 0),
+        }
+        .axi_translate(path)
+        .unwrap()
+        .addr
+    }
+
+    /// A wormhole whose ARC claims to have booted, but with no firmware behind it. Shared by the
+    /// tests of the other chip modules.
+    pub(crate) fn wormhole(rules: Vec<FaultRule>) -> Wormhole {
+        let memory = MemoryInterface::new();
+        memory
+            .axi_write(
+                axi_addr("ARC_RESET.POST_CODE") as u32,
+                &POST_CODE_INIT_DONE.to_le_bytes(),
+            )
+            .unwrap();
+
+        let mut injector = FaultInjector::new(memory);
+        for rule in rules {
+            injector = injector.with_rule(rule);
+        }
+
+        Wormhole::init(
+            false,
+            true,
+            ArcIf {
+                axi_data: load_axi_table("wormhole-axi-pci.bin", 0),
+            },
+            injector,
+        )
+        .unwrap()
+    }
+
+    fn arc_error(status: &InitStatus) -> Option<ArcReadyError> {
+        match &status.arc_status.wait_status[0] {
+            WaitStatus::Error(ArcInitError::WaitingForInit(reason)) => Some(reason.clone()),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn fault_rules() {
+        let injector = FaultInjector::new(MemoryInterface::new());
+        injector.axi_write(0x100, &[0x11; 8]).unwrap();
+
+        // Only the second word of the read is overwritten
+        let value = injector.add_rule(FaultRule::new(Fault::Value(0xffffffff)).addr(0x104));
+        let mut data = [0; 8];
+        injector.axi_read(0x100, &mut data).unwrap();
+        assert_eq!(data, [0x11, 0x11, 0x11, 0x11, 0xff, 0xff, 0xff, 0xff]);
+
+        // Writes to a dead address are dropped
+        injector.axi_write(0x104, &[0x22; 4]).unwrap();
+        assert_eq!(injector.fired(value), 2);
+        injector.clear_rules();
+        assert_eq!(read32(&injector, 0x104), 0x11111111);
+
+        // Fail after N accesses, a limited number of times
+        let error = injector.add_rule(
+            FaultRule::new(Fault::Error("injected".to_string()))
+                .kind(AccessKind::Axi)
+                .reads_only()
+                .after(2)
+                .times(1),
+        );
+        assert!(injector.axi_read(0x100, &mut data).is_ok());
+        assert!(injector.axi_read(0x100, &mut data).is_ok());
+        assert!(injector.axi_read(0x100, &mut data).is_err());
+        assert!(injector.axi_read(0x100, &mut data).is_ok());
+        assert!(injector.axi_write(0x100, &data).is_ok());
+        assert_eq!(injector.fired(error), 1);
+
+        // Noc rules only hit the selected core
+        injector.clear_rules();
+        injector.add_rule(FaultRule::new(Fault::FlipBits(0x1)).core(1, 0));
+        injector.noc_write(0, 1, 0, 0x0, &[0; 4]).unwrap();
+        injector.noc_write(0, 2, 0, 0x0, &[0; 4]).unwrap();
+        injector.clear_rules();
+        let mut core_1 = [0; 4];
+        let mut core_2 = [0; 4];
+        injector.noc_read(0, 1, 0, 0x0, &mut core_1).unwrap();
+        injector.noc_read(0, 2, 0, 0x0, &mut core_2).unwrap();
+        assert_eq!((core_1, core_2), ([1, 0, 0, 0], [0, 0, 0, 0]));
+    }
+
+    #[test]
+    fn arc_no_access() {
+        let mut chip = wormhole(vec![
+            FaultRule::new(Fault::Value(0xffffffff)).addr(axi_addr("ARC_RESET.POST_CODE"))
+        ]);
+
+        let mut status = InitStatus::new_unknown();
+        chip.update_init_state(&mut status).unwrap();
+
+        assert!(matches!(arc_error(&status), Some(ArcReadyError::NoAccess)));
+        assert!(status.init_complete());
+        assert!(status.has_error());
+    }
+
+    #[test]
+    fn arc_watchdog() {
+        let mut chip = wormhole(vec![
+            FaultRule::new(Fault::Value(0xDEADC0DE)).addr(axi_addr("ARC_RESET.SCRATCH[5]"))
+        ]);
+
+        let mut status = InitStatus::new_unknown();
+        chip.update_init_state(&mut status).unwrap();
+
+        assert!(matches!(
+            arc_error(&status),
+            Some(ArcReadyError::WatchdogTriggered)
+        ));
+    }
+
+    #[test]
+    fn axi_failure_aborts_init() {
+        let mut chip = wormhole(vec![FaultRule::new(Fault::Error(
+            "pci link went down".to_string(),
+        ))
+        .kind(AccessKind::Axi)
+        .after(1)]);
+
+        let mut status = InitStatus::new_unknown();
+        match chip.update_init_state(&mut status).unwrap() {
+            crate::chip::ChipInitResult::ErrorAbort(message, _) => {
+                assert!(message.contains("pci link went down"), "{message}")
+            }
+            _ => panic!("Expected init to be aborted"),
+        }
+    }
+
+    #[test]
+    fn eth_timeout() {
+        let chip = wormhole(vec![FaultRule::new(Fault::Timeout(Duration::from_millis(
+            10,
+        )))
+        .kind(AccessKind::Eth)]);
+
+        let remote = chip
+            .open_remote(EthAddr {
+                shelf_x: 0,
+                shelf_y: 1,
+                rack_x: 0,
+                rack_y: 0,
+            })
+            .unwrap();
+
+        let start = Instant::now();
+        let err = remote.axi_sread32("ARC_RESET.POST_CODE").unwrap_err();
+        assert!(start.elapsed() >= Duration::from_millis(10));
+        assert!(err.to_string().contains("Timeout"), "{err}");
+
+        // Local accesses are unaffected
+        assert_eq!(
+            chip.axi_sread32("ARC_RESET.POST_CODE").unwrap(),
+            POST_CODE_INIT_DONE
+        );
+    }
+
+    #[test]
+    fn detect_reports_hung_arc() {
+        let chip = wormhole(vec![
+            FaultRule::new(Fault::Value(0xffffffff)).addr(axi_addr("ARC_RESET.POST_CODE"))
+        ]);
+        let chip = crate::chip::Chip::from(Box::new(chip) as Box<dyn ChipImpl>);
+
+        let result = crate::detect_chips::<std::convert::Infallible>(
+            vec![chip],
+            &mut |_| Ok(()),
+            ChipDetectOptions::default(),
+        );
+
+        // A chip with a hung ARC fails detection even when continuing on failure
+        match result {
+            Err(crate::chip::InitError::PlatformError(err)) => {
+                let err = err.to_string();
+                assert!(err.contains("Chip initialization failed"), "{err}");
+                assert!(err.contains("ARC Status"), "{err}");
+            }
+            _ => panic!("Expected detection to fail"),
+        }
+    }
+
+    #[test]
+    fn access_errors_record_context() {
+        let chip = wormhole(vec![
+            FaultRule::new(Fault::Error("link down".to_string())).kind(AccessKind::Eth)
+        ]);
+
+        let eth_addr = EthAddr {
+            shelf_x: 0,
+            shelf_y: 1,
+            rack_x: 0,
+            rack_y: 0,
+        };
+        let remote = chip.open_remote(eth_addr).unwrap();
+
+        let err = PlatformError::from(remote.noc_read32(0, 1, 2, 0x100).unwrap_err());
+        let identity = err.chip().unwrap();
+        assert_eq!(identity.arch, Some(luwen_core::Arch::Wormhole));
+        assert_eq!(identity.eth_addr, Some(eth_addr));
+        assert_eq!(
+            err.operation(),
+            Some(&Operation::noc(OperationKind::NocRead, 0, 1, 2, 0x100, 4))
+        );
+        assert!(
+            err.to_string()
+                .starts_with("noc read of 4 bytes from noc0 (1, 2) 0x100 on Wormhole chip [0, 0, 0, 1] failed: link down"),
+            "{err}"
+        );
+
+        // The register helpers go through the same path
+        let err = remote.axi_sread32("ARC_RESET.POST_CODE").unwrap_err();
+        assert_eq!(
+            err.operation().map(|operation| operation.kind),
+            Some(OperationKind::AxiRead)
+        );
+
+        // Local chips have no ethernet address, and no board id until telemetry has been read
+        let local = FaultRule::new(Fault::Error("link down".to_string())).kind(AccessKind::Axi);
+        chip.get_if::<FaultInjector>().unwrap().add_rule(local);
+        let err = PlatformError::from(chip.axi_write32(0x1000, 0).unwrap_err());
+        let identity = err.chip().unwrap();
+        assert_eq!(identity.eth_addr, None);
+        assert_eq!(identity.board_id, None);
+        assert!(
+            err.to_string()
+                .starts_with("axi write of 4 bytes to 0x1000 on Wormhole chip failed: link down"),
+            "{err}"
+        );
+    }
+}
@@ -0,0 +1,377 @@
+// SPDX-FileCopyrightText: © 2024 Tenstorrent Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! [`InstrumentedInterface`] wraps any [`ChipInterface`], emitting a `tracing` span for every
+//! access and feeding the [`stats`] counters when they are enabled.
+//!
+//! The spans are at trace level under the `luwen_if::chip::communication::instrumented` target,
+//! they have `duration_us` and `error` fields which are filled in once the access completes.
+
+use std::time::{Duration, Instant};
+
+use once_cell::sync::OnceCell;
+use tracing::{field::Empty, Span};
+
+use crate::{chip::NeighbouringChip, ConnectionState, DeviceInfo, EthAddr};
+
+use super::{
+    chip_interface::ChipInterface,
+    stats::{self, StatsKey, Transport},
+};
+
+/// Run `f` inside `span`, recording how long it took and the error if it failed.
+/// The span must declare `duration_us` and `error` fields.
+pub(crate) fn traced<T, E: std::fmt::Display>(
+    span: Span,
+    f: impl FnOnce() -> Result<T, E>,
+) -> Result<T, E> {
+    if span.is_disabled() {
+        return f();
+    }
+
+    let start = Instant::now();
+    let result = span.in_scope(f);
+    finish(&span, start.elapsed(), &result);
+
+    result
+}
+
+fn finish<T, E: std::fmt::Display>(span: &Span, elapsed: Duration, result: &Result<T, E>) {
+    span.record("duration_us", elapsed.as_micros() as u64);
+    if let Err(err) = result {
+        span.record("error", tracing::field::display(err));
+    }
+}
+
+pub struct InstrumentedInterface {
+    pub backing: Box<dyn ChipInterface + Send + Sync>,
+    interface_id: OnceCell<Option<u32>>,
+}
+
+impl InstrumentedInterface {
+    pub fn new(backing: impl ChipInterface + Send + Sync) -> Self {
+        InstrumentedInterface {
+            backing: Box::new(backing),
+            interface_id: OnceCell::new(),
+        }
+    }
+
+    /// Looked up on first use, the device info doesn't change while the chip is open.
+    fn interface_id(&self) -> Option<u32> {
+        *self.interface_id.get_or_init(|| {
+            self.backing
+                .get_device_info()
+                .ok()
+                .flatten()
+                .map(|info| info.interface_id)
+        })
+    }
+
+    fn access(
+        &self,
+        span: Span,
+        eth_addr: Option<EthAddr>,
+        write: bool,
+        len: usize,
+        f: impl FnOnce() -> Result<(), Box<dyn std::error::Error>>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let collect = stats::is_enabled();
+        if span.is_disabled() && !collect {
+            return f();
+        }
+
+        let start = Instant::now();
+        let result = span.in_scope(f);
+        let elapsed = start.elapsed();
+        finish(&span, elapsed, &result);
+
+        if collect {
+            let key = StatsKey {
+                interface_id: self.interface_id(),
+                eth_addr,
+                transport: if eth_addr.is_some() {
+                    Transport::Ethernet
+                } else {
+                    Transport::Mmio
+                },
+            };
+            stats::record(key, write, len, elapsed, result.is_err());
+        }
+
+        result
+    }
+}
+
+impl ChipInterface for InstrumentedInterface {
+    fn get_device_info(&self) -> Result<Option<DeviceInfo>, Box<dyn std::error::Error>> {
+        self.backing.get_device_info()
+    }
+
+    fn set_eth_routes(
+        &self,
+        routes: &[NeighbouringChip],
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        self.backing.set_eth_routes(routes)
+    }
+
+    fn connection_state(&self) -> ConnectionState {
+        self.backing.connection_state()
+    }
+
+    fn reopen(&self) -> Result<(), Box<dyn std::error::Error>> {
+        self.backing.reopen()
+    }
+
+    fn acquire(&self) -> Result<(), Box<dyn std::error::Error>> {
+        self.backing.acquire()
+    }
+
+    fn release(&self) -> Result<(), Box<dyn std::error::Error>> {
+        self.backing.release()
+    }
+
+    fn axi_read(&self, addr: u32, data: &mut [u8]) -> Result<(), Box<dyn std::error::Error>> {
+        let len = data.len();
+        let span = tracing::trace_span!(
+            "axi_read",
+            interface = self.interface_id(),
+            addr,
+            len,
+            duration_us = Empty,
+            error = Empty
+        );
+        self.access(span, None, false, len, || self.backing.axi_read(addr, data))
+    }
+
+    fn axi_write(&self, addr: u32, data: &[u8]) -> Result<(), Box<dyn std::error::Error>> {
+        let len = data.len();
+        let span = tracing::trace_span!(
+            "axi_write",
+            interface = self.interface_id(),
+            addr,
+            len,
+            duration_us = Empty,
+            error = Empty
+        );
+        self.access(span, None, true, len, || self.backing.axi_write(addr, data))
+    }
+
+    fn noc_read(
+        &self,
+        noc_id: u8,
+        x: u8,
+        y: u8,
+        addr: u64,
+        data: &mut [u8],
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let len = data.len();
+        let span = tracing::trace_span!(
+            "noc_read",
+            interface = self.interface_id(),
+            noc_id,
+            x,
+            y,
+            addr,
+            len,
+            duration_us = Empty,
+            error = Empty
+        );
+        self.access(span, None, false, len, || {
+            self.backing.noc_read(noc_id, x, y, addr, data)
+        })
+    }
+
+    fn noc_write(
+        &self,
+        noc_id: u8,
+        x: u8,
+        y: u8,
+        addr: u64,
+        data: &[u8],
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let len = data.len();
+        let span = tracing::trace_span!(
+            "noc_write",
+            interface = self.interface_id(),
+            noc_id,
+            x,
+            y,
+            addr,
+            len,
+            duration_us = Empty,
+            error = Empty
+        );
+        self.access(span, None, true, len, || {
+            self.backing.noc_write(noc_id, x, y, addr, data)
+        })
+    }
+
+    fn noc_broadcast(
+        &self,
+        noc_id: u8,
+        addr: u64,
+        data: &[u8],
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let len = data.len();
+        let span = tracing::trace_span!(
+            "noc_broadcast",
+            interface = self.interface_id(),
+            noc_id,
+            addr,
+            len,
+            duration_us = Empty,
+            error = Empty
+        );
+        self.access(span, None, true, len, || {
+            self.backing.noc_broadcast(noc_id, addr, data)
+        })
+    }
+
+    fn eth_noc_read(
+        &self,
+        eth_addr: EthAddr,
+        noc_id: u8,
+        x: u8,
+        y: u8,
+        addr: u64,
+        data: &mut [u8],
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let len = data.len();
+        let span = tracing::trace_span!(
+            "eth_noc_read",
+            interface = self.interface_id(),
+            %eth_addr,
+            noc_id,
+            x,
+            y,
+            addr,
+            len,
+            duration_us = Empty,
+            error = Empty
+        );
+        self.access(span, Some(eth_addr), false, len, || {
+            self.backing
+                .eth_noc_read(eth_addr, noc_id, x, y, addr, data)
+        })
+    }
+
+    fn eth_noc_write(
+        &self,
+        eth_addr: EthAddr,
+        noc_id: u8,
+        x: u8,
+        y: u8,
+        addr: u64,
+        data: &[u8],
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let len = data.len();
+        let span = tracing::trace_span!(
+            "eth_noc_write",
+            interface = self.interface_id(),
+            %eth_addr,
+            noc_id,
+            x,
+            y,
+            addr,
+            len,
+            duration_us = Empty,
+            error = Empty
+        );
+        self.access(span, Some(eth_addr), true, len, || {
+            self.backing
+                .eth_noc_write(eth_addr, noc_id, x, y, addr, data)
+        })
+    }
+
+    fn eth_noc_broadcast(
+        &self,
+        eth_addr: EthAddr,
+        noc_id: u8,
+        addr: u64,
+        data: &[u8],
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let len = data.len();
+        let span = tracing::trace_span!(
+            "eth_noc_broadcast",
+            interface = self.interface_id(),
+            %eth_addr,
+            noc_id,
+            addr,
+            len,
+            duration_us = Empty,
+            error = Empty
+        );
+        self.access(span, Some(eth_addr), true, len, || {
+            self.backing.eth_noc_broadcast(eth_addr, noc_id, addr, data)
+        })
+    }
+
+    /// Forwarded so that callers can still downcast to the wrapped interface.
+    fn as_any(&self) -> &dyn std::any::Any {
+        self.backing.as_any()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::chip::communication::fault_injection::MemoryInterface;
+
+    use super::*;
+
+    #[test]
+    fn access_stats() {
+        // Use an interface id which no other test will, the counters are global
+        let mut memory = MemoryInterface::new();
+        memory.device_info = Some(DeviceInfo {
+            interface_id: 0xfeed,
+            domain: 0,
+            bus: 0,
+            slot: 0,
+            function: 0,
+            vendor: 0,
+            device_id: 0,
+            board_id: 0,
+            bar_size: 0,
+        });
+        let chip_if = InstrumentedInterface::new(memory);
+        let remote = EthAddr {
+            shelf_x: 1,
+            shelf_y: 0,
+            rack_x: 0,
+            rack_y: 0,
+        };
+
+        // Nothing is counted until collection is enabled
+        chip_if.axi_write(0x0, &[0; 4]).unwrap();
+
+        stats::enable();
+        let mut data = [0; 8];
+        chip_if.axi_read(0x0, &mut data).unwrap();
+        chip_if.noc_write(0, 1, 1, 0x100, &data).unwrap();
+        chip_if
+            .eth_noc_read(remote, 0, 1, 1, 0x100, &mut data)
+            .unwrap();
+        chip_if
+            .eth_noc_write(remote, 0, 1, 1, 0x100, &data[..4])
+            .unwrap();
+
+        let snapshot = stats::snapshot();
+        let mmio = &snapshot[&StatsKey {
+            interface_id: Some(0xfeed),
+            eth_addr: None,
+            transport: Transport::Mmio,
+        }];
+        assert_eq!((mmio.reads, mmio.writes), (1, 1));
+        assert_eq!((mmio.read_bytes, mmio.write_bytes), (8, 8));
+        assert_eq!(mmio.latency.count(), 2);
+
+        let eth = &snapshot[&StatsKey {
+            interface_id: Some(0xfeed),
+            eth_addr: Some(remote),
+            transport: Transport::Ethernet,
+        }];
+        assert_eq!((eth.reads, eth.writes), (1, 1));
+        assert_eq!((eth.read_bytes, eth.write_bytes), (8, 4));
+        assert_eq!(eth.errors, 0);
+    }
+}
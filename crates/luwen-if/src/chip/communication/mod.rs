@@ -3,3 +3,6 @@
 
 pub mod chip_comms;
 pub mod chip_interface;
+pub mod fault_injection;
+pub mod instrumented;
+pub mod stats;
@@ -0,0 +1,192 @@
+// SPDX-FileCopyrightText: © 2024 Tenstorrent Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Opt-in counters for the accesses made to each chip.
+//!
+//! Collection is off by default, call [`enable`] and read the counters back with [`snapshot`].
+//! Only accesses made through an [`InstrumentedInterface`](super::instrumented::InstrumentedInterface)
+//! are counted, the chips opened through [`Chip::open`](crate::chip::Chip::open) use one.
+
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Mutex,
+    },
+    time::Duration,
+};
+
+use once_cell::sync::Lazy;
+
+use crate::EthAddr;
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+static STATS: Lazy<Mutex<HashMap<StatsKey, AccessStats>>> = Lazy::new(Default::default);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Transport {
+    /// Axi and noc accesses made directly over pci.
+    Mmio,
+    /// Noc accesses routed over ethernet.
+    Ethernet,
+}
+
+/// The chip and transport a set of counters belongs to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct StatsKey {
+    /// The pci interface the accesses were made through.
+    pub interface_id: Option<u32>,
+    /// The chip the accesses were routed to, None for accesses to the local chip.
+    pub eth_addr: Option<EthAddr>,
+    pub transport: Transport,
+}
+
+const BUCKETS: usize = 24;
+
+/// Access latencies in power of two buckets, bucket 0 counts accesses which took less than 1us
+/// and bucket n those which took less than 2^n us. The last bucket also counts everything above it.
+#[derive(Clone, Debug, Default)]
+pub struct LatencyHistogram {
+    buckets: [u64; BUCKETS],
+    count: u64,
+    total: Duration,
+    max: Duration,
+}
+
+impl LatencyHistogram {
+    pub fn record(&mut self, latency: Duration) {
+        let micros = latency.as_micros().min(u64::MAX as u128) as u64;
+        let bucket = (u64::BITS - micros.leading_zeros()) as usize;
+
+        self.buckets[bucket.min(BUCKETS - 1)] += 1;
+        self.count += 1;
+        self.total += latency;
+        self.max = self.max.max(latency);
+    }
+
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    pub fn max(&self) -> Duration {
+        self.max
+    }
+
+    pub fn mean(&self) -> Duration {
+        if self.count == 0 {
+            Duration::ZERO
+        } else {
+            Duration::from_nanos((self.total.as_nanos() / self.count as u128) as u64)
+        }
+    }
+
+    /// The upper bound of each bucket along with the number of accesses in it.
+    /// The bound of the last bucket is Duration::MAX.
+    pub fn buckets(&self) -> impl Iterator<Item = (Duration, u64)> + '_ {
+        self.buckets.iter().enumerate().map(|(bucket, count)| {
+            if bucket == BUCKETS - 1 {
+                (Duration::MAX, *count)
+            } else {
+                (Duration::from_micros(1 << bucket), *count)
+            }
+        })
+    }
+
+    /// An upper bound on the latency of the given fraction (0.0 to 1.0) of accesses.
+    /// Returns None if nothing has been recorded.
+    pub fn quantile(&self, quantile: f64) -> Option<Duration> {
+        if self.count == 0 {
+            return None;
+        }
+
+        let target = (quantile.clamp(0.0, 1.0) * self.count as f64)
+            .ceil()
+            .max(1.0) as u64;
+        let mut seen = 0;
+        for (bound, count) in self.buckets() {
+            seen += count;
+            if seen >= target {
+                return Some(bound.min(self.max));
+            }
+        }
+
+        unreachable!("The buckets hold every recorded latency")
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct AccessStats {
+    pub reads: u64,
+    pub writes: u64,
+    pub read_bytes: u64,
+    pub write_bytes: u64,
+    /// Accesses which returned an error, these are also counted as reads or writes.
+    pub errors: u64,
+    pub latency: LatencyHistogram,
+}
+
+/// Start counting accesses.
+pub fn enable() {
+    ENABLED.store(true, Ordering::Relaxed);
+}
+
+/// Stop counting accesses, the counters collected so far are kept.
+pub fn disable() {
+    ENABLED.store(false, Ordering::Relaxed);
+}
+
+pub fn is_enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+/// Clear all counters.
+pub fn reset() {
+    STATS.lock().unwrap().clear();
+}
+
+/// A copy of the current counters.
+pub fn snapshot() -> HashMap<StatsKey, AccessStats> {
+    STATS.lock().unwrap().clone()
+}
+
+pub(crate) fn record(key: StatsKey, write: bool, len: usize, latency: Duration, failed: bool) {
+    let mut stats = STATS.lock().unwrap();
+    let stats = stats.entry(key).or_default();
+
+    if write {
+        stats.writes += 1;
+        stats.write_bytes += len as u64;
+    } else {
+        stats.reads += 1;
+        stats.read_bytes += len as u64;
+    }
+    if failed {
+        stats.errors += 1;
+    }
+    stats.latency.record(latency);
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn latency_histogram() {
+        let mut histogram = LatencyHistogram::default();
+        assert_eq!(histogram.quantile(0.5), None);
+
+        for micros in [0, 1, 3, 3, 100] {
+            histogram.record(Duration::from_micros(micros));
+        }
+        histogram.record(Duration::from_secs(3600));
+
+        let counts: Vec<_> = histogram.buckets().map(|(_, count)| count).collect();
+        assert_eq!(&counts[..8], &[1, 1, 2, 0, 0, 0, 0, 1]);
+        assert_eq!(counts[BUCKETS - 1], 1);
+
+        assert_eq!(histogram.count(), 6);
+        assert_eq!(histogram.quantile(0.5), Some(Duration::from_micros(4)));
+        assert_eq!(histogram.quantile(0.8), Some(Duration::from_micros(128)));
+        assert_eq!(histogram.quantile(1.0), Some(Duration::from_secs(3600)));
+    }
+}
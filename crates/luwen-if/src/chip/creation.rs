@@ -12,8 +12,8 @@ use crate::{
 };
 
 use super::{
-    communication::chip_comms::load_axi_table, ArcIf, Blackhole, Chip, ChipComms, ChipInterface,
-    Grayskull, Wormhole,
+    communication::{chip_comms::load_axi_table, instrumented::InstrumentedInterface},
+    ArcIf, Blackhole, Chip, ChipComms, ChipInterface, Grayskull, Wormhole,
 };
 
 impl Chip {
@@ -30,7 +30,7 @@ impl Chip {
             });
 
             Ok(Grayskull::create(
-                Arc::new(backend),
+                Arc::new(InstrumentedInterface::new(backend)),
                 arc_if.clone(),
                 ArcMsgAddr {
                     scratch_base: arc_if.axi_translate("ARC_RESET.SCRATCH[0]")?.addr,
@@ -62,7 +62,8 @@ impl Chip {
                 false,
                 true,
                 arc_if,
-                Arc::new(backend) as Arc<dyn ChipInterface + Sync + Send>,
+                Arc::new(InstrumentedInterface::new(backend))
+                    as Arc<dyn ChipInterface + Sync + Send>,
             )?)
         } else {
             Err(PlatformError::WrongChipArch {
@@ -94,7 +95,7 @@ impl Chip {
                     noc_id: 0,
                     x: 8,
                     y: 0,
-                    backing: Box::new(backend),
+                    backing: Box::new(InstrumentedInterface::new(backend)),
                 }) as Arc<dyn ChipInterface + Sync + Send>,
             )?)
         } else {
@@ -0,0 +1,574 @@
+// SPDX-FileCopyrightText: © 2024 Tenstorrent Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Exercise the DRAM of a chip by writing patterns to every channel through the noc and
+//! reading them back.
+//!
+//! Transfers are issued in large blocks so that the backend can use the fastest path it has,
+//! for the pci backend that means dma when it is configured and the largest tlb window
+//! otherwise.
+
+use std::fmt;
+
+use luwen_core::Arch;
+
+use super::{init::status::DramChannelStatus, ChipImpl, HlComms};
+
+/// The noc endpoints of a DRAM channel, they all access the same memory.
+#[derive(Clone, Copy, Debug)]
+pub struct DramChannel {
+    pub endpoints: &'static [(u8, u8)],
+    /// Bytes of memory behind the channel.
+    pub size: u64,
+}
+
+const fn channel(endpoints: &'static [(u8, u8)], size: u64) -> DramChannel {
+    DramChannel { endpoints, size }
+}
+
+const GIB: u64 = 1 << 30;
+
+const GRAYSKULL_CHANNELS: [DramChannel; 8] = [
+    channel(&[(1, 0)], GIB),
+    channel(&[(1, 6)], GIB),
+    channel(&[(4, 0)], GIB),
+    channel(&[(4, 6)], GIB),
+    channel(&[(7, 0)], GIB),
+    channel(&[(7, 6)], GIB),
+    channel(&[(10, 0)], GIB),
+    channel(&[(10, 6)], GIB),
+];
+
+const WORMHOLE_CHANNELS: [DramChannel; 6] = [
+    channel(&[(0, 0), (0, 1), (0, 11)], 2 * GIB),
+    channel(&[(0, 5), (0, 6), (0, 7)], 2 * GIB),
+    channel(&[(5, 0), (5, 1), (5, 11)], 2 * GIB),
+    channel(&[(5, 2), (5, 9), (5, 10)], 2 * GIB),
+    channel(&[(5, 3), (5, 4), (5, 8)], 2 * GIB),
+    channel(&[(5, 5), (5, 6), (5, 7)], 2 * GIB),
+];
+
+const BLACKHOLE_CHANNELS: [DramChannel; 8] = [
+    channel(&[(0, 0), (0, 1), (0, 11)], 4 * GIB),
+    channel(&[(0, 2), (0, 10), (0, 3)], 4 * GIB),
+    channel(&[(0, 9), (0, 4), (0, 8)], 4 * GIB),
+    channel(&[(0, 5), (0, 7), (0, 6)], 4 * GIB),
+    channel(&[(9, 0), (9, 1), (9, 11)], 4 * GIB),
+    channel(&[(9, 2), (9, 10), (9, 3)], 4 * GIB),
+    channel(&[(9, 9), (9, 4), (9, 8)], 4 * GIB),
+    channel(&[(9, 5), (9, 7), (9, 6)], 4 * GIB),
+];
+
+/// The DRAM channels of `arch` with their noc0 endpoints.
+pub fn dram_channels(arch: Arch) -> &'static [DramChannel] {
+    match arch {
+        Arch::Grayskull => &GRAYSKULL_CHANNELS,
+        Arch::Wormhole => &WORMHOLE_CHANNELS,
+        Arch::Blackhole => &BLACKHOLE_CHANNELS,
+        Arch::Unknown(_) => &[],
+    }
+}
+
+/// Decode the per channel training results that firmware reports in the `ddr_status` telemetry.
+/// Channels whose status couldn't be decoded are None.
+pub fn dram_training_status(arch: Arch, ddr_status: u32) -> Vec<Option<DramChannelStatus>> {
+    (0..dram_channels(arch).len())
+        .map(|channel| match arch {
+            // Grayskull only reports whether training passed
+            Arch::Grayskull => match (ddr_status >> (channel * 4)) & 0xF {
+                0 => Some(DramChannelStatus::TrainingFail),
+                1 => Some(DramChannelStatus::TrainingPass),
+                _ => None,
+            },
+            Arch::Wormhole => {
+                DramChannelStatus::try_from(((ddr_status >> (channel * 4)) & 0xF) as u8).ok()
+            }
+            // Two bits per channel, training done and training failed
+            Arch::Blackhole => match (ddr_status >> (channel * 2)) & 0x3 {
+                0 => Some(DramChannelStatus::TrainingNone),
+                1 => Some(DramChannelStatus::TrainingPass),
+                _ => Some(DramChannelStatus::TrainingFail),
+            },
+            Arch::Unknown(_) => None,
+        })
+        .collect()
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MemoryPattern {
+    /// A single set bit which moves up by one every word.
+    WalkingOnes,
+    /// Every word holds its own address, catches aliased address lines.
+    AddressInAddress,
+    /// Pseudo random data, the same seed always produces the same data.
+    Random { seed: u64 },
+}
+
+impl MemoryPattern {
+    /// The word this pattern writes at `addr`.
+    pub fn word(&self, addr: u64) -> u32 {
+        match self {
+            MemoryPattern::WalkingOnes => 1 << ((addr / 4) % 32),
+            MemoryPattern::AddressInAddress => (addr ^ (addr >> 32)) as u32,
+            MemoryPattern::Random { seed } => {
+                // splitmix64, so that any word can be regenerated without replaying the sequence
+                let mut z = seed.wrapping_add((addr / 4).wrapping_mul(0x9e3779b97f4a7c15));
+                z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+                z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+                (z ^ (z >> 31)) as u32
+            }
+        }
+    }
+
+    pub(super) fn fill(&self, addr: u64, data: &mut [u8]) {
+        for (index, word) in data.chunks_exact_mut(4).enumerate() {
+            word.copy_from_slice(&self.word(addr + index as u64 * 4).to_le_bytes());
+        }
+    }
+
+    /// The address, expected and read value of every word in `data` (read from `addr`) which
+    /// doesn't match this pattern.
+    pub(super) fn mismatches<'a>(
+        &'a self,
+        addr: u64,
+        data: &'a [u8],
+    ) -> impl Iterator<Item = (u64, u32, u32)> + 'a {
+        data.chunks_exact(4)
+            .enumerate()
+            .filter_map(move |(index, word)| {
+                let word_addr = addr + index as u64 * 4;
+                let expected = self.word(word_addr);
+                let actual = u32::from_le_bytes(word.try_into().unwrap());
+                (expected != actual).then_some((word_addr, expected, actual))
+            })
+    }
+}
+
+impl fmt::Display for MemoryPattern {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MemoryPattern::WalkingOnes => f.write_str("walking ones"),
+            MemoryPattern::AddressInAddress => f.write_str("address in address"),
+            MemoryPattern::Random { seed } => write!(f, "random (seed {seed:#x})"),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct DramTestOptions {
+    /// The channels to test, all of them if empty.
+    pub channels: Vec<usize>,
+    pub patterns: Vec<MemoryPattern>,
+    /// Where in each channel the test starts.
+    pub offset: u64,
+    /// How many bytes of each channel to test, the rest of the channel if None.
+    pub size: Option<u64>,
+    /// Verify every noc endpoint (subchannel) of a channel instead of only the first.
+    pub all_endpoints: bool,
+    /// Bytes moved per noc access.
+    pub transfer_size: usize,
+    /// Failures beyond this many per channel are counted but not recorded.
+    pub max_failures: usize,
+    /// Also test channels which firmware reports did not train.
+    pub untrained: bool,
+    pub noc_id: u8,
+}
+
+impl Default for DramTestOptions {
+    fn default() -> Self {
+        Self {
+            channels: Vec::new(),
+            patterns: vec![
+                MemoryPattern::WalkingOnes,
+                MemoryPattern::AddressInAddress,
+                MemoryPattern::Random { seed: 0 },
+            ],
+            offset: 0,
+            size: None,
+            all_endpoints: false,
+            transfer_size: 1 << 20,
+            max_failures: 64,
+            untrained: false,
+            noc_id: 0,
+        }
+    }
+}
+
+impl DramTestOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn channels(mut self, channels: Vec<usize>) -> Self {
+        self.channels = channels;
+        self
+    }
+
+    pub fn patterns(mut self, patterns: Vec<MemoryPattern>) -> Self {
+        self.patterns = patterns;
+        self
+    }
+
+    pub fn range(mut self, offset: u64, size: Option<u64>) -> Self {
+        self.offset = offset;
+        self.size = size;
+        self
+    }
+
+    pub fn all_endpoints(mut self, all_endpoints: bool) -> Self {
+        self.all_endpoints = all_endpoints;
+        self
+    }
+
+    pub fn transfer_size(mut self, transfer_size: usize) -> Self {
+        self.transfer_size = transfer_size;
+        self
+    }
+
+    pub fn max_failures(mut self, max_failures: usize) -> Self {
+        self.max_failures = max_failures;
+        self
+    }
+
+    pub fn untrained(mut self, untrained: bool) -> Self {
+        self.untrained = untrained;
+        self
+    }
+}
+
+/// A word which did not read back what was written.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DramFailure {
+    pub endpoint: (u8, u8),
+    pub pattern: MemoryPattern,
+    /// Address of the word within the channel.
+    pub addr: u64,
+    pub expected: u32,
+    pub actual: u32,
+}
+
+impl DramFailure {
+    /// Mask of the bits which were wrong.
+    pub fn failing_bits(&self) -> u32 {
+        self.expected ^ self.actual
+    }
+}
+
+impl fmt::Display for DramFailure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:#x} via {}-{} ({}): expected {:#010x}, read {:#010x}, bits {:#010x}",
+            self.addr,
+            self.endpoint.0,
+            self.endpoint.1,
+            self.pattern,
+            self.expected,
+            self.actual,
+            self.failing_bits()
+        )
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct DramChannelReport {
+    pub channel: usize,
+    /// The training result reported by firmware, if it could be read.
+    pub training: Option<DramChannelStatus>,
+    /// Why the channel was not (fully) tested.
+    pub error: Option<String>,
+    /// Bytes verified through each tested endpoint.
+    pub bytes_tested: u64,
+    pub failure_count: u64,
+    /// The first [`DramTestOptions::max_failures`] failures.
+    pub failures: Vec<DramFailure>,
+}
+
+impl DramChannelReport {
+    pub fn passed(&self) -> bool {
+        self.error.is_none() && self.failure_count == 0
+    }
+
+    /// Mask of every bit which failed in the recorded failures.
+    pub fn failing_bits(&self) -> u32 {
+        self.failures
+            .iter()
+            .fold(0, |bits, failure| bits | failure.failing_bits())
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct DramTestReport {
+    pub channels: Vec<DramChannelReport>,
+}
+
+impl DramTestReport {
+    pub fn passed(&self) -> bool {
+        self.channels.iter().all(DramChannelReport::passed)
+    }
+
+    pub fn failing_channels(&self) -> Vec<usize> {
+        self.channels
+            .iter()
+            .filter(|channel| !channel.passed())
+            .map(|channel| channel.channel)
+            .collect()
+    }
+}
+
+impl fmt::Display for DramTestReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for channel in &self.channels {
+            let training = channel
+                .training
+                .map(|status| status.to_string())
+                .unwrap_or_else(|| "unknown training".to_string());
+            write!(f, "Channel {} ({training}): ", channel.channel)?;
+            if let Some(error) = &channel.error {
+                writeln!(f, "{error}")?;
+            } else if channel.failure_count == 0 {
+                writeln!(f, "passed {} bytes", channel.bytes_tested)?;
+            } else {
+                writeln!(
+                    f,
+                    "{} failures, failing bits {:#010x}",
+                    channel.failure_count,
+                    channel.failing_bits()
+                )?;
+            }
+            for failure in &channel.failures {
+                writeln!(f, "    {failure}")?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Reported after each transfer, `done` out of `total` bytes have been written and verified
+/// for the current channel.
+#[derive(Clone, Copy, Debug)]
+pub struct DramTestProgress {
+    pub channel: usize,
+    pub endpoint: (u8, u8),
+    pub pattern: MemoryPattern,
+    pub done: u64,
+    pub total: u64,
+}
+
+/// Write and verify every pattern on the selected DRAM channels of `chip`.
+///
+/// A channel which can't be accessed is reported as failed and the test moves on to the next
+/// one, the chip should not be used for anything else while the test runs since its DRAM is
+/// overwritten.
+pub fn test_dram(
+    chip: &(impl ChipImpl + ?Sized),
+    options: &DramTestOptions,
+    mut progress: impl FnMut(DramTestProgress),
+) -> DramTestReport {
+    let arch = chip.get_arch();
+    let channels = dram_channels(arch);
+    let training = chip
+        .get_telemetry()
+        .map(|telemetry| dram_training_status(arch, telemetry.ddr_status))
+        .unwrap_or_default();
+
+    let selected = if options.channels.is_empty() {
+        (0..channels.len()).collect()
+    } else {
+        options.channels.clone()
+    };
+
+    let mut report = DramTestReport::default();
+    for index in selected {
+        let mut channel_report = DramChannelReport {
+            channel: index,
+            training: training.get(index).copied().flatten(),
+            error: None,
+            bytes_tested: 0,
+            failure_count: 0,
+            failures: Vec::new(),
+        };
+
+        let Some(channel) = channels.get(index) else {
+            channel_report.error = Some(format!("{arch} only has {} channels", channels.len()));
+            report.channels.push(channel_report);
+            continue;
+        };
+
+        if let Some(status) = channel_report.training {
+            if !matches!(status, DramChannelStatus::TrainingPass) && !options.untrained {
+                channel_report.error =
+                    Some(format!("Not tested, the channel {}", status.to_string()));
+                report.channels.push(channel_report);
+                continue;
+            }
+        }
+
+        if let Err(err) = test_channel(
+            chip,
+            index,
+            channel,
+            options,
+            &mut channel_report,
+            &mut progress,
+        ) {
+            channel_report.error = Some(err.to_string());
+        }
+        report.channels.push(channel_report);
+    }
+
+    report
+}
+
+fn test_channel(
+    chip: &(impl HlComms + ?Sized),
+    index: usize,
+    channel: &DramChannel,
+    options: &DramTestOptions,
+    report: &mut DramChannelReport,
+    progress: &mut impl FnMut(DramTestProgress),
+) -> Result<(), Box<dyn std::error::Error>> {
+    let start = options.offset.min(channel.size) & !3;
+    let end = options
+        .size
+        .map(|size| start.saturating_add(size))
+        .unwrap_or(channel.size)
+        .min(channel.size)
+        & !3;
+    let transfer_size = options.transfer_size.max(4) & !3;
+
+    let endpoints = if options.all_endpoints {
+        channel.endpoints
+    } else {
+        &channel.endpoints[..1]
+    };
+
+    let mut written = vec![0; transfer_size];
+    let mut read = vec![0; transfer_size];
+    for &endpoint @ (x, y) in endpoints {
+        for &pattern in &options.patterns {
+            // Everything is written before it is read back so that aliased addresses show up
+            let mut addr = start;
+            while addr < end {
+                let len = ((end - addr) as usize).min(transfer_size);
+                pattern.fill(addr, &mut written[..len]);
+                chip.noc_write(options.noc_id, x, y, addr, &written[..len])?;
+                addr += len as u64;
+            }
+
+            let mut addr = start;
+            while addr < end {
+                let len = ((end - addr) as usize).min(transfer_size);
+                chip.noc_read(options.noc_id, x, y, addr, &mut read[..len])?;
+
+                for (word_addr, expected, actual) in pattern.mismatches(addr, &read[..len]) {
+                    report.failure_count += 1;
+                    if report.failures.len() < options.max_failures {
+                        report.failures.push(DramFailure {
+                            endpoint,
+                            pattern,
+                            addr: word_addr,
+                            expected,
+                            actual,
+                        });
+                    }
+                }
+
+                addr += len as u64;
+                progress(DramTestProgress {
+                    channel: index,
+                    endpoint,
+                    pattern,
+                    done: addr - start,
+                    total: end - start,
+                });
+            }
+        }
+        report.bytes_tested = end - start;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use crate::chip::{
+        communication::fault_injection::{test::wormhole, AccessKind, Fault, FaultRule},
+        Chip,
+    };
+
+    use super::*;
+
+    #[test]
+    fn patterns() {
+        assert_eq!(MemoryPattern::WalkingOnes.word(0), 1);
+        assert_eq!(MemoryPattern::WalkingOnes.word(4 * 31), 1 << 31);
+        assert_eq!(MemoryPattern::WalkingOnes.word(4 * 32), 1);
+        assert_eq!(MemoryPattern::AddressInAddress.word(0x1234), 0x1234);
+
+        let random = MemoryPattern::Random { seed: 7 };
+        assert_eq!(random.word(0x100), random.word(0x100));
+        assert_ne!(random.word(0x100), random.word(0x104));
+        assert_ne!(
+            random.word(0x100),
+            MemoryPattern::Random { seed: 8 }.word(0x100)
+        );
+    }
+
+    #[test]
+    fn training_status() {
+        let status = dram_training_status(Arch::Wormhole, 0x01_2222);
+        assert!(matches!(status[0], Some(DramChannelStatus::TrainingPass)));
+        assert!(matches!(status[4], Some(DramChannelStatus::TrainingFail)));
+        assert!(matches!(status[5], Some(DramChannelStatus::TrainingNone)));
+
+        let status = dram_training_status(Arch::Blackhole, 0b10_01);
+        assert!(matches!(status[0], Some(DramChannelStatus::TrainingPass)));
+        assert!(matches!(status[1], Some(DramChannelStatus::TrainingFail)));
+        assert!(matches!(status[2], Some(DramChannelStatus::TrainingNone)));
+    }
+
+    #[test]
+    fn stuck_bit() {
+        // Channel 1 has bit 3 stuck high at 0x104
+        let wh = wormhole(vec![FaultRule::new(Fault::Value(0x8))
+            .kind(AccessKind::Noc)
+            .reads_only()
+            .core(0, 5)
+            .addr(0x104)]);
+        let chip = Chip::from(Box::new(wh) as Box<dyn ChipImpl>);
+
+        let options = DramTestOptions::new()
+            .channels(vec![0, 1, 6])
+            .patterns(vec![MemoryPattern::AddressInAddress])
+            .range(0x100, Some(0x1000))
+            .transfer_size(0x400);
+        let mut transfers = 0;
+        let report = test_dram(&chip, &options, |_| transfers += 1);
+
+        // There is no firmware to report the training status
+        assert!(report.channels[0].training.is_none());
+        assert!(report.channels[0].passed());
+        assert_eq!(report.channels[0].bytes_tested, 0x1000);
+        assert_eq!(transfers, 8);
+
+        let failing = &report.channels[1];
+        assert_eq!(failing.failure_count, 1);
+        assert_eq!(
+            failing.failures[0],
+            DramFailure {
+                endpoint: (0, 5),
+                pattern: MemoryPattern::AddressInAddress,
+                addr: 0x104,
+                expected: 0x104,
+                actual: 0x8,
+            }
+        );
+        assert_eq!(failing.failing_bits(), 0x10c);
+
+        // Wormhole only has 6 channels
+        assert!(report.channels[2].error.is_some());
+        assert_eq!(report.failing_channels(), vec![1, 6]);
+    }
+}
@@ -3,11 +3,13 @@
 
 use std::fmt::Display;
 
+use serde::{Deserialize, Serialize};
+
 use crate::error::PlatformError;
 
 use super::{ChipComms, ChipInterface};
 
-#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, Serialize, Deserialize)]
 pub struct EthAddr {
     pub shelf_x: u8,
     pub shelf_y: u8,
@@ -0,0 +1,272 @@
+// SPDX-FileCopyrightText: © 2024 Tenstorrent Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Per port ethernet diagnostics, read from the status areas that the erisc firmware keeps in
+//! the L1 of each ethernet core.
+
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use super::{remote::EthAddresses, ChipImpl, HlComms, Wormhole};
+use crate::{error::PlatformError, EthAddr};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum EthLinkState {
+    Unknown,
+    Unconnected,
+    /// The link is up but the firmware won't route over it.
+    NoRouting,
+    Connected,
+}
+
+impl EthLinkState {
+    pub fn from_port_status(status: u32) -> Self {
+        match status {
+            0 => EthLinkState::Unknown,
+            1 => EthLinkState::Unconnected,
+            2 => EthLinkState::NoRouting,
+            _ => EthLinkState::Connected,
+        }
+    }
+
+    pub fn is_up(&self) -> bool {
+        matches!(self, EthLinkState::NoRouting | EthLinkState::Connected)
+    }
+}
+
+impl fmt::Display for EthLinkState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            EthLinkState::Unknown => "unknown",
+            EthLinkState::Unconnected => "unconnected",
+            EthLinkState::NoRouting => "no routing",
+            EthLinkState::Connected => "connected",
+        })
+    }
+}
+
+/// The port on the other end of a link.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EthRemotePort {
+    pub eth_addr: EthAddr,
+    pub noc_addr: (u8, u8),
+    /// The index of the remote ethernet core, if its noc address is a known ethernet core.
+    pub port: Option<usize>,
+    /// 0 for a galaxy, non-zero for an n150/n300.
+    pub board_type: u32,
+}
+
+/// Everything which was read for a port, values which could not be read are None. Disabled
+/// ports are not read at all.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EthPortStatus {
+    pub port: usize,
+    pub noc_addr: (u8, u8),
+    pub enabled: bool,
+    pub link: Option<EthLinkState>,
+    pub fw_version: Option<u32>,
+    pub heartbeat: Option<u32>,
+    /// Whether the heartbeat moved during the interval, only checked if an interval was given.
+    pub heartbeat_alive: Option<bool>,
+    pub remote: Option<EthRemotePort>,
+}
+
+impl EthPortStatus {
+    /// Formats the firmware version as MAJOR.MINOR.PATCH.
+    pub fn fw_version_string(&self) -> Option<String> {
+        self.fw_version.map(|version| {
+            let major = (version >> 16) & 0x0FF;
+            let minor = (version >> 12) & 0x00F;
+            let patch = version & 0xFFF;
+            format!("{major}.{minor}.{patch}")
+        })
+    }
+}
+
+impl Wormhole {
+    fn eth_addresses(&self) -> Result<EthAddresses, PlatformError> {
+        if self.eth_addrs.masked_version == 0 {
+            Ok(EthAddresses::new(self.get_telemetry()?.eth_fw_version))
+        } else {
+            Ok(self.eth_addrs.clone())
+        }
+    }
+
+    /// Decode the eth address and noc location of the chip on the other side of an ethernet core.
+    pub(crate) fn read_remote_eth_id(
+        &self,
+        eth_addrs: &EthAddresses,
+        (eth_x, eth_y): (u8, u8),
+    ) -> Result<(EthAddr, (u8, u8)), PlatformError> {
+        const SHELF_OFFSET: u64 = 9;
+        const RACK_OFFSET: u64 = 10;
+
+        let remote_id =
+            self.noc_read32(0, eth_x, eth_y, eth_addrs.node_info + (4 * RACK_OFFSET))?;
+        let rack_x = remote_id & 0xFF;
+        let rack_y = (remote_id >> 8) & 0xFF;
+
+        let remote_id =
+            self.noc_read32(0, eth_x, eth_y, eth_addrs.node_info + (4 * SHELF_OFFSET))?;
+        let shelf_x = (remote_id >> 16) & 0x3F;
+        let shelf_y = (remote_id >> 22) & 0x3F;
+
+        let noc_x = (remote_id >> 4) & 0x3F;
+        let noc_y = (remote_id >> 10) & 0x3F;
+
+        Ok((
+            EthAddr {
+                shelf_x: shelf_x as u8,
+                shelf_y: shelf_y as u8,
+                rack_x: rack_x as u8,
+                rack_y: rack_y as u8,
+            },
+            (noc_x as u8, noc_y as u8),
+        ))
+    }
+
+    /// Read the link state and firmware health of every ethernet port.
+    ///
+    /// With a `heartbeat_interval` the heartbeats are sampled twice that far apart to check that
+    /// the firmware is still running. A value which can't be read is left as None, so that one
+    /// hung ethernet core doesn't hide the state of the others.
+    pub fn eth_port_status(
+        &self,
+        heartbeat_interval: Option<std::time::Duration>,
+    ) -> Result<Vec<EthPortStatus>, PlatformError> {
+        let addrs = self.eth_addresses()?;
+
+        let mut ports = Vec::with_capacity(self.eth_locations.len());
+        for (port, core) in self.eth_locations.iter().enumerate() {
+            let mut status = EthPortStatus {
+                port,
+                noc_addr: (core.x, core.y),
+                enabled: core.enabled,
+                link: None,
+                fw_version: None,
+                heartbeat: None,
+                heartbeat_alive: None,
+                remote: None,
+            };
+            if !core.enabled {
+                ports.push(status);
+                continue;
+            }
+
+            let read = |addr| self.noc_read32(0, core.x, core.y, addr).ok();
+
+            status.link =
+                read(addrs.eth_conn_info + (port as u64 * 4)).map(EthLinkState::from_port_status);
+            status.fw_version = read(addrs.version);
+            status.heartbeat = read(addrs.heartbeat);
+
+            if matches!(status.link, Some(link) if link.is_up()) {
+                let remote_id = self.read_remote_eth_id(&addrs, (core.x, core.y)).ok();
+                let board_type =
+                    read(addrs.results_buf + (addrs.erisc_remote_board_type_offset * 4));
+                status.remote =
+                    remote_id
+                        .zip(board_type)
+                        .map(|((eth_addr, noc_addr), board_type)| EthRemotePort {
+                            eth_addr,
+                            noc_addr,
+                            port: self
+                                .eth_locations
+                                .iter()
+                                .position(|core| (core.x, core.y) == noc_addr),
+                            board_type,
+                        });
+            }
+
+            ports.push(status);
+        }
+
+        if let Some(interval) = heartbeat_interval {
+            std::thread::sleep(interval);
+            for status in ports.iter_mut() {
+                if let Some(heartbeat) = status.heartbeat {
+                    let (x, y) = status.noc_addr;
+                    status.heartbeat_alive = self
+                        .noc_read32(0, x, y, addrs.heartbeat)
+                        .ok()
+                        .map(|now| now != heartbeat);
+                }
+            }
+        }
+
+        Ok(ports)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::chip::communication::fault_injection::{
+        test::wormhole, AccessKind, Fault, FaultRule,
+    };
+
+    use super::*;
+
+    #[test]
+    fn port_status() {
+        let addrs = EthAddresses::new(0x061100);
+
+        // The version of port 3 can't be read
+        let port3 = wormhole(Vec::new()).eth_locations[3];
+        let mut wh = wormhole(vec![FaultRule::new(Fault::Error("hung".to_string()))
+            .kind(AccessKind::Noc)
+            .reads_only()
+            .core(port3.x, port3.y)
+            .addr(addrs.version)]);
+        wh.eth_addrs = addrs.clone();
+
+        // Port 1 is connected to port 0 of the chip at shelf 0, rack 1-0
+        let (x, y) = (wh.eth_locations[1].x, wh.eth_locations[1].y);
+        let remote = (wh.eth_locations[0].x as u32, wh.eth_locations[0].y as u32);
+        wh.noc_write32(0, x, y, addrs.eth_conn_info + 4, 3).unwrap();
+        wh.noc_write32(0, x, y, addrs.version, 0x06b000).unwrap();
+        wh.noc_write32(0, x, y, addrs.node_info + 40, 1).unwrap();
+        wh.noc_write32(
+            0,
+            x,
+            y,
+            addrs.node_info + 36,
+            remote.0 << 4 | remote.1 << 10,
+        )
+        .unwrap();
+        wh.eth_locations[2].enabled = false;
+
+        let ports = wh.eth_port_status(Some(std::time::Duration::ZERO)).unwrap();
+        assert_eq!(ports.len(), 16);
+
+        assert_eq!(ports[0].link, Some(EthLinkState::Unknown));
+        assert!(ports[0].remote.is_none());
+
+        let port = &ports[1];
+        assert_eq!(port.link, Some(EthLinkState::Connected));
+        assert_eq!(port.fw_version_string().unwrap(), "6.11.0");
+        assert_eq!(port.heartbeat_alive, Some(false));
+        assert_eq!(
+            port.remote,
+            Some(EthRemotePort {
+                eth_addr: EthAddr {
+                    shelf_x: 0,
+                    shelf_y: 0,
+                    rack_x: 1,
+                    rack_y: 0,
+                },
+                noc_addr: (remote.0 as u8, remote.1 as u8),
+                port: Some(0),
+                board_type: 0,
+            })
+        );
+
+        assert!(!ports[2].enabled);
+        assert!(ports[2].fw_version.is_none());
+
+        // Only the value which failed is missing
+        assert!(ports[3].fw_version.is_none());
+        assert_eq!(ports[3].link, Some(EthLinkState::Unknown));
+        assert_eq!(ports[3].heartbeat, Some(0));
+    }
+}
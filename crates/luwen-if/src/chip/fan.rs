@@ -0,0 +1,331 @@
+// SPDX-FileCopyrightText: © 2024 Tenstorrent Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Fan control for boards whose fan is driven by the chip firmware.
+//!
+//! Only blackhole is supported, its firmware follows the curve from the fw_table until a speed
+//! is forced over the message queue. The speed limits and curve checks are enforced here so that
+//! a bad request never reaches the firmware, and an override is handed back to the firmware
+//! when the [`FanOverride`] holding it is dropped.
+//!
+//! Wormhole is not supported, luwen does not decode the SPI parameter table that its fan curve
+//! would have to be read from. Every request on a wormhole (or grayskull) fails with
+//! [`PowerError::Unsupported`] instead of being sent to the firmware.
+//!
+//! There is no watchdog. The firmware has no timeout for a forced speed and dropping is the only
+//! thing which restores it, so a process which exits without unwinding (a default handled
+//! SIGINT, `std::process::exit`, an abort or SIGKILL) leaves the fan forced until the speed is
+//! cleared or the chip is reset. Chips opened through luwen-broker don't have this gap, the
+//! broker hands the fan back to the firmware as soon as the client's connection closes, so a
+//! fan controller which has to survive its own crash should run as a broker client.
+
+use std::time::Duration;
+
+use luwen_core::Arch;
+use serde::{Deserialize, Serialize};
+
+use super::{
+    blackhole::spirom_tables::fw_table::fw_table::FanTable,
+    power::{arc_request, PowerError},
+    Blackhole, ChipImpl, PowerLimits,
+};
+use crate::{error::PlatformError, TypedArcMsg};
+
+/// The lowest fan speed (in percent) which can be forced.
+pub const FAN_SPEED_MIN: u32 = 20;
+
+/// Used as the thermal limit when the firmware doesn't report one.
+const DEFAULT_THM_LIMIT: f64 = 90.0;
+
+/// Forcing this speed hands control back to the firmware curve.
+const FAN_SPEED_AUTO: u32 = 0xFFFF_FFFF;
+
+/// A piecewise linear fan curve, the speed is held at the first and last point outside of the
+/// curve.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct FanCurve {
+    /// (temperature in degrees celsius, fan speed in percent)
+    pub points: Vec<(f64, u32)>,
+}
+
+impl FanCurve {
+    pub fn new(points: Vec<(f64, u32)>) -> Self {
+        Self { points }
+    }
+
+    pub fn from_fw_table(table: &FanTable) -> Self {
+        Self::new(vec![
+            (table.fan_table_point_x1 as f64, table.fan_table_point_y1),
+            (table.fan_table_point_x2 as f64, table.fan_table_point_y2),
+        ])
+    }
+
+    pub fn speed_at(&self, temperature: f64) -> u32 {
+        let (first, last) = match (self.points.first(), self.points.last()) {
+            (Some(first), Some(last)) => (first, last),
+            _ => return 100,
+        };
+        if temperature <= first.0 {
+            return first.1;
+        }
+
+        for window in self.points.windows(2) {
+            let ((t0, s0), (t1, s1)) = (window[0], window[1]);
+            if temperature <= t1 {
+                let speed = s0 as f64 + (s1 as f64 - s0 as f64) * (temperature - t0) / (t1 - t0);
+                return speed.round() as u32;
+            }
+        }
+
+        last.1
+    }
+
+    /// Check that the curve only rises, stays within the forcable speeds and has the fan at
+    /// full speed by `thm_limit`.
+    pub fn validate(&self, thm_limit: f64) -> Result<(), PowerError> {
+        let invalid = |reason: String| Err(PowerError::InvalidFanCurve(reason));
+
+        let Some(&(last_temperature, last_speed)) = self.points.last() else {
+            return invalid("the curve has no points".to_string());
+        };
+        for &(_, speed) in &self.points {
+            if !(FAN_SPEED_MIN..=100).contains(&speed) {
+                return Err(PowerError::FanSpeedOutOfRange {
+                    requested: speed,
+                    min: FAN_SPEED_MIN,
+                });
+            }
+        }
+        for window in self.points.windows(2) {
+            let ((t0, s0), (t1, s1)) = (window[0], window[1]);
+            if t1 <= t0 || s1 < s0 {
+                return invalid(format!(
+                    "({t1}C, {s1}%) does not rise from the previous point ({t0}C, {s0}%)"
+                ));
+            }
+        }
+        if last_speed != 100 || last_temperature > thm_limit {
+            return invalid(format!(
+                "the fan must reach 100% at or below the thermal limit of {thm_limit}C"
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct FanStatus {
+    /// The speed reported by the firmware.
+    pub speed: u32,
+    /// ASIC temperature in degrees celsius.
+    pub asic_temperature: f64,
+    /// The temperature at which a forced speed or curve is handed back to the firmware.
+    pub thm_limit: f64,
+}
+
+/// Fan requests for a single chip.
+pub struct FanControl<'a, C: ChipImpl + ?Sized> {
+    chip: &'a C,
+}
+
+impl<'a, C: ChipImpl + ?Sized> FanControl<'a, C> {
+    pub fn new(chip: &'a C) -> Self {
+        Self { chip }
+    }
+
+    fn check_supported(&self, what: &'static str) -> Result<(), PlatformError> {
+        match self.chip.get_arch() {
+            Arch::Blackhole => Ok(()),
+            // The wormhole fan curve lives in SPI parameters which are not decoded yet
+            arch => Err(PowerError::Unsupported { arch, what }.into()),
+        }
+    }
+
+    pub fn status(&self) -> Result<FanStatus, PlatformError> {
+        let telemetry = self.chip.get_telemetry()?;
+        let arch = self.chip.get_arch();
+        let thm_limit = match PowerLimits::from_telemetry(arch, &telemetry).thm_limit {
+            0 => DEFAULT_THM_LIMIT,
+            limit => limit as f64,
+        };
+
+        Ok(FanStatus {
+            speed: telemetry.fan_speed,
+            asic_temperature: telemetry.asic_temperature_for(arch),
+            thm_limit,
+        })
+    }
+
+    /// The curve the firmware follows when no speed is forced.
+    pub fn curve(&self) -> Result<FanCurve, PlatformError> {
+        self.check_supported("reading the fan curve")?;
+        let blackhole = self
+            .chip
+            .as_any()
+            .downcast_ref::<Blackhole>()
+            .ok_or_else(|| format!("Expected a blackhole, got {}", self.chip.get_arch()))?;
+
+        let table = blackhole.fw_table()?;
+        Ok(FanCurve::from_fw_table(
+            &table.fan_table.unwrap_or_default(),
+        ))
+    }
+
+    /// Run the fan at `speed` percent until [`Self::clear_forced_speed`] is called, prefer
+    /// [`Self::override_speed`] which can't leave the fan forced.
+    pub fn force_speed(&self, speed: u32) -> Result<(), PlatformError> {
+        self.check_supported("forcing the fan speed")?;
+        if !(FAN_SPEED_MIN..=100).contains(&speed) {
+            return Err(PowerError::FanSpeedOutOfRange {
+                requested: speed,
+                min: FAN_SPEED_MIN,
+            }
+            .into());
+        }
+
+        arc_request(
+            self.chip,
+            "force fan speed",
+            TypedArcMsg::ForceFanSpeed { speed },
+        )?;
+        Ok(())
+    }
+
+    /// Hand the fan back to the firmware curve.
+    pub fn clear_forced_speed(&self) -> Result<(), PlatformError> {
+        self.check_supported("forcing the fan speed")?;
+        arc_request(
+            self.chip,
+            "clear forced fan speed",
+            TypedArcMsg::ForceFanSpeed {
+                speed: FAN_SPEED_AUTO,
+            },
+        )?;
+        Ok(())
+    }
+
+    /// Force `speed` until the returned guard is dropped.
+    pub fn override_speed(&self, speed: u32) -> Result<FanOverride<'a, C>, PlatformError> {
+        self.force_speed(speed)?;
+        Ok(FanOverride {
+            control: FanControl { chip: self.chip },
+            speed,
+        })
+    }
+
+    /// Drive the fan from `curve` instead of the firmware curve until `stop` returns true, the
+    /// temperature is sampled every `interval`.
+    ///
+    /// If the ASIC reaches the thermal limit the fan is handed back to the firmware and
+    /// [`PowerError::OverTemperature`] is returned.
+    pub fn run_curve(
+        &self,
+        curve: &FanCurve,
+        interval: Duration,
+        mut stop: impl FnMut() -> bool,
+    ) -> Result<(), PlatformError> {
+        let status = self.status()?;
+        curve.validate(status.thm_limit)?;
+
+        let mut fan = self.override_speed(curve.speed_at(status.asic_temperature))?;
+        while !stop() {
+            std::thread::sleep(interval);
+
+            let status = self.status()?;
+            if status.asic_temperature >= status.thm_limit {
+                drop(fan);
+                return Err(PowerError::OverTemperature {
+                    temperature: status.asic_temperature,
+                    limit: status.thm_limit,
+                }
+                .into());
+            }
+            fan.set_speed(curve.speed_at(status.asic_temperature))?;
+        }
+
+        Ok(())
+    }
+}
+
+/// A forced fan speed, the fan goes back to the firmware curve when this is dropped. This is not
+/// a watchdog, nothing restores the fan if the process exits without dropping this, see the
+/// [module docs](self).
+pub struct FanOverride<'a, C: ChipImpl + ?Sized> {
+    control: FanControl<'a, C>,
+    speed: u32,
+}
+
+impl<C: ChipImpl + ?Sized> FanOverride<'_, C> {
+    pub fn speed(&self) -> u32 {
+        self.speed
+    }
+
+    pub fn set_speed(&mut self, speed: u32) -> Result<(), PlatformError> {
+        if speed != self.speed {
+            self.control.force_speed(speed)?;
+            self.speed = speed;
+        }
+        Ok(())
+    }
+}
+
+impl<C: ChipImpl + ?Sized> Drop for FanOverride<'_, C> {
+    fn drop(&mut self) {
+        if let Err(err) = self.control.clear_forced_speed() {
+            tracing::warn!("Failed to restore automatic fan control: {err}");
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::chip::communication::fault_injection::test::wormhole;
+
+    #[test]
+    fn curve() {
+        let curve = FanCurve::from_fw_table(&FanTable {
+            fan_table_point_x1: 45,
+            fan_table_point_x2: 85,
+            fan_table_point_y1: 40,
+            fan_table_point_y2: 100,
+        });
+        assert_eq!(curve.speed_at(20.0), 40);
+        assert_eq!(curve.speed_at(65.0), 70);
+        assert_eq!(curve.speed_at(95.0), 100);
+        assert!(curve.validate(90.0).is_ok());
+
+        // The fan has to be at full speed before the chip reaches its thermal limit
+        assert!(matches!(
+            curve.validate(80.0),
+            Err(PowerError::InvalidFanCurve(_))
+        ));
+        assert!(matches!(
+            FanCurve::new(vec![(30.0, 10), (80.0, 100)]).validate(90.0),
+            Err(PowerError::FanSpeedOutOfRange {
+                requested: 10,
+                min: FAN_SPEED_MIN
+            })
+        ));
+        assert!(matches!(
+            FanCurve::new(vec![(30.0, 60), (50.0, 40), (80.0, 100)]).validate(90.0),
+            Err(PowerError::InvalidFanCurve(_))
+        ));
+    }
+
+    #[test]
+    fn wormhole_unsupported() {
+        let wh = wormhole(vec![]);
+        let fan = FanControl::new(&wh);
+        for result in [fan.curve().map(|_| ()), fan.force_speed(50)] {
+            assert!(matches!(
+                result,
+                Err(PlatformError::PowerError(PowerError::Unsupported {
+                    arch: Arch::Wormhole,
+                    ..
+                }))
+            ));
+        }
+    }
+}
@@ -4,18 +4,20 @@
 use std::{backtrace, sync::Arc};
 
 use luwen_core::Arch;
+use tracing::field::Empty;
 
 use crate::{
     arc_msg::{ArcMsgAddr, ArcMsgOk, ArcMsgProtocolError, TypedArcMsg},
-    chip::HlCommsInterface,
-    error::{BtWrapper, PlatformError},
+    chip::{ExclusiveAccess, HlCommsInterface},
+    error::{BtWrapper, ChipIdentity, PlatformError},
     ArcMsg, ChipImpl,
 };
 
 use super::{
+    communication::instrumented::traced,
     init::status::{ArcInitError, ComponentStatusInfo, InitOptions, WaitStatus},
-    ArcMsgOptions, ChipComms, ChipInitResult, ChipInterface, CommsStatus, HlComms, InitStatus,
-    NeighbouringChip,
+    ArcMsgOptions, ChipComms, ChipInitResult, ChipInterface, CommsStatus, Harvesting, HlComms,
+    InitStatus, NeighbouringChip,
 };
 
 #[derive(Clone)]
@@ -26,6 +28,8 @@ pub struct Grayskull {
     pub arc_addrs: ArcMsgAddr,
 
     telemetry_addr: Arc<once_cell::sync::OnceCell<u32>>,
+    board_id: Arc<once_cell::sync::OnceCell<u64>>,
+    harvesting: Arc<once_cell::sync::OnceCell<Harvesting>>,
 }
 
 impl Grayskull {
@@ -39,6 +43,8 @@ impl Grayskull {
             arc_if,
             arc_addrs,
             telemetry_addr: Arc::new(once_cell::sync::OnceCell::new()),
+            board_id: Arc::new(once_cell::sync::OnceCell::new()),
+            harvesting: Arc::new(once_cell::sync::OnceCell::new()),
         }
     }
 
@@ -140,23 +146,43 @@ impl Grayskull {
     }
 
     pub fn spi_write(&self, addr: u32, value: &[u8]) -> Result<(), Box<dyn std::error::Error>> {
-        // Grayskull doesn't have support for arc based spi read/write. The messages are
-        // unpopulated, but I am explicily setting it to false out of an abundence of caution.
-        let spi = super::spi::ActiveSpi::new(self, false)?;
-
-        spi.write(self, addr, value)?;
-
-        Ok(())
+        let span = tracing::debug_span!(
+            "spi_write",
+            chip = %self.chip_identity(),
+            addr,
+            len = value.len(),
+            duration_us = Empty,
+            error = Empty
+        );
+        traced(span, || {
+            // Grayskull doesn't have support for arc based spi read/write. The messages are
+            // unpopulated, but I am explicily setting it to false out of an abundence of caution.
+            let spi = super::spi::ActiveSpi::new(self, false)?;
+
+            spi.write(self, addr, value)?;
+
+            Ok(())
+        })
     }
 
     pub fn spi_read(&self, addr: u32, value: &mut [u8]) -> Result<(), Box<dyn std::error::Error>> {
-        // Grayskull doesn't have support for arc based spi read/write. The messages are
-        // unpopulated, but I am explicily setting it to false out of an abundence of caution.
-        let spi = super::spi::ActiveSpi::new(self, false)?;
-
-        spi.read(self, addr, value)?;
-
-        Ok(())
+        let span = tracing::debug_span!(
+            "spi_read",
+            chip = %self.chip_identity(),
+            addr,
+            len = value.len(),
+            duration_us = Empty,
+            error = Empty
+        );
+        traced(span, || {
+            // Grayskull doesn't have support for arc based spi read/write. The messages are
+            // unpopulated, but I am explicily setting it to false out of an abundence of caution.
+            let spi = super::spi::ActiveSpi::new(self, false)?;
+
+            spi.read(self, addr, value)?;
+
+            Ok(())
+        })
     }
 
     fn get_telemetry_offset(&self) -> Result<u32, PlatformError> {
@@ -200,12 +226,24 @@ impl HlComms for Grayskull {
     fn comms_obj(&self) -> (&dyn ChipComms, &dyn ChipInterface) {
         (self.arc_if.as_ref(), self.chip_if.as_ref())
     }
+
+    fn chip_identity(&self) -> ChipIdentity {
+        ChipIdentity {
+            arch: Some(Arch::Grayskull),
+            board_id: self.board_id.get().copied(),
+            ..ChipIdentity::from_comms(self.arc_if.as_ref(), self.chip_if.as_ref())
+        }
+    }
 }
 
 impl HlComms for &Grayskull {
     fn comms_obj(&self) -> (&dyn ChipComms, &dyn ChipInterface) {
         (self.arc_if.as_ref(), self.chip_if.as_ref())
     }
+
+    fn chip_identity(&self) -> ChipIdentity {
+        Grayskull::chip_identity(self)
+    }
 }
 
 fn default_status() -> InitStatus {
@@ -315,6 +353,13 @@ impl ChipImpl for Grayskull {
                                         ));
                                     }
 
+                                    PlatformError::WaitError(error) => {
+                                        return Ok(ChipInitResult::ErrorContinue(
+                                            error.to_string(),
+                                            backtrace::Backtrace::capture(),
+                                        ));
+                                    }
+
                                     // This is fine to hit at this stage (though it should have been already verified to not be the case).
                                     // For now we just ignore it and hope that it will be resolved by the time the timeout expires...
                                     PlatformError::EthernetTrainingNotComplete(eth_cores) => {
@@ -372,6 +417,13 @@ impl ChipImpl for Grayskull {
                                         ));
                                     }
 
+                                    PlatformError::PowerError(error) => {
+                                        return Ok(ChipInitResult::ErrorAbort(
+                                            error.to_string(),
+                                            backtrace::Backtrace::capture(),
+                                        ));
+                                    }
+
                                     PlatformError::Generic(error, backtrace) => {
                                         return Ok(ChipInitResult::ErrorAbort(error, backtrace.0));
                                     }
@@ -382,6 +434,13 @@ impl ChipImpl for Grayskull {
                                             backtrace.0,
                                         ));
                                     }
+
+                                    PlatformError::AccessError(error) => {
+                                        return Ok(ChipInitResult::ErrorAbort(
+                                            error.to_string(),
+                                            backtrace::Backtrace::capture(),
+                                        ));
+                                    }
                                 }
                             }
                         }
@@ -470,7 +529,7 @@ impl ChipImpl for Grayskull {
                                 }
                             }
 
-                            PlatformError::MessageError(_err) =>  {
+                            PlatformError::MessageError(_) | PlatformError::WaitError(_) =>  {
                                 None
                             }
 
@@ -490,6 +549,13 @@ impl ChipImpl for Grayskull {
                                 return Ok(ChipInitResult::ErrorAbort(format!("Expected chip: {}, actual detected chips: {}", expected_chips, actual), backtrace.0));
                             }
 
+                            PlatformError::PowerError(error) => {
+                                return Ok(ChipInitResult::ErrorAbort(
+                                    error.to_string(),
+                                    backtrace::Backtrace::capture(),
+                                ));
+                            }
+
                             PlatformError::Generic(error, backtrace) => {
                                 return Ok(ChipInitResult::ErrorAbort(error, backtrace.0));
                             }
@@ -497,28 +563,21 @@ impl ChipImpl for Grayskull {
                             PlatformError::GenericError(error, backtrace) => {
                                 return Ok(ChipInitResult::ErrorAbort(error.to_string(), backtrace.0));
                             }
+
+                            PlatformError::AccessError(error) => {
+                                return Ok(ChipInitResult::ErrorAbort(
+                                    error.to_string(),
+                                    backtrace::Backtrace::capture(),
+                                ));
+                            }
                         },
                     };
 
                     if let Some(telem) = telem {
                         let dram_status = telem.ddr_status;
 
-                        let mut channels = [None; 6];
-                        for (i, channel) in channels.iter_mut().enumerate() {
-                            let status = (dram_status >> (i * 4)) & 0xF;
-                            let status = status as u8;
-
-                            // In the firmware these are just magic values, we'll translate them to
-                            // something meaningful here (but it should be noted that these are
-                            // based on wormhole definitions).
-                            *channel = if status == 1 {
-                                Some(super::init::status::DramChannelStatus::TrainingPass)
-                            } else if status == 0 {
-                                Some(super::init::status::DramChannelStatus::TrainingFail)
-                            } else {
-                                None
-                            }
-                        }
+                        let channels =
+                            super::dram_training_status(luwen_core::Arch::Grayskull, dram_status);
 
                         for (dram_status, channel_status) in
                             status.wait_status.iter_mut().zip(channels)
@@ -588,23 +647,40 @@ impl ChipImpl for Grayskull {
     }
 
     fn arc_msg(&self, msg: ArcMsgOptions) -> Result<ArcMsgOk, PlatformError> {
-        let (msg_reg, return_reg) = if msg.use_second_mailbox {
-            return Err(ArcMsgProtocolError::InvalidMailbox(2).into_error())?;
-        } else {
-            (5, 3)
-        };
+        let span = tracing::debug_span!(
+            "arc_msg",
+            chip = %self.chip_identity(),
+            msg = msg.msg.msg_code(),
+            duration_us = Empty,
+            error = Empty
+        );
+        traced(span, || {
+            let (msg_reg, return_reg) = if msg.use_second_mailbox {
+                return Err(ArcMsgProtocolError::InvalidMailbox(2).into_error())?;
+            } else {
+                (5, 3)
+            };
+
+            // Other users of a shared device must not touch the mailbox until we have the reply
+            let _exclusive = ExclusiveAccess::acquire(self.comms_obj().1)?;
+            self.check_arc_msg_safe(msg_reg, return_reg)?;
+
+            crate::arc_msg::arc_msg(
+                self,
+                &msg.msg,
+                msg.wait_for_done,
+                msg.timeout,
+                msg_reg,
+                return_reg,
+                msg.addrs.as_ref().unwrap_or(&self.arc_addrs),
+            )
+        })
+    }
 
-        self.check_arc_msg_safe(msg_reg, return_reg)?;
-
-        crate::arc_msg::arc_msg(
-            self,
-            &msg.msg,
-            msg.wait_for_done,
-            msg.timeout,
-            msg_reg,
-            return_reg,
-            msg.addrs.as_ref().unwrap_or(&self.arc_addrs),
-        )
+    fn harvesting(&self) -> Result<Harvesting, PlatformError> {
+        self.harvesting
+            .get_or_try_init(|| Harvesting::read(self))
+            .cloned()
     }
 
     fn get_neighbouring_chips(&self) -> Result<Vec<NeighbouringChip>, PlatformError> {
@@ -758,8 +834,12 @@ impl ChipImpl for Grayskull {
         //     self.arc_if
         //         .axi_read32(&self.chip_if, telemetry_struct_offset + (5 * 4))? as u64;
 
+        let board_id = ((board_id_high as u64) << 32) | (board_id_low as u64);
+        // Remembered so that later errors can report which board they came from
+        let _ = self.board_id.set(board_id);
+
         Ok(super::Telemetry {
-            board_id: ((board_id_high as u64) << 32) | (board_id_low as u64),
+            board_id,
             enum_version,
             device_id,
             asic_ro,
@@ -0,0 +1,132 @@
+// SPDX-FileCopyrightText: © 2024 Tenstorrent Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Which parts of a chip were fused off or disabled, decoded the same way for every arch.
+//!
+//! Grayskull and wormhole only report their tensix harvesting, through an arc message.
+//! Blackhole reports the tensix harvesting in its telemetry, newer firmware also lists the
+//! enabled ethernet ports, GDDR channels and L2CPUs there. Anything which isn't reported is
+//! assumed to be enabled.
+
+use luwen_core::Arch;
+use num_traits::FromPrimitive;
+use serde::{Deserialize, Serialize};
+
+use super::{ArcMsgOptions, Blackhole, ChipImpl, TelemetryTags, TensixGrid};
+use crate::{error::PlatformError, ArcMsg, ArcMsgOk, TypedArcMsg};
+
+const BLACKHOLE_ETH_PORTS: u32 = 14;
+const BLACKHOLE_GDDR_CHANNELS: u32 = 8;
+const BLACKHOLE_L2CPUS: u32 = 4;
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Harvesting {
+    /// The tensix harvesting mask as reported by the firmware, see [`TensixGrid`].
+    pub tensix_mask: u32,
+    /// Physical (noc0) rows of tensix cores which were harvested, grayskull and wormhole only.
+    pub harvested_tensix_rows: Vec<u8>,
+    /// Physical (noc0) columns of tensix cores which were harvested, blackhole only.
+    pub harvested_tensix_cols: Vec<u8>,
+    pub disabled_dram_channels: Vec<u8>,
+    /// Indexes into the `eth_locations` of the chip.
+    pub disabled_eth_ports: Vec<u8>,
+    pub disabled_l2cpus: Vec<u8>,
+}
+
+/// The indexes of the clear bits in the lowest `count` bits of `enabled`.
+fn disabled(enabled: u32, count: u32) -> Vec<u8> {
+    (0..count)
+        .filter(|bit| enabled & (1 << bit) == 0)
+        .map(|bit| bit as u8)
+        .collect()
+}
+
+impl Harvesting {
+    fn from_tensix_mask(arch: Arch, tensix_mask: u32) -> Self {
+        let grid = TensixGrid::new(arch, tensix_mask);
+        let (harvested_tensix_rows, harvested_tensix_cols) = if grid.harvests_rows() {
+            (grid.harvested(), vec![])
+        } else {
+            (vec![], grid.harvested())
+        };
+
+        Self {
+            tensix_mask,
+            harvested_tensix_rows,
+            harvested_tensix_cols,
+            ..Default::default()
+        }
+    }
+
+    fn from_blackhole(blackhole: &Blackhole) -> Result<Self, PlatformError> {
+        let tags = blackhole.get_telemetry_tags()?;
+        let tag = |wanted| {
+            tags.iter()
+                .find(|(tag, _)| TelemetryTags::from_u32(*tag) == Some(wanted))
+                .map(|(_, data)| *data)
+        };
+
+        let mut harvesting = Self::from_tensix_mask(
+            Arch::Blackhole,
+            tag(TelemetryTags::HarvestingState).unwrap_or(0),
+        );
+        if let Some(enabled) = tag(TelemetryTags::EnabledEth) {
+            harvesting.disabled_eth_ports = disabled(enabled, BLACKHOLE_ETH_PORTS);
+        }
+        if let Some(enabled) = tag(TelemetryTags::EnabledGddr) {
+            harvesting.disabled_dram_channels = disabled(enabled, BLACKHOLE_GDDR_CHANNELS);
+        }
+        if let Some(enabled) = tag(TelemetryTags::EnabledL2Cpu) {
+            harvesting.disabled_l2cpus = disabled(enabled, BLACKHOLE_L2CPUS);
+        }
+
+        Ok(harvesting)
+    }
+
+    /// Ask the firmware of `chip`, [`ChipImpl::harvesting`] should be used instead as it is
+    /// only read once.
+    pub(crate) fn read(chip: &(impl ChipImpl + ?Sized)) -> Result<Self, PlatformError> {
+        let arch = chip.get_arch();
+        if let Some(blackhole) = chip.as_any().downcast_ref::<Blackhole>() {
+            return Self::from_blackhole(blackhole);
+        }
+
+        match chip.arc_msg(ArcMsgOptions {
+            msg: ArcMsg::Typed(TypedArcMsg::GetHarvesting),
+            ..Default::default()
+        })? {
+            ArcMsgOk::Ok { arg, .. } => Ok(Self::from_tensix_mask(arch, arg)),
+            ArcMsgOk::OkNoWait => unreachable!(),
+        }
+    }
+
+    /// The functional tensix cores left after harvesting.
+    pub fn tensix_grid(&self, arch: Arch) -> TensixGrid {
+        TensixGrid::new(arch, self.tensix_mask)
+    }
+
+    pub fn eth_port_enabled(&self, port: usize) -> bool {
+        !self.disabled_eth_ports.contains(&(port as u8))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn decode() {
+        let harvesting = Harvesting::from_tensix_mask(Arch::Wormhole, 0b11);
+        assert_eq!(harvesting.harvested_tensix_rows, vec![1, 11]);
+        assert!(harvesting.harvested_tensix_cols.is_empty());
+        assert_eq!(harvesting.tensix_grid(Arch::Wormhole).size(), (8, 8));
+
+        let harvesting = Harvesting::from_tensix_mask(Arch::Blackhole, 0b10);
+        assert_eq!(harvesting.harvested_tensix_cols, vec![16]);
+        assert!(harvesting.harvested_tensix_rows.is_empty());
+
+        assert_eq!(disabled(0x3ff7, BLACKHOLE_ETH_PORTS), vec![3]);
+        assert_eq!(disabled(0xff, BLACKHOLE_GDDR_CHANNELS), Vec::<u8>::new());
+        assert_eq!(disabled(0b0101, BLACKHOLE_L2CPUS), vec![1, 3]);
+    }
+}
@@ -1,14 +1,57 @@
 // SPDX-FileCopyrightText: © 2023 Tenstorrent Inc.
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::error::PlatformError;
+use crate::error::{AccessError, ChipIdentity, Operation, OperationKind, PlatformError};
 
-use super::{AxiData, AxiError, ChipComms, ChipInterface};
+use super::{poll, AxiData, AxiError, ChipComms, ChipInterface, PollOptions};
+
+/// A register given either by its axi path or by its address.
+#[derive(Clone, Copy, Debug)]
+pub enum AxiLocation<'a> {
+    Path(&'a str),
+    Addr(u64),
+}
+
+impl<'a> From<&'a str> for AxiLocation<'a> {
+    fn from(path: &'a str) -> Self {
+        AxiLocation::Path(path)
+    }
+}
+
+impl<'a> From<&'a String> for AxiLocation<'a> {
+    fn from(path: &'a String) -> Self {
+        AxiLocation::Path(path)
+    }
+}
+
+impl From<u64> for AxiLocation<'_> {
+    fn from(addr: u64) -> Self {
+        AxiLocation::Addr(addr)
+    }
+}
+
+impl std::fmt::Display for AxiLocation<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AxiLocation::Path(path) => f.write_str(path),
+            AxiLocation::Addr(addr) => write!(f, "{addr:#x}"),
+        }
+    }
+}
 
 /// Convinence trait for high-level communication with an arbitrary chip.
+///
+/// Errors returned by the access functions are wrapped in an [`AccessError`] recording the chip
+/// and the operation that failed.
 pub trait HlComms {
     fn comms_obj(&self) -> (&dyn ChipComms, &dyn ChipInterface);
 
+    /// Describes this chip in errors, chips override this to add their arch and board id.
+    fn chip_identity(&self) -> ChipIdentity {
+        let (arc_if, chip_if) = self.comms_obj();
+        ChipIdentity::from_comms(arc_if, chip_if)
+    }
+
     fn noc_read(
         &self,
         noc_id: u8,
@@ -18,7 +61,10 @@ pub trait HlComms {
         data: &mut [u8],
     ) -> Result<(), Box<dyn std::error::Error>> {
         let (arc_if, chip_if) = self.comms_obj();
-        arc_if.noc_read(chip_if, noc_id, x, y, addr, data)
+        let operation = Operation::noc(OperationKind::NocRead, noc_id, x, y, addr, data.len());
+        with_context(self, operation, || {
+            arc_if.noc_read(chip_if, noc_id, x, y, addr, data)
+        })
     }
 
     fn noc_write(
@@ -30,7 +76,10 @@ pub trait HlComms {
         data: &[u8],
     ) -> Result<(), Box<dyn std::error::Error>> {
         let (arc_if, chip_if) = self.comms_obj();
-        arc_if.noc_write(chip_if, noc_id, x, y, addr, data)
+        let operation = Operation::noc(OperationKind::NocWrite, noc_id, x, y, addr, data.len());
+        with_context(self, operation, || {
+            arc_if.noc_write(chip_if, noc_id, x, y, addr, data)
+        })
     }
 
     fn noc_broadcast(
@@ -40,7 +89,10 @@ pub trait HlComms {
         data: &[u8],
     ) -> Result<(), Box<dyn std::error::Error>> {
         let (arc_if, chip_if) = self.comms_obj();
-        arc_if.noc_broadcast(chip_if, noc_id, addr, data)
+        let operation = Operation::broadcast(noc_id, addr, data.len());
+        with_context(self, operation, || {
+            arc_if.noc_broadcast(chip_if, noc_id, addr, data)
+        })
     }
 
     fn noc_read32(
@@ -51,7 +103,10 @@ pub trait HlComms {
         addr: u64,
     ) -> Result<u32, Box<dyn std::error::Error>> {
         let (arc_if, chip_if) = self.comms_obj();
-        arc_if.noc_read32(chip_if, noc_id, x, y, addr)
+        let operation = Operation::noc(OperationKind::NocRead, noc_id, x, y, addr, 4);
+        with_context(self, operation, || {
+            arc_if.noc_read32(chip_if, noc_id, x, y, addr)
+        })
     }
 
     fn noc_write32(
@@ -63,7 +118,10 @@ pub trait HlComms {
         value: u32,
     ) -> Result<(), Box<dyn std::error::Error>> {
         let (arc_if, chip_if) = self.comms_obj();
-        arc_if.noc_write32(chip_if, noc_id, x, y, addr, value)
+        let operation = Operation::noc(OperationKind::NocWrite, noc_id, x, y, addr, 4);
+        with_context(self, operation, || {
+            arc_if.noc_write32(chip_if, noc_id, x, y, addr, value)
+        })
     }
 
     fn noc_broadcast32(
@@ -73,30 +131,47 @@ pub trait HlComms {
         value: u32,
     ) -> Result<(), Box<dyn std::error::Error>> {
         let (arc_if, chip_if) = self.comms_obj();
-        arc_if.noc_broadcast32(chip_if, noc_id, addr, value)
+        let operation = Operation::broadcast(noc_id, addr, 4);
+        with_context(self, operation, || {
+            arc_if.noc_broadcast32(chip_if, noc_id, addr, value)
+        })
     }
 
     fn axi_read(&self, addr: u64, data: &mut [u8]) -> Result<(), Box<dyn std::error::Error>> {
         let (arc_if, chip_if) = self.comms_obj();
-        arc_if.axi_read(chip_if, addr, data)
+        let operation = Operation::axi(OperationKind::AxiRead, addr, data.len());
+        with_context(self, operation, || arc_if.axi_read(chip_if, addr, data))
     }
 
     fn axi_write(&self, addr: u64, data: &[u8]) -> Result<(), Box<dyn std::error::Error>> {
         let (arc_if, chip_if) = self.comms_obj();
-        arc_if.axi_write(chip_if, addr, data)
+        let operation = Operation::axi(OperationKind::AxiWrite, addr, data.len());
+        with_context(self, operation, || arc_if.axi_write(chip_if, addr, data))
     }
 
     fn axi_read32(&self, addr: u64) -> Result<u32, Box<dyn std::error::Error>> {
         let (arc_if, chip_if) = self.comms_obj();
-        arc_if.axi_read32(chip_if, addr)
+        let operation = Operation::axi(OperationKind::AxiRead, addr, 4);
+        with_context(self, operation, || arc_if.axi_read32(chip_if, addr))
     }
 
     fn axi_write32(&self, addr: u64, value: u32) -> Result<(), Box<dyn std::error::Error>> {
         let (arc_if, chip_if) = self.comms_obj();
-        arc_if.axi_write32(chip_if, addr, value)
+        let operation = Operation::axi(OperationKind::AxiWrite, addr, 4);
+        with_context(self, operation, || arc_if.axi_write32(chip_if, addr, value))
     }
 }
 
+/// Run an access, attaching the chip identity to the error if it fails.
+/// The identity is only looked up on failure because it may require talking to the driver.
+fn with_context<T>(
+    comms: &(impl HlComms + ?Sized),
+    operation: Operation,
+    f: impl FnOnce() -> Result<T, Box<dyn std::error::Error>>,
+) -> Result<T, Box<dyn std::error::Error>> {
+    f().map_err(|err| AccessError::new(comms.chip_identity(), operation, err).into())
+}
+
 #[inline]
 fn right_shift(existing: &mut [u8], shift: u32) {
     let byte_shift = shift as usize / 8;
@@ -230,13 +305,11 @@ pub trait HlCommsInterface: HlComms {
         addr: &AxiData,
         value: &'a mut [u8],
     ) -> Result<&'a [u8], PlatformError> {
-        let (arc_if, chip_if) = self.comms_obj();
-
         if value.len() < addr.size as usize {
             return Err(AxiError::ReadBufferTooSmall)?;
         }
 
-        arc_if.axi_read(chip_if, addr.addr, &mut value[..addr.size as usize])?;
+        self.axi_read(addr.addr, &mut value[..addr.size as usize])?;
 
         let value = if let Some((lower, upper)) = addr.bits {
             read_modify(value, lower, upper);
@@ -250,22 +323,20 @@ pub trait HlCommsInterface: HlComms {
     }
 
     fn axi_write_field(&self, addr: &AxiData, value: &[u8]) -> Result<(), PlatformError> {
-        let (arc_if, chip_if) = self.comms_obj();
-
         if value.len() < addr.size as usize {
             return Err(AxiError::ReadBufferTooSmall)?;
         }
 
         if let Some((lower, upper)) = addr.bits {
             let mut existing = vec![0u8; addr.size as usize];
-            arc_if.axi_read(chip_if, addr.addr, &mut existing)?;
+            self.axi_read(addr.addr, &mut existing)?;
 
             write_modify(&mut existing, value, lower, upper);
 
-            arc_if.axi_write(chip_if, addr.addr, &existing)?;
+            self.axi_write(addr.addr, &existing)?;
         } else {
             // We are writing the full size of the field
-            arc_if.axi_write(chip_if, addr.addr, &value[..addr.size as usize])?;
+            self.axi_write(addr.addr, &value[..addr.size as usize])?;
         };
 
         Ok(())
@@ -285,7 +356,7 @@ pub trait HlCommsInterface: HlComms {
     }
 
     fn axi_sread_to_vec(&self, addr: impl AsRef<str>) -> Result<Vec<u8>, PlatformError> {
-        let (arc_if, chip_if) = self.comms_obj();
+        let (arc_if, _chip_if) = self.comms_obj();
 
         let addr = addr.as_ref();
 
@@ -295,7 +366,7 @@ pub trait HlCommsInterface: HlComms {
 
         let value: &mut [u8] = unsafe { std::mem::transmute(output.spare_capacity_mut()) };
 
-        arc_if.axi_read(chip_if, addr.addr, &mut value[..addr.size as usize])?;
+        self.axi_read(addr.addr, &mut value[..addr.size as usize])?;
 
         unsafe {
             output.set_len(addr.size as usize);
@@ -329,6 +400,28 @@ pub trait HlCommsInterface: HlComms {
     fn axi_swrite32(&self, addr: impl AsRef<str>, value: u32) -> Result<(), PlatformError> {
         self.axi_swrite(addr, &value.to_le_bytes())
     }
+
+    /// Read a register until `predicate` accepts its value, returning that value.
+    /// If it never does, the returned error contains the last value that was read.
+    fn wait_until<'a>(
+        &self,
+        location: impl Into<AxiLocation<'a>>,
+        mut predicate: impl FnMut(u32) -> bool,
+        options: &PollOptions,
+    ) -> Result<u32, PlatformError> {
+        let location = location.into();
+        let value = poll(
+            format_args!("{location} to change"),
+            options,
+            || match location {
+                AxiLocation::Path(path) => self.axi_sread32(path),
+                AxiLocation::Addr(addr) => Ok(self.axi_read32(addr)?),
+            },
+            |value| predicate(*value),
+        )??;
+
+        Ok(value)
+    }
 }
 
 impl<T: HlComms> HlCommsInterface for T {}
@@ -3,9 +3,13 @@
 
 use std::convert::Infallible;
 
+use tracing::field::Empty;
+
 use crate::{error::PlatformError, ChipImpl};
 
-use status::InitStatus;
+use status::{ComponentStatusInfo, InitStatus};
+
+use super::communication::instrumented::traced;
 
 pub mod status;
 
@@ -62,10 +66,24 @@ pub fn wait_for_init<E>(
     })
     .map_err(|v| InitError::CallbackError(v))?;
 
+    let _span = tracing::debug_span!("wait_for_init", chip = %chip.chip_identity()).entered();
+
     let mut status = InitStatus::new_unknown();
     status.init_options.noc_safe = noc_safe;
+    let mut states = component_states(&status);
     loop {
-        match chip.update_init_state(&mut status)? {
+        let span = tracing::debug_span!("update_init_state", duration_us = Empty, error = Empty);
+        let result = traced(span, || chip.update_init_state(&mut status))?;
+
+        let new_states = component_states(&status);
+        for ((component, from), (_, to)) in states.iter().zip(new_states.iter()) {
+            if from != to {
+                tracing::debug!(component, from, to, "init state changed");
+            }
+        }
+        states = new_states;
+
+        match result {
             super::ChipInitResult::NoError => {
                 // No error, we don't have to do anything.
             }
@@ -120,3 +138,33 @@ pub fn wait_for_init<E>(
         callback(ChipDetectState { chip, call }).map_err(InitError::CallbackError)?;
     }
 }
+
+fn component_state<P, E>(status: &ComponentStatusInfo<P, E>) -> &'static str {
+    if !status.is_present() {
+        "not present"
+    } else if status.has_error() {
+        "error"
+    } else if status.is_waiting() {
+        "waiting"
+    } else {
+        "done"
+    }
+}
+
+/// A coarse summary of the init status, used to log when a component changes state.
+fn component_states(status: &InitStatus) -> [(&'static str, &'static str); 5] {
+    [
+        (
+            "comms",
+            if status.comms_status.ok() {
+                "ok"
+            } else {
+                "error"
+            },
+        ),
+        ("arc", component_state(&status.arc_status)),
+        ("dram", component_state(&status.dram_status)),
+        ("eth", component_state(&status.eth_status)),
+        ("cpu", component_state(&status.cpu_status)),
+    ]
+}
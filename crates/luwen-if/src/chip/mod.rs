@@ -4,32 +4,65 @@
 mod blackhole;
 pub mod communication;
 mod creation;
+mod dram;
 pub mod eth_addr;
+mod eth_status;
+mod fan;
 mod grayskull;
+mod harvesting;
 mod hl_comms;
 mod init;
+mod poll;
+mod power;
 mod remote;
+mod risc;
 mod spi;
+mod tensix;
 mod wormhole;
 
-pub use blackhole::{message::MessageError, Blackhole};
+pub use blackhole::{message::MessageError, telemetry_tags::TelemetryTags, Blackhole};
 pub use communication::chip_comms::{
     axi_translate, ArcIf, AxiData, AxiError, ChipComms, MemorySlice, MemorySlices,
 };
-pub use communication::chip_interface::{ChipInterface, NocInterface};
+pub use communication::chip_interface::{ChipInterface, ExclusiveAccess, NocInterface};
+pub use dram::{
+    dram_channels, dram_training_status, test_dram, DramChannel, DramChannelReport, DramFailure,
+    DramTestOptions, DramTestProgress, DramTestReport, MemoryPattern,
+};
+pub use eth_status::{EthLinkState, EthPortStatus, EthRemotePort};
+pub use fan::{FanControl, FanCurve, FanOverride, FanStatus, FAN_SPEED_MIN};
 pub use grayskull::Grayskull;
-pub use hl_comms::{HlComms, HlCommsInterface};
+pub use harvesting::Harvesting;
+pub use hl_comms::{AxiLocation, HlComms, HlCommsInterface};
 pub use init::status::InitStatus;
 pub use init::{
-    status::{CommsStatus, ComponentStatusInfo},
+    status::{
+        ArcInitError, CommsStatus, ComponentStatusInfo, CpuInitError, DramChannelStatus,
+        DramInitError, EthernetInitError, EthernetPartialInitError, WaitStatus,
+    },
     wait_for_init, CallReason, ChipDetectState, InitError,
 };
 use luwen_core::Arch;
+pub use poll::{poll, PollOptions, WaitError};
+pub use power::{PowerControl, PowerError, PowerLimits, PowerStatus};
+pub use risc::{
+    assert_risc_reset, deassert_risc_reset, load_elf, read_mailbox, risc_in_reset, run_elf,
+    set_risc_entry, ElfSegment, RiscCore, RiscElf, RiscError, SOFT_RESET_ADDR,
+};
+use serde::{Deserialize, Serialize};
+pub use tensix::{
+    tensix_harvest_mask, test_l1, L1CoreReport, L1Failure, L1TestOptions, L1TestProgress,
+    L1TestReport, TensixCore, TensixGrid,
+};
 pub use wormhole::Wormhole;
 
 use crate::arc_msg::TypedArcMsg;
 pub use crate::arc_msg::{ArcMsg, ArcMsgOk};
-use crate::{arc_msg::ArcMsgAddr, error::PlatformError, DeviceInfo};
+use crate::{
+    arc_msg::ArcMsgAddr,
+    error::{ChipIdentity, PlatformError},
+    ConnectionState, DeviceInfo,
+};
 
 /// Arc message interface
 #[derive(Debug)]
@@ -53,7 +86,7 @@ impl Default for ArcMsgOptions {
     }
 }
 
-#[derive(Debug, Hash, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq, Serialize, Deserialize)]
 pub struct NeighbouringChip {
     pub routing_enabled: bool,
     pub local_noc_addr: (u8, u8),
@@ -61,7 +94,7 @@ pub struct NeighbouringChip {
     pub eth_addr: crate::EthAddr,
 }
 
-#[derive(Default, Debug)]
+#[derive(Default, Debug, Serialize, Deserialize)]
 pub struct Telemetry {
     pub board_id: u64,
     pub enum_version: u32,
@@ -227,6 +260,15 @@ impl Telemetry {
         ((self.asic_temperature & 0xffff) >> 4) as f64
     }
 
+    /// Return the ASIC temperature of a chip of `arch` in degrees celsius, blackhole reports it
+    /// as a signed 16.16 fixed point value.
+    pub fn asic_temperature_for(&self, arch: Arch) -> f64 {
+        match arch {
+            Arch::Blackhole => self.asic_temperature as i32 as f64 / 65536.0,
+            _ => self.asic_temperature(),
+        }
+    }
+
     /// Return the voltage regulator temperature in degrees celsius.
     pub fn vreg_temperature(&self) -> f64 {
         (self.vreg_temperature & 0xffff) as f64
@@ -301,6 +343,10 @@ pub trait ChipImpl: HlComms + Send + Sync + 'static {
     /// Send an arc_msg to the underlying chip.
     fn arc_msg(&self, msg: ArcMsgOptions) -> Result<ArcMsgOk, PlatformError>;
 
+    /// Which tensix rows or columns, DRAM channels, ethernet ports and L2CPUs are unusable.
+    /// Read from the firmware once ARC is up and cached for the lifetime of the chip.
+    fn harvesting(&self) -> Result<Harvesting, PlatformError>;
+
     /// Get a list of neighbouring chips.
     /// Will return an empty list for gs and up to four chips for wh.
     fn get_neighbouring_chips(&self) -> Result<Vec<NeighbouringChip>, PlatformError>;
@@ -342,12 +388,52 @@ impl Chip {
     pub fn as_bh(&self) -> Option<&Blackhole> {
         self.inner.as_any().downcast_ref::<Blackhole>()
     }
+
+    /// Whether the transport to the chip is still usable.
+    pub fn connection_state(&self) -> ConnectionState {
+        self.inner.comms_obj().1.connection_state()
+    }
+
+    /// Reopen the transport to the chip after its connection was lost or it was reset.
+    /// Anything the chip object cached about the hardware is kept, if the chip was reset it
+    /// should be initialized again with [`wait_for_init`] before use.
+    pub fn reopen(&self) -> Result<(), PlatformError> {
+        self.inner
+            .comms_obj()
+            .1
+            .reopen()
+            .map_err(PlatformError::from)
+    }
+
+    /// Write and verify test patterns on the DRAM of the chip, see [`test_dram`].
+    pub fn test_dram(&self, options: &DramTestOptions) -> DramTestReport {
+        test_dram(self, options, |_| {})
+    }
+
+    /// Write and verify test patterns in the L1 of every functional tensix, see [`test_l1`].
+    pub fn test_l1(&self, options: &L1TestOptions) -> Result<L1TestReport, PlatformError> {
+        test_l1(self, options, |_| {})
+    }
+
+    /// Query and request clock and power states, see [`PowerControl`].
+    pub fn power(&self) -> PowerControl<'_, Self> {
+        PowerControl::new(self)
+    }
+
+    /// Read and override the fan curve, see [`FanControl`].
+    pub fn fan(&self) -> FanControl<'_, Self> {
+        FanControl::new(self)
+    }
 }
 
 impl HlComms for Chip {
     fn comms_obj(&self) -> (&dyn ChipComms, &dyn ChipInterface) {
         self.inner.comms_obj()
     }
+
+    fn chip_identity(&self) -> ChipIdentity {
+        self.inner.chip_identity()
+    }
 }
 
 impl ChipImpl for Chip {
@@ -366,6 +452,10 @@ impl ChipImpl for Chip {
         self.inner.arc_msg(msg)
     }
 
+    fn harvesting(&self) -> Result<Harvesting, PlatformError> {
+        self.inner.harvesting()
+    }
+
     fn get_neighbouring_chips(&self) -> Result<Vec<NeighbouringChip>, PlatformError> {
         self.inner.get_neighbouring_chips()
     }
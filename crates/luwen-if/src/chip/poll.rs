@@ -0,0 +1,238 @@
+// SPDX-FileCopyrightText: © 2024 Tenstorrent Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Waiting for a value read from the chip to reach some expected state.
+
+use std::{
+    fmt::{Debug, Display},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+
+use thiserror::Error;
+
+/// Controls how often and for how long a value is polled.
+///
+/// The first read happens immediately, after that the delay between reads starts at
+/// `initial_delay` and is multiplied by `multiplier` after every read until it reaches
+/// `max_delay`. With an initial delay of zero the value is polled in a busy loop.
+#[derive(Clone, Debug)]
+pub struct PollOptions {
+    pub timeout: Duration,
+    pub initial_delay: Duration,
+    pub max_delay: Duration,
+    pub multiplier: u32,
+    /// When set the wait is abandoned with [`WaitError::Cancelled`].
+    pub cancel: Option<Arc<AtomicBool>>,
+}
+
+impl Default for PollOptions {
+    fn default() -> Self {
+        Self {
+            timeout: Duration::from_secs(1),
+            initial_delay: Duration::from_micros(10),
+            max_delay: Duration::from_millis(10),
+            multiplier: 2,
+            cancel: None,
+        }
+    }
+}
+
+impl PollOptions {
+    pub fn new(timeout: Duration) -> Self {
+        Self {
+            timeout,
+            ..Default::default()
+        }
+    }
+
+    /// Poll without ever sleeping, for hardware which is expected to respond within microseconds.
+    pub fn busy(timeout: Duration) -> Self {
+        Self::new(timeout).backoff(Duration::ZERO, Duration::ZERO)
+    }
+
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    pub fn backoff(mut self, initial_delay: Duration, max_delay: Duration) -> Self {
+        self.initial_delay = initial_delay;
+        self.max_delay = max_delay;
+        self
+    }
+
+    pub fn multiplier(mut self, multiplier: u32) -> Self {
+        self.multiplier = multiplier;
+        self
+    }
+
+    pub fn cancel(mut self, cancel: Arc<AtomicBool>) -> Self {
+        self.cancel = Some(cancel);
+        self
+    }
+
+    fn cancelled(&self) -> bool {
+        self.cancel
+            .as_ref()
+            .is_some_and(|cancel| cancel.load(Ordering::Relaxed))
+    }
+}
+
+#[derive(Clone, Error, Debug)]
+pub enum WaitError {
+    #[error("Timed out after {elapsed:?} waiting for {what} ({polls} reads, last value {last})")]
+    Timeout {
+        what: String,
+        elapsed: Duration,
+        polls: u64,
+        last: String,
+    },
+
+    #[error(
+        "Cancelled after {elapsed:?} while waiting for {what} ({polls} reads, last value {last})"
+    )]
+    Cancelled {
+        what: String,
+        elapsed: Duration,
+        polls: u64,
+        last: String,
+    },
+}
+
+/// Call `read` until `done` accepts its result.
+///
+/// Errors returned by `read` abort the wait and are passed through in the outer result, running
+/// out of time or being cancelled is reported in the inner result. `what` is only formatted if the
+/// wait fails, so it is cheap to pass `format_args!(..)`.
+pub fn poll<T: Debug, E>(
+    what: impl Display,
+    options: &PollOptions,
+    mut read: impl FnMut() -> Result<T, E>,
+    mut done: impl FnMut(&T) -> bool,
+) -> Result<Result<T, WaitError>, E> {
+    let start = Instant::now();
+    let mut delay = options.initial_delay;
+    let mut polls = 0;
+    loop {
+        let value = read()?;
+        polls += 1;
+        if done(&value) {
+            return Ok(Ok(value));
+        }
+
+        let elapsed = start.elapsed();
+        let cancelled = options.cancelled();
+        if cancelled || elapsed > options.timeout {
+            let (what, last) = (what.to_string(), format!("{value:x?}"));
+            return Ok(Err(if cancelled {
+                WaitError::Cancelled {
+                    what,
+                    elapsed,
+                    polls,
+                    last,
+                }
+            } else {
+                WaitError::Timeout {
+                    what,
+                    elapsed,
+                    polls,
+                    last,
+                }
+            }));
+        }
+
+        if delay.is_zero() {
+            std::hint::spin_loop();
+        } else {
+            // Don't oversleep the deadline by more than one read
+            std::thread::sleep(delay.min(options.timeout.saturating_sub(elapsed)));
+            delay = (delay * options.multiplier).min(options.max_delay);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn poll_until_done() {
+        let mut count = 0;
+        let result = poll(
+            "count",
+            &PollOptions::busy(Duration::from_secs(1)),
+            || -> Result<u32, ()> {
+                count += 1;
+                Ok(count)
+            },
+            |count| *count == 5,
+        );
+        assert!(matches!(result, Ok(Ok(5))));
+
+        // Read errors stop the wait right away
+        let result = poll(
+            "error",
+            &PollOptions::default(),
+            || Err::<u32, _>("broken"),
+            |_| false,
+        );
+        assert!(matches!(result, Err("broken")));
+    }
+
+    #[test]
+    fn poll_timeout() {
+        let options = PollOptions::new(Duration::from_millis(20))
+            .backoff(Duration::from_millis(1), Duration::from_millis(4));
+        let result = poll(
+            format_args!("register {:#x}", 0x80),
+            &options,
+            || Ok::<_, ()>(0xdead_u32),
+            |_| false,
+        );
+
+        let err = result.unwrap().unwrap_err();
+        let WaitError::Timeout {
+            ref what,
+            elapsed,
+            polls,
+            ref last,
+        } = err
+        else {
+            panic!("Expected a timeout, got {err:?}");
+        };
+        assert_eq!(what, "register 0x80");
+        assert_eq!(last, "dead");
+        assert!(elapsed >= Duration::from_millis(20));
+        // 1 + 2 + 4 + 4 + ... ms, so the backoff should keep the number of reads low
+        assert!((2..=12).contains(&polls), "{polls} polls");
+        assert!(err.to_string().contains("last value dead"));
+    }
+
+    #[test]
+    fn poll_cancel() {
+        let cancel = Arc::new(AtomicBool::new(false));
+        let options = PollOptions::new(Duration::from_secs(10)).cancel(cancel.clone());
+
+        let mut count = 0;
+        let result = poll(
+            "cancel",
+            &options,
+            || {
+                count += 1;
+                if count == 3 {
+                    cancel.store(true, Ordering::Relaxed);
+                }
+                Ok::<_, ()>(count)
+            },
+            |_| false,
+        );
+        assert!(matches!(
+            result,
+            Ok(Err(WaitError::Cancelled { polls: 3, .. }))
+        ));
+    }
+}
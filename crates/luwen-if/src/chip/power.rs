@@ -0,0 +1,356 @@
+// SPDX-FileCopyrightText: © 2024 Tenstorrent Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Typed control over the AI clock and power state of a chip, fan control lives in `fan.rs`.
+//!
+//! Grayskull and wormhole take these requests through the ARC mailbox, blackhole through its
+//! message queue. In both cases a non-zero return code means that the firmware refused the
+//! request, which is reported as [`PowerError::Rejected`].
+//!
+//! Grayskull and wormhole pack the board limits into the upper halves of the telemetry values.
+//! Blackhole telemetry only carries the current values, so its limits come from the `chip_limits`
+//! of the fw_table instead.
+//!
+//! None of the firmwares have a dedicated throttle request, [`PowerControl::force_throttle`] pins
+//! AICLK to the board minimum instead. Only blackhole reports that minimum, so throttling is
+//! refused with [`PowerError::Unsupported`] on grayskull and wormhole.
+
+use luwen_core::Arch;
+use serde::{Deserialize, Serialize};
+
+use super::{
+    blackhole::spirom_tables::fw_table::fw_table::ChipLimits, ArcMsgOptions, Blackhole, ChipImpl,
+    Telemetry,
+};
+use crate::{arc_msg::PowerState, error::PlatformError, ArcMsg, ArcMsgOk, TypedArcMsg};
+
+#[derive(Debug, thiserror::Error)]
+pub enum PowerError {
+    #[error("AICLK of {requested} MHz is above the board limit of {max} MHz")]
+    AiclkOutOfRange { requested: u32, max: u32 },
+    #[error("The firmware did not report the {0}")]
+    UnknownLimit(&'static str),
+    #[error("{what} is not supported on {arch:?}")]
+    Unsupported { arch: Arch, what: &'static str },
+    #[error("Firmware rejected {request} with return code {rc}")]
+    Rejected { request: &'static str, rc: u32 },
+    #[error("Fan speed of {requested}% is outside of the allowed range {min}-100%")]
+    FanSpeedOutOfRange { requested: u32, min: u32 },
+    #[error("Invalid fan curve: {0}")]
+    InvalidFanCurve(String),
+    #[error("ASIC temperature of {temperature}C reached the limit of {limit}C")]
+    OverTemperature { temperature: f64, limit: f64 },
+}
+
+/// Send `msg` and turn a non-zero return code into [`PowerError::Rejected`].
+pub(super) fn arc_request(
+    chip: &(impl ChipImpl + ?Sized),
+    request: &'static str,
+    msg: TypedArcMsg,
+) -> Result<u32, PlatformError> {
+    match chip.arc_msg(ArcMsgOptions {
+        msg: ArcMsg::Typed(msg),
+        ..Default::default()
+    })? {
+        ArcMsgOk::Ok { rc: 0, arg } => Ok(arg),
+        ArcMsgOk::Ok { rc, .. } => Err(PowerError::Rejected { request, rc }.into()),
+        ArcMsgOk::OkNoWait => unreachable!(),
+    }
+}
+
+/// The limits the firmware enforces on the board. A limit of 0 means the firmware did not report
+/// it.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PowerLimits {
+    /// Minimum AICLK in MHz, only reported by blackhole.
+    pub aiclk_min: u32,
+    /// Maximum AICLK in MHz.
+    pub aiclk_max: u32,
+    /// Power limit in watts.
+    pub tdp_limit: u32,
+    /// Current limit in amperes.
+    pub tdc_limit: u32,
+    /// Temperature at which the chip is throttled, in degrees celsius.
+    pub thm_limit: u32,
+    /// Core voltage range in millivolts.
+    pub vdd_min: u32,
+    pub vdd_max: u32,
+}
+
+impl PowerLimits {
+    /// The limits which are part of the telemetry, on blackhole this leaves the AICLK, TDP and
+    /// TDC limits at 0.
+    pub fn from_telemetry(arch: Arch, telemetry: &Telemetry) -> Self {
+        let upper = |value: u32| match arch {
+            Arch::Blackhole => 0,
+            _ => value >> 16,
+        };
+
+        Self {
+            aiclk_min: 0,
+            aiclk_max: upper(telemetry.aiclk),
+            tdp_limit: upper(telemetry.tdp),
+            tdc_limit: upper(telemetry.tdc),
+            thm_limit: telemetry.thm_limits & 0xffff,
+            vdd_min: telemetry.vdd_limits & 0xffff,
+            vdd_max: telemetry.vdd_limits >> 16,
+        }
+    }
+
+    /// The limits from the blackhole fw_table.
+    pub fn from_chip_limits(limits: &ChipLimits) -> Self {
+        Self {
+            aiclk_min: limits.asic_fmin,
+            aiclk_max: limits.asic_fmax,
+            tdp_limit: limits.tdp_limit,
+            tdc_limit: limits.tdc_limit,
+            thm_limit: limits.thm_limit,
+            vdd_min: limits.vdd_min,
+            vdd_max: limits.vdd_max,
+        }
+    }
+
+    /// Check that `mhz` can be requested, 0 is always allowed because it releases the clock.
+    /// Anything else is refused if the board limit is unknown.
+    pub fn check_aiclk(&self, mhz: u32) -> Result<(), PowerError> {
+        if mhz == 0 {
+            return Ok(());
+        }
+        if self.aiclk_max == 0 {
+            return Err(PowerError::UnknownLimit("maximum AICLK"));
+        }
+        if mhz > self.aiclk_max {
+            return Err(PowerError::AiclkOutOfRange {
+                requested: mhz,
+                max: self.aiclk_max,
+            });
+        }
+        Ok(())
+    }
+}
+
+/// The current operating point of the chip.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct PowerStatus {
+    /// AICLK in MHz.
+    pub aiclk: u32,
+    /// Power draw in watts.
+    pub power: f64,
+    /// Current draw in amperes.
+    pub current: f64,
+    /// ASIC temperature in degrees celsius.
+    pub asic_temperature: f64,
+    /// Bitmask of the throttlers which are currently limiting the clock.
+    pub throttler: u32,
+    pub limits: PowerLimits,
+}
+
+impl PowerStatus {
+    pub fn from_telemetry(arch: Arch, telemetry: &Telemetry) -> Self {
+        Self {
+            aiclk: telemetry.ai_clk(),
+            power: telemetry.power(),
+            current: telemetry.current(),
+            asic_temperature: telemetry.asic_temperature_for(arch),
+            throttler: telemetry.throttler,
+            limits: PowerLimits::from_telemetry(arch, telemetry),
+        }
+    }
+}
+
+/// Clock and power state requests for a single chip.
+pub struct PowerControl<'a, C: ChipImpl + ?Sized> {
+    chip: &'a C,
+}
+
+impl<'a, C: ChipImpl + ?Sized> PowerControl<'a, C> {
+    pub fn new(chip: &'a C) -> Self {
+        Self { chip }
+    }
+
+    pub fn status(&self) -> Result<PowerStatus, PlatformError> {
+        let telemetry = self.chip.get_telemetry()?;
+        Ok(PowerStatus {
+            limits: self.board_limits(&telemetry)?,
+            ..PowerStatus::from_telemetry(self.chip.get_arch(), &telemetry)
+        })
+    }
+
+    pub fn limits(&self) -> Result<PowerLimits, PlatformError> {
+        self.board_limits(&self.chip.get_telemetry()?)
+    }
+
+    /// The limits from `telemetry`, or from the fw_table on blackhole.
+    fn board_limits(&self, telemetry: &Telemetry) -> Result<PowerLimits, PlatformError> {
+        match self.chip.as_any().downcast_ref::<Blackhole>() {
+            Some(blackhole) => {
+                let table = blackhole.fw_table()?;
+                Ok(PowerLimits::from_chip_limits(
+                    &table.chip_limits.unwrap_or_default(),
+                ))
+            }
+            None => Ok(PowerLimits::from_telemetry(self.chip.get_arch(), telemetry)),
+        }
+    }
+
+    /// Ask the firmware for the current AICLK in MHz.
+    pub fn aiclk(&self) -> Result<u32, PlatformError> {
+        arc_request(self.chip, "get aiclk", TypedArcMsg::GetAiclk)
+    }
+
+    /// Move the chip to the busy or one of the idle clock states.
+    pub fn set_power_state(&self, state: PowerState) -> Result<(), PlatformError> {
+        let arch = self.chip.get_arch();
+        if matches!((arch, state), (Arch::Blackhole, PowerState::ShortIdle)) {
+            return Err(PowerError::Unsupported {
+                arch,
+                what: "the short idle power state",
+            }
+            .into());
+        }
+
+        arc_request(
+            self.chip,
+            "set power state",
+            TypedArcMsg::SetPowerState(state),
+        )?;
+        Ok(())
+    }
+
+    /// Pin AICLK to `mhz`, the throttlers will not move it until [`Self::clear_forced_aiclk`]
+    /// is called. The request is checked against the board limit first.
+    pub fn force_aiclk(&self, mhz: u32) -> Result<(), PlatformError> {
+        if mhz == 0 {
+            return self.clear_forced_aiclk();
+        }
+        self.limits()?.check_aiclk(mhz)?;
+
+        arc_request(self.chip, "force aiclk", TypedArcMsg::ForceAiclk { mhz })?;
+        Ok(())
+    }
+
+    /// Hand AICLK back to the firmware.
+    pub fn clear_forced_aiclk(&self) -> Result<(), PlatformError> {
+        arc_request(
+            self.chip,
+            "clear forced aiclk",
+            TypedArcMsg::ForceAiclk { mhz: 0 },
+        )?;
+        Ok(())
+    }
+
+    fn check_throttle_supported(&self) -> Result<(), PlatformError> {
+        match self.chip.get_arch() {
+            Arch::Blackhole => Ok(()),
+            arch => Err(PowerError::Unsupported {
+                arch,
+                what: "throttling",
+            }
+            .into()),
+        }
+    }
+
+    /// Throttle the chip by pinning AICLK to the board minimum, undone by
+    /// [`Self::clear_throttle`]. Only supported on blackhole, and refused if its fw_table does
+    /// not have a minimum.
+    pub fn force_throttle(&self) -> Result<(), PlatformError> {
+        self.check_throttle_supported()?;
+        let mhz = match self.limits()?.aiclk_min {
+            0 => return Err(PowerError::UnknownLimit("minimum AICLK").into()),
+            mhz => mhz,
+        };
+
+        arc_request(self.chip, "force throttle", TypedArcMsg::ForceAiclk { mhz })?;
+        Ok(())
+    }
+
+    /// Stop throttling and hand AICLK back to the firmware.
+    pub fn clear_throttle(&self) -> Result<(), PlatformError> {
+        self.check_throttle_supported()?;
+        self.clear_forced_aiclk()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::chip::communication::fault_injection::test::wormhole;
+
+    #[test]
+    fn limits() {
+        let telemetry = Telemetry {
+            aiclk: 1000 << 16 | 500,
+            tdp: 150 << 16 | 42,
+            tdc: 160 << 16 | 50,
+            thm_limits: 75,
+            vdd_limits: 950 << 16 | 700,
+            ..Default::default()
+        };
+
+        let status = PowerStatus::from_telemetry(Arch::Wormhole, &telemetry);
+        assert_eq!(status.aiclk, 500);
+        assert_eq!(status.power, 42.0);
+        assert_eq!(
+            status.limits,
+            PowerLimits {
+                aiclk_min: 0,
+                aiclk_max: 1000,
+                tdp_limit: 150,
+                tdc_limit: 160,
+                thm_limit: 75,
+                vdd_min: 700,
+                vdd_max: 950,
+            }
+        );
+
+        assert!(status.limits.check_aiclk(1000).is_ok());
+        assert!(status.limits.check_aiclk(0).is_ok());
+        assert!(matches!(
+            status.limits.check_aiclk(1001),
+            Err(PowerError::AiclkOutOfRange {
+                requested: 1001,
+                max: 1000
+            })
+        ));
+
+        // Refuse to force a clock that can't be validated
+        assert!(matches!(
+            PowerLimits::default().check_aiclk(5000),
+            Err(PowerError::UnknownLimit(_))
+        ));
+        assert!(PowerLimits::default().check_aiclk(0).is_ok());
+    }
+
+    #[test]
+    fn blackhole_telemetry() {
+        let telemetry = Telemetry {
+            aiclk: 800,
+            tdp: 42,
+            asic_temperature: (45 << 16) | 0x8000,
+            thm_limits: 90,
+            ..Default::default()
+        };
+
+        let status = PowerStatus::from_telemetry(Arch::Blackhole, &telemetry);
+        assert_eq!(status.aiclk, 800);
+        assert_eq!(status.asic_temperature, 45.5);
+        assert_eq!(status.limits.aiclk_max, 0);
+        assert_eq!(status.limits.tdp_limit, 0);
+        assert_eq!(status.limits.thm_limit, 90);
+    }
+
+    #[test]
+    fn throttle_unsupported() {
+        let wh = wormhole(vec![]);
+        let power = PowerControl::new(&wh);
+        for result in [power.force_throttle(), power.clear_throttle()] {
+            assert!(matches!(
+                result,
+                Err(PlatformError::PowerError(PowerError::Unsupported {
+                    arch: Arch::Wormhole,
+                    what: "throttling",
+                }))
+            ));
+        }
+    }
+}
@@ -20,6 +20,10 @@ impl ChipComms for RemoteArcIf {
         axi_translate(self.axi_data.as_ref(), addr)
     }
 
+    fn eth_addr(&self) -> Option<EthAddr> {
+        Some(self.addr)
+    }
+
     fn axi_read(
         &self,
         chip_if: &dyn ChipInterface,
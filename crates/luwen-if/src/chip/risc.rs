@@ -0,0 +1,460 @@
+// SPDX-FileCopyrightText: © 2024 Tenstorrent Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Control over the individual RISC-V cores of tensix and ethernet cores, enough to load and
+//! start a small program without the rest of the runtime.
+//!
+//! A core is given by its noc0 coordinates. Programs are 32 bit little endian RISC-V ELFs, each
+//! loadable segment is written to its load (physical) address. Segments which run from a core's
+//! local memory have to be linked with a load address in L1 and copied over by the program,
+//! the same as firmware does.
+//!
+//! Results are read back from a mailbox the program writes to in L1, see [`read_mailbox`]. The
+//! debug registers of the RISC cores are not described in this tree, so they can't be read.
+
+use std::{fmt, str::FromStr};
+
+use thiserror::Error;
+
+use super::{tensix::tensix_harvest_mask, ChipImpl, HlComms, TensixGrid};
+
+/// Holds the RISC cores of a tensix or ethernet core in soft reset, one bit per core.
+pub const SOFT_RESET_ADDR: u64 = 0xFFB1_21B0;
+
+const TRISC0_RESET_PC_ADDR: u64 = 0xFFB1_2228;
+const TRISC_RESET_PC_OVERRIDE_ADDR: u64 = 0xFFB1_2234;
+const NCRISC_RESET_PC_ADDR: u64 = 0xFFB1_2238;
+const NCRISC_RESET_PC_OVERRIDE_ADDR: u64 = 0xFFB1_223C;
+
+const NOC_ID: u8 = 0;
+
+#[derive(Error, Debug)]
+pub enum RiscError {
+    #[error("Unknown RISC core {0}, expected brisc, ncrisc, trisc0-2 or erisc")]
+    UnknownRisc(String),
+
+    #[error("Not an ELF file")]
+    NotElf,
+
+    #[error("Only 32 bit little endian RISC-V ELFs can be loaded, {0}")]
+    UnsupportedElf(&'static str),
+
+    #[error("ELF is truncated, {0} is outside the file")]
+    TruncatedElf(&'static str),
+
+    #[error("The erisc starts in the base ethernet firmware, its entry point can't be moved")]
+    EriscEntry,
+
+    #[error("Can't broadcast to a chip with tensix {0:?} harvested")]
+    HarvestedBroadcast(Vec<u8>),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum RiscCore {
+    Brisc,
+    Ncrisc,
+    Trisc0,
+    Trisc1,
+    Trisc2,
+    /// The RISC of an ethernet core.
+    Erisc,
+}
+
+impl RiscCore {
+    /// The RISC cores of a tensix.
+    pub const TENSIX: [RiscCore; 5] = [
+        RiscCore::Brisc,
+        RiscCore::Ncrisc,
+        RiscCore::Trisc0,
+        RiscCore::Trisc1,
+        RiscCore::Trisc2,
+    ];
+
+    /// The bit holding this core in [`SOFT_RESET_ADDR`].
+    pub fn reset_bit(&self) -> u32 {
+        match self {
+            RiscCore::Brisc | RiscCore::Erisc => 1 << 11,
+            RiscCore::Trisc0 => 1 << 12,
+            RiscCore::Trisc1 => 1 << 13,
+            RiscCore::Trisc2 => 1 << 14,
+            RiscCore::Ncrisc => 1 << 18,
+        }
+    }
+}
+
+impl fmt::Display for RiscCore {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            RiscCore::Brisc => "brisc",
+            RiscCore::Ncrisc => "ncrisc",
+            RiscCore::Trisc0 => "trisc0",
+            RiscCore::Trisc1 => "trisc1",
+            RiscCore::Trisc2 => "trisc2",
+            RiscCore::Erisc => "erisc",
+        })
+    }
+}
+
+impl FromStr for RiscCore {
+    type Err = RiscError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "brisc" => Ok(RiscCore::Brisc),
+            "ncrisc" => Ok(RiscCore::Ncrisc),
+            "trisc0" => Ok(RiscCore::Trisc0),
+            "trisc1" => Ok(RiscCore::Trisc1),
+            "trisc2" => Ok(RiscCore::Trisc2),
+            "erisc" => Ok(RiscCore::Erisc),
+            _ => Err(RiscError::UnknownRisc(s.to_string())),
+        }
+    }
+}
+
+fn reset_mask(riscs: &[RiscCore]) -> u32 {
+    riscs.iter().fold(0, |mask, risc| mask | risc.reset_bit())
+}
+
+/// Hold `riscs` in soft reset on each of `cores`, the other RISC cores are left alone.
+pub fn assert_risc_reset(
+    chip: &(impl HlComms + ?Sized),
+    cores: &[(u8, u8)],
+    riscs: &[RiscCore],
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mask = reset_mask(riscs);
+    for &(x, y) in cores {
+        let reset = chip.noc_read32(NOC_ID, x, y, SOFT_RESET_ADDR)?;
+        chip.noc_write32(NOC_ID, x, y, SOFT_RESET_ADDR, reset | mask)?;
+    }
+
+    Ok(())
+}
+
+/// Release `riscs` from soft reset on each of `cores`, they start running from their entry point.
+pub fn deassert_risc_reset(
+    chip: &(impl HlComms + ?Sized),
+    cores: &[(u8, u8)],
+    riscs: &[RiscCore],
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mask = reset_mask(riscs);
+    for &(x, y) in cores {
+        let reset = chip.noc_read32(NOC_ID, x, y, SOFT_RESET_ADDR)?;
+        chip.noc_write32(NOC_ID, x, y, SOFT_RESET_ADDR, reset & !mask)?;
+    }
+
+    Ok(())
+}
+
+pub fn risc_in_reset(
+    chip: &(impl HlComms + ?Sized),
+    (x, y): (u8, u8),
+    risc: RiscCore,
+) -> Result<bool, Box<dyn std::error::Error>> {
+    Ok(chip.noc_read32(NOC_ID, x, y, SOFT_RESET_ADDR)? & risc.reset_bit() != 0)
+}
+
+/// Make `risc` start at `entry` when it next leaves reset.
+///
+/// The trisc and ncrisc cores have a reset pc register which is overridden. The brisc always
+/// starts at address 0, so a jump to `entry` is written there instead. The erisc starts at
+/// address 0 as well, but that is where the base ethernet firmware lives so only an entry of 0
+/// is accepted for it.
+pub fn set_risc_entry(
+    chip: &(impl HlComms + ?Sized),
+    (x, y): (u8, u8),
+    risc: RiscCore,
+    entry: u32,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let (pc_addr, override_addr, override_bit) = match risc {
+        RiscCore::Erisc if entry != 0 => return Err(RiscError::EriscEntry.into()),
+        RiscCore::Brisc | RiscCore::Erisc => {
+            if entry != 0 {
+                chip.noc_write(NOC_ID, x, y, 0, &jump_to(entry))?;
+            }
+            return Ok(());
+        }
+        RiscCore::Ncrisc => (NCRISC_RESET_PC_ADDR, NCRISC_RESET_PC_OVERRIDE_ADDR, 1),
+        RiscCore::Trisc0 => (TRISC0_RESET_PC_ADDR, TRISC_RESET_PC_OVERRIDE_ADDR, 1),
+        RiscCore::Trisc1 => (TRISC0_RESET_PC_ADDR + 4, TRISC_RESET_PC_OVERRIDE_ADDR, 2),
+        RiscCore::Trisc2 => (TRISC0_RESET_PC_ADDR + 8, TRISC_RESET_PC_OVERRIDE_ADDR, 4),
+    };
+
+    chip.noc_write32(NOC_ID, x, y, pc_addr, entry)?;
+    let overrides = chip.noc_read32(NOC_ID, x, y, override_addr)?;
+    chip.noc_write32(NOC_ID, x, y, override_addr, overrides | override_bit)?;
+
+    Ok(())
+}
+
+/// `lui t0, %hi(entry)` followed by `jalr zero, %lo(entry)(t0)`, which reaches any address.
+fn jump_to(entry: u32) -> [u8; 8] {
+    let hi = entry.wrapping_add(0x800) & 0xFFFF_F000;
+    let lo = entry & 0xFFF;
+    let lui = hi | (5 << 7) | 0x37;
+    let jalr = (lo << 20) | (5 << 15) | 0x67;
+
+    let mut code = [0; 8];
+    code[..4].copy_from_slice(&lui.to_le_bytes());
+    code[4..].copy_from_slice(&jalr.to_le_bytes());
+    code
+}
+
+/// A loadable part of an ELF, `data` is already padded out to the size in memory.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ElfSegment {
+    pub addr: u64,
+    pub data: Vec<u8>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RiscElf {
+    pub entry: u32,
+    pub segments: Vec<ElfSegment>,
+}
+
+impl RiscElf {
+    pub fn parse(elf: &[u8]) -> Result<Self, RiscError> {
+        const PT_LOAD: u32 = 1;
+        const EM_RISCV: u16 = 243;
+
+        let u16_at = |offset: usize, what| {
+            elf.get(offset..offset + 2)
+                .map(|v| u16::from_le_bytes(v.try_into().unwrap()))
+                .ok_or(RiscError::TruncatedElf(what))
+        };
+        let u32_at = |offset: usize, what| {
+            elf.get(offset..offset + 4)
+                .map(|v| u32::from_le_bytes(v.try_into().unwrap()))
+                .ok_or(RiscError::TruncatedElf(what))
+        };
+
+        if !elf.starts_with(b"\x7fELF") {
+            return Err(RiscError::NotElf);
+        }
+        if elf.get(4) != Some(&1) {
+            return Err(RiscError::UnsupportedElf("the file is not 32 bit"));
+        }
+        if elf.get(5) != Some(&1) {
+            return Err(RiscError::UnsupportedElf("the file is not little endian"));
+        }
+        if u16_at(18, "the header")? != EM_RISCV {
+            return Err(RiscError::UnsupportedElf("the file is not for RISC-V"));
+        }
+
+        let entry = u32_at(24, "the header")?;
+        let phoff = u32_at(28, "the header")? as usize;
+        let phentsize = u16_at(42, "the header")? as usize;
+        let phnum = u16_at(44, "the header")? as usize;
+
+        let mut segments = Vec::new();
+        for index in 0..phnum {
+            let header = phoff + index * phentsize;
+            if u32_at(header, "a program header")? != PT_LOAD {
+                continue;
+            }
+
+            let offset = u32_at(header + 4, "a program header")? as usize;
+            let addr = u32_at(header + 12, "a program header")?;
+            let file_size = u32_at(header + 16, "a program header")? as usize;
+            let mem_size = u32_at(header + 20, "a program header")? as usize;
+            if mem_size == 0 {
+                continue;
+            }
+
+            let mut data = elf
+                .get(offset..offset + file_size)
+                .ok_or(RiscError::TruncatedElf("a segment"))?
+                .to_vec();
+            data.resize(mem_size.max(file_size), 0);
+
+            segments.push(ElfSegment {
+                addr: addr as u64,
+                data,
+            });
+        }
+
+        Ok(Self { entry, segments })
+    }
+}
+
+/// Write the segments of `elf` to each of `cores`. The RISC cores which run it should be held in
+/// reset while it is loaded.
+///
+/// With `broadcast` every segment is written once with a noc broadcast, which reaches every
+/// tensix on the chip and not only `cores`. The broadcast also reaches harvested cores, so it
+/// is refused on chips with any tensix harvested.
+pub fn load_elf(
+    chip: &(impl ChipImpl + ?Sized),
+    cores: &[(u8, u8)],
+    elf: &RiscElf,
+    broadcast: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    if broadcast {
+        let harvested = TensixGrid::new(chip.get_arch(), tensix_harvest_mask(chip)?).harvested();
+        if !harvested.is_empty() {
+            return Err(RiscError::HarvestedBroadcast(harvested).into());
+        }
+    }
+
+    for segment in &elf.segments {
+        if broadcast {
+            chip.noc_broadcast(NOC_ID, segment.addr, &segment.data)?;
+        } else {
+            for &(x, y) in cores {
+                chip.noc_write(NOC_ID, x, y, segment.addr, &segment.data)?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Load `elf` onto each of `cores` and start it on `risc`: the risc is put in reset, the program
+/// loaded, its entry point set and the risc released again.
+pub fn run_elf(
+    chip: &(impl ChipImpl + ?Sized),
+    cores: &[(u8, u8)],
+    risc: RiscCore,
+    elf: &RiscElf,
+    broadcast: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    assert_risc_reset(chip, cores, &[risc])?;
+    load_elf(chip, cores, elf, broadcast)?;
+    for &core in cores {
+        set_risc_entry(chip, core, risc, elf.entry)?;
+    }
+    deassert_risc_reset(chip, cores, &[risc])
+}
+
+/// Read `count` words at `addr` in the L1 of each of `cores`, i.e. a mailbox a program writes
+/// its status or results to. The layout of the mailbox is up to the program.
+pub fn read_mailbox(
+    chip: &(impl HlComms + ?Sized),
+    cores: &[(u8, u8)],
+    addr: u64,
+    count: usize,
+) -> Result<Vec<Vec<u32>>, Box<dyn std::error::Error>> {
+    let mut mailboxes = Vec::with_capacity(cores.len());
+    for &(x, y) in cores {
+        let mut data = vec![0; count * 4];
+        chip.noc_read(NOC_ID, x, y, addr, &mut data)?;
+        mailboxes.push(
+            data.chunks_exact(4)
+                .map(|word| u32::from_le_bytes(word.try_into().unwrap()))
+                .collect(),
+        );
+    }
+
+    Ok(mailboxes)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::chip::{communication::fault_injection::test::wormhole, Chip, ChipImpl};
+
+    use super::*;
+
+    /// An ELF with one program segment of `code` at `addr` followed by `bss` zeroed bytes.
+    fn build_elf(entry: u32, addr: u32, code: &[u8], bss: u32) -> Vec<u8> {
+        let mut elf = vec![0; 52 + 32];
+        elf[..4].copy_from_slice(b"\x7fELF");
+        elf[4] = 1;
+        elf[5] = 1;
+        elf[18..20].copy_from_slice(&243u16.to_le_bytes());
+        elf[24..28].copy_from_slice(&entry.to_le_bytes());
+        elf[28..32].copy_from_slice(&52u32.to_le_bytes());
+        elf[42..44].copy_from_slice(&32u16.to_le_bytes());
+        elf[44..46].copy_from_slice(&1u16.to_le_bytes());
+
+        let header = &mut elf[52..];
+        header[..4].copy_from_slice(&1u32.to_le_bytes());
+        header[4..8].copy_from_slice(&84u32.to_le_bytes());
+        header[8..12].copy_from_slice(&addr.to_le_bytes());
+        header[12..16].copy_from_slice(&addr.to_le_bytes());
+        header[16..20].copy_from_slice(&(code.len() as u32).to_le_bytes());
+        header[20..24].copy_from_slice(&(code.len() as u32 + bss).to_le_bytes());
+
+        elf.extend_from_slice(code);
+        elf
+    }
+
+    #[test]
+    fn parse_elf() {
+        let elf = RiscElf::parse(&build_elf(0x1000, 0x1000, &[1, 2, 3, 4], 4)).unwrap();
+        assert_eq!(elf.entry, 0x1000);
+        assert_eq!(
+            elf.segments,
+            vec![ElfSegment {
+                addr: 0x1000,
+                data: vec![1, 2, 3, 4, 0, 0, 0, 0],
+            }]
+        );
+
+        assert!(matches!(RiscElf::parse(b"MZ"), Err(RiscError::NotElf)));
+        let mut truncated = build_elf(0x1000, 0x1000, &[1, 2, 3, 4], 0);
+        truncated.truncate(86);
+        assert!(matches!(
+            RiscElf::parse(&truncated),
+            Err(RiscError::TruncatedElf(_))
+        ));
+
+        assert_eq!("NCRISC".parse::<RiscCore>().unwrap(), RiscCore::Ncrisc);
+        assert!("trisc3".parse::<RiscCore>().is_err());
+    }
+
+    #[test]
+    fn jump() {
+        // lui t0, 0x1; jalr zero, -0x800(t0)
+        assert_eq!(
+            jump_to(0x800),
+            [0xb7, 0x12, 0x00, 0x00, 0x67, 0x80, 0x02, 0x80]
+        );
+    }
+
+    #[test]
+    fn run() {
+        let chip = Chip::from(Box::new(wormhole(Vec::new())) as Box<dyn ChipImpl>);
+
+        let cores = [(1, 1), (6, 7)];
+        let elf = RiscElf::parse(&build_elf(0x4000, 0x4000, &[1, 2, 3, 4], 0)).unwrap();
+
+        // The brisc is held in reset throughout
+        assert_risc_reset(&chip, &cores, &RiscCore::TENSIX).unwrap();
+        run_elf(&chip, &cores, RiscCore::Ncrisc, &elf, false).unwrap();
+
+        for (x, y) in cores {
+            assert_eq!(chip.noc_read32(0, x, y, 0x4000).unwrap(), 0x04030201);
+            assert_eq!(
+                chip.noc_read32(0, x, y, NCRISC_RESET_PC_ADDR).unwrap(),
+                0x4000
+            );
+            assert_eq!(
+                chip.noc_read32(0, x, y, NCRISC_RESET_PC_OVERRIDE_ADDR)
+                    .unwrap(),
+                1
+            );
+            assert!(!risc_in_reset(&chip, (x, y), RiscCore::Ncrisc).unwrap());
+            assert!(risc_in_reset(&chip, (x, y), RiscCore::Brisc).unwrap());
+        }
+
+        // The brisc gets a jump to the entry point at address 0 instead
+        set_risc_entry(&chip, (1, 1), RiscCore::Brisc, 0x4000).unwrap();
+        let mut code = [0; 8];
+        chip.noc_read(0, 1, 1, 0, &mut code).unwrap();
+        assert_eq!(code, jump_to(0x4000));
+
+        // Which would overwrite the base firmware of an erisc
+        let err = set_risc_entry(&chip, (9, 0), RiscCore::Erisc, 0x4000).unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<RiscError>(),
+            Some(RiscError::EriscEntry)
+        ));
+        assert_eq!(chip.noc_read32(0, 9, 0, 0).unwrap(), 0);
+
+        chip.noc_write32(0, 6, 7, 0x5000, 7).unwrap();
+        assert_eq!(
+            read_mailbox(&chip, &cores, 0x5000, 2).unwrap(),
+            vec![vec![0, 0], vec![7, 0]]
+        );
+    }
+}
@@ -1,7 +1,9 @@
 // SPDX-FileCopyrightText: © 2023 Tenstorrent Inc.
 // SPDX-License-Identifier: Apache-2.0
 
-use super::{AxiError, HlCommsInterface};
+use std::time::Duration;
+
+use super::{poll, AxiError, HlCommsInterface, PollOptions};
 use crate::{arc_msg::TypedArcMsg, ArcMsg, ChipImpl};
 
 pub struct Spi {
@@ -57,6 +59,13 @@ const SPI_SR_RFNE: u32 = 0x1 << 3;
 const SPI_SR_TFE: u32 = 0x1 << 2;
 const SPI_SR_BUSY: u32 = 0x1 << 0;
 
+/// The controller fifos drain within a few spi clocks.
+const SPI_FIFO_TIMEOUT: Duration = Duration::from_secs(1);
+/// Page programs and status register writes take milliseconds, sector erases up to ~0.5s.
+const SPI_WRITE_TIMEOUT: Duration = Duration::from_secs(1);
+const SPI_ERASE_TIMEOUT: Duration = Duration::from_secs(5);
+const SPI_CHIP_ERASE_TIMEOUT: Duration = Duration::from_secs(300);
+
 const SPI_PAGE_ERASE_SIZE: u32 = 0x1000;
 const SPI_ROM_SIZE: u32 = 1 << 24;
 const ARC_SPI_CHUNK_SIZE: u32 = SPI_PAGE_ERASE_SIZE;
@@ -128,6 +137,42 @@ impl Spi {
         Ok(())
     }
 
+    /// Wait for everything written to the data register to be sent.
+    fn wait_for_tx(&self, chip: &impl ChipImpl) -> Result<(), Box<dyn std::error::Error>> {
+        let options = PollOptions::busy(SPI_FIFO_TIMEOUT);
+        chip.wait_until(self.spi_sr, |sr| sr & SPI_SR_TFE == SPI_SR_TFE, &options)?;
+        chip.wait_until(self.spi_sr, |sr| sr & SPI_SR_BUSY != SPI_SR_BUSY, &options)?;
+
+        Ok(())
+    }
+
+    /// Wait for a frame to show up in the receive fifo.
+    fn wait_for_rx(&self, chip: &impl ChipImpl) -> Result<(), Box<dyn std::error::Error>> {
+        chip.wait_until(
+            self.spi_sr,
+            |sr| sr & SPI_SR_RFNE != 0,
+            &PollOptions::busy(SPI_FIFO_TIMEOUT),
+        )?;
+
+        Ok(())
+    }
+
+    /// Wait for the flash to clear its write in progress bit.
+    fn wait_for_flash(
+        &self,
+        chip: &impl ChipImpl,
+        timeout: Duration,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        poll(
+            "spi flash to finish writing",
+            &PollOptions::new(timeout),
+            || self.read_status(chip, SPI_RD_STATUS_CMD),
+            |status| status & 0x1 == 0,
+        )??;
+
+        Ok(())
+    }
+
     pub fn read_status(
         &self,
         chip: &impl ChipImpl,
@@ -151,11 +196,7 @@ impl Spi {
         chip.axi_write32(self.spi_ser, spi_ser_slave_enable(0))?;
 
         // Read value
-        loop {
-            if (chip.axi_read32(self.spi_sr)? & SPI_SR_RFNE) != 0 {
-                break;
-            }
-        }
+        self.wait_for_rx(chip)?;
         let read_buf = (chip.axi_read32(self.spi_dr)? & 0xff) as u8;
 
         chip.axi_write32(self.spi_ser, spi_ser_slave_disable(0))?;
@@ -189,18 +230,7 @@ impl Spi {
         chip.axi_write32(self.spi_ser, spi_ser_slave_enable(0))?;
 
         // Add some delay to make sure the above propagates
-        loop {
-            let spi_status = chip.axi_read32(self.spi_sr)?;
-            if spi_status & SPI_SR_TFE == SPI_SR_TFE {
-                break;
-            }
-        }
-        loop {
-            let spi_status = chip.axi_read32(self.spi_sr)?;
-            if spi_status & SPI_SR_BUSY != SPI_SR_BUSY {
-                break;
-            }
-        }
+        self.wait_for_tx(chip)?;
 
         chip.axi_write32(self.spi_ser, spi_ser_slave_disable(0))?;
 
@@ -227,28 +257,12 @@ impl Spi {
         chip.axi_write32(self.spi_ser, spi_ser_slave_enable(0))?;
 
         // Add some delay to make sure the above propagates
-        loop {
-            let spi_status = chip.axi_read32(self.spi_sr)?;
-            if spi_status & SPI_SR_TFE == SPI_SR_TFE {
-                break;
-            }
-        }
-        loop {
-            let spi_status = chip.axi_read32(self.spi_sr)?;
-            if spi_status & SPI_SR_BUSY != SPI_SR_BUSY {
-                break;
-            }
-        }
+        self.wait_for_tx(chip)?;
 
         chip.axi_write32(self.spi_ser, spi_ser_slave_disable(0))?;
 
-        // Wait for lock operation to complete
-        loop {
-            let busy = self.read_status(chip, SPI_RD_STATUS_CMD)? & 0x1;
-            if busy != 0x1 {
-                break;
-            }
-        }
+        // Wait for lock to complete
+        self.wait_for_flash(chip, SPI_WRITE_TIMEOUT)?;
 
         Ok(())
     }
@@ -292,12 +306,7 @@ impl Spi {
 
             // read frames
             for _ in 0..frames {
-                loop {
-                    let spi_status = chip.axi_read32(self.spi_sr)?;
-                    if spi_status & SPI_SR_RFNE != 0 {
-                        break;
-                    }
-                }
+                self.wait_for_rx(chip)?;
 
                 read[frame_index] = (chip.axi_read32(self.spi_dr)? & 0xff) as u8;
 
@@ -340,18 +349,7 @@ impl Spi {
             chip.axi_write32(self.spi_ser, spi_ser_slave_enable(0))?;
 
             // Add some delay to make sure enable propagates
-            loop {
-                let spi_status = chip.axi_read32(self.spi_sr)?;
-                if spi_status & SPI_SR_TFE == SPI_SR_TFE {
-                    break;
-                }
-            }
-            loop {
-                let spi_status = chip.axi_read32(self.spi_sr)?;
-                if spi_status & SPI_SR_BUSY != SPI_SR_BUSY {
-                    break;
-                }
-            }
+            self.wait_for_tx(chip)?;
             chip.axi_write32(self.spi_ser, spi_ser_slave_disable(0))?;
 
             // Write address and values
@@ -367,29 +365,13 @@ impl Spi {
             chip.axi_write32(self.spi_ser, spi_ser_slave_enable(0))?;
 
             // Add some delay to make sure that the enable above propogates
-            loop {
-                let spi_status = chip.axi_read32(self.spi_sr)?;
-                if spi_status & SPI_SR_TFE == SPI_SR_TFE {
-                    break;
-                }
-            }
-            loop {
-                let spi_status = chip.axi_read32(self.spi_sr)?;
-                if spi_status & SPI_SR_BUSY != SPI_SR_BUSY {
-                    break;
-                }
-            }
+            self.wait_for_tx(chip)?;
 
             chip.axi_write32(self.spi_ser, spi_ser_slave_disable(0))?;
             addr += frames;
 
             // Wait for write to complete
-            loop {
-                let busy = self.read_status(chip, SPI_RD_STATUS_CMD)? & 0x1;
-                if busy != 0x1 {
-                    break;
-                }
-            }
+            self.wait_for_flash(chip, SPI_WRITE_TIMEOUT)?;
         }
 
         Ok(())
@@ -412,18 +394,7 @@ impl Spi {
         chip.axi_write32(self.spi_ser, spi_ser_slave_enable(0))?;
 
         // Add some delay to make sure enable propagates
-        loop {
-            let spi_status = chip.axi_read32(self.spi_sr)?;
-            if spi_status & SPI_SR_TFE == SPI_SR_TFE {
-                break;
-            }
-        }
-        loop {
-            let spi_status = chip.axi_read32(self.spi_sr)?;
-            if spi_status & SPI_SR_BUSY != SPI_SR_BUSY {
-                break;
-            }
-        }
+        self.wait_for_tx(chip)?;
         chip.axi_write32(self.spi_ser, spi_ser_slave_disable(0))?;
 
         // Write sector to erase
@@ -434,27 +405,11 @@ impl Spi {
         chip.axi_write32(self.spi_ser, spi_ser_slave_enable(0))?;
 
         // Add some delay to make sure enable propagates
-        loop {
-            let spi_status = chip.axi_read32(self.spi_sr)?;
-            if spi_status & SPI_SR_TFE == SPI_SR_TFE {
-                break;
-            }
-        }
-        loop {
-            let spi_status = chip.axi_read32(self.spi_sr)?;
-            if spi_status & SPI_SR_BUSY != SPI_SR_BUSY {
-                break;
-            }
-        }
+        self.wait_for_tx(chip)?;
         chip.axi_write32(self.spi_ser, spi_ser_slave_disable(0))?;
 
         // Wait for erase to complete
-        loop {
-            let busy = self.read_status(chip, SPI_RD_STATUS_CMD)? & 0x1;
-            if busy != 0x1 {
-                break;
-            }
-        }
+        self.wait_for_flash(chip, SPI_ERASE_TIMEOUT)?;
 
         Ok(())
     }
@@ -477,18 +432,7 @@ impl Spi {
         chip.axi_write32(self.spi_ser, spi_ser_slave_enable(0))?;
 
         // Add some delay to make sure enable propagates
-        loop {
-            let spi_status = chip.axi_read32(self.spi_sr)?;
-            if spi_status & SPI_SR_TFE == SPI_SR_TFE {
-                break;
-            }
-        }
-        loop {
-            let spi_status = chip.axi_read32(self.spi_sr)?;
-            if spi_status & SPI_SR_BUSY != SPI_SR_BUSY {
-                break;
-            }
-        }
+        self.wait_for_tx(chip)?;
         chip.axi_write32(self.spi_ser, spi_ser_slave_disable(0))?;
 
         // Write chip erase command
@@ -496,27 +440,11 @@ impl Spi {
         chip.axi_write32(self.spi_ser, spi_ser_slave_enable(0))?;
 
         // Add some delay to make sure enable propagates
-        loop {
-            let spi_status = chip.axi_read32(self.spi_sr)?;
-            if spi_status & SPI_SR_TFE == SPI_SR_TFE {
-                break;
-            }
-        }
-        loop {
-            let spi_status = chip.axi_read32(self.spi_sr)?;
-            if spi_status & SPI_SR_BUSY != SPI_SR_BUSY {
-                break;
-            }
-        }
+        self.wait_for_tx(chip)?;
         chip.axi_write32(self.spi_ser, spi_ser_slave_disable(0))?;
 
         // Wait for erase to complete
-        loop {
-            let busy = self.read_status(chip, SPI_RD_STATUS_CMD)? & 0x1;
-            if busy != 0x1 {
-                break;
-            }
-        }
+        self.wait_for_flash(chip, SPI_CHIP_ERASE_TIMEOUT)?;
 
         Ok(())
     }
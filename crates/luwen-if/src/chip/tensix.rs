@@ -0,0 +1,598 @@
+// SPDX-FileCopyrightText: © 2024 Tenstorrent Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! The tensix grid of a chip after harvesting, and a sweep which verifies the L1 of every
+//! functional tensix core.
+//!
+//! Physical coordinates are the noc0 coordinates of a core. Logical coordinates only count the
+//! functional cores, so (0, 0) is the top left tensix which survived harvesting.
+
+use std::fmt;
+
+use luwen_core::Arch;
+
+use super::{ChipImpl, HlComms, MemoryPattern};
+use crate::error::PlatformError;
+
+struct TensixLayout {
+    cols: &'static [u8],
+    rows: &'static [u8],
+    /// The physical row (or column on blackhole) removed by each bit of the harvesting mask.
+    harvesting: &'static [u8],
+    harvests_rows: bool,
+    l1_size: u64,
+}
+
+const GRAYSKULL_LAYOUT: TensixLayout = TensixLayout {
+    cols: &[1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12],
+    rows: &[1, 2, 3, 4, 5, 7, 8, 9, 10, 11],
+    harvesting: &[5, 7, 4, 8, 3, 9, 2, 10, 1, 11],
+    harvests_rows: true,
+    l1_size: 1024 * 1024,
+};
+
+const WORMHOLE_LAYOUT: TensixLayout = TensixLayout {
+    cols: &[1, 2, 3, 4, 6, 7, 8, 9],
+    rows: &[1, 2, 3, 4, 5, 7, 8, 9, 10, 11],
+    harvesting: &[11, 1, 10, 2, 9, 3, 8, 4, 7, 5],
+    harvests_rows: true,
+    l1_size: 1464 * 1024,
+};
+
+const BLACKHOLE_LAYOUT: TensixLayout = TensixLayout {
+    cols: &[1, 2, 3, 4, 5, 6, 7, 10, 11, 12, 13, 14, 15, 16],
+    rows: &[2, 3, 4, 5, 6, 7, 8, 9, 10, 11],
+    harvesting: &[1, 16, 2, 15, 3, 14, 4, 13, 5, 12, 6, 11, 7, 10],
+    harvests_rows: false,
+    l1_size: 1536 * 1024,
+};
+
+const UNKNOWN_LAYOUT: TensixLayout = TensixLayout {
+    cols: &[],
+    rows: &[],
+    harvesting: &[],
+    harvests_rows: true,
+    l1_size: 0,
+};
+
+fn layout(arch: Arch) -> &'static TensixLayout {
+    match arch {
+        Arch::Grayskull => &GRAYSKULL_LAYOUT,
+        Arch::Wormhole => &WORMHOLE_LAYOUT,
+        Arch::Blackhole => &BLACKHOLE_LAYOUT,
+        Arch::Unknown(_) => &UNKNOWN_LAYOUT,
+    }
+}
+
+/// The tensix harvesting mask of `chip`, see [`ChipImpl::harvesting`].
+pub fn tensix_harvest_mask(chip: &(impl ChipImpl + ?Sized)) -> Result<u32, PlatformError> {
+    Ok(chip.harvesting()?.tensix_mask)
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct TensixCore {
+    /// The noc0 coordinates of the core.
+    pub physical: (u8, u8),
+    /// The position of the core among the functional cores.
+    pub logical: (u8, u8),
+}
+
+impl fmt::Display for TensixCore {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}-{} (logical {}, {})",
+            self.physical.0, self.physical.1, self.logical.0, self.logical.1
+        )
+    }
+}
+
+/// The functional tensix cores of a chip.
+#[derive(Clone, Debug)]
+pub struct TensixGrid {
+    pub arch: Arch,
+    pub harvest_mask: u32,
+    cols: Vec<u8>,
+    rows: Vec<u8>,
+}
+
+impl TensixGrid {
+    pub fn new(arch: Arch, harvest_mask: u32) -> Self {
+        let layout = layout(arch);
+        let harvested = harvested(layout, harvest_mask);
+        let (cols, rows) = if layout.harvests_rows {
+            (layout.cols.to_vec(), remove(layout.rows, &harvested))
+        } else {
+            (remove(layout.cols, &harvested), layout.rows.to_vec())
+        };
+
+        Self {
+            arch,
+            harvest_mask,
+            cols,
+            rows,
+        }
+    }
+
+    /// The physical rows (or columns on blackhole) which were harvested.
+    pub fn harvested(&self) -> Vec<u8> {
+        harvested(layout(self.arch), self.harvest_mask)
+    }
+
+    /// Whether whole rows are harvested, blackhole harvests columns instead.
+    pub fn harvests_rows(&self) -> bool {
+        layout(self.arch).harvests_rows
+    }
+
+    /// Bytes of L1 in each core.
+    pub fn l1_size(&self) -> u64 {
+        layout(self.arch).l1_size
+    }
+
+    /// The number of functional columns and rows.
+    pub fn size(&self) -> (usize, usize) {
+        (self.cols.len(), self.rows.len())
+    }
+
+    /// Every functional core, row by row.
+    pub fn cores(&self) -> Vec<TensixCore> {
+        let mut cores = Vec::with_capacity(self.cols.len() * self.rows.len());
+        for (logical_y, &y) in self.rows.iter().enumerate() {
+            for (logical_x, &x) in self.cols.iter().enumerate() {
+                cores.push(TensixCore {
+                    physical: (x, y),
+                    logical: (logical_x as u8, logical_y as u8),
+                });
+            }
+        }
+
+        cores
+    }
+
+    /// None if there is no functional tensix at `physical`.
+    pub fn to_logical(&self, physical: (u8, u8)) -> Option<(u8, u8)> {
+        let x = self.cols.iter().position(|x| *x == physical.0)?;
+        let y = self.rows.iter().position(|y| *y == physical.1)?;
+        Some((x as u8, y as u8))
+    }
+
+    pub fn to_physical(&self, logical: (u8, u8)) -> Option<(u8, u8)> {
+        Some((
+            *self.cols.get(logical.0 as usize)?,
+            *self.rows.get(logical.1 as usize)?,
+        ))
+    }
+}
+
+fn harvested(layout: &TensixLayout, harvest_mask: u32) -> Vec<u8> {
+    let mut harvested: Vec<u8> = layout
+        .harvesting
+        .iter()
+        .enumerate()
+        .filter(|(bit, _)| harvest_mask & (1 << bit) != 0)
+        .map(|(_, line)| *line)
+        .collect();
+    harvested.sort_unstable();
+
+    harvested
+}
+
+fn remove(lines: &[u8], harvested: &[u8]) -> Vec<u8> {
+    lines
+        .iter()
+        .copied()
+        .filter(|line| !harvested.contains(line))
+        .collect()
+}
+
+#[derive(Clone, Debug)]
+pub struct L1TestOptions {
+    pub patterns: Vec<MemoryPattern>,
+    /// Where in L1 the test starts.
+    pub offset: u64,
+    /// How many bytes of L1 to test, the rest of L1 if None.
+    pub size: Option<u64>,
+    /// Write the patterns with a noc broadcast instead of to each core in turn. A broadcast
+    /// also reaches the harvested cores, so it is refused on harvested chips.
+    pub broadcast: bool,
+    /// Bytes moved per noc access.
+    pub transfer_size: usize,
+    /// Failures beyond this many per core are counted but not recorded.
+    pub max_failures: usize,
+    /// Use this harvesting mask instead of asking the chip for it.
+    pub harvest_mask: Option<u32>,
+    pub noc_id: u8,
+}
+
+impl Default for L1TestOptions {
+    fn default() -> Self {
+        Self {
+            patterns: vec![
+                MemoryPattern::WalkingOnes,
+                MemoryPattern::AddressInAddress,
+                MemoryPattern::Random { seed: 0 },
+            ],
+            offset: 0,
+            size: None,
+            broadcast: false,
+            transfer_size: 64 * 1024,
+            max_failures: 16,
+            harvest_mask: None,
+            noc_id: 0,
+        }
+    }
+}
+
+impl L1TestOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn patterns(mut self, patterns: Vec<MemoryPattern>) -> Self {
+        self.patterns = patterns;
+        self
+    }
+
+    pub fn range(mut self, offset: u64, size: Option<u64>) -> Self {
+        self.offset = offset;
+        self.size = size;
+        self
+    }
+
+    pub fn broadcast(mut self, broadcast: bool) -> Self {
+        self.broadcast = broadcast;
+        self
+    }
+
+    pub fn transfer_size(mut self, transfer_size: usize) -> Self {
+        self.transfer_size = transfer_size;
+        self
+    }
+
+    pub fn max_failures(mut self, max_failures: usize) -> Self {
+        self.max_failures = max_failures;
+        self
+    }
+
+    pub fn harvest_mask(mut self, harvest_mask: Option<u32>) -> Self {
+        self.harvest_mask = harvest_mask;
+        self
+    }
+}
+
+/// A word of L1 which did not read back what was written.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct L1Failure {
+    pub pattern: MemoryPattern,
+    pub addr: u64,
+    pub expected: u32,
+    pub actual: u32,
+}
+
+impl L1Failure {
+    /// Mask of the bits which were wrong.
+    pub fn failing_bits(&self) -> u32 {
+        self.expected ^ self.actual
+    }
+}
+
+impl fmt::Display for L1Failure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:#x} ({}): expected {:#010x}, read {:#010x}, bits {:#010x}",
+            self.addr,
+            self.pattern,
+            self.expected,
+            self.actual,
+            self.failing_bits()
+        )
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct L1CoreReport {
+    pub core: TensixCore,
+    /// Why the core could not be (fully) tested.
+    pub error: Option<String>,
+    pub failure_count: u64,
+    /// The first [`L1TestOptions::max_failures`] failures.
+    pub failures: Vec<L1Failure>,
+}
+
+impl L1CoreReport {
+    pub fn passed(&self) -> bool {
+        self.error.is_none() && self.failure_count == 0
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct L1TestReport {
+    pub grid: TensixGrid,
+    /// Bytes of L1 verified in each core.
+    pub bytes_tested: u64,
+    pub cores: Vec<L1CoreReport>,
+}
+
+impl L1TestReport {
+    pub fn passed(&self) -> bool {
+        self.cores.iter().all(L1CoreReport::passed)
+    }
+
+    pub fn bad_cores(&self) -> Vec<TensixCore> {
+        self.cores
+            .iter()
+            .filter(|core| !core.passed())
+            .map(|core| core.core)
+            .collect()
+    }
+}
+
+impl fmt::Display for L1TestReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let bad = self.bad_cores();
+        writeln!(
+            f,
+            "{} of {} tensix cores passed, {} bytes of L1 each (harvesting mask {:#x})",
+            self.cores.len() - bad.len(),
+            self.cores.len(),
+            self.bytes_tested,
+            self.grid.harvest_mask
+        )?;
+
+        for core in self.cores.iter().filter(|core| !core.passed()) {
+            write!(f, "{}: ", core.core)?;
+            if let Some(error) = &core.error {
+                writeln!(f, "{error}")?;
+            } else {
+                writeln!(f, "{} failures", core.failure_count)?;
+            }
+            for failure in &core.failures {
+                writeln!(f, "    {failure}")?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Reported after each core is verified, `done` out of `total` cores have been checked for the
+/// current pattern.
+#[derive(Clone, Copy, Debug)]
+pub struct L1TestProgress {
+    pub pattern: MemoryPattern,
+    pub core: TensixCore,
+    pub done: usize,
+    pub total: usize,
+}
+
+/// The pattern written to `core`. When every core is written separately the random pattern is
+/// seeded per core, so a write which the noc delivers to the wrong core is caught.
+fn core_pattern(pattern: MemoryPattern, core: TensixCore, broadcast: bool) -> MemoryPattern {
+    match pattern {
+        MemoryPattern::Random { seed } if !broadcast => MemoryPattern::Random {
+            seed: seed ^ ((core.physical.0 as u64) << 40 | (core.physical.1 as u64) << 32),
+        },
+        pattern => pattern,
+    }
+}
+
+/// Write and verify every pattern in the L1 of each functional tensix core of `chip`.
+///
+/// Each pattern is written to every core before any of them are read back, so that routing
+/// problems on the noc show up as well as bad SRAM. A core which can't be accessed is reported
+/// as failed and skipped for the remaining patterns.
+///
+/// The L1 is overwritten, the cores should be held in reset or idle while the test runs.
+pub fn test_l1(
+    chip: &(impl ChipImpl + ?Sized),
+    options: &L1TestOptions,
+    mut progress: impl FnMut(L1TestProgress),
+) -> Result<L1TestReport, PlatformError> {
+    let harvest_mask = match options.harvest_mask {
+        Some(harvest_mask) => harvest_mask,
+        None => tensix_harvest_mask(chip)?,
+    };
+    let grid = TensixGrid::new(chip.get_arch(), harvest_mask);
+    if options.broadcast && !grid.harvested().is_empty() {
+        let lines = if grid.harvests_rows() {
+            "rows"
+        } else {
+            "columns"
+        };
+        return Err(format!(
+            "Can't broadcast to the L1 with tensix {lines} {:?} harvested",
+            grid.harvested()
+        )
+        .into());
+    }
+
+    let l1_size = grid.l1_size();
+    let start = options.offset.min(l1_size) & !3;
+    let end = options
+        .size
+        .map(|size| start.saturating_add(size))
+        .unwrap_or(l1_size)
+        .min(l1_size)
+        & !3;
+    let transfer_size = options.transfer_size.max(4) & !3;
+
+    let mut cores: Vec<_> = grid
+        .cores()
+        .into_iter()
+        .map(|core| L1CoreReport {
+            core,
+            error: None,
+            failure_count: 0,
+            failures: Vec::new(),
+        })
+        .collect();
+
+    let mut written = vec![0; transfer_size];
+    let mut read = vec![0; transfer_size];
+    for &pattern in &options.patterns {
+        if options.broadcast {
+            let mut addr = start;
+            while addr < end {
+                let len = ((end - addr) as usize).min(transfer_size);
+                pattern.fill(addr, &mut written[..len]);
+                if let Err(err) = chip.noc_broadcast(options.noc_id, addr, &written[..len]) {
+                    let err = err.to_string();
+                    for core in cores.iter_mut().filter(|core| core.error.is_none()) {
+                        core.error = Some(err.clone());
+                    }
+                    break;
+                }
+                addr += len as u64;
+            }
+        } else {
+            for core in cores.iter_mut().filter(|core| core.error.is_none()) {
+                let (x, y) = core.core.physical;
+                let core_pattern = core_pattern(pattern, core.core, false);
+
+                let mut addr = start;
+                while addr < end {
+                    let len = ((end - addr) as usize).min(transfer_size);
+                    core_pattern.fill(addr, &mut written[..len]);
+                    if let Err(err) = chip.noc_write(options.noc_id, x, y, addr, &written[..len]) {
+                        core.error = Some(err.to_string());
+                        break;
+                    }
+                    addr += len as u64;
+                }
+            }
+        }
+
+        let total = cores.len();
+        for (done, core) in cores.iter_mut().enumerate() {
+            if core.error.is_none() {
+                if let Err(err) =
+                    verify_core(chip, options, pattern, (start, end), core, &mut read[..])
+                {
+                    core.error = Some(err.to_string());
+                }
+            }
+
+            progress(L1TestProgress {
+                pattern,
+                core: core.core,
+                done: done + 1,
+                total,
+            });
+        }
+    }
+
+    Ok(L1TestReport {
+        grid,
+        bytes_tested: end - start,
+        cores,
+    })
+}
+
+fn verify_core(
+    chip: &(impl HlComms + ?Sized),
+    options: &L1TestOptions,
+    pattern: MemoryPattern,
+    (start, end): (u64, u64),
+    report: &mut L1CoreReport,
+    read: &mut [u8],
+) -> Result<(), Box<dyn std::error::Error>> {
+    let (x, y) = report.core.physical;
+    let core_pattern = core_pattern(pattern, report.core, options.broadcast);
+
+    let mut addr = start;
+    while addr < end {
+        let len = ((end - addr) as usize).min(read.len());
+        chip.noc_read(options.noc_id, x, y, addr, &mut read[..len])?;
+
+        for (word_addr, expected, actual) in core_pattern.mismatches(addr, &read[..len]) {
+            report.failure_count += 1;
+            if report.failures.len() < options.max_failures {
+                report.failures.push(L1Failure {
+                    pattern,
+                    addr: word_addr,
+                    expected,
+                    actual,
+                });
+            }
+        }
+
+        addr += len as u64;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use crate::chip::{
+        communication::fault_injection::{test::wormhole, AccessKind, Fault, FaultRule},
+        Chip,
+    };
+
+    use super::*;
+
+    #[test]
+    fn harvested_grid() {
+        // The first two bits of the wormhole mask harvest physical rows 11 and 1
+        let grid = TensixGrid::new(Arch::Wormhole, 0b11);
+        assert_eq!(grid.harvested(), vec![1, 11]);
+        assert_eq!(grid.size(), (8, 8));
+        assert_eq!(grid.cores().len(), 64);
+        assert_eq!(grid.to_logical((1, 2)), Some((0, 0)));
+        assert_eq!(grid.to_logical((6, 7)), Some((4, 4)));
+        assert_eq!(grid.to_logical((1, 1)), None);
+        assert_eq!(grid.to_logical((5, 2)), None);
+        assert_eq!(grid.to_physical((4, 4)), Some((6, 7)));
+        assert_eq!(grid.to_physical((0, 8)), None);
+
+        // Blackhole harvests columns instead
+        let grid = TensixGrid::new(Arch::Blackhole, 0b10);
+        assert_eq!(grid.harvested(), vec![16]);
+        assert_eq!(grid.size(), (13, 10));
+        assert_eq!(grid.to_physical((12, 0)), Some((15, 2)));
+    }
+
+    #[test]
+    fn bad_core() {
+        // Bit 5 of the word at 0x40 in the L1 of core 6-7 is stuck high
+        let wh = wormhole(vec![FaultRule::new(Fault::Value(0x20))
+            .kind(AccessKind::Noc)
+            .reads_only()
+            .core(6, 7)
+            .addr(0x40)]);
+        let chip = Chip::from(Box::new(wh) as Box<dyn ChipImpl>);
+
+        let options = L1TestOptions::new()
+            .patterns(vec![MemoryPattern::AddressInAddress])
+            .range(0, Some(0x100))
+            .harvest_mask(Some(0b11));
+        let mut checked = 0;
+        let report = test_l1(&chip, &options, |_| checked += 1).unwrap();
+
+        assert_eq!(checked, 64);
+        assert_eq!(report.bytes_tested, 0x100);
+
+        // A broadcast would also write the harvested rows
+        assert!(test_l1(&chip, &options.clone().broadcast(true), |_| {}).is_err());
+        assert_eq!(
+            report.bad_cores(),
+            vec![TensixCore {
+                physical: (6, 7),
+                logical: (4, 4),
+            }]
+        );
+
+        let bad = report.cores.iter().find(|core| !core.passed()).unwrap();
+        assert_eq!(bad.failure_count, 1);
+        assert_eq!(
+            bad.failures[0],
+            L1Failure {
+                pattern: MemoryPattern::AddressInAddress,
+                addr: 0x40,
+                expected: 0x40,
+                actual: 0x20,
+            }
+        );
+    }
+}
@@ -3,25 +3,28 @@
 
 use std::{backtrace, sync::Arc};
 
+use tracing::field::Empty;
+
 use crate::{
     arc_msg::{ArcMsgAddr, ArcMsgOk, TypedArcMsg},
     chip::{
         communication::{
             chip_comms::{load_axi_table, ChipComms},
-            chip_interface::ChipInterface,
+            chip_interface::{ChipInterface, ExclusiveAccess},
         },
         hl_comms::HlCommsInterface,
     },
-    error::{BtWrapper, PlatformError},
+    error::{BtWrapper, ChipIdentity, PlatformError},
     ArcMsg, ChipImpl, IntoChip,
 };
 
 use super::{
+    communication::instrumented::traced,
     eth_addr::EthAddr,
     hl_comms::HlComms,
     init::status::{ComponentStatusInfo, EthernetPartialInitError, InitOptions, WaitStatus},
     remote::{EthAddresses, RemoteArcIf},
-    ArcMsgOptions, ChipInitResult, CommsStatus, InitStatus, NeighbouringChip,
+    ArcMsgOptions, ChipInitResult, CommsStatus, Harvesting, InitStatus, NeighbouringChip,
 };
 
 /// Implementation of the interface for a Wormhole
@@ -38,18 +41,32 @@ pub struct Wormhole {
     pub eth_locations: [EthCore; 16],
     pub eth_addrs: EthAddresses,
     telemetry_addr: Arc<once_cell::sync::OnceCell<u32>>,
+    board_id: Arc<once_cell::sync::OnceCell<u64>>,
+    harvesting: Arc<once_cell::sync::OnceCell<Harvesting>>,
 }
 
 impl HlComms for Wormhole {
     fn comms_obj(&self) -> (&dyn ChipComms, &dyn ChipInterface) {
         (self.arc_if.as_ref(), self.chip_if.as_ref())
     }
+
+    fn chip_identity(&self) -> ChipIdentity {
+        ChipIdentity {
+            arch: Some(luwen_core::Arch::Wormhole),
+            board_id: self.board_id.get().copied(),
+            ..ChipIdentity::from_comms(self.arc_if.as_ref(), self.chip_if.as_ref())
+        }
+    }
 }
 
 impl HlComms for &Wormhole {
     fn comms_obj(&self) -> (&dyn ChipComms, &dyn ChipInterface) {
         (self.arc_if.as_ref(), self.chip_if.as_ref())
     }
+
+    fn chip_identity(&self) -> ChipIdentity {
+        Wormhole::chip_identity(self)
+    }
 }
 
 #[derive(Clone, Copy, Debug)]
@@ -99,6 +116,8 @@ impl Wormhole {
             eth_addrs: EthAddresses::default(),
 
             telemetry_addr: Arc::new(once_cell::sync::OnceCell::new()),
+            board_id: Arc::new(once_cell::sync::OnceCell::new()),
+            harvesting: Arc::new(once_cell::sync::OnceCell::new()),
 
             eth_locations: [
                 EthCore {
@@ -315,19 +334,39 @@ impl Wormhole {
     }
 
     pub fn spi_write(&self, addr: u32, value: &[u8]) -> Result<(), Box<dyn std::error::Error>> {
-        let spi = super::spi::ActiveSpi::new(self, self.use_arc_for_spi)?;
-
-        spi.write(self, addr, value)?;
-
-        Ok(())
+        let span = tracing::debug_span!(
+            "spi_write",
+            chip = %self.chip_identity(),
+            addr,
+            len = value.len(),
+            duration_us = Empty,
+            error = Empty
+        );
+        traced(span, || {
+            let spi = super::spi::ActiveSpi::new(self, self.use_arc_for_spi)?;
+
+            spi.write(self, addr, value)?;
+
+            Ok(())
+        })
     }
 
     pub fn spi_read(&self, addr: u32, value: &mut [u8]) -> Result<(), Box<dyn std::error::Error>> {
-        let spi = super::spi::ActiveSpi::new(self, self.use_arc_for_spi)?;
-
-        spi.read(self, addr, value)?;
-
-        Ok(())
+        let span = tracing::debug_span!(
+            "spi_read",
+            chip = %self.chip_identity(),
+            addr,
+            len = value.len(),
+            duration_us = Empty,
+            error = Empty
+        );
+        traced(span, || {
+            let spi = super::spi::ActiveSpi::new(self, self.use_arc_for_spi)?;
+
+            spi.read(self, addr, value)?;
+
+            Ok(())
+        })
     }
 }
 
@@ -438,6 +477,13 @@ impl ChipImpl for Wormhole {
                                         ));
                                     }
 
+                                    PlatformError::WaitError(error) => {
+                                        return Ok(ChipInitResult::ErrorContinue(
+                                            error.to_string(),
+                                            backtrace::Backtrace::capture(),
+                                        ));
+                                    }
+
                                     // This is fine to hit at this stage (though it should have been already verified to not be the case).
                                     // For now we just ignore it and hope that it will be resolved by the time the timeout expires...
                                     PlatformError::EthernetTrainingNotComplete(_) => {
@@ -494,6 +540,13 @@ impl ChipImpl for Wormhole {
                                         ));
                                     }
 
+                                    PlatformError::PowerError(error) => {
+                                        return Ok(ChipInitResult::ErrorAbort(
+                                            error.to_string(),
+                                            backtrace::Backtrace::capture(),
+                                        ));
+                                    }
+
                                     PlatformError::Generic(error, backtrace) => {
                                         return Ok(ChipInitResult::ErrorAbort(error, backtrace.0));
                                     }
@@ -505,6 +558,13 @@ impl ChipImpl for Wormhole {
                                             backtrace.0,
                                         ));
                                     }
+
+                                    PlatformError::AccessError(error) => {
+                                        return Ok(ChipInitResult::ErrorAbort(
+                                            error.to_string(),
+                                            backtrace::Backtrace::capture(),
+                                        ));
+                                    }
                                 }
                             }
                         }
@@ -574,6 +634,10 @@ impl ChipImpl for Wormhole {
                                 return Ok(ChipInitResult::ErrorContinue(format!("Telemetry ARC message error: {:?}; we expected to have communication, but lost it.", error), backtrace::Backtrace::capture()));
                             }
 
+                            PlatformError::WaitError(error) => {
+                                return Ok(ChipInitResult::ErrorContinue(format!("Telemetry ARC message error: {:?}; we expected to have communication, but lost it.", error), backtrace::Backtrace::capture()));
+                            }
+
                             // This is an "expected error" but we probably can't recover from it, so we should abort the init.
                             PlatformError::AxiError(error) => return Ok(ChipInitResult::ErrorAbort(error.to_string(), backtrace::Backtrace::capture())),
 
@@ -588,6 +652,13 @@ impl ChipImpl for Wormhole {
                                 return Ok(ChipInitResult::ErrorAbort(format!("expected chip: {}, actual detected chips: {}", expected_chips, actual), backtrace.0));
                             }
 
+                            PlatformError::PowerError(error) => {
+                                return Ok(ChipInitResult::ErrorAbort(
+                                    error.to_string(),
+                                    backtrace::Backtrace::capture(),
+                                ));
+                            }
+
                             PlatformError::Generic(error, backtrace) => {
                                 return Ok(ChipInitResult::ErrorAbort(error, backtrace.0));
                             }
@@ -596,20 +667,21 @@ impl ChipImpl for Wormhole {
                                 let err_msg = error.to_string();
                                 return Ok(ChipInitResult::ErrorAbort(err_msg, backtrace.0));
                             }
+
+                            PlatformError::AccessError(error) => {
+                                return Ok(ChipInitResult::ErrorAbort(
+                                    error.to_string(),
+                                    backtrace::Backtrace::capture(),
+                                ));
+                            }
                         },
                     };
 
                     if let Some(telem) = telem {
                         let dram_status = telem.ddr_status;
 
-                        let mut channels = [None; 6];
-                        for (i, channel) in channels.iter_mut().enumerate() {
-                            let status = (dram_status >> (i * 4)) & 0xF;
-                            let status = status as u8;
-
-                            *channel =
-                                super::init::status::DramChannelStatus::try_from(status).ok();
-                        }
+                        let channels =
+                            super::dram_training_status(luwen_core::Arch::Wormhole, dram_status);
 
                         for (dram_status, channel_status) in
                             status.wait_status.iter_mut().zip(channels)
@@ -691,6 +763,13 @@ impl ChipImpl for Wormhole {
                                     ));
                                 }
 
+                                PlatformError::WaitError(error) => {
+                                    return Ok(ChipInitResult::ErrorContinue(
+                                        error.to_string(),
+                                        backtrace::Backtrace::capture(),
+                                    ));
+                                }
+
                                 PlatformError::ArcNotReady(error, backtrace) => {
                                     return Ok(ChipInitResult::ErrorContinue(
                                         error.to_string(),
@@ -757,6 +836,13 @@ impl ChipImpl for Wormhole {
                                     ));
                                 }
 
+                                PlatformError::PowerError(error) => {
+                                    return Ok(ChipInitResult::ErrorAbort(
+                                        error.to_string(),
+                                        backtrace::Backtrace::capture(),
+                                    ));
+                                }
+
                                 PlatformError::Generic(error, backtrace) => {
                                     return Ok(ChipInitResult::ErrorAbort(error, backtrace.0));
                                 }
@@ -765,6 +851,13 @@ impl ChipImpl for Wormhole {
                                     let err_msg = error.to_string();
                                     return Ok(ChipInitResult::ErrorAbort(err_msg, backtrace.0));
                                 }
+
+                                PlatformError::AccessError(error) => {
+                                    return Ok(ChipInitResult::ErrorAbort(
+                                        error.to_string(),
+                                        backtrace::Backtrace::capture(),
+                                    ));
+                                }
                             },
                         };
                         for (eth_status, training_complete) in
@@ -829,23 +922,40 @@ impl ChipImpl for Wormhole {
     }
 
     fn arc_msg(&self, msg: ArcMsgOptions) -> Result<ArcMsgOk, PlatformError> {
-        let (msg_reg, return_reg) = if msg.use_second_mailbox {
-            (2, 4)
-        } else {
-            (5, 3)
-        };
+        let span = tracing::debug_span!(
+            "arc_msg",
+            chip = %self.chip_identity(),
+            msg = msg.msg.msg_code(),
+            duration_us = Empty,
+            error = Empty
+        );
+        traced(span, || {
+            let (msg_reg, return_reg) = if msg.use_second_mailbox {
+                (2, 4)
+            } else {
+                (5, 3)
+            };
+
+            // Other users of a shared device must not touch the mailbox until we have the reply
+            let _exclusive = ExclusiveAccess::acquire(self.comms_obj().1)?;
+            self.check_arc_msg_safe(msg_reg, return_reg)?;
+
+            crate::arc_msg::arc_msg(
+                self,
+                &msg.msg,
+                msg.wait_for_done,
+                msg.timeout,
+                msg_reg,
+                return_reg,
+                msg.addrs.as_ref().unwrap_or(&self.arc_addrs),
+            )
+        })
+    }
 
-        self.check_arc_msg_safe(msg_reg, return_reg)?;
-
-        crate::arc_msg::arc_msg(
-            self,
-            &msg.msg,
-            msg.wait_for_done,
-            msg.timeout,
-            msg_reg,
-            return_reg,
-            msg.addrs.as_ref().unwrap_or(&self.arc_addrs),
-        )
+    fn harvesting(&self) -> Result<Harvesting, PlatformError> {
+        self.harvesting
+            .get_or_try_init(|| Harvesting::read(self))
+            .cloned()
     }
 
     fn get_neighbouring_chips(&self) -> Result<Vec<NeighbouringChip>, crate::error::PlatformError> {
@@ -853,9 +963,6 @@ impl ChipImpl for Wormhole {
         const ETH_UNCONNECTED: u32 = 1;
         const ETH_NO_ROUTING: u32 = 2;
 
-        const SHELF_OFFSET: u64 = 9;
-        const RACK_OFFSET: u64 = 10;
-
         let mut output = Vec::with_capacity(self.eth_locations.len());
 
         for (
@@ -909,37 +1016,14 @@ impl ChipImpl for Wormhole {
 
             // Decode the remote eth_addr for our erisc core
             // This can be used to build a map of the full mesh
-            let remote_id = self.noc_read32(
-                0,
-                eth_x,
-                eth_y,
-                self.eth_addrs.node_info + (4 * RACK_OFFSET),
-            )?;
-            let remote_rack_x = remote_id & 0xFF;
-            let remote_rack_y = (remote_id >> 8) & 0xFF;
-
-            let remote_id = self.noc_read32(
-                0,
-                eth_x,
-                eth_y,
-                self.eth_addrs.node_info + (4 * SHELF_OFFSET),
-            )?;
-            let remote_shelf_x = (remote_id >> 16) & 0x3F;
-            let remote_shelf_y = (remote_id >> 22) & 0x3F;
-
-            let remote_noc_x = (remote_id >> 4) & 0x3F;
-            let remote_noc_y = (remote_id >> 10) & 0x3F;
+            let (eth_addr, remote_noc_addr) =
+                self.read_remote_eth_id(&self.eth_addrs, (eth_x, eth_y))?;
 
             output.push(NeighbouringChip {
                 routing_enabled: !routing_disabled,
                 local_noc_addr: (eth_x, eth_y),
-                remote_noc_addr: (remote_noc_x as u8, remote_noc_y as u8),
-                eth_addr: EthAddr {
-                    shelf_x: remote_shelf_x as u8,
-                    shelf_y: remote_shelf_y as u8,
-                    rack_x: remote_rack_x as u8,
-                    rack_y: remote_rack_y as u8,
-                },
+                remote_noc_addr,
+                eth_addr,
             });
         }
 
@@ -1126,8 +1210,12 @@ impl ChipImpl for Wormhole {
             fw_bundle_version = 0;
         }
 
+        let board_id = ((board_id_high as u64) << 32) | (board_id_low as u64);
+        // Remembered so that later errors can report which board they came from
+        let _ = self.board_id.set(board_id);
+
         Ok(super::Telemetry {
-            board_id: ((board_id_high as u64) << 32) | (board_id_low as u64),
+            board_id,
             enum_version,
             device_id,
             asic_ro,
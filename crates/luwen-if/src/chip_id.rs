@@ -0,0 +1,432 @@
+// SPDX-FileCopyrightText: © 2024 Tenstorrent Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Identifying chips in a way that survives reboots and resets.
+//!
+//! Interface ids (the N in /dev/tenstorrent/N) are handed out in probe order and can change
+//! whenever the driver is reloaded, so anything which needs to refer to the same physical chip
+//! over time should use a [`ChipSelector`] matched against the chip's [`ChipId`] instead.
+//!
+//! Selectors are written as `kind:value`
+//! - `bdf:0000:41:00.0` the pci address of a chip, the domain may be left off
+//! - `board:100014511abcd` every chip on a board, the serial is always read as hex
+//! - `board:100014511abcd/1` a single chip on a board, by its position on the board
+//! - `eth:0,0,1,0` the ethernet coordinate of a chip as rack_x,rack_y,shelf_x,shelf_y
+//! - `if:3` the interface id, a bare number is also taken as an interface id
+
+use std::{collections::HashMap, fmt::Display, str::FromStr};
+
+use thiserror::Error;
+
+use crate::{
+    chip::{Chip, HlComms},
+    ChipImpl, DeviceInfo, EthAddr,
+};
+
+/// The pci address (domain:bus:slot.function) of a device.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct PciBdf {
+    pub domain: u16,
+    pub bus: u16,
+    pub slot: u16,
+    pub function: u16,
+}
+
+impl From<&DeviceInfo> for PciBdf {
+    fn from(info: &DeviceInfo) -> Self {
+        PciBdf {
+            domain: info.domain,
+            bus: info.bus,
+            slot: info.slot,
+            function: info.function,
+        }
+    }
+}
+
+impl Display for PciBdf {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{:04x}:{:02x}:{:02x}.{:x}",
+            self.domain, self.bus, self.slot, self.function
+        )
+    }
+}
+
+impl FromStr for PciBdf {
+    type Err = ChipSelectorError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || ChipSelectorError::Invalid {
+            kind: "bdf",
+            value: s.to_string(),
+        };
+        let hex = |value: &str| u16::from_str_radix(value, 16).map_err(|_| invalid());
+
+        let (rest, function) = s.rsplit_once('.').ok_or_else(invalid)?;
+        let parts = rest.split(':').collect::<Vec<_>>();
+        let (domain, bus, slot) = match parts.as_slice() {
+            [domain, bus, slot] => (hex(domain)?, hex(bus)?, hex(slot)?),
+            [bus, slot] => (0, hex(bus)?, hex(slot)?),
+            _ => return Err(invalid()),
+        };
+
+        Ok(PciBdf {
+            domain,
+            bus,
+            slot,
+            function: hex(function)?,
+        })
+    }
+}
+
+/// Everything we know about where a chip is.
+///
+/// A field is None if it doesn't apply to the chip (remote chips have no pci address)
+/// or if it couldn't be read (the board id needs a working ARC).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct ChipId {
+    /// The board serial number.
+    pub board_id: Option<u64>,
+    /// The position of the chip on its board, 0 for the chip which is connected to the host.
+    /// Only known when the chip was identified along with the other chips on its board,
+    /// see [`ChipId::assign_asic_locations`].
+    pub asic_location: Option<u8>,
+    pub pci_bdf: Option<PciBdf>,
+    pub interface_id: Option<u32>,
+    pub eth_addr: Option<EthAddr>,
+}
+
+impl ChipId {
+    /// Identify a chip using only what is already known about it, the chip won't be accessed.
+    pub fn cached(chip: &Chip) -> Self {
+        let identity = chip.chip_identity();
+
+        // Remote chips report the device info of the chip they are reached through
+        if identity.eth_addr.is_some() {
+            return ChipId {
+                board_id: identity.board_id,
+                eth_addr: identity.eth_addr,
+                ..Default::default()
+            };
+        }
+
+        let info = chip.get_device_info().ok().flatten();
+        ChipId {
+            board_id: identity.board_id,
+            asic_location: None,
+            pci_bdf: info.as_ref().map(PciBdf::from),
+            interface_id: info.map(|info| info.interface_id),
+            eth_addr: None,
+        }
+    }
+
+    /// Identify a chip, reading the board id and ethernet coordinate from the chip if they
+    /// aren't already known. The chip must be initialized enough to respond to an ARC message.
+    pub fn read(chip: &Chip) -> Self {
+        let mut id = Self::cached(chip);
+
+        if id.board_id.is_none() {
+            id.board_id = chip.get_telemetry().ok().map(|telem| telem.board_id);
+        }
+        if id.eth_addr.is_none() {
+            if let Some(wh) = chip.as_wh() {
+                id.eth_addr = wh.get_local_chip_coord().ok();
+            }
+        }
+
+        id
+    }
+
+    /// Number the chips on each board, the chips connected to the host come first in order of
+    /// their pci address followed by the remote chips in order of their ethernet coordinate.
+    /// This only depends on how the board is wired, so it stays the same across reboots.
+    pub fn assign_asic_locations(ids: &mut [ChipId]) {
+        let mut boards: HashMap<u64, Vec<usize>> = HashMap::new();
+        for (index, id) in ids.iter().enumerate() {
+            if let Some(board_id) = id.board_id {
+                boards.entry(board_id).or_default().push(index);
+            }
+        }
+
+        for mut chips in boards.into_values() {
+            chips.sort_by_key(|index| {
+                let id = &ids[*index];
+                (
+                    id.pci_bdf.is_none(),
+                    id.pci_bdf,
+                    id.eth_addr
+                        .map(|addr| (addr.rack_x, addr.rack_y, addr.shelf_x, addr.shelf_y)),
+                )
+            });
+            for (location, index) in chips.into_iter().enumerate() {
+                ids[index].asic_location = Some(location as u8);
+            }
+        }
+    }
+
+    pub fn matches(&self, selector: &ChipSelector) -> bool {
+        match selector {
+            ChipSelector::Bdf(bdf) => self.pci_bdf == Some(*bdf),
+            ChipSelector::Board {
+                board_id,
+                asic_location,
+            } => {
+                self.board_id == Some(*board_id)
+                    && (asic_location.is_none() || self.asic_location == *asic_location)
+            }
+            ChipSelector::Eth(addr) => self.eth_addr == Some(*addr),
+            ChipSelector::Interface(id) => self.interface_id == Some(*id),
+        }
+    }
+
+    /// True if the chip matches any of the selectors, or if no selectors were given.
+    pub fn matches_any(&self, selectors: &[ChipSelector]) -> bool {
+        selectors.is_empty() || selectors.iter().any(|selector| self.matches(selector))
+    }
+}
+
+/// Written as the list of selectors which match the chip.
+impl Display for ChipId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut selectors = Vec::new();
+        if let Some(board_id) = self.board_id {
+            selectors.push(ChipSelector::Board {
+                board_id,
+                asic_location: self.asic_location,
+            });
+        }
+        if let Some(bdf) = self.pci_bdf {
+            selectors.push(ChipSelector::Bdf(bdf));
+        }
+        if let Some(addr) = self.eth_addr {
+            selectors.push(ChipSelector::Eth(addr));
+        }
+        if let Some(id) = self.interface_id {
+            selectors.push(ChipSelector::Interface(id));
+        }
+
+        if selectors.is_empty() {
+            return f.write_str("unknown chip");
+        }
+        for (index, selector) in selectors.iter().enumerate() {
+            if index > 0 {
+                f.write_str(" ")?;
+            }
+            write!(f, "{selector}")?;
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum ChipSelectorError {
+    #[error("Unknown chip selector {0:?}, expected one of bdf:, board:, eth: or if:")]
+    UnknownKind(String),
+
+    #[error("Could not parse {value:?} as a {kind} selector")]
+    Invalid { kind: &'static str, value: String },
+}
+
+/// Picks out one or more chips by a property of their [`ChipId`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ChipSelector {
+    Bdf(PciBdf),
+    /// All chips on a board, or only the one at the given position.
+    Board {
+        board_id: u64,
+        asic_location: Option<u8>,
+    },
+    Eth(EthAddr),
+    /// Not stable across reboots, prefer one of the other selectors.
+    Interface(u32),
+}
+
+impl Display for ChipSelector {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ChipSelector::Bdf(bdf) => write!(f, "bdf:{bdf}"),
+            ChipSelector::Board {
+                board_id,
+                asic_location: None,
+            } => write!(f, "board:{board_id:x}"),
+            ChipSelector::Board {
+                board_id,
+                asic_location: Some(location),
+            } => write!(f, "board:{board_id:x}/{location}"),
+            ChipSelector::Eth(addr) => write!(
+                f,
+                "eth:{},{},{},{}",
+                addr.rack_x, addr.rack_y, addr.shelf_x, addr.shelf_y
+            ),
+            ChipSelector::Interface(id) => write!(f, "if:{id}"),
+        }
+    }
+}
+
+impl FromStr for ChipSelector {
+    type Err = ChipSelectorError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        if let Ok(id) = s.parse() {
+            return Ok(ChipSelector::Interface(id));
+        }
+
+        let Some((kind, value)) = s.split_once(':') else {
+            return Err(ChipSelectorError::UnknownKind(s.to_string()));
+        };
+        let invalid = |kind| ChipSelectorError::Invalid {
+            kind,
+            value: value.to_string(),
+        };
+
+        match kind {
+            "bdf" => Ok(ChipSelector::Bdf(value.parse()?)),
+            "board" => {
+                let (board_id, asic_location) = match value.split_once('/') {
+                    Some((board_id, location)) => (
+                        board_id,
+                        Some(location.parse().map_err(|_| invalid("board"))?),
+                    ),
+                    None => (value, None),
+                };
+                let board_id = board_id.strip_prefix("0x").unwrap_or(board_id);
+
+                Ok(ChipSelector::Board {
+                    board_id: u64::from_str_radix(board_id, 16).map_err(|_| invalid("board"))?,
+                    asic_location,
+                })
+            }
+            "eth" => {
+                let coord = value
+                    .split(',')
+                    .map(|v| v.trim().parse::<u8>())
+                    .collect::<Result<Vec<_>, _>>()
+                    .map_err(|_| invalid("eth"))?;
+                let [rack_x, rack_y, shelf_x, shelf_y] = coord[..] else {
+                    return Err(invalid("eth"));
+                };
+
+                Ok(ChipSelector::Eth(EthAddr {
+                    rack_x,
+                    rack_y,
+                    shelf_x,
+                    shelf_y,
+                }))
+            }
+            "if" => Ok(ChipSelector::Interface(
+                value.parse().map_err(|_| invalid("if"))?,
+            )),
+            _ => Err(ChipSelectorError::UnknownKind(s.to_string())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parse_selectors() {
+        let bdf = PciBdf {
+            domain: 0,
+            bus: 0x41,
+            slot: 0,
+            function: 0,
+        };
+        let eth = EthAddr {
+            rack_x: 1,
+            rack_y: 0,
+            shelf_x: 2,
+            shelf_y: 0,
+        };
+
+        for (text, selector) in [
+            ("bdf:0000:41:00.0", ChipSelector::Bdf(bdf)),
+            ("bdf:41:00.0", ChipSelector::Bdf(bdf)),
+            (
+                "board:0x100014511abcd",
+                ChipSelector::Board {
+                    board_id: 0x100014511abcd,
+                    asic_location: None,
+                },
+            ),
+            (
+                "board:100014511abcd/1",
+                ChipSelector::Board {
+                    board_id: 0x100014511abcd,
+                    asic_location: Some(1),
+                },
+            ),
+            ("eth:1,0,2,0", ChipSelector::Eth(eth)),
+            ("if:3", ChipSelector::Interface(3)),
+            ("3", ChipSelector::Interface(3)),
+        ] {
+            assert_eq!(text.parse::<ChipSelector>(), Ok(selector), "{text}");
+            // Display must round trip
+            assert_eq!(selector.to_string().parse::<ChipSelector>(), Ok(selector));
+        }
+
+        assert!(matches!(
+            "pci:3".parse::<ChipSelector>(),
+            Err(ChipSelectorError::UnknownKind(_))
+        ));
+        for text in ["bdf:41:00", "board:xyz", "eth:1,0,0", "if:-1"] {
+            assert!(
+                matches!(
+                    text.parse::<ChipSelector>(),
+                    Err(ChipSelectorError::Invalid { .. })
+                ),
+                "{text}"
+            );
+        }
+    }
+
+    #[test]
+    fn asic_locations() {
+        let board = 0x100014511abcd;
+        let remote = ChipId {
+            board_id: Some(board),
+            eth_addr: Some(EthAddr {
+                rack_x: 0,
+                rack_y: 0,
+                shelf_x: 1,
+                shelf_y: 0,
+            }),
+            ..Default::default()
+        };
+        let local = ChipId {
+            board_id: Some(board),
+            pci_bdf: Some(PciBdf {
+                domain: 0,
+                bus: 0x41,
+                slot: 0,
+                function: 0,
+            }),
+            interface_id: Some(1),
+            ..Default::default()
+        };
+        let other = ChipId {
+            board_id: Some(0x1),
+            interface_id: Some(0),
+            ..Default::default()
+        };
+
+        // Found remote first, the local chip should still be first on the board
+        let mut ids = [remote, other, local, ChipId::default()];
+        ChipId::assign_asic_locations(&mut ids);
+        assert_eq!(
+            ids.map(|id| id.asic_location),
+            [Some(1), Some(0), Some(0), None]
+        );
+
+        let second_asic = "board:100014511abcd/1".parse().unwrap();
+        assert!(ids[0].matches(&second_asic));
+        assert!(!ids[2].matches(&second_asic));
+        assert!(ids[2].matches(&"board:0x100014511abcd".parse().unwrap()));
+        assert!(ids[2].matches_any(&[ChipSelector::Interface(0), ChipSelector::Interface(1)]));
+        assert!(ids[3].matches_any(&[]));
+    }
+}
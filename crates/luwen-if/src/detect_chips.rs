@@ -6,9 +6,9 @@ use std::collections::HashSet;
 use luwen_core::Arch;
 
 use crate::{
-    chip::{wait_for_init, Chip, InitError, InitStatus},
+    chip::{wait_for_init, Chip, HlComms, InitError, InitStatus},
     error::{BtWrapper, PlatformError},
-    ChipImpl, EthAddr,
+    ChipId, ChipImpl, ChipSelector, EthAddr,
 };
 
 #[derive(PartialEq, Eq, Hash, Debug, Clone)]
@@ -170,6 +170,45 @@ impl UninitChip {
             UninitChip::Initialized(_) => true,
         }
     }
+
+    /// Identify the chip, the board id and ethernet coordinate are only read from chips which
+    /// are far enough through init to answer. The asic location is filled in by [`chip_ids`].
+    pub fn chip_id(&self) -> ChipId {
+        let chip = match self {
+            UninitChip::Partially { underlying, .. } => underlying,
+            UninitChip::Initialized(chip) => chip,
+        };
+
+        if self.arc_alive() && self.eth_safe() {
+            ChipId::read(chip)
+        } else {
+            ChipId::cached(chip)
+        }
+    }
+}
+
+/// Identify a set of detected chips, this includes the position of each chip on its board.
+pub fn chip_ids(chips: &[UninitChip]) -> Vec<ChipId> {
+    let mut ids = chips.iter().map(UninitChip::chip_id).collect::<Vec<_>>();
+    ChipId::assign_asic_locations(&mut ids);
+
+    ids
+}
+
+/// Keep only the chips which match at least one of the selectors, all chips are kept if there
+/// are no selectors.
+pub fn select_chips(chips: Vec<UninitChip>, selectors: &[ChipSelector]) -> Vec<UninitChip> {
+    if selectors.is_empty() {
+        return chips;
+    }
+
+    let ids = chip_ids(&chips);
+    chips
+        .into_iter()
+        .zip(ids)
+        .filter(|(_, id)| id.matches_any(selectors))
+        .map(|(chip, _)| chip)
+        .collect()
 }
 
 pub struct ChipDetectOptions {
@@ -183,6 +222,9 @@ pub struct ChipDetectOptions {
     pub chip_filter: Vec<Arch>,
     /// If true, then we will not initialize anything that might cause a problem (i.e. a noc hang).
     pub noc_safe: bool,
+    /// If len > 0 then only chips matching at least one of the selectors will be returned.
+    /// All chips are still searched, a remote chip can only be found through its mmio chip.
+    pub chip_selectors: Vec<ChipSelector>,
 }
 
 impl Default for ChipDetectOptions {
@@ -192,6 +234,7 @@ impl Default for ChipDetectOptions {
             local_only: false,
             chip_filter: Vec::new(),
             noc_safe: false,
+            chip_selectors: Vec::new(),
         }
     }
 }
@@ -215,6 +258,11 @@ impl ChipDetectOptions {
         self.noc_safe = noc_safe;
         self
     }
+
+    pub fn chip_selectors(mut self, chip_selectors: Vec<ChipSelector>) -> Self {
+        self.chip_selectors = chip_selectors;
+        self
+    }
 }
 
 /// Find all chips accessible from the given set of root chips.
@@ -230,6 +278,8 @@ impl ChipDetectOptions {
 /// 1. Add all given chips to output list removing duplicates this will ensure that if list indexes are used to
 /// assign a chip id pci chips will always be output instead of the remote equivalent.
 /// 2. To a depth first search for each root chip, adding all new chips found to the output list.
+/// 3. If any chip selectors were given, drop all chips which don't match at least one of them.
+///    This is done last because the asic location of a chip depends on the other chips on its board.
 ///
 /// When continue on failure is true, we report errors, but continue searching for chips.
 /// We pass all chips that did not complete initializations as UninitChip, the user will see the status and can
@@ -258,6 +308,7 @@ pub fn detect_chips<E>(
         local_only,
         chip_filter,
         noc_safe,
+        chip_selectors,
     } = options;
 
     let mut remotes_to_investigate = Vec::new();
@@ -322,6 +373,14 @@ pub fn detect_chips<E>(
     for root_chip in remotes_to_investigate.into_iter().map(|v| &root_chips[v]) {
         let mut to_check = root_chip.get_neighbouring_chips()?;
 
+        // Let the transport know which of the mmio chip's ethernet cores it can use to reach
+        // the rest of the mesh.
+        root_chip
+            .comms_obj()
+            .1
+            .set_eth_routes(&to_check)
+            .map_err(PlatformError::from)?;
+
         let mut seen_coords = HashSet::new();
         while let Some(nchip) = to_check.pop() {
             if !nchip.routing_enabled {
@@ -382,7 +441,7 @@ pub fn detect_chips<E>(
         }
     }
 
-    Ok(output)
+    Ok(select_chips(output, &chip_selectors))
 }
 
 pub fn detect_initialized_chips<E>(
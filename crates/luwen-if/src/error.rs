@@ -6,7 +6,11 @@ use std::fmt::Display;
 use luwen_core::Arch;
 use thiserror::Error;
 
-use crate::arc_msg::ArcMsgError;
+use crate::{
+    arc_msg::ArcMsgError,
+    chip::{ChipComms, ChipInterface},
+    EthAddr,
+};
 
 #[derive(Debug)]
 pub struct BtWrapper(pub std::backtrace::Backtrace);
@@ -75,12 +79,21 @@ pub enum PlatformError {
     #[error(transparent)]
     MessageError(#[from] crate::chip::MessageError),
 
+    #[error(transparent)]
+    PowerError(#[from] crate::chip::PowerError),
+
     #[error("Ethernet training not complete on {} ports", .0.iter().copied().filter(|v| *v).count())]
     EthernetTrainingNotComplete(Vec<bool>),
 
     #[error(transparent)]
     AxiError(#[from] crate::chip::AxiError),
 
+    #[error(transparent)]
+    WaitError(Box<crate::chip::WaitError>),
+
+    #[error(transparent)]
+    AccessError(Box<AccessError>),
+
     #[error("{0}\n{1}")]
     Generic(String, BtWrapper),
 
@@ -88,10 +101,47 @@ pub enum PlatformError {
     GenericError(Box<dyn std::error::Error>, BtWrapper),
 }
 
+impl PlatformError {
+    /// The chip that was being accessed, if this error came from a chip access.
+    pub fn chip(&self) -> Option<&ChipIdentity> {
+        self.access_error().map(AccessError::chip)
+    }
+
+    /// The access that failed, if this error came from a chip access.
+    pub fn operation(&self) -> Option<&Operation> {
+        self.access_error().map(AccessError::operation)
+    }
+
+    fn access_error(&self) -> Option<&AccessError> {
+        match self {
+            PlatformError::AccessError(err) => Some(err),
+            PlatformError::GenericError(err, _) => err.downcast_ref::<AccessError>(),
+            _ => None,
+        }
+    }
+}
+
 impl From<Box<dyn std::error::Error>> for PlatformError {
     #[inline]
     fn from(e: Box<dyn std::error::Error>) -> Self {
-        Self::GenericError(e, BtWrapper::capture())
+        match e.downcast::<AccessError>() {
+            Ok(e) => Self::AccessError(e),
+            Err(e) => Self::GenericError(e, BtWrapper::capture()),
+        }
+    }
+}
+
+impl From<AccessError> for PlatformError {
+    #[inline]
+    fn from(e: AccessError) -> Self {
+        Self::AccessError(Box::new(e))
+    }
+}
+
+impl From<crate::chip::WaitError> for PlatformError {
+    #[inline]
+    fn from(e: crate::chip::WaitError) -> Self {
+        Self::WaitError(Box::new(e))
     }
 }
 
@@ -101,3 +151,171 @@ impl From<String> for PlatformError {
         Self::Generic(e, BtWrapper::capture())
     }
 }
+
+/// Everything that is known about the chip an access was made to.
+/// Fields are None when they could not be determined.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ChipIdentity {
+    /// The pci interface of the chip, for remote chips this is the local chip they are reached through.
+    pub interface_id: Option<u32>,
+    /// Set when the chip is accessed over ethernet.
+    pub eth_addr: Option<EthAddr>,
+    pub arch: Option<Arch>,
+    /// Only known once telemetry has been read from the chip.
+    pub board_id: Option<u64>,
+}
+
+impl ChipIdentity {
+    /// Fill in the identity from the communication objects of a chip, the arch and board id are
+    /// left for the chip to add.
+    pub fn from_comms(arc_if: &dyn ChipComms, chip_if: &dyn ChipInterface) -> Self {
+        ChipIdentity {
+            interface_id: chip_if
+                .get_device_info()
+                .ok()
+                .flatten()
+                .map(|info| info.interface_id),
+            eth_addr: arc_if.eth_addr(),
+            arch: None,
+            board_id: None,
+        }
+    }
+}
+
+impl Display for ChipIdentity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.arch {
+            Some(arch) => write!(f, "{arch} chip")?,
+            None => f.write_str("chip")?,
+        }
+        match (self.eth_addr, self.interface_id) {
+            (Some(eth_addr), Some(interface_id)) => {
+                write!(f, " {eth_addr} via interface {interface_id}")?
+            }
+            (Some(eth_addr), None) => write!(f, " {eth_addr}")?,
+            (None, Some(interface_id)) => write!(f, " on interface {interface_id}")?,
+            (None, None) => {}
+        }
+        if let Some(board_id) = self.board_id {
+            write!(f, " (board {board_id:016x})")?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OperationKind {
+    AxiRead,
+    AxiWrite,
+    NocRead,
+    NocWrite,
+    NocBroadcast,
+}
+
+impl OperationKind {
+    pub fn is_write(&self) -> bool {
+        !matches!(self, OperationKind::AxiRead | OperationKind::NocRead)
+    }
+}
+
+/// A single chip access, noc_id and the core are only set for noc accesses.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Operation {
+    pub kind: OperationKind,
+    pub noc_id: Option<u8>,
+    pub x: Option<u8>,
+    pub y: Option<u8>,
+    pub addr: u64,
+    pub len: usize,
+}
+
+impl Operation {
+    pub fn axi(kind: OperationKind, addr: u64, len: usize) -> Self {
+        Operation {
+            kind,
+            noc_id: None,
+            x: None,
+            y: None,
+            addr,
+            len,
+        }
+    }
+
+    pub fn noc(kind: OperationKind, noc_id: u8, x: u8, y: u8, addr: u64, len: usize) -> Self {
+        Operation {
+            kind,
+            noc_id: Some(noc_id),
+            x: Some(x),
+            y: Some(y),
+            addr,
+            len,
+        }
+    }
+
+    pub fn broadcast(noc_id: u8, addr: u64, len: usize) -> Self {
+        Operation {
+            noc_id: Some(noc_id),
+            ..Operation::axi(OperationKind::NocBroadcast, addr, len)
+        }
+    }
+}
+
+impl Display for Operation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let len = self.len;
+        match self.kind {
+            OperationKind::AxiRead => write!(f, "axi read of {len} bytes from")?,
+            OperationKind::AxiWrite => write!(f, "axi write of {len} bytes to")?,
+            OperationKind::NocRead => write!(f, "noc read of {len} bytes from")?,
+            OperationKind::NocWrite => write!(f, "noc write of {len} bytes to")?,
+            OperationKind::NocBroadcast => write!(f, "noc broadcast of {len} bytes to")?,
+        }
+        if let Some(noc_id) = self.noc_id {
+            write!(f, " noc{noc_id}")?;
+        }
+        if let (Some(x), Some(y)) = (self.x, self.y) {
+            write!(f, " ({x}, {y})")?;
+        }
+        write!(f, " {:#x}", self.addr)
+    }
+}
+
+/// A failed chip access along with the chip and operation it failed on.
+#[derive(Error, Debug)]
+#[error("{operation} on {chip} failed: {source}")]
+pub struct AccessError {
+    chip: ChipIdentity,
+    operation: Operation,
+    source: Box<dyn std::error::Error>,
+}
+
+impl AccessError {
+    pub fn new(
+        chip: ChipIdentity,
+        operation: Operation,
+        source: Box<dyn std::error::Error>,
+    ) -> Self {
+        AccessError {
+            chip,
+            operation,
+            source,
+        }
+    }
+
+    pub fn chip(&self) -> &ChipIdentity {
+        &self.chip
+    }
+
+    pub fn operation(&self) -> &Operation {
+        &self.operation
+    }
+
+    /// The error returned by the underlying interface.
+    pub fn inner(&self) -> &(dyn std::error::Error + 'static) {
+        self.source.as_ref()
+    }
+
+    pub fn into_inner(self) -> Box<dyn std::error::Error> {
+        self.source
+    }
+}
@@ -1,8 +1,9 @@
 // SPDX-FileCopyrightText: © 2023 Tenstorrent Inc.
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::chip::{eth_addr::EthAddr, ChipInterface};
-use std::fs;
+use serde::{Deserialize, Serialize};
+
+use crate::chip::{eth_addr::EthAddr, ChipInterface, NeighbouringChip};
 
 #[derive(Debug)]
 pub enum FnNoc {
@@ -50,7 +51,7 @@ pub enum FnAxi {
     },
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct DeviceInfo {
     pub interface_id: u32,
 
@@ -65,71 +66,32 @@ pub struct DeviceInfo {
     pub bar_size: u64,
 }
 
-impl DeviceInfo {
-    /// Return the sysfs path for the PCIe device.
-    fn pcie_base_path(&self) -> String {
-        let domain = format!("{:04x}", self.domain);
-        let bus = format!("{:02x}", self.bus);
-        let slot = format!("{:02x}", self.slot);
-        let function = format!("{:01x}", self.function);
-        format!(
-            "/sys/bus/pci/devices/{}:{}:{}.{}/",
-            domain, bus, slot, function
-        )
-    }
-
-    /// Return link width; valid values of `s` are "current" and "max".
-    fn pcie_link_width(&self, s: &str) -> u32 {
-        let base_path = self.pcie_base_path();
-        let path = format!("{}{}{}", &base_path, s, "_link_width");
-        let width = fs::read_to_string(path)
-            .map(|s| s.trim().to_string())
-            .unwrap();
-        width.parse::<u32>().unwrap()
-    }
-
-    /// Return link gen; valid values of `s` are "current" and "max".
-    fn pcie_link_gen(&self, s: &str) -> i32 {
-        let base_path = self.pcie_base_path();
-        let path = format!("{}{}{}", &base_path, s, "_link_speed");
-        let speed = fs::read_to_string(path)
-            .map(|s| s.trim().to_string())
-            .unwrap();
-        match speed.split_whitespace().next().unwrap_or("") {
-            "2.5" => 1,
-            "5.0" => 2,
-            "8.0" => 3,
-            "16.0" => 4,
-            "32.0" => 5,
-            "64.0" => 6,
-            _ => -1,
-        }
-    }
-
-    /// Return the current PCIe link width.
-    pub fn pcie_current_link_width(&self) -> u32 {
-        self.pcie_link_width("current")
-    }
-
-    /// Return the current PCIe link generation.
-    pub fn pcie_current_link_gen(&self) -> i32 {
-        self.pcie_link_gen("current")
-    }
-
-    /// Return the maximum PCIe link width.
-    pub fn pcie_max_link_width(&self) -> u32 {
-        self.pcie_link_width("max")
-    }
-
-    /// Return the maximum PCIe link generation.
-    pub fn pcie_max_link_gen(&self) -> i32 {
-        self.pcie_link_gen("max")
-    }
+/// Whether the transport to a chip is still usable.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum ConnectionState {
+    #[default]
+    Connected,
+    /// The link dropped or the device went away (i.e. every read returns 0xffffffff),
+    /// all accesses fail until the chip is reopened.
+    Lost,
+    /// The chip is being reset, all accesses fail until the chip is reopened.
+    Resetting,
 }
 
 #[derive(Debug)]
 pub enum FnDriver {
     DeviceInfo(*mut Option<DeviceInfo>),
+    ConnectionState(*mut ConnectionState),
+    /// Reopen the device and restore the transport state after the connection was lost.
+    Reopen,
+    /// Keep other users of a shared device off it until [`FnDriver::Release`].
+    Acquire,
+    Release,
+    /// The ethernet links of this chip which can be used to reach remote chips.
+    EthRoutes {
+        routes: *const NeighbouringChip,
+        len: usize,
+    },
 }
 
 #[derive(Debug)]
@@ -168,6 +130,42 @@ impl<T: Clone + Send + 'static> ChipInterface for CallbackStorage<T> {
         Ok(driver_info)
     }
 
+    fn set_eth_routes(
+        &self,
+        routes: &[NeighbouringChip],
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        (self.callback)(
+            &self.user_data,
+            FnOptions::Driver(FnDriver::EthRoutes {
+                routes: routes.as_ptr(),
+                len: routes.len(),
+            }),
+        )
+    }
+
+    fn connection_state(&self) -> ConnectionState {
+        let mut state = ConnectionState::Connected;
+        // Callbacks which don't track the connection leave it as connected
+        let _ = (self.callback)(
+            &self.user_data,
+            FnOptions::Driver(FnDriver::ConnectionState((&mut state) as *mut _)),
+        );
+
+        state
+    }
+
+    fn reopen(&self) -> Result<(), Box<dyn std::error::Error>> {
+        (self.callback)(&self.user_data, FnOptions::Driver(FnDriver::Reopen))
+    }
+
+    fn acquire(&self) -> Result<(), Box<dyn std::error::Error>> {
+        (self.callback)(&self.user_data, FnOptions::Driver(FnDriver::Acquire))
+    }
+
+    fn release(&self) -> Result<(), Box<dyn std::error::Error>> {
+        (self.callback)(&self.user_data, FnOptions::Driver(FnDriver::Release))
+    }
+
     fn axi_read(&self, addr: u32, data: &mut [u8]) -> Result<(), Box<dyn std::error::Error>> {
         (self.callback)(
             &self.user_data,
@@ -324,3 +322,45 @@ impl<T: Clone + Send + 'static> ChipInterface for CallbackStorage<T> {
         self
     }
 }
+
+#[cfg(test)]
+mod test {
+    use std::sync::{Arc, Mutex};
+
+    use super::*;
+
+    fn callback(
+        state: &Arc<Mutex<Option<ConnectionState>>>,
+        op: FnOptions,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        match op {
+            FnOptions::Driver(FnDriver::ConnectionState(out)) => match *state.lock().unwrap() {
+                Some(current) => {
+                    unsafe { *out = current };
+                    Ok(())
+                }
+                None => Err("not tracked".into()),
+            },
+            FnOptions::Driver(FnDriver::Reopen) => {
+                *state.lock().unwrap() = Some(ConnectionState::Connected);
+                Ok(())
+            }
+            _ => Ok(()),
+        }
+    }
+
+    #[test]
+    fn connection_state() {
+        let state = Arc::new(Mutex::new(None));
+        let storage = CallbackStorage::new(callback, state.clone());
+
+        // A callback which doesn't track the connection is always connected
+        assert_eq!(storage.connection_state(), ConnectionState::Connected);
+
+        *state.lock().unwrap() = Some(ConnectionState::Lost);
+        assert_eq!(storage.connection_state(), ConnectionState::Lost);
+
+        storage.reopen().unwrap();
+        assert_eq!(storage.connection_state(), ConnectionState::Connected);
+    }
+}
@@ -7,14 +7,24 @@
 ///
 mod arc_msg;
 pub mod chip;
+mod chip_id;
 mod detect_chips;
 pub mod error;
 mod interface;
+mod pcie;
 
 pub use arc_msg::{
     ArcMsg, ArcMsgError, ArcMsgOk, ArcMsgProtocolError, ArcState, PowerState, TypedArcMsg,
 };
 pub use chip::eth_addr::{EthAddr, IntoChip};
 pub use chip::ChipImpl;
-pub use detect_chips::{detect_chips, detect_chips_silent, ChipDetectOptions, UninitChip};
-pub use interface::{CallbackStorage, DeviceInfo, FnAxi, FnDriver, FnNoc, FnOptions, FnRemote};
+pub use chip_id::{ChipId, ChipSelector, ChipSelectorError, PciBdf};
+pub use detect_chips::{
+    chip_ids, detect_chips, detect_chips_silent, select_chips, ChipDetectOptions, UninitChip,
+};
+pub use interface::{
+    CallbackStorage, ConnectionState, DeviceInfo, FnAxi, FnDriver, FnNoc, FnOptions, FnRemote,
+};
+pub use pcie::{
+    AerCounters, PcieAer, PcieBar, PcieBridge, PcieHealth, PcieLink, PcieSysfsError, SYSFS_ROOT,
+};
@@ -0,0 +1,492 @@
+// SPDX-FileCopyrightText: © 2024 Tenstorrent Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! PCIe link and error state of a device, read from sysfs.
+//!
+//! All functions take the sysfs mount point as a parameter (with the `*_from` variants) so that
+//! they can be pointed at a fake tree, the plain variants read from [`SYSFS_ROOT`].
+
+use std::{
+    io,
+    path::{Path, PathBuf},
+};
+
+use thiserror::Error;
+
+use crate::DeviceInfo;
+
+pub const SYSFS_ROOT: &str = "/sys";
+
+#[derive(Error, Debug)]
+pub enum PcieSysfsError {
+    #[error("Could not read {}: {source}", path.display())]
+    Read { path: PathBuf, source: io::Error },
+
+    #[error("Could not parse {} (read {value:?})", path.display())]
+    Parse { path: PathBuf, value: String },
+}
+
+fn read(path: PathBuf) -> Result<String, PcieSysfsError> {
+    match std::fs::read_to_string(&path) {
+        Ok(value) => Ok(value.trim().to_string()),
+        Err(source) => Err(PcieSysfsError::Read { path, source }),
+    }
+}
+
+/// Read a file which may not exist, for example because the kernel was built without AER support.
+fn read_optional(path: PathBuf) -> Result<Option<String>, PcieSysfsError> {
+    match read(path) {
+        Ok(value) => Ok(Some(value)),
+        Err(PcieSysfsError::Read { source, .. }) if source.kind() == io::ErrorKind::NotFound => {
+            Ok(None)
+        }
+        Err(err) => Err(err),
+    }
+}
+
+fn parse_int(path: PathBuf, value: &str) -> Result<u64, PcieSysfsError> {
+    let parsed = if let Some(hex) = value.strip_prefix("0x") {
+        u64::from_str_radix(hex, 16)
+    } else {
+        value.parse()
+    };
+
+    parsed.map_err(|_| PcieSysfsError::Parse {
+        path,
+        value: value.to_string(),
+    })
+}
+
+fn read_int(path: PathBuf) -> Result<u64, PcieSysfsError> {
+    let value = read(path.clone())?;
+    parse_int(path, &value)
+}
+
+/// Convert a link speed such as "16.0 GT/s PCIe" into a pcie generation, -1 if unknown.
+fn link_gen(speed: &str) -> i32 {
+    match speed.split_whitespace().next().unwrap_or("") {
+        "2.5" => 1,
+        "5.0" => 2,
+        "8.0" => 3,
+        "16.0" => 4,
+        "32.0" => 5,
+        "64.0" => 6,
+        _ => -1,
+    }
+}
+
+/// True if `name` looks like a pci address (dddd:bb:ss.f).
+fn is_bdf(name: &str) -> bool {
+    let bytes = name.as_bytes();
+    bytes.len() == 12
+        && bytes[4] == b':'
+        && bytes[7] == b':'
+        && bytes[10] == b'.'
+        && bytes
+            .iter()
+            .enumerate()
+            .all(|(i, b)| matches!(i, 4 | 7 | 10) || b.is_ascii_hexdigit())
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PcieLink {
+    pub current_width: u32,
+    pub max_width: u32,
+    /// PCIe generation, -1 if the speed was not recognized.
+    pub current_gen: i32,
+    pub max_gen: i32,
+}
+
+impl PcieLink {
+    fn read(device: &Path) -> Result<Self, PcieSysfsError> {
+        Ok(PcieLink {
+            current_width: read_int(device.join("current_link_width"))? as u32,
+            max_width: read_int(device.join("max_link_width"))? as u32,
+            current_gen: link_gen(&read(device.join("current_link_speed"))?),
+            max_gen: link_gen(&read(device.join("max_link_speed"))?),
+        })
+    }
+
+    /// The link trained below its maximum width or speed.
+    pub fn is_degraded(&self) -> bool {
+        self.current_width < self.max_width || self.current_gen < self.max_gen
+    }
+}
+
+/// The counters from one of the aer_dev_* files.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct AerCounters {
+    /// Per error type counts, in the order the kernel lists them.
+    pub counters: Vec<(String, u64)>,
+    pub total: u64,
+}
+
+impl AerCounters {
+    fn parse(path: PathBuf, value: &str) -> Result<Self, PcieSysfsError> {
+        let mut output = AerCounters::default();
+        for line in value.lines() {
+            let Some((name, count)) = line.split_once(' ') else {
+                continue;
+            };
+            let count = parse_int(path.clone(), count.trim())?;
+
+            if name.starts_with("TOTAL_") {
+                output.total = count;
+            } else {
+                output.counters.push((name.to_string(), count));
+            }
+        }
+
+        Ok(output)
+    }
+
+    pub fn get(&self, name: &str) -> u64 {
+        self.counters
+            .iter()
+            .find(|(counter, _)| counter == name)
+            .map_or(0, |(_, count)| *count)
+    }
+}
+
+/// Advanced error reporting counters, None if the kernel does not expose them for this device.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct PcieAer {
+    pub correctable: Option<AerCounters>,
+    pub nonfatal: Option<AerCounters>,
+    pub fatal: Option<AerCounters>,
+}
+
+impl PcieAer {
+    fn read(device: &Path) -> Result<Self, PcieSysfsError> {
+        let counters = |name: &str| -> Result<Option<AerCounters>, PcieSysfsError> {
+            let path = device.join(name);
+            read_optional(path.clone())?
+                .map(|value| AerCounters::parse(path, &value))
+                .transpose()
+        };
+
+        Ok(PcieAer {
+            correctable: counters("aer_dev_correctable")?,
+            nonfatal: counters("aer_dev_nonfatal")?,
+            fatal: counters("aer_dev_fatal")?,
+        })
+    }
+
+    /// Number of times the link went down unexpectedly (surprise down errors).
+    pub fn link_down_events(&self) -> u64 {
+        [&self.nonfatal, &self.fatal]
+            .into_iter()
+            .flatten()
+            .map(|counters| counters.get("SDES"))
+            .sum()
+    }
+
+    /// Number of times the link had to be retrained because the replay count rolled over.
+    pub fn retrain_events(&self) -> u64 {
+        self.correctable
+            .as_ref()
+            .map_or(0, |counters| counters.get("Rollover"))
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PcieBar {
+    pub index: usize,
+    pub start: u64,
+    pub end: u64,
+    pub flags: u64,
+}
+
+impl PcieBar {
+    pub fn size(&self) -> u64 {
+        self.end - self.start + 1
+    }
+
+    /// Parse the sysfs resource file, unused bars are skipped.
+    fn read(device: &Path) -> Result<Vec<Self>, PcieSysfsError> {
+        let path = device.join("resource");
+        let mut bars = Vec::new();
+        // Only the first 6 entries are bars, the rest are the rom and bridge windows
+        for (index, line) in read(path.clone())?.lines().take(6).enumerate() {
+            let mut fields = line.split_whitespace();
+            let mut next = || parse_int(path.clone(), fields.next().unwrap_or(""));
+            let (start, end, flags) = (next()?, next()?, next()?);
+
+            if start == 0 && end == 0 {
+                continue;
+            }
+            bars.push(PcieBar {
+                index,
+                start,
+                end,
+                flags,
+            });
+        }
+
+        Ok(bars)
+    }
+}
+
+/// A bridge or switch port between the device and the root complex.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PcieBridge {
+    pub bdf: String,
+    pub vendor: Option<u16>,
+    pub device: Option<u16>,
+    /// None for ports which don't report a link (i.e. root complex internals).
+    pub link: Option<PcieLink>,
+    pub aer: PcieAer,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PcieHealth {
+    pub bdf: String,
+    pub link: PcieLink,
+    pub aer: PcieAer,
+    pub numa_node: Option<u32>,
+    pub iommu_group: Option<u32>,
+    pub bars: Vec<PcieBar>,
+    /// Bridges above the device, starting with the one closest to it.
+    pub upstream: Vec<PcieBridge>,
+}
+
+impl PcieHealth {
+    /// Link down events seen by the device or any bridge above it.
+    pub fn link_down_events(&self) -> u64 {
+        self.aer.link_down_events()
+            + self
+                .upstream
+                .iter()
+                .map(|bridge| bridge.aer.link_down_events())
+                .sum::<u64>()
+    }
+
+    /// Link retrain events seen by the device or any bridge above it.
+    pub fn retrain_events(&self) -> u64 {
+        self.aer.retrain_events()
+            + self
+                .upstream
+                .iter()
+                .map(|bridge| bridge.aer.retrain_events())
+                .sum::<u64>()
+    }
+}
+
+impl DeviceInfo {
+    /// The pci address of the device (dddd:bb:ss.f).
+    pub fn pcie_bdf(&self) -> String {
+        format!(
+            "{:04x}:{:02x}:{:02x}.{:x}",
+            self.domain, self.bus, self.slot, self.function
+        )
+    }
+
+    /// Return the sysfs path for the PCIe device.
+    pub fn pcie_sysfs_path(&self, sysfs_root: impl AsRef<Path>) -> PathBuf {
+        sysfs_root
+            .as_ref()
+            .join("bus/pci/devices")
+            .join(self.pcie_bdf())
+    }
+
+    pub fn pcie_link_from(&self, sysfs_root: impl AsRef<Path>) -> Result<PcieLink, PcieSysfsError> {
+        PcieLink::read(&self.pcie_sysfs_path(sysfs_root))
+    }
+
+    /// Return the current PCIe link width.
+    pub fn pcie_current_link_width(&self) -> Result<u32, PcieSysfsError> {
+        Ok(self.pcie_link_from(SYSFS_ROOT)?.current_width)
+    }
+
+    /// Return the current PCIe link generation.
+    pub fn pcie_current_link_gen(&self) -> Result<i32, PcieSysfsError> {
+        Ok(self.pcie_link_from(SYSFS_ROOT)?.current_gen)
+    }
+
+    /// Return the maximum PCIe link width.
+    pub fn pcie_max_link_width(&self) -> Result<u32, PcieSysfsError> {
+        Ok(self.pcie_link_from(SYSFS_ROOT)?.max_width)
+    }
+
+    /// Return the maximum PCIe link generation.
+    pub fn pcie_max_link_gen(&self) -> Result<i32, PcieSysfsError> {
+        Ok(self.pcie_link_from(SYSFS_ROOT)?.max_gen)
+    }
+
+    pub fn pcie_health(&self) -> Result<PcieHealth, PcieSysfsError> {
+        self.pcie_health_from(SYSFS_ROOT)
+    }
+
+    pub fn pcie_health_from(
+        &self,
+        sysfs_root: impl AsRef<Path>,
+    ) -> Result<PcieHealth, PcieSysfsError> {
+        let device = self.pcie_sysfs_path(sysfs_root);
+
+        let numa_node = read_optional(device.join("numa_node"))?
+            // -1 means that the device is not attached to a numa node
+            .and_then(|node| node.parse::<u32>().ok());
+
+        let iommu_group = std::fs::read_link(device.join("iommu_group"))
+            .ok()
+            .and_then(|group| group.file_name()?.to_str()?.parse::<u32>().ok());
+
+        // The real location of the device in sysfs follows the topology, so every parent directory
+        // which is named like a pci device is a bridge above us.
+        let mut upstream = Vec::new();
+        let real_path = device
+            .canonicalize()
+            .map_err(|source| PcieSysfsError::Read {
+                path: device.clone(),
+                source,
+            })?;
+        for bridge in real_path.ancestors().skip(1) {
+            let Some(bdf) = bridge.file_name().and_then(|name| name.to_str()) else {
+                break;
+            };
+            if !is_bdf(bdf) {
+                break;
+            }
+
+            let id = |name: &str| -> Result<Option<u16>, PcieSysfsError> {
+                let path = bridge.join(name);
+                read_optional(path.clone())?
+                    .map(|value| parse_int(path, &value).map(|v| v as u16))
+                    .transpose()
+            };
+
+            upstream.push(PcieBridge {
+                bdf: bdf.to_string(),
+                vendor: id("vendor")?,
+                device: id("device")?,
+                link: PcieLink::read(bridge).ok(),
+                aer: PcieAer::read(bridge)?,
+            });
+        }
+
+        Ok(PcieHealth {
+            bdf: self.pcie_bdf(),
+            link: PcieLink::read(&device)?,
+            aer: PcieAer::read(&device)?,
+            numa_node,
+            iommu_group,
+            bars: PcieBar::read(&device)?,
+            upstream,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn write(path: PathBuf, value: &str) {
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, value).unwrap();
+    }
+
+    fn write_link(device: &Path, width: u32, speed: &str) {
+        write(device.join("current_link_width"), &format!("{width}\n"));
+        write(device.join("max_link_width"), "16\n");
+        write(device.join("current_link_speed"), &format!("{speed}\n"));
+        write(device.join("max_link_speed"), "16.0 GT/s PCIe\n");
+    }
+
+    fn device_info() -> DeviceInfo {
+        DeviceInfo {
+            interface_id: 0,
+            domain: 0,
+            bus: 2,
+            slot: 0,
+            function: 0,
+            vendor: 0x1e52,
+            device_id: 0x401e,
+            board_id: 0,
+            bar_size: 0,
+        }
+    }
+
+    #[test]
+    fn fake_sysfs() {
+        let root = std::env::temp_dir().join(format!("luwen-pcie-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+
+        // root port -> switch -> device
+        let root_port = root.join("devices/pci0000:00/0000:00:01.1");
+        let switch = root_port.join("0000:01:00.0");
+        let device = switch.join("0000:02:00.0");
+
+        write(root_port.join("vendor"), "0x1022\n");
+        write(switch.join("vendor"), "0x10b5\n");
+        write(switch.join("device"), "0x8747\n");
+        write_link(&switch, 16, "16.0 GT/s PCIe");
+        write(
+            switch.join("aer_dev_fatal"),
+            "Undefined 0\nDLP 0\nSDES 2\nTOTAL_ERR_FATAL 2\n",
+        );
+
+        write_link(&device, 8, "8.0 GT/s PCIe");
+        write(
+            device.join("aer_dev_correctable"),
+            "RxErr 5\nBadTLP 1\nRollover 3\nTimeout 0\nTOTAL_ERR_COR 9\n",
+        );
+        write(device.join("numa_node"), "-1\n");
+        write(
+            device.join("resource"),
+            "0x00000000f8000000 0x00000000f9ffffff 0x000000000014220c\n\
+             0x0000000000000000 0x0000000000000000 0x0000000000000000\n\
+             0x00000000fa000000 0x00000000fa0fffff 0x0000000000040200\n",
+        );
+        std::os::unix::fs::symlink("/nonexistent/iommu_groups/17", device.join("iommu_group"))
+            .unwrap();
+
+        std::fs::create_dir_all(root.join("bus/pci/devices")).unwrap();
+        std::os::unix::fs::symlink(&device, root.join("bus/pci/devices/0000:02:00.0")).unwrap();
+
+        let info = device_info();
+        let health = info.pcie_health_from(&root).unwrap();
+
+        assert_eq!(
+            health.link,
+            PcieLink {
+                current_width: 8,
+                max_width: 16,
+                current_gen: 3,
+                max_gen: 4,
+            }
+        );
+        assert!(health.link.is_degraded());
+
+        let correctable = health.aer.correctable.as_ref().unwrap();
+        assert_eq!(correctable.total, 9);
+        assert_eq!(correctable.get("RxErr"), 5);
+        assert!(health.aer.fatal.is_none());
+
+        assert_eq!(health.numa_node, None);
+        assert_eq!(health.iommu_group, Some(17));
+        assert_eq!(health.bars.len(), 2);
+        assert_eq!((health.bars[1].index, health.bars[1].size()), (2, 0x100000));
+
+        assert_eq!(
+            health
+                .upstream
+                .iter()
+                .map(|b| b.bdf.as_str())
+                .collect::<Vec<_>>(),
+            ["0000:01:00.0", "0000:00:01.1"]
+        );
+        assert_eq!(health.upstream[0].device, Some(0x8747));
+        assert!(health.upstream[1].link.is_none());
+
+        assert_eq!(health.link_down_events(), 2);
+        assert_eq!(health.retrain_events(), 3);
+
+        // Missing files are reported instead of panicking
+        assert!(matches!(
+            DeviceInfo { bus: 3, ..info }.pcie_link_from(&root),
+            Err(PcieSysfsError::Read { .. })
+        ));
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -10,7 +10,7 @@ use luwen_if::{
         InitStatus,
     },
     error::PlatformError,
-    CallbackStorage, ChipDetectOptions, ChipImpl, UninitChip,
+    CallbackStorage, ChipDetectOptions, ChipImpl, ChipSelector, UninitChip,
 };
 use ttkmd_if::PciDevice;
 
@@ -235,12 +235,15 @@ pub fn detect_chips_options_tui(
     Ok(chips)
 }
 
-pub fn detect_chips_options(options: ChipDetectOptions) -> Result<Vec<UninitChip>, LuwenError> {
+type FailedChip = (usize, Chip, PlatformError);
+
+/// Open the chips on the given pci interfaces and check that they can be talked to at all;
+/// chips which don't respond are returned separately along with the error.
+fn open_interfaces(interfaces: &[usize]) -> Result<(Vec<Chip>, Vec<FailedChip>), LuwenError> {
     let mut chips = Vec::new();
     let mut failed_chips = Vec::new();
 
-    let device_ids = PciDevice::scan();
-    for device_id in device_ids {
+    for device_id in interfaces.iter().copied() {
         let ud = ExtendedPciDevice::open(device_id)?;
 
         let arch = ud.borrow().device.arch;
@@ -265,37 +268,68 @@ pub fn detect_chips_options(options: ChipDetectOptions) -> Result<Vec<UninitChip
         }
     }
 
-    let mut chips = if std::io::stderr().is_terminal() {
-        detect_chips_options_tui(options, &failed_chips, chips)?
-    } else {
-        detect_chips_options_notui(options, &failed_chips, chips)?
-    };
+    Ok((chips, failed_chips))
+}
 
+/// Put the chips which failed basic communication back at their interface index.
+fn insert_failed_chips(chips: &mut Vec<UninitChip>, failed_chips: Vec<FailedChip>) {
     for (id, chip, err) in failed_chips.into_iter() {
         let mut status = InitStatus::new_unknown();
         status.comms_status = CommsStatus::CommunicationError(err.to_string());
         status.unknown_state = false;
         chips.insert(
-            id,
+            id.min(chips.len()),
             UninitChip::Partially {
                 status: Box::new(status),
                 underlying: chip,
             },
         );
     }
+}
+
+pub fn detect_chips_options(mut options: ChipDetectOptions) -> Result<Vec<UninitChip>, LuwenError> {
+    // Selection is done at the end so that the chips which failed to communicate are still
+    // inserted at their interface index.
+    let chip_selectors = std::mem::take(&mut options.chip_selectors);
+
+    let (chips, failed_chips) = open_interfaces(&PciDevice::scan())?;
+
+    let mut chips = if std::io::stderr().is_terminal() {
+        detect_chips_options_tui(options, &failed_chips, chips)?
+    } else {
+        detect_chips_options_notui(options, &failed_chips, chips)?
+    };
+
+    insert_failed_chips(&mut chips, failed_chips);
 
     println!();
 
-    Ok(chips)
+    Ok(luwen_if::select_chips(chips, &chip_selectors))
+}
+
+/// Detect the chips reachable from the given pci interfaces without reporting any progress,
+/// for callers which are not attached to a terminal (i.e. library bindings).
+pub fn detect_interfaces_silent(
+    interfaces: &[usize],
+    mut options: ChipDetectOptions,
+) -> Result<Vec<UninitChip>, LuwenError> {
+    let chip_selectors = std::mem::take(&mut options.chip_selectors);
+
+    let (chips, failed_chips) = open_interfaces(interfaces)?;
+
+    let mut chips = luwen_if::detect_chips(chips, &mut |_| Ok::<(), Infallible>(()), options)
+        .map_err(PlatformError::from)?;
+
+    insert_failed_chips(&mut chips, failed_chips);
+
+    Ok(luwen_if::select_chips(chips, &chip_selectors))
 }
 
 pub fn detect_chips_fallible() -> Result<Vec<UninitChip>, LuwenError> {
     detect_chips_options(ChipDetectOptions::default())
 }
 
-pub fn detect_chips() -> Result<Vec<Chip>, LuwenError> {
-    let chips = detect_chips_fallible()?;
-
+fn init_chips(chips: Vec<UninitChip>) -> Result<Vec<Chip>, LuwenError> {
     let mut output = Vec::with_capacity(chips.len());
     for chip in chips {
         output.push(
@@ -307,19 +341,50 @@ pub fn detect_chips() -> Result<Vec<Chip>, LuwenError> {
     Ok(output)
 }
 
+pub fn detect_chips() -> Result<Vec<Chip>, LuwenError> {
+    init_chips(detect_chips_fallible()?)
+}
+
 pub fn detect_local_chips() -> Result<Vec<Chip>, LuwenError> {
-    let chips = detect_chips_options(ChipDetectOptions {
+    init_chips(detect_chips_options(ChipDetectOptions {
         local_only: true,
         ..Default::default()
-    })?;
+    })?)
+}
 
-    let mut output = Vec::with_capacity(chips.len());
+/// Detect and initialize only the chips matching at least one of the selectors,
+/// all chips are returned if there are no selectors.
+pub fn detect_selected_chips(selectors: Vec<ChipSelector>) -> Result<Vec<Chip>, LuwenError> {
+    init_chips(detect_chips_options(
+        ChipDetectOptions::default().chip_selectors(selectors),
+    )?)
+}
+
+/// Find the pci interfaces of the selected chips, remote chips resolve to the interface they
+/// are reached through. All interfaces are returned if there are no selectors.
+pub fn resolve_interfaces(selectors: &[ChipSelector]) -> Result<Vec<usize>, LuwenError> {
+    if selectors.is_empty() {
+        return Ok(PciDevice::scan());
+    }
+
+    // Only need to look past the local chips if a selector could match a remote chip
+    let local_only = selectors
+        .iter()
+        .all(|selector| matches!(selector, ChipSelector::Bdf(_) | ChipSelector::Interface(_)));
+    let chips = detect_chips_options(
+        ChipDetectOptions::default()
+            .local_only(local_only)
+            .chip_selectors(selectors.to_vec()),
+    )?;
+
+    let mut interfaces = Vec::with_capacity(chips.len());
     for chip in chips {
-        output.push(
-            chip.init(&mut |_| Ok::<(), Infallible>(()))
-                .map_err(Into::<luwen_if::error::PlatformError>::into)?,
-        );
+        if let Some(info) = chip.upgrade().get_device_info()? {
+            interfaces.push(info.interface_id as usize);
+        }
     }
+    interfaces.sort();
+    interfaces.dedup();
 
-    Ok(output)
+    Ok(interfaces)
 }
@@ -1,7 +1,11 @@
 // SPDX-FileCopyrightText: © 2023 Tenstorrent Inc.
 // SPDX-License-Identifier: Apache-2.0
 
-use luwen_if::{chip::AxiError, error::PlatformError, ArcMsgError};
+use luwen_if::{
+    chip::{AxiError, WaitError},
+    error::PlatformError,
+    ArcMsgError, ConnectionState, PciBdf,
+};
 use thiserror::Error;
 use ttkmd_if::{PciError, PciOpenError};
 
@@ -16,10 +20,59 @@ pub enum LuwenError {
     #[error(transparent)]
     PciError(#[from] PciError),
 
+    #[error("Ethernet timeout: {0}")]
+    EthernetTimeout(Box<WaitError>),
+
+    /// A write timed out after part of it had already been handed to the erisc, so it can't be
+    /// retried without applying that part twice.
+    #[error("Ethernet timeout after the write was partially queued: {0}")]
+    EthernetWriteTimeout(Box<WaitError>),
+
+    #[error(transparent)]
+    ResetError(#[from] ResetError),
+
+    #[error(
+        "Lost the connection to /dev/tenstorrent/{interface} ({state:?}), it must be reopened"
+    )]
+    ConnectionLost {
+        interface: usize,
+        state: ConnectionState,
+    },
+
+    #[error("Could not find the device at {bdf} to reopen /dev/tenstorrent/{interface}")]
+    DeviceMissing { interface: usize, bdf: PciBdf },
+
     #[error("{0}")]
     Custom(String),
 }
 
+#[derive(Error, Debug)]
+pub enum ResetError {
+    #[error("Could not open /dev/tenstorrent/{interface}: {source}")]
+    Open {
+        interface: usize,
+        source: std::io::Error,
+    },
+
+    #[error("Reset request on /dev/tenstorrent/{interface} failed: {source}")]
+    Ioctl {
+        interface: usize,
+        source: std::io::Error,
+    },
+
+    #[error("Reset request on /dev/tenstorrent/{interface} was rejected by the driver (result {result})")]
+    Rejected { interface: usize, result: u32 },
+
+    #[error("Could not read the pci config space of {bdf}: {source}")]
+    ConfigSpace { bdf: String, source: std::io::Error },
+
+    #[error(transparent)]
+    PciOpenError(#[from] PciOpenError),
+
+    #[error(transparent)]
+    PlatformError(#[from] PlatformError),
+}
+
 impl From<ArcMsgError> for LuwenError {
     fn from(value: ArcMsgError) -> Self {
         LuwenError::PlatformError(value.into())
@@ -1,29 +1,51 @@
 // SPDX-FileCopyrightText: © 2023 Tenstorrent Inc.
 // SPDX-License-Identifier: Apache-2.0
 
-use std::{
-    collections::{hash_map::Entry, HashMap},
-    sync::{Arc, RwLock, RwLockReadGuard, RwLockWriteGuard},
-};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, RwLock, RwLockReadGuard, RwLockWriteGuard, Weak};
 
 use error::LuwenError;
-use luwen_if::{FnDriver, FnOptions};
+use luwen_if::{ConnectionState, EthAddr, FnDriver, FnOptions, PciBdf};
 use ttkmd_if::PciError;
 
 mod detect;
 pub mod error;
+pub mod reset;
 mod wormhole;
 
 use wormhole::ethernet::{self, EthCommCoord};
 
-pub use detect::{detect_chips, detect_chips_fallible, detect_local_chips};
+pub use detect::{
+    detect_chips, detect_chips_fallible, detect_chips_options, detect_interfaces_silent,
+    detect_local_chips, detect_selected_chips, resolve_interfaces,
+};
 pub use ttkmd_if::{DmaBuffer, DmaConfig, PciDevice, Tlb};
+pub use wormhole::ethernet::{
+    EthCore, EthCoreState, EthQueue, EthQueueEntry, EthQueueRecovery, EthQueueState,
+    EthRecoveryPolicy, EthRoute, EthRouter, EthTransferStats,
+};
 
 #[derive(Clone)]
 pub struct ExtendedPciDeviceWrapper {
     inner: Arc<RwLock<ExtendedPciDevice>>,
 }
 
+/// Every device opened by this process, so that a reset can pause the handles which still
+/// point at the device it is resetting.
+static OPEN_DEVICES: Mutex<Vec<Weak<RwLock<ExtendedPciDevice>>>> = Mutex::new(Vec::new());
+
+/// The handles this process has open to the given interface.
+pub(crate) fn open_handles(interface: usize) -> Vec<ExtendedPciDeviceWrapper> {
+    let mut devices = OPEN_DEVICES.lock().unwrap();
+    devices.retain(|device| device.strong_count() > 0);
+    devices
+        .iter()
+        .filter_map(Weak::upgrade)
+        .map(|inner| ExtendedPciDeviceWrapper { inner })
+        .filter(|device| device.borrow().device.id == interface)
+        .collect()
+}
+
 impl ExtendedPciDeviceWrapper {
     pub fn borrow_mut(&self) -> RwLockWriteGuard<ExtendedPciDevice> {
         self.inner.as_ref().write().unwrap()
@@ -32,27 +54,169 @@ impl ExtendedPciDeviceWrapper {
     pub fn borrow(&self) -> RwLockReadGuard<ExtendedPciDevice> {
         self.inner.as_ref().read().unwrap()
     }
+
+    pub fn connection_state(&self) -> ConnectionState {
+        self.borrow().connection
+    }
+
+    /// Run `callback` with the interface id whenever the connection to the device is lost.
+    /// It runs on the thread which noticed, after the device lock has been released.
+    pub fn on_connection_lost(&self, callback: impl Fn(usize) + Send + Sync + 'static) {
+        self.borrow_mut().lost_callbacks.push(Arc::new(callback));
+    }
+
+    /// Stop all accesses to the device, this is done automatically when a read shows that
+    /// the link is down.
+    pub fn mark_lost(&self) {
+        let (interface, callbacks) = {
+            let mut borrow = self.borrow_mut();
+            if borrow.connection == ConnectionState::Lost {
+                return;
+            }
+            borrow.connection = ConnectionState::Lost;
+            (borrow.device.id, borrow.lost_callbacks.clone())
+        };
+
+        tracing::warn!("Lost the connection to /dev/tenstorrent/{interface}");
+        for callback in callbacks {
+            callback(interface);
+        }
+    }
+
+    /// Stop all accesses to the device while it is reset, reopen it once the reset is done.
+    /// The reset module does this for every handle the process has open to the device.
+    pub fn mark_resetting(&self) {
+        self.borrow_mut().connection = ConnectionState::Resetting;
+    }
+
+    /// Reopen the device once a reset has finished, if it can't be found again the connection
+    /// is treated as lost.
+    pub fn finish_reset(&self) -> Result<(), LuwenError> {
+        let result = self.reopen();
+        if result.is_err() {
+            self.mark_lost();
+        }
+
+        result
+    }
+
+    /// Open the device again, remapping its bars and reprogramming the tlbs that were configured
+    /// through setup_tlb. The device is found by its pci address because the driver may hand
+    /// out a different interface id after a reset.
+    pub fn reopen(&self) -> Result<(), LuwenError> {
+        let (interface, bdf) = {
+            let borrow = self.borrow();
+            (borrow.device.id, pci_bdf(&borrow.device))
+        };
+        let device = open_by_bdf(interface, bdf)?;
+        if device.id != interface {
+            tracing::info!(
+                "{bdf} moved from /dev/tenstorrent/{interface} to /dev/tenstorrent/{}",
+                device.id
+            );
+        }
+
+        let mut borrow = self.borrow_mut();
+        let dma_config = borrow.device.dma_config.take();
+        // Dropping the old device unmaps its bars
+        borrow.device = device;
+        borrow.device.dma_config = dma_config;
+        // The ethernet dma buffers were allocated through the old device
+        borrow.eth_router = Arc::new(borrow.eth_router.with_new_cores());
+        for (index, tlb) in borrow.tlbs.clone() {
+            borrow.setup_tlb(index, tlb)?;
+        }
+        borrow.connection = ConnectionState::Connected;
+
+        Ok(())
+    }
+
+    // The device is only locked for a single access, so that transactions through different
+    // erisc cores can be interleaved.
+    fn erisc_read32(&self, eth_x: u8, eth_y: u8, addr: u32) -> Result<u32, PciError> {
+        let mut borrow = self.borrow_mut();
+        let default_tlb = borrow.default_tlb;
+        noc_read32(&mut borrow.device, default_tlb, 0, eth_x, eth_y, addr)
+    }
+
+    fn erisc_write32(&self, eth_x: u8, eth_y: u8, addr: u32, data: u32) -> Result<(), PciError> {
+        let mut borrow = self.borrow_mut();
+        let default_tlb = borrow.default_tlb;
+        noc_write32(&mut borrow.device, default_tlb, 0, eth_x, eth_y, addr, data)
+    }
+
+    /// Block transfer totals for each erisc core which is used to reach remote chips.
+    pub fn eth_transfer_stats(&self) -> Vec<((u8, u8), EthTransferStats)> {
+        let router = self.borrow().eth_router.clone();
+        router
+            .routes()
+            .iter()
+            .map(|route| {
+                let stats = route.core.lock().transfer_stats;
+                ((route.core.x, route.core.y), stats)
+            })
+            .collect()
+    }
+
+    /// Read the command queues of the erisc at the given noc0 coordinate.
+    pub fn eth_queue_state(&self, eth_x: u8, eth_y: u8) -> Result<EthQueueState, PciError> {
+        let mut ud = self;
+        let command_q_addr = ud.erisc_read32(eth_x, eth_y, 0x170)?;
+
+        ethernet::get_queue_state(
+            &mut ud,
+            move |ud: &mut &Self, addr| ud.erisc_read32(eth_x, eth_y, addr),
+            command_q_addr,
+        )
+    }
+
+    /// Wait for the erisc at the given noc0 coordinate to drain its request queue and then drop
+    /// any responses that were never picked up.
+    pub fn recover_eth_queues(&self, eth_x: u8, eth_y: u8) -> Result<EthQueueRecovery, LuwenError> {
+        let mut ud = self;
+        let command_q_addr = ud.erisc_read32(eth_x, eth_y, 0x170)?;
+        let drain_timeout = ud.borrow().eth_recovery.drain_timeout;
+
+        ethernet::recover_queues(
+            &mut ud,
+            move |ud: &mut &Self, addr| ud.erisc_read32(eth_x, eth_y, addr),
+            move |ud: &mut &Self, addr, data| ud.erisc_write32(eth_x, eth_y, addr, data),
+            command_q_addr,
+            drain_timeout,
+        )
+    }
 }
 
 pub struct ExtendedPciDevice {
     pub device: PciDevice,
 
-    pub harvested_rows: u32,
+    /// The size of the full NOC grid, harvesting doesn't change it. The harvested rows are only
+    /// known once ARC is up, see `ChipImpl::harvesting`.
     pub grid_size_x: u8,
     pub grid_size_y: u8,
 
-    pub eth_x: u8,
-    pub eth_y: u8,
+    /// The erisc cores used to reach remote chips.
+    pub eth_router: Arc<EthRouter>,
     pub command_q_addr: u32,
     pub fake_block: bool,
+    pub eth_recovery: EthRecoveryPolicy,
 
     pub default_tlb: u32,
 
-    pub ethernet_dma_buffer: HashMap<(u8, u8), DmaBuffer>,
+    /// Accesses fail without touching the device unless it is connected.
+    pub connection: ConnectionState,
+    /// Run with the interface id when the connection is lost.
+    lost_callbacks: Vec<Arc<dyn Fn(usize) + Send + Sync>>,
+    /// The tlbs configured through setup_tlb, they are reprogrammed when the device is reopened.
+    /// The default tlb is set up for every access so it isn't tracked.
+    tlbs: HashMap<u32, Tlb>,
 }
 
 impl ExtendedPciDevice {
     pub fn setup_tlb(&mut self, index: u32, tlb: Tlb) -> Result<(u64, u64), PciError> {
+        if index != self.default_tlb {
+            self.tlbs.insert(index, tlb.clone());
+        }
         ttkmd_if::tlb::setup_tlb(&mut self.device, index, tlb)
     }
 
@@ -167,27 +331,31 @@ impl ExtendedPciDevice {
             luwen_core::Arch::Unknown(id) => unreachable!("Found unrecognizable id {id:x}"),
         };
 
-        Ok(ExtendedPciDeviceWrapper {
-            inner: Arc::new(RwLock::new(ExtendedPciDevice {
-                harvested_rows: 0,
-                grid_size_x,
-                grid_size_y,
-                eth_x: 4,
-                eth_y: 6,
-                command_q_addr: 0,
-                fake_block: false,
-
-                default_tlb: match device.arch {
-                    luwen_core::Arch::Grayskull | luwen_core::Arch::Wormhole => 184,
-                    luwen_core::Arch::Blackhole => 190,
-                    luwen_core::Arch::Unknown(id) => unreachable!("Found unrecognizable id {id:x}"),
-                },
-
-                device,
-
-                ethernet_dma_buffer: HashMap::with_capacity(16),
-            })),
-        })
+        let inner = Arc::new(RwLock::new(ExtendedPciDevice {
+            grid_size_x,
+            grid_size_y,
+            // Until the chip tells us which links are usable only use the core which
+            // has always been used.
+            eth_router: Arc::new(EthRouter::single(4, 6)),
+            command_q_addr: 0,
+            fake_block: false,
+            eth_recovery: EthRecoveryPolicy::default(),
+
+            default_tlb: match device.arch {
+                luwen_core::Arch::Grayskull | luwen_core::Arch::Wormhole => 184,
+                luwen_core::Arch::Blackhole => 190,
+                luwen_core::Arch::Unknown(id) => unreachable!("Found unrecognizable id {id:x}"),
+            },
+
+            connection: ConnectionState::Connected,
+            lost_callbacks: Vec::new(),
+            tlbs: HashMap::new(),
+
+            device,
+        }));
+        OPEN_DEVICES.lock().unwrap().push(Arc::downgrade(&inner));
+
+        Ok(ExtendedPciDeviceWrapper { inner })
     }
 
     pub fn read_block(&mut self, addr: u32, data: &mut [u8]) -> Result<(), PciError> {
@@ -199,6 +367,29 @@ impl ExtendedPciDevice {
     }
 }
 
+fn pci_bdf(device: &PciDevice) -> PciBdf {
+    PciBdf {
+        domain: device.physical.pci_domain,
+        bus: device.physical.pci_bus,
+        slot: device.physical.slot,
+        function: device.physical.pci_function,
+    }
+}
+
+/// Open the device at `bdf`, trying `interface` first.
+fn open_by_bdf(interface: usize, bdf: PciBdf) -> Result<PciDevice, LuwenError> {
+    let others = PciDevice::scan().into_iter().filter(|id| *id != interface);
+    for id in std::iter::once(interface).chain(others) {
+        if let Ok(device) = PciDevice::open(id) {
+            if pci_bdf(&device) == bdf {
+                return Ok(device);
+            }
+        }
+    }
+
+    Err(LuwenError::DeviceMissing { interface, bdf })
+}
+
 fn noc_write32(
     device: &mut PciDevice,
     tlb_index: u32,
@@ -250,6 +441,106 @@ fn noc_read32(
     Ok(u32::from_le_bytes(data))
 }
 
+/// Run an ethernet transaction, recovering the command queues and retrying if it times out.
+fn with_eth_recovery<T>(
+    ud: &ExtendedPciDeviceWrapper,
+    core: &EthCore,
+    mut f: impl FnMut() -> Result<T, LuwenError>,
+) -> Result<T, LuwenError> {
+    let retries = ud.borrow().eth_recovery.retries;
+
+    let mut attempt = 0;
+    loop {
+        match f() {
+            Err(err @ LuwenError::EthernetTimeout(_)) if attempt < retries => {
+                attempt += 1;
+                tracing::warn!(
+                    "{err} on erisc ({}, {}), attempting recovery",
+                    core.x,
+                    core.y
+                );
+                match ud.recover_eth_queues(core.x, core.y) {
+                    Ok(recovery) => {
+                        if !recovery.is_clean() {
+                            tracing::warn!(
+                                "Recovered erisc ({}, {}) queues: {recovery:?}",
+                                core.x,
+                                core.y
+                            );
+                        }
+                    }
+                    Err(recovery_err) => {
+                        tracing::warn!(
+                            "Failed to recover erisc ({}, {}) queues: {recovery_err}",
+                            core.x,
+                            core.y
+                        );
+                        return Err(err);
+                    }
+                }
+            }
+            result => return result,
+        }
+    }
+}
+
+/// Run an ethernet transaction to `dest` through the best available erisc core, failing over to
+/// the next core if it stops responding.
+fn eth_transaction<T>(
+    ud: &ExtendedPciDeviceWrapper,
+    dest: &EthAddr,
+    mut f: impl FnMut(&ExtendedPciDeviceWrapper, &EthCore, &mut EthCoreState) -> Result<T, LuwenError>,
+) -> Result<T, LuwenError> {
+    let router = ud.borrow().eth_router.clone();
+
+    let mut last_err = None;
+    for core in router.candidates(dest) {
+        let mut state = core.lock();
+        match with_eth_recovery(ud, &core, || f(ud, &core, &mut state)) {
+            Err(err @ LuwenError::EthernetTimeout(_)) => {
+                tracing::warn!(
+                    "erisc ({}, {}) stopped responding, failing over to the next core",
+                    core.x,
+                    core.y
+                );
+                core.set_healthy(false);
+                last_err = Some(err);
+            }
+            result => {
+                if result.is_ok() {
+                    core.set_healthy(true);
+                }
+                return result;
+            }
+        }
+    }
+
+    Err(last_err.unwrap_or_else(|| {
+        LuwenError::Custom("No ethernet cores available to reach remote chips".to_string())
+    }))
+}
+
+/// Get the dma buffer used for block transfers through a core, allocating it on first use.
+fn core_dma_buffer<'a>(
+    ud: &ExtendedPciDeviceWrapper,
+    state: &'a mut EthCoreState,
+) -> Option<&'a mut DmaBuffer> {
+    if state.dma_buffer.is_none() && !state.dma_unavailable {
+        // 1 MB buffer
+        match ud.borrow_mut().device.allocate_dma_buffer(1 << 20) {
+            Ok(buffer) => state.dma_buffer = Some(buffer),
+            Err(err) => {
+                tracing::warn!(
+                    "Could not allocate an ethernet dma buffer, falling back to word transfers: {err}"
+                );
+                state.dma_unavailable = true;
+            }
+        }
+    }
+
+    state.dma_buffer.as_mut()
+}
+
 pub fn comms_callback(
     ud: &ExtendedPciDeviceWrapper,
     op: FnOptions,
@@ -261,6 +552,27 @@ pub fn comms_callback_inner(
     ud: &ExtendedPciDeviceWrapper,
     op: FnOptions,
 ) -> Result<(), LuwenError> {
+    // The mappings of a lost device may no longer be backed by anything, so don't touch them.
+    // Driver requests only use what we already know about the device.
+    if !matches!(op, FnOptions::Driver(_)) {
+        let (interface, state) = {
+            let borrow = ud.borrow();
+            (borrow.device.id, borrow.connection)
+        };
+        if state != ConnectionState::Connected {
+            return Err(LuwenError::ConnectionLost { interface, state });
+        }
+    }
+
+    let result = device_callback(ud, op);
+    if let Err(LuwenError::PciError(PciError::BrokenConnection)) = &result {
+        ud.mark_lost();
+    }
+
+    result
+}
+
+fn device_callback(ud: &ExtendedPciDeviceWrapper, op: FnOptions) -> Result<(), LuwenError> {
     match op {
         FnOptions::Driver(op) => match op {
             FnDriver::DeviceInfo(info) => {
@@ -283,6 +595,21 @@ pub fn comms_callback_inner(
                     }
                 }
             }
+            FnDriver::EthRoutes { routes, len } => {
+                let routes = unsafe { std::slice::from_raw_parts(routes, len) };
+                let mut borrow = ud.borrow_mut();
+                borrow.eth_router = Arc::new(borrow.eth_router.with_routes(routes));
+            }
+            FnDriver::ConnectionState(state) => {
+                if !state.is_null() {
+                    unsafe {
+                        *state = ud.connection_state();
+                    }
+                }
+            }
+            FnDriver::Reopen => ud.reopen()?,
+            // Each process opens the device itself, so there is nobody to keep off it
+            FnDriver::Acquire | FnDriver::Release => {}
         },
         FnOptions::Axi(op) => match op {
             luwen_if::FnAxi::Read { addr, data, len } => {
@@ -433,84 +760,72 @@ pub fn comms_callback_inner(
                 data,
                 len,
             } => {
-                let mut borrow = ud.borrow_mut();
-                let borrow: &mut ExtendedPciDevice = &mut borrow;
-
-                let eth_x = borrow.eth_x;
-                let eth_y = borrow.eth_y;
-
-                let command_q_addr = noc_read32(
-                    &mut borrow.device,
-                    borrow.default_tlb,
-                    0,
-                    eth_x,
-                    eth_y,
-                    0x170,
-                )?;
-                let fake_block = borrow.fake_block;
+                eth_transaction(ud, &op.addr, |mut ud, core, state| {
+                    let (eth_x, eth_y) = (core.x, core.y);
+                    let (fake_block, timeout) = {
+                        let borrow = ud.borrow();
+                        (borrow.fake_block, borrow.eth_recovery.timeout)
+                    };
+
+                    let read32 = move |ud: &mut &ExtendedPciDeviceWrapper, addr| {
+                        ud.erisc_read32(eth_x, eth_y, addr)
+                    };
+                    let write32 = move |ud: &mut &ExtendedPciDeviceWrapper, addr, data| {
+                        ud.erisc_write32(eth_x, eth_y, addr, data)
+                    };
+
+                    let command_q_addr = read32(&mut ud, 0x170)?;
+
+                    ethernet::fixup_queues(&mut ud, read32, write32, command_q_addr)?;
+
+                    let coord = EthCommCoord {
+                        coord: op.addr,
+                        noc_id,
+                        noc_x: x as u8,
+                        noc_y: y as u8,
+                        offset: addr,
+                    };
 
-                let default_tlb = borrow.default_tlb;
-                let read32 =
-                    |borrow: &mut _, addr| noc_read32(borrow, default_tlb, 0, eth_x, eth_y, addr);
-
-                let write32 = |borrow: &mut _, addr, data| {
-                    noc_write32(borrow, default_tlb, 0, eth_x, eth_y, addr, data)
-                };
-
-                let dma_buffer = {
-                    let key = (eth_x, eth_y);
-                    if let Entry::Vacant(e) = borrow.ethernet_dma_buffer.entry(key) {
-                        // 1 MB buffer
-                        e.insert(borrow.device.allocate_dma_buffer(1 << 20)?);
+                    if len <= 4 {
+                        let value = ethernet::eth_read32(
+                            &mut ud,
+                            read32,
+                            write32,
+                            command_q_addr,
+                            coord,
+                            timeout,
+                        )?;
+
+                        let sl = unsafe { std::slice::from_raw_parts_mut(data, len as usize) };
+                        let vl = value.to_le_bytes();
+
+                        for (s, v) in sl.iter_mut().zip(vl.iter()) {
+                            *s = *v;
+                        }
+                    } else {
+                        let dma_buffer = core_dma_buffer(ud, state);
+                        let stats = ethernet::block_read(
+                            &mut ud,
+                            read32,
+                            write32,
+                            dma_buffer,
+                            command_q_addr,
+                            timeout,
+                            fake_block,
+                            coord,
+                            unsafe { std::slice::from_raw_parts_mut(data, len as usize) },
+                        )?;
+                        tracing::debug!(
+                            "erisc ({eth_x}, {eth_y}) moved {} bytes in {:?} ({:.1} MB/s)",
+                            stats.bytes,
+                            stats.elapsed,
+                            stats.throughput() / 1_048_576.0
+                        );
+                        state.transfer_stats.accumulate(&stats);
                     }
 
-                    // SAFETY: Can never get here without first inserting something into the hashmap
-                    unsafe { borrow.ethernet_dma_buffer.get_mut(&key).unwrap_unchecked() }
-                };
-
-                ethernet::fixup_queues(&mut borrow.device, read32, write32, command_q_addr)?;
-
-                if len <= 4 {
-                    let value = ethernet::eth_read32(
-                        &mut borrow.device,
-                        read32,
-                        write32,
-                        command_q_addr,
-                        EthCommCoord {
-                            coord: op.addr,
-                            noc_id,
-                            noc_x: x as u8,
-                            noc_y: y as u8,
-                            offset: addr,
-                        },
-                        std::time::Duration::from_secs(5 * 60),
-                    )?;
-
-                    let sl = unsafe { std::slice::from_raw_parts_mut(data, len as usize) };
-                    let vl = value.to_le_bytes();
-
-                    for (s, v) in sl.iter_mut().zip(vl.iter()) {
-                        *s = *v;
-                    }
-                } else {
-                    ethernet::block_read(
-                        &mut borrow.device,
-                        read32,
-                        write32,
-                        dma_buffer,
-                        command_q_addr,
-                        std::time::Duration::from_secs(5 * 60),
-                        fake_block,
-                        EthCommCoord {
-                            coord: op.addr,
-                            noc_id,
-                            noc_x: x as u8,
-                            noc_y: y as u8,
-                            offset: addr,
-                        },
-                        unsafe { std::slice::from_raw_parts_mut(data, len as usize) },
-                    )?;
-                }
+                    Ok(())
+                })?;
             }
             luwen_if::FnNoc::Write {
                 noc_id,
@@ -520,79 +835,73 @@ pub fn comms_callback_inner(
                 data,
                 len,
             } => {
-                let mut borrow = ud.borrow_mut();
-                let borrow: &mut ExtendedPciDevice = &mut borrow;
-
-                let eth_x = borrow.eth_x;
-                let eth_y = borrow.eth_y;
-
-                let command_q_addr =
-                    borrow.noc_read32(borrow.default_tlb, 0, eth_x, eth_y, 0x170)?;
-                let fake_block = borrow.fake_block;
-
-                let default_tlb = borrow.default_tlb;
-                let read32 =
-                    |borrow: &mut _, addr| noc_read32(borrow, default_tlb, 0, eth_x, eth_y, addr);
-
-                let write32 = |borrow: &mut _, addr, data| {
-                    noc_write32(borrow, default_tlb, 0, eth_x, eth_y, addr, data)
-                };
-
-                let dma_buffer = {
-                    let key = (eth_x, eth_y);
-                    if let Entry::Vacant(e) = borrow.ethernet_dma_buffer.entry(key) {
-                        // 1 MB buffer
-                        e.insert(borrow.device.allocate_dma_buffer(1 << 20)?);
-                    }
+                eth_transaction(ud, &op.addr, |mut ud, core, state| {
+                    let (eth_x, eth_y) = (core.x, core.y);
+                    let (fake_block, timeout) = {
+                        let borrow = ud.borrow();
+                        (borrow.fake_block, borrow.eth_recovery.timeout)
+                    };
+
+                    let read32 = move |ud: &mut &ExtendedPciDeviceWrapper, addr| {
+                        ud.erisc_read32(eth_x, eth_y, addr)
+                    };
+                    let write32 = move |ud: &mut &ExtendedPciDeviceWrapper, addr, data| {
+                        ud.erisc_write32(eth_x, eth_y, addr, data)
+                    };
+
+                    let command_q_addr = read32(&mut ud, 0x170)?;
+
+                    ethernet::fixup_queues(&mut ud, read32, write32, command_q_addr)?;
+
+                    let coord = EthCommCoord {
+                        coord: op.addr,
+                        noc_id,
+                        noc_x: x as u8,
+                        noc_y: y as u8,
+                        offset: addr,
+                    };
 
-                    // SAFETY: Can never get here without first inserting something into the hashmap
-                    unsafe { borrow.ethernet_dma_buffer.get_mut(&key).unwrap_unchecked() }
-                };
-
-                ethernet::fixup_queues(&mut borrow.device, read32, write32, command_q_addr)?;
+                    if len <= 4 {
+                        let sl = unsafe { std::slice::from_raw_parts(data, len as usize) };
+                        let mut value = 0u32;
+                        for s in sl.iter().rev() {
+                            value <<= 8;
+                            value |= *s as u32;
+                        }
 
-                if len <= 4 {
-                    let sl = unsafe { std::slice::from_raw_parts(data, len as usize) };
-                    let mut value = 0u32;
-                    for s in sl.iter().rev() {
-                        value <<= 8;
-                        value |= *s as u32;
+                        ethernet::eth_write32(
+                            &mut ud,
+                            read32,
+                            write32,
+                            command_q_addr,
+                            coord,
+                            timeout,
+                            value,
+                        )?;
+                    } else {
+                        let dma_buffer = core_dma_buffer(ud, state);
+                        let stats = ethernet::block_write(
+                            &mut ud,
+                            read32,
+                            write32,
+                            dma_buffer,
+                            command_q_addr,
+                            timeout,
+                            fake_block,
+                            coord,
+                            unsafe { std::slice::from_raw_parts(data, len as usize) },
+                        )?;
+                        tracing::debug!(
+                            "erisc ({eth_x}, {eth_y}) moved {} bytes in {:?} ({:.1} MB/s)",
+                            stats.bytes,
+                            stats.elapsed,
+                            stats.throughput() / 1_048_576.0
+                        );
+                        state.transfer_stats.accumulate(&stats);
                     }
 
-                    ethernet::eth_write32(
-                        &mut borrow.device,
-                        read32,
-                        write32,
-                        command_q_addr,
-                        EthCommCoord {
-                            coord: op.addr,
-                            noc_id,
-                            noc_x: x as u8,
-                            noc_y: y as u8,
-                            offset: addr,
-                        },
-                        std::time::Duration::from_secs(5 * 60),
-                        value,
-                    )?;
-                } else {
-                    ethernet::block_write(
-                        &mut borrow.device,
-                        read32,
-                        write32,
-                        dma_buffer,
-                        command_q_addr,
-                        std::time::Duration::from_secs(5 * 60),
-                        fake_block,
-                        EthCommCoord {
-                            coord: op.addr,
-                            noc_id,
-                            noc_x: x as u8,
-                            noc_y: y as u8,
-                            offset: addr,
-                        },
-                        unsafe { std::slice::from_raw_parts(data, len as usize) },
-                    )?;
-                }
+                    Ok(())
+                })?;
             }
             luwen_if::FnNoc::Broadcast {
                 noc_id,
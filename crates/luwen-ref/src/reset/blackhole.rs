@@ -0,0 +1,63 @@
+// SPDX-FileCopyrightText: © 2024 Tenstorrent Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use std::{fs::OpenOptions, os::unix::fs::FileExt};
+
+use crate::error::ResetError;
+
+/// Reset through the pci config space.
+///
+/// While the chip is in reset bit 1 of byte 4 of its config space is set, the reset is
+/// complete once we have seen that bit go high and then low again.
+pub struct ResetTracker {
+    pci_bdf: String,
+    interface: usize,
+    saw_in_reset: bool,
+}
+
+impl ResetTracker {
+    pub fn init(device: &ttkmd_if::PciDevice) -> Self {
+        let info = &device.physical;
+        Self {
+            pci_bdf: format!(
+                "{:04x}:{:02x}:{:02x}.{:x}",
+                info.pci_domain, info.pci_bus, info.slot, info.pci_function
+            ),
+            interface: device.id,
+            saw_in_reset: false,
+        }
+    }
+}
+
+impl super::ResetTracker for ResetTracker {
+    fn reset(&mut self) -> Result<(), ResetError> {
+        super::config_space_reset(self.interface)
+    }
+
+    fn poll(&mut self) -> Result<Option<bool>, ResetError> {
+        // The config space may briefly disappear while the chip is in reset
+        let Ok(file) = OpenOptions::new()
+            .read(true)
+            .open(format!("/sys/bus/pci/devices/{}/config", self.pci_bdf))
+        else {
+            return Ok(Some(false));
+        };
+
+        let mut config_bit = [0; 1];
+        file.read_exact_at(&mut config_bit, 4)
+            .map_err(|source| ResetError::ConfigSpace {
+                bdf: self.pci_bdf.clone(),
+                source,
+            })?;
+        let reset_complete = (config_bit[0] >> 1) & 0x1 == 0;
+
+        if !self.saw_in_reset {
+            if !reset_complete {
+                self.saw_in_reset = true;
+            }
+            Ok(Some(false))
+        } else {
+            Ok(Some(reset_complete))
+        }
+    }
+}
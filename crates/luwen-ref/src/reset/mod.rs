@@ -0,0 +1,704 @@
+// SPDX-FileCopyrightText: © 2024 Tenstorrent Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Resetting chips and bringing them back into a usable state.
+//!
+//! Local chips are reset through the kernel driver or the ARC, remote chips can only be reset
+//! with an ARC message sent over ethernet. Because resetting a chip takes down the ethernet
+//! links running through it, remote chips are reset starting from the far edge of the mesh.
+
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::time::Duration;
+
+use luwen_core::Arch;
+use luwen_if::{
+    chip::{poll, ArcMsgOptions, Chip, PollOptions},
+    ArcState, ChipDetectOptions, ChipImpl, EthAddr, TypedArcMsg,
+};
+
+use crate::error::{LuwenError, ResetError};
+
+mod blackhole;
+mod wormhole;
+
+#[derive(Clone, Debug)]
+pub struct ResetOptions {
+    /// Retrain the pcie link of each interface before resetting it.
+    pub link_reset: bool,
+    /// Also reset the chips which are only reachable over ethernet.
+    pub reset_remote: bool,
+    /// How long to wait for the chips to come back out of reset.
+    pub timeout: Duration,
+    /// Initial time between checks of the reset state, backing off to ten times this.
+    pub poll_interval: Duration,
+    /// Rerun chip detection after the reset and compare against what was found before.
+    pub verify: bool,
+}
+
+impl Default for ResetOptions {
+    fn default() -> Self {
+        ResetOptions {
+            link_reset: true,
+            reset_remote: true,
+            timeout: Duration::from_secs(2),
+            poll_interval: Duration::from_millis(10),
+            verify: true,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum ResetStatus {
+    /// The chip signalled that it came out of reset.
+    Completed,
+    /// The reset was triggered but the chip has no way of signalling completion,
+    /// we waited for the full timeout.
+    Unconfirmed,
+    /// The chip did not come back out of reset before the timeout.
+    TimedOut,
+    Failed(ResetError),
+}
+
+impl ResetStatus {
+    pub fn is_ok(&self) -> bool {
+        matches!(self, ResetStatus::Completed | ResetStatus::Unconfirmed)
+    }
+}
+
+impl std::fmt::Display for ResetStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ResetStatus::Completed => f.write_str("completed"),
+            ResetStatus::Unconfirmed => f.write_str("triggered"),
+            ResetStatus::TimedOut => f.write_str("timed out"),
+            ResetStatus::Failed(err) => write!(f, "failed: {err}"),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct InterfaceReset {
+    pub interface: usize,
+    pub arch: Option<Arch>,
+    /// Result of retraining the pcie link, None if it was not attempted.
+    pub link: Option<Result<(), ResetError>>,
+    pub status: ResetStatus,
+    /// Result of restoring the pci state after the reset, None if the reset was never triggered.
+    pub restore: Option<Result<(), ResetError>>,
+}
+
+impl InterfaceReset {
+    pub fn is_ok(&self) -> bool {
+        self.status.is_ok() && matches!(self.restore, Some(Ok(())))
+    }
+}
+
+#[derive(Debug)]
+pub struct RemoteReset {
+    pub coord: EthAddr,
+    /// Number of ethernet hops from the closest mmio chip, None if no route was found.
+    pub hops: Option<u32>,
+    pub status: ResetStatus,
+}
+
+#[derive(Debug, Default)]
+pub struct ResetVerification {
+    /// Interfaces which were present before the reset but could not be found afterwards.
+    pub missing_interfaces: Vec<usize>,
+    /// Interfaces which showed up after the reset.
+    pub new_interfaces: Vec<usize>,
+    /// Interfaces which came back but did not finish initializing.
+    pub uninitialized_interfaces: Vec<usize>,
+    /// Remote chips which were reset but could not be found afterwards.
+    pub missing_remote: Vec<EthAddr>,
+}
+
+impl ResetVerification {
+    pub fn is_ok(&self) -> bool {
+        self.missing_interfaces.is_empty()
+            && self.new_interfaces.is_empty()
+            && self.uninitialized_interfaces.is_empty()
+            && self.missing_remote.is_empty()
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct ResetReport {
+    pub interfaces: Vec<InterfaceReset>,
+    /// Remote chips in the order they were reset.
+    pub remote: Vec<RemoteReset>,
+    /// None if verification was not requested.
+    pub verification: Option<Result<ResetVerification, LuwenError>>,
+}
+
+impl ResetReport {
+    pub fn is_ok(&self) -> bool {
+        self.interfaces.iter().all(|i| i.is_ok())
+            && self.remote.iter().all(|r| r.status.is_ok())
+            && match &self.verification {
+                Some(Ok(verification)) => verification.is_ok(),
+                Some(Err(_)) => false,
+                None => true,
+            }
+    }
+}
+
+/// The per arch reset procedure for a chip directly connected over pci.
+trait ResetTracker {
+    fn reset(&mut self) -> Result<(), ResetError>;
+
+    /// Returns Some(true) once the chip is out of reset, or None if the chip can't tell us.
+    fn poll(&mut self) -> Result<Option<bool>, ResetError>;
+}
+
+fn reset_ioctl(interface: usize, flags: u32) -> Result<(), ResetError> {
+    let fd = std::fs::OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open(format!("/dev/tenstorrent/{interface}"))
+        .map_err(|source| ResetError::Open { interface, source })?;
+
+    let mut reset_device = ttkmd_if::ioctl::ResetDevice {
+        input: ttkmd_if::ioctl::ResetDeviceIn {
+            flags,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    unsafe {
+        ttkmd_if::ioctl::reset_device(std::os::fd::AsRawFd::as_raw_fd(&fd), &mut reset_device)
+    }
+    .map_err(|err| ResetError::Ioctl {
+        interface,
+        source: err.into(),
+    })?;
+
+    if reset_device.output.result != 0 {
+        return Err(ResetError::Rejected {
+            interface,
+            result: reset_device.output.result,
+        });
+    }
+
+    Ok(())
+}
+
+/// Retrain the pcie link of the given interface.
+pub fn link_reset(interface: usize) -> Result<(), ResetError> {
+    reset_ioctl(interface, ttkmd_if::ioctl::RESET_DEVICE_RESET_PCIE_LINK)
+}
+
+/// Ask the driver to reset the chip by writing to its pci config space.
+pub fn config_space_reset(interface: usize) -> Result<(), ResetError> {
+    reset_ioctl(interface, ttkmd_if::ioctl::RESET_DEVICE_RESET_CONFIG_WRITE)
+}
+
+/// Restore the pci state saved by the driver, this is required before the chip can be
+/// accessed again after a reset.
+pub fn restore_state(interface: usize) -> Result<(), ResetError> {
+    reset_ioctl(interface, ttkmd_if::ioctl::RESET_DEVICE_RESTORE_STATE)
+}
+
+/// Warm reset of a chip.
+///
+/// Grayskull and Wormhole (including remote chips) are reset by the ARC,
+/// Blackhole is reset through its pci config space.
+/// This only triggers the reset, the chip will not be accessible until it has come back up and
+/// (for local chips) its pci state has been restored.
+pub fn asic_reset(chip: &Chip) -> Result<(), ResetError> {
+    match chip.get_arch() {
+        Arch::Blackhole => {
+            let interface = chip
+                .get_device_info()?
+                .ok_or_else(|| {
+                    luwen_if::error::PlatformError::Generic(
+                        "Cannot reset a blackhole which is not connected over pci".to_string(),
+                        luwen_if::error::BtWrapper::capture(),
+                    )
+                })?
+                .interface_id;
+            config_space_reset(interface as usize)
+        }
+        _ => {
+            chip.arc_msg(ArcMsgOptions {
+                msg: TypedArcMsg::SetArcState {
+                    state: ArcState::A3,
+                }
+                .into(),
+                ..Default::default()
+            })?;
+            chip.arc_msg(ArcMsgOptions {
+                msg: TypedArcMsg::TriggerReset.into(),
+                wait_for_done: false,
+                ..Default::default()
+            })?;
+
+            Ok(())
+        }
+    }
+}
+
+/// Number of ethernet hops from the closest root to each chip in the mesh.
+fn hops_from_roots(
+    roots: &[EthAddr],
+    links: &HashMap<EthAddr, Vec<EthAddr>>,
+) -> HashMap<EthAddr, u32> {
+    let mut hops = HashMap::new();
+    let mut queue = VecDeque::new();
+    for root in roots {
+        if hops.insert(*root, 0).is_none() {
+            queue.push_back(*root);
+        }
+    }
+
+    while let Some(coord) = queue.pop_front() {
+        let next = hops[&coord] + 1;
+        for neighbour in links.get(&coord).into_iter().flatten() {
+            if !hops.contains_key(neighbour) {
+                hops.insert(*neighbour, next);
+                queue.push_back(*neighbour);
+            }
+        }
+    }
+
+    hops
+}
+
+/// Sort chips so that the ones furthest away from the roots come first, chips without a known
+/// route are treated as being the furthest away.
+fn order_far_to_near<T>(
+    chips: Vec<(EthAddr, T)>,
+    roots: &[EthAddr],
+    links: &HashMap<EthAddr, Vec<EthAddr>>,
+) -> Vec<(EthAddr, Option<u32>, T)> {
+    let hops = hops_from_roots(roots, links);
+    let mut chips = chips
+        .into_iter()
+        .map(|(coord, chip)| (coord, hops.get(&coord).copied(), chip))
+        .collect::<Vec<_>>();
+    chips.sort_by_key(|(_, hops, _)| std::cmp::Reverse(hops.unwrap_or(u32::MAX)));
+
+    chips
+}
+
+/// Pick the remote chips which are reached through the selected interfaces, ordered far to
+/// near. Routes through local chips of other interfaces are not followed, they may belong to a
+/// different host. When every local chip is selected, chips without a known route are reset too.
+fn remote_to_reset<T>(
+    remote: Vec<(EthAddr, T)>,
+    local: &[(usize, EthAddr)],
+    interfaces: &[usize],
+    links: &HashMap<EthAddr, Vec<EthAddr>>,
+) -> Vec<(EthAddr, Option<u32>, T)> {
+    let (selected, other): (Vec<_>, Vec<_>) = local
+        .iter()
+        .partition(|(interface, _)| interfaces.contains(interface));
+    let roots = selected.iter().map(|(_, coord)| *coord).collect::<Vec<_>>();
+    let other = other
+        .iter()
+        .map(|(_, coord)| *coord)
+        .collect::<HashSet<_>>();
+
+    let links = links
+        .iter()
+        .filter(|(coord, _)| !other.contains(*coord))
+        .map(|(coord, neighbours)| {
+            (
+                *coord,
+                neighbours
+                    .iter()
+                    .filter(|n| !other.contains(*n))
+                    .copied()
+                    .collect(),
+            )
+        })
+        .collect();
+
+    let reset_unrouted = other.is_empty();
+    order_far_to_near(remote, &roots, &links)
+        .into_iter()
+        .filter(|(_, hops, _)| hops.is_some() || reset_unrouted)
+        .collect()
+}
+
+/// The chips which can currently be found; remote chips by coordinate plus the mesh links.
+struct Inventory {
+    /// Interface and coordinate of every local chip.
+    local: Vec<(usize, EthAddr)>,
+    remote: Vec<(EthAddr, Chip)>,
+    links: HashMap<EthAddr, Vec<EthAddr>>,
+}
+
+fn take_inventory() -> Result<Inventory, LuwenError> {
+    let mut inventory = Inventory {
+        local: Vec::new(),
+        remote: Vec::new(),
+        links: HashMap::new(),
+    };
+
+    for chip in crate::detect::detect_chips_options(ChipDetectOptions::default())? {
+        let chip = chip.upgrade();
+        let Some(wh) = chip.as_wh() else {
+            continue;
+        };
+
+        let Ok(coord) = wh.get_local_chip_coord() else {
+            continue;
+        };
+        if let Ok(neighbours) = chip.get_neighbouring_chips() {
+            inventory.links.insert(
+                coord,
+                neighbours
+                    .into_iter()
+                    .filter(|n| n.routing_enabled)
+                    .map(|n| n.eth_addr)
+                    .collect(),
+            );
+        }
+
+        if wh.is_remote {
+            inventory.remote.push((coord, chip));
+        } else if let Ok(Some(info)) = chip.get_device_info() {
+            inventory.local.push((info.interface_id as usize, coord));
+        }
+    }
+
+    Ok(inventory)
+}
+
+/// Reset the given pci interfaces (and optionally the remote chips reachable through them).
+pub fn reset_interfaces(
+    interfaces: &[usize],
+    options: &ResetOptions,
+) -> Result<ResetReport, LuwenError> {
+    let mut report = ResetReport::default();
+
+    // Cards which are not being reset are left out of the verification
+    let untouched = if options.verify {
+        ttkmd_if::PciDevice::scan()
+            .into_iter()
+            .filter(|interface| !interfaces.contains(interface))
+            .collect()
+    } else {
+        Vec::new()
+    };
+
+    let inventory = if options.reset_remote {
+        Some(take_inventory()?)
+    } else {
+        None
+    };
+
+    // Remote chips go first, otherwise we would lose the route to them
+    if let Some(inventory) = &inventory {
+        for (coord, hops, chip) in remote_to_reset(
+            inventory
+                .remote
+                .iter()
+                .map(|(c, chip)| (*c, chip))
+                .collect(),
+            &inventory.local,
+            interfaces,
+            &inventory.links,
+        ) {
+            let status = match asic_reset(chip) {
+                Ok(()) => ResetStatus::Unconfirmed,
+                Err(err) => ResetStatus::Failed(err),
+            };
+            report.remote.push(RemoteReset {
+                coord,
+                hops,
+                status,
+            });
+        }
+    }
+
+    let mut trackers = Vec::with_capacity(interfaces.len());
+    for interface in interfaces.iter().copied() {
+        // Keep the handles this process has open from touching the device until it is back
+        let handles = crate::open_handles(interface);
+        for handle in &handles {
+            handle.mark_resetting();
+        }
+
+        let link = options.link_reset.then(|| link_reset(interface));
+
+        let tracker = ttkmd_if::PciDevice::open(interface).map(|device| {
+            let tracker = match device.arch {
+                Arch::Blackhole => {
+                    Box::new(blackhole::ResetTracker::init(&device)) as Box<dyn ResetTracker>
+                }
+                _ => Box::new(wormhole::ResetTracker::init(interface)) as Box<dyn ResetTracker>,
+            };
+            (device.arch, tracker)
+        });
+
+        let (arch, status, tracker) = match tracker {
+            Ok((arch, mut tracker)) => match tracker.reset() {
+                Ok(()) => (Some(arch), None, Some(tracker)),
+                Err(err) => (Some(arch), Some(ResetStatus::Failed(err)), None),
+            },
+            Err(err) => (None, Some(ResetStatus::Failed(err.into())), None),
+        };
+
+        trackers.push((
+            InterfaceReset {
+                interface,
+                arch,
+                link,
+                // Filled in below once we know how the reset went
+                status: status.unwrap_or(ResetStatus::Unconfirmed),
+                restore: None,
+            },
+            tracker,
+            false,
+            handles,
+        ));
+    }
+
+    // Chips which have not finished by the deadline are sorted out below
+    let _ = poll(
+        "chips to come out of reset",
+        &PollOptions::new(options.timeout)
+            .backoff(options.poll_interval, options.poll_interval * 10),
+        || {
+            let mut pending = 0;
+            for (result, tracker, done, _) in trackers.iter_mut() {
+                let Some(tracker) = tracker else {
+                    continue;
+                };
+                if *done {
+                    continue;
+                }
+
+                match tracker.poll() {
+                    Ok(Some(true)) => {
+                        result.status = ResetStatus::Completed;
+                        *done = true;
+                    }
+                    Ok(Some(false)) | Ok(None) => pending += 1,
+                    Err(err) => {
+                        result.status = ResetStatus::Failed(err);
+                        *done = true;
+                    }
+                }
+            }
+            Ok::<_, std::convert::Infallible>(pending)
+        },
+        |pending| *pending == 0,
+    );
+
+    for (mut result, tracker, done, handles) in trackers {
+        if let Some(mut tracker) = tracker {
+            if !done {
+                result.status = match tracker.poll() {
+                    Ok(None) => ResetStatus::Unconfirmed,
+                    Ok(Some(true)) => ResetStatus::Completed,
+                    Ok(Some(false)) => ResetStatus::TimedOut,
+                    Err(err) => ResetStatus::Failed(err),
+                };
+            }
+            result.restore = Some(restore_state(result.interface));
+        }
+
+        for handle in handles {
+            if let Err(err) = handle.finish_reset() {
+                tracing::warn!(
+                    "Could not reopen interface {} after reset: {err}",
+                    result.interface
+                );
+            }
+        }
+
+        report.interfaces.push(result);
+    }
+
+    if options.verify {
+        report.verification = Some(verify(
+            interfaces,
+            &untouched,
+            &report
+                .remote
+                .iter()
+                .filter(|r| r.status.is_ok())
+                .map(|r| r.coord)
+                .collect::<Vec<_>>(),
+        ));
+    }
+
+    Ok(report)
+}
+
+/// Reset every chip in the system.
+pub fn reset_all(options: &ResetOptions) -> Result<ResetReport, LuwenError> {
+    reset_interfaces(&ttkmd_if::PciDevice::scan(), options)
+}
+
+/// Rerun detection and compare the chips that were found against what we expected to come back.
+/// The `untouched` interfaces were present but not reset, they are not reported as new.
+pub fn verify(
+    interfaces: &[usize],
+    untouched: &[usize],
+    remote: &[EthAddr],
+) -> Result<ResetVerification, LuwenError> {
+    let chips = crate::detect::detect_chips_options(
+        ChipDetectOptions::default().local_only(remote.is_empty()),
+    )?;
+
+    let mut found = HashSet::new();
+    let mut found_remote = HashSet::new();
+    let mut uninitialized = Vec::new();
+    for chip in chips {
+        let initialized = chip.is_initialized() && !chip.status().is_some_and(|s| s.has_error());
+        let chip = chip.upgrade();
+
+        if let Some(wh) = chip.as_wh() {
+            if wh.is_remote {
+                if let Ok(coord) = wh.get_local_chip_coord() {
+                    found_remote.insert(coord);
+                }
+                continue;
+            }
+        }
+
+        if let Ok(Some(info)) = chip.get_device_info() {
+            let interface = info.interface_id as usize;
+            found.insert(interface);
+            if !initialized {
+                uninitialized.push(interface);
+            }
+        }
+    }
+
+    let mut verification = compare_interfaces(interfaces, untouched, &found, &uninitialized);
+    verification.missing_remote = remote
+        .iter()
+        .filter(|coord| !found_remote.contains(*coord))
+        .copied()
+        .collect();
+
+    Ok(verification)
+}
+
+/// Compare the interfaces found after a reset against the ones which were reset, ignoring the
+/// `untouched` interfaces.
+fn compare_interfaces(
+    reset: &[usize],
+    untouched: &[usize],
+    found: &HashSet<usize>,
+    uninitialized: &[usize],
+) -> ResetVerification {
+    let expected = reset.iter().copied().collect::<HashSet<_>>();
+    let found = found
+        .iter()
+        .copied()
+        .filter(|interface| !untouched.contains(interface))
+        .collect::<HashSet<_>>();
+
+    let mut verification = ResetVerification {
+        missing_interfaces: expected.difference(&found).copied().collect(),
+        new_interfaces: found.difference(&expected).copied().collect(),
+        uninitialized_interfaces: uninitialized
+            .iter()
+            .copied()
+            .filter(|interface| !untouched.contains(interface))
+            .collect(),
+        missing_remote: Vec::new(),
+    };
+    verification.missing_interfaces.sort();
+    verification.new_interfaces.sort();
+    verification.uninitialized_interfaces.sort();
+
+    verification
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn coord(shelf_x: u8, shelf_y: u8) -> EthAddr {
+        EthAddr {
+            shelf_x,
+            shelf_y,
+            rack_x: 0,
+            rack_y: 0,
+        }
+    }
+
+    #[test]
+    fn remote_reset_order() {
+        // root - a - b - c, d is not connected
+        let (root, a, b, c, d) = (
+            coord(0, 0),
+            coord(1, 0),
+            coord(2, 0),
+            coord(3, 0),
+            coord(9, 9),
+        );
+        let links = HashMap::from([
+            (root, vec![a]),
+            (a, vec![root, b]),
+            (b, vec![a, c]),
+            (c, vec![b]),
+        ]);
+
+        let order = order_far_to_near(
+            vec![(a, "a"), (c, "c"), (d, "d"), (b, "b")],
+            &[root],
+            &links,
+        );
+        assert_eq!(
+            order,
+            vec![
+                (d, None, "d"),
+                (c, Some(3), "c"),
+                (b, Some(2), "b"),
+                (a, Some(1), "a"),
+            ]
+        );
+    }
+
+    #[test]
+    fn remote_reset_only_follows_selected_interfaces() {
+        // root0 - a - root1 - b, c is not connected
+        let (root0, root1, a, b, c) = (
+            coord(0, 0),
+            coord(0, 1),
+            coord(1, 0),
+            coord(1, 1),
+            coord(9, 9),
+        );
+        let links = HashMap::from([
+            (root0, vec![a]),
+            (a, vec![root0, root1]),
+            (root1, vec![a, b]),
+            (b, vec![root1]),
+        ]);
+        let local = [(0, root0), (1, root1)];
+        let remote = vec![(a, "a"), (b, "b"), (c, "c")];
+
+        let order = remote_to_reset(remote.clone(), &local, &[0], &links);
+        assert_eq!(order, vec![(a, Some(1), "a")]);
+
+        let order = remote_to_reset(remote, &local, &[0, 1], &links);
+        assert_eq!(
+            order,
+            vec![(c, None, "c"), (a, Some(1), "a"), (b, Some(1), "b")]
+        );
+    }
+
+    #[test]
+    fn verify_partial_reset() {
+        // 1 and 3 were reset, 0 and 2 were left alone and 2 is still not initialized
+        let found = HashSet::from([0, 1, 2, 3]);
+        let verification = compare_interfaces(&[1, 3], &[0, 2], &found, &[2]);
+        assert!(verification.is_ok(), "{verification:?}");
+
+        // 3 came back as 4 and did not finish initializing
+        let found = HashSet::from([0, 1, 2, 4]);
+        let verification = compare_interfaces(&[1, 3], &[0, 2], &found, &[2, 4]);
+        assert_eq!(verification.missing_interfaces, vec![3]);
+        assert_eq!(verification.new_interfaces, vec![4]);
+        assert_eq!(verification.uninitialized_interfaces, vec![4]);
+    }
+}
@@ -0,0 +1,36 @@
+// SPDX-FileCopyrightText: © 2024 Tenstorrent Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use luwen_if::chip::Chip;
+
+use crate::{error::ResetError, ExtendedPciDevice};
+
+/// Reset through the ARC, used for grayskull and wormhole.
+///
+/// The ARC gives us no way to tell when the reset has completed so we can only wait it out.
+pub struct ResetTracker {
+    interface: usize,
+}
+
+impl ResetTracker {
+    pub fn init(interface: usize) -> Self {
+        Self { interface }
+    }
+}
+
+impl super::ResetTracker for ResetTracker {
+    fn reset(&mut self) -> Result<(), ResetError> {
+        let ud = ExtendedPciDevice::open(self.interface)?;
+        let arch = ud.borrow().device.arch;
+        let chip = Chip::open(
+            arch,
+            luwen_if::CallbackStorage::new(crate::comms_callback, ud),
+        )?;
+
+        super::asic_reset(&chip)
+    }
+
+    fn poll(&mut self) -> Result<Option<bool>, ResetError> {
+        Ok(None)
+    }
+}
@@ -1,7 +1,10 @@
 // SPDX-FileCopyrightText: © 2023 Tenstorrent Inc.
 // SPDX-License-Identifier: Apache-2.0
 
-use luwen_if::EthAddr;
+use luwen_if::{
+    chip::{poll, PollOptions},
+    EthAddr,
+};
 use ttkmd_if::PciError;
 
 use crate::error::LuwenError;
@@ -76,30 +79,47 @@ fn wait_for_idle<D>(
     command_q_addr: u32,
     timeout: std::time::Duration,
 ) -> Result<u32, LuwenError> {
-    let mut curr_wptr = read32(user_data, command_q_addr + REQ_Q_ADDR + 4 * WR_PTR_OFFSET)?;
-
-    let start = std::time::Instant::now();
-    loop {
-        let curr_rptr = read32(user_data, command_q_addr + REQ_Q_ADDR + 4 * RD_PTR_OFFSET)?;
-
-        let is_command_q_full = (curr_wptr != curr_rptr)
-            && ((curr_wptr & CMD_BUF_SIZE_MASK) == (curr_rptr & CMD_BUF_SIZE_MASK));
-
-        if !is_command_q_full {
-            break;
-        }
-
-        if start.elapsed() > timeout {
-            return Err(LuwenError::Custom(
-                "Ethernet timeout while waiting for command queue to be idle".to_string(),
-            ));
-        }
-        curr_wptr = read32(user_data, command_q_addr + REQ_Q_ADDR + 4 * WR_PTR_OFFSET)?;
-    }
+    let (curr_wptr, _) = poll(
+        "command queue to be idle",
+        &PollOptions::busy(timeout),
+        || -> Result<_, PciError> {
+            Ok((
+                read32(user_data, command_q_addr + REQ_Q_ADDR + 4 * WR_PTR_OFFSET)?,
+                read32(user_data, command_q_addr + REQ_Q_ADDR + 4 * RD_PTR_OFFSET)?,
+            ))
+        },
+        |&(curr_wptr, curr_rptr)| {
+            let is_command_q_full = (curr_wptr != curr_rptr)
+                && ((curr_wptr & CMD_BUF_SIZE_MASK) == (curr_rptr & CMD_BUF_SIZE_MASK));
+            !is_command_q_full
+        },
+    )?
+    .map_err(|err| LuwenError::EthernetTimeout(Box::new(err)))?;
 
     Ok(curr_wptr)
 }
 
+/// Wait for a queue register to reach the expected state. The erisc responds within
+/// microseconds, so this busy polls rather than slowing every transfer down with sleeps.
+fn wait_for<D>(
+    user_data: &mut D,
+    mut read32: impl FnMut(&mut D, u32) -> Result<u32, PciError>,
+    addr: u32,
+    phase: &'static str,
+    timeout: std::time::Duration,
+    done: impl FnMut(&u32) -> bool,
+) -> Result<u32, LuwenError> {
+    let value = poll(
+        phase,
+        &PollOptions::busy(timeout),
+        || read32(user_data, addr),
+        done,
+    )?
+    .map_err(|err| LuwenError::EthernetTimeout(Box::new(err)))?;
+
+    Ok(value)
+}
+
 pub fn eth_read32<D>(
     user_data: &mut D,
     mut read32: impl FnMut(&mut D, u32) -> Result<u32, PciError>,
@@ -132,31 +152,26 @@ pub fn eth_read32<D>(
     )?;
 
     let curr_rptr = read32(user_data, command_q_addr + RESP_Q_ADDR + 4 * RD_PTR_OFFSET)?;
-    let mut curr_wptr = read32(user_data, command_q_addr + RESP_Q_ADDR + 4 * WR_PTR_OFFSET)?;
-
-    let start_time = std::time::Instant::now();
-    while curr_wptr == curr_rptr {
-        curr_wptr = read32(user_data, command_q_addr + RESP_Q_ADDR + 4 * WR_PTR_OFFSET)?;
-        if start_time.elapsed() > timeout {
-            return Err(LuwenError::Custom(
-                "Ethernet timeout while waiting for read queue to be cleared".to_string(),
-            ));
-        }
-    }
+    wait_for(
+        user_data,
+        &mut read32,
+        command_q_addr + RESP_Q_ADDR + 4 * WR_PTR_OFFSET,
+        "read queue to be cleared",
+        timeout,
+        |&curr_wptr| curr_wptr != curr_rptr,
+    )?;
 
     let cmd_addr =
         command_q_addr + RESP_Q_ADDR + 4 * CMD_OFFSET + (curr_rptr % CMD_BUF_SIZE) * Q_ENTRY_BYTES;
 
-    let mut flags = 0;
-    let start_time = std::time::Instant::now();
-    while flags == 0 {
-        flags = read32(user_data, cmd_addr + 12)?;
-        if start_time.elapsed() > timeout {
-            return Err(LuwenError::Custom(
-                "Ethernet timeout while waiting for flags to come back".to_string(),
-            ));
-        }
-    }
+    let flags = wait_for(
+        user_data,
+        &mut read32,
+        cmd_addr + 12,
+        "flags to come back",
+        timeout,
+        |&flags| flags != 0,
+    )?;
 
     let is_block = (flags & CMD_DATA_BLOCK) == 64;
     let data = read32(user_data, cmd_addr + 8)?;
@@ -192,6 +207,137 @@ pub fn eth_read32<D>(
     Ok(data)
 }
 
+/// Measurements for a single block transfer.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct EthTransferStats {
+    pub bytes: u64,
+    pub requests: u64,
+    pub elapsed: std::time::Duration,
+}
+
+impl EthTransferStats {
+    /// Bytes per second.
+    pub fn throughput(&self) -> f64 {
+        let secs = self.elapsed.as_secs_f64();
+        if secs == 0.0 {
+            0.0
+        } else {
+            self.bytes as f64 / secs
+        }
+    }
+
+    pub fn accumulate(&mut self, other: &EthTransferStats) {
+        self.bytes += other.bytes;
+        self.requests += other.requests;
+        self.elapsed += other.elapsed;
+    }
+}
+
+/// Write a block request into the next free request slot and hand it to the erisc.
+#[allow(clippy::too_many_arguments)]
+fn issue_block_request<D>(
+    user_data: &mut D,
+    mut read32: impl FnMut(&mut D, u32) -> Result<u32, PciError>,
+    mut write32: impl FnMut(&mut D, u32, u32) -> Result<(), PciError>,
+    command_q_addr: u32,
+    timeout: std::time::Duration,
+    coord: &EthCommCoord,
+    flags: u32,
+    dma_phys_pointer: u64,
+    block_len: u32,
+) -> Result<(), LuwenError> {
+    let curr_wptr = wait_for_idle(user_data, &mut read32, command_q_addr, timeout)?;
+
+    let cmd_addr =
+        command_q_addr + REQ_Q_ADDR + 4 * CMD_OFFSET + (curr_wptr % CMD_BUF_SIZE) * Q_ENTRY_BYTES;
+
+    let sys_addr = get_sys_addr(coord);
+    let rack_addr = get_rack_addr(coord);
+
+    write32(user_data, cmd_addr, (sys_addr & 0xFFFFFFFF) as u32)?;
+    write32(user_data, cmd_addr + 4, (sys_addr >> 32) as u32)?;
+    write32(user_data, cmd_addr + 16, rack_addr as u32)?;
+
+    let flags = flags
+        | ((coord.noc_id as u32 & NOC_ID_MASK) << NOC_ID_SHIFT)
+        | CMD_DATA_BLOCK
+        | CMD_DATA_BLOCK_DRAM;
+    write32(user_data, cmd_addr + 8, block_len)?;
+    write32(user_data, cmd_addr + 28, dma_phys_pointer as u32)?;
+    write32(user_data, cmd_addr + 12, flags)?;
+
+    let next_wptr = (curr_wptr + 1) % (2 * CMD_BUF_SIZE);
+    write32(
+        user_data,
+        command_q_addr + REQ_Q_ADDR + 4 * WR_PTR_OFFSET,
+        next_wptr,
+    )?;
+
+    Ok(())
+}
+
+/// Wait for the response to the oldest outstanding block read and release its slot.
+fn retire_block_read<D>(
+    user_data: &mut D,
+    mut read32: impl FnMut(&mut D, u32) -> Result<u32, PciError>,
+    mut write32: impl FnMut(&mut D, u32, u32) -> Result<(), PciError>,
+    command_q_addr: u32,
+    timeout: std::time::Duration,
+    curr_rptr: u32,
+) -> Result<(), LuwenError> {
+    wait_for(
+        user_data,
+        &mut read32,
+        command_q_addr + RESP_Q_ADDR + 4 * WR_PTR_OFFSET,
+        "read queue to be cleared",
+        timeout,
+        |&curr_wptr| curr_wptr != curr_rptr,
+    )?;
+
+    let cmd_addr =
+        command_q_addr + RESP_Q_ADDR + 4 * CMD_OFFSET + (curr_rptr % CMD_BUF_SIZE) * Q_ENTRY_BYTES;
+
+    let flags = wait_for(
+        user_data,
+        &mut read32,
+        cmd_addr + 12,
+        "flags to come back",
+        timeout,
+        |&flags| flags != 0,
+    )?;
+
+    let next_rptr = (curr_rptr + 1) % (2 * CMD_BUF_SIZE);
+    write32(
+        user_data,
+        command_q_addr + RESP_Q_ADDR + 4 * RD_PTR_OFFSET,
+        next_rptr,
+    )?;
+
+    if flags & CMD_DEST_UNREACHABLE != 0 {
+        return Err(LuwenError::Custom("Destination Unreachable.".to_string()));
+    }
+    if flags & CMD_DATA_BLOCK_UNAVAILABLE != 0 {
+        return Err(LuwenError::Custom(
+            "Unable to reserve data block on destination route.".to_string(),
+        ));
+    }
+
+    let is_block = (flags & CMD_DATA_BLOCK) == 64;
+    if !(is_block && flags & CMD_RD_DATA != 0) {
+        return Err(LuwenError::Custom(
+            "Found non block read response expected something else".to_string(),
+        ));
+    }
+
+    Ok(())
+}
+
+/// Split the dma buffer into one chunk per command slot, so that every slot can have a transfer
+/// in flight at the same time.
+fn dma_chunk_len(dma_buffer: &ttkmd_if::DmaBuffer) -> u64 {
+    dma_buffer.size / CMD_BUF_SIZE as u64
+}
+
 // These functions are used in one place.
 // Should be fixed if more calls are added.
 #[allow(clippy::too_many_arguments)]
@@ -199,19 +345,20 @@ pub fn block_read<D>(
     user_data: &mut D,
     mut read32: impl FnMut(&mut D, u32) -> Result<u32, PciError>,
     mut write32: impl FnMut(&mut D, u32, u32) -> Result<(), PciError>,
-    dma_buffer: &mut ttkmd_if::DmaBuffer,
+    dma_buffer: Option<&mut ttkmd_if::DmaBuffer>,
     command_q_addr: u32,
     timeout: std::time::Duration,
     fake_it: bool,
     mut coord: EthCommCoord,
     data: &mut [u8],
-) -> Result<(), LuwenError> {
-    if fake_it {
-        assert_eq!(data.len() % 4, 0);
+) -> Result<EthTransferStats, LuwenError> {
+    let start = std::time::Instant::now();
 
-        let data = unsafe { std::mem::transmute::<_, &mut [u32]>(data) };
-        for d in data {
-            *d = eth_read32(
+    // Without a dma buffer fall back to issuing one word at a time
+    let Some(dma_buffer) = dma_buffer.filter(|_| !fake_it) else {
+        let mut requests = 0;
+        for chunk in data.chunks_mut(4) {
+            let value = eth_read32(
                 user_data,
                 &mut read32,
                 &mut write32,
@@ -219,117 +366,74 @@ pub fn block_read<D>(
                 coord.clone(),
                 timeout,
             )?;
+            chunk.copy_from_slice(&value.to_le_bytes()[..chunk.len()]);
+            requests += 1;
             coord.offset += 4;
         }
 
-        return Ok(());
-    }
-
-    let rack_addr = get_rack_addr(&coord);
-
-    let mut buffer_pos = 0;
-
-    let number_of_slices = 4;
-    let buffer_slice_len = dma_buffer.size / number_of_slices;
-    while buffer_pos < data.len() as u64 {
-        let sys_addr = get_sys_addr(&coord);
-
-        let curr_wptr = wait_for_idle(user_data, &mut read32, command_q_addr, timeout)?;
+        return Ok(EthTransferStats {
+            bytes: data.len() as u64,
+            requests,
+            elapsed: start.elapsed(),
+        });
+    };
 
-        let cmd_addr = command_q_addr
-            + REQ_Q_ADDR
-            + 4 * CMD_OFFSET
-            + (curr_wptr % CMD_BUF_SIZE) * Q_ENTRY_BYTES;
+    let chunk_len = dma_chunk_len(dma_buffer);
 
-        let dma_offset = buffer_slice_len * (curr_wptr as u64 % number_of_slices);
+    // (data offset, dma offset, length) of each outstanding request, oldest first.
+    // The erisc answers requests in order, so the oldest request is always the next to complete.
+    let mut in_flight = std::collections::VecDeque::with_capacity(CMD_BUF_SIZE as usize);
+    let mut resp_rptr = read32(user_data, command_q_addr + RESP_Q_ADDR + 4 * RD_PTR_OFFSET)?;
+    let mut chunk_index = 0;
+    let mut requests = 0;
 
-        let dma_phys_pointer = dma_buffer.physical_address + dma_offset;
-        let block_len = (data.len() as u64 - buffer_pos).min(buffer_slice_len) as usize;
-
-        write32(user_data, cmd_addr, (sys_addr & 0xFFFFFFFF) as u32)?;
-        write32(user_data, cmd_addr + 4, (sys_addr >> 32) as u32)?;
-        write32(user_data, cmd_addr + 16, rack_addr as u32)?;
-
-        let mut flags = CMD_RD_REQ;
-        flags |= (coord.noc_id as u32 & NOC_ID_MASK) << NOC_ID_SHIFT;
-        flags |= CMD_DATA_BLOCK | CMD_DATA_BLOCK_DRAM;
-        write32(user_data, cmd_addr + 8, block_len as u32)?;
-        write32(user_data, cmd_addr + 28, dma_phys_pointer as u32)?;
-        write32(user_data, cmd_addr + 12, flags)?;
-
-        let next_wptr = (curr_wptr + 1) % (2 * CMD_BUF_SIZE);
-        write32(
-            user_data,
-            command_q_addr + REQ_Q_ADDR + 4 * WR_PTR_OFFSET,
-            next_wptr,
-        )?;
-
-        let curr_rptr = read32(user_data, command_q_addr + RESP_Q_ADDR + 4 * RD_PTR_OFFSET)?;
-        let mut curr_wptr = read32(user_data, command_q_addr + RESP_Q_ADDR + 4 * WR_PTR_OFFSET)?;
-
-        let start_time = std::time::Instant::now();
-        while curr_wptr == curr_rptr {
-            curr_wptr = read32(user_data, command_q_addr + RESP_Q_ADDR + 4 * WR_PTR_OFFSET)?;
-            if start_time.elapsed() > timeout {
-                return Err(LuwenError::Custom(
-                    "Ethernet timeout while waiting for read queue to be cleared".to_string(),
-                ));
-            }
-        }
-
-        let cmd_addr = command_q_addr
-            + RESP_Q_ADDR
-            + 4 * CMD_OFFSET
-            + (curr_rptr % CMD_BUF_SIZE) * Q_ENTRY_BYTES;
-
-        let mut flags = 0;
-        let start_time = std::time::Instant::now();
-        while flags == 0 {
-            flags = read32(user_data, cmd_addr + 12)?;
-            if start_time.elapsed() > timeout {
-                return Err(LuwenError::Custom(
-                    "Ethernet timeout while waiting for flags to come back".to_string(),
-                ));
-            }
-        }
+    let mut buffer_pos = 0;
+    while buffer_pos < data.len() || !in_flight.is_empty() {
+        // Keep every command slot busy
+        while buffer_pos < data.len() && in_flight.len() < CMD_BUF_SIZE as usize {
+            let dma_offset = chunk_len * (chunk_index % CMD_BUF_SIZE as u64);
+            let block_len = (data.len() - buffer_pos).min(chunk_len as usize);
 
-        let is_block = (flags & CMD_DATA_BLOCK) == 64;
+            issue_block_request(
+                user_data,
+                &mut read32,
+                &mut write32,
+                command_q_addr,
+                timeout,
+                &coord,
+                CMD_RD_REQ,
+                dma_buffer.physical_address + dma_offset,
+                block_len as u32,
+            )?;
 
-        if flags & CMD_DEST_UNREACHABLE != 0 {
-            return Err(LuwenError::Custom("Destination Unreachable.".to_string()));
-        }
-        if flags & CMD_DATA_BLOCK_UNAVAILABLE != 0 {
-            return Err(LuwenError::Custom(
-                "Unable to reserve data block on destination route.".to_string(),
-            ));
+            in_flight.push_back((buffer_pos, dma_offset as usize, block_len));
+            chunk_index += 1;
+            requests += 1;
+            buffer_pos += block_len;
+            coord.offset += block_len as u64;
         }
 
-        let mut flag_block_read = false;
-        if is_block && flags & CMD_RD_DATA != 0 {
-            flag_block_read = true;
-        }
+        if let Some((data_offset, dma_offset, block_len)) = in_flight.pop_front() {
+            retire_block_read(
+                user_data,
+                &mut read32,
+                &mut write32,
+                command_q_addr,
+                timeout,
+                resp_rptr,
+            )?;
+            resp_rptr = (resp_rptr + 1) % (2 * CMD_BUF_SIZE);
 
-        if !flag_block_read {
-            return Err(LuwenError::Custom(
-                "Found non block read response expected something else".to_string(),
-            ));
+            data[data_offset..][..block_len]
+                .copy_from_slice(&dma_buffer.buffer[dma_offset..][..block_len]);
         }
-
-        let next_rptr = (curr_rptr + 1) % (2 * CMD_BUF_SIZE);
-        write32(
-            user_data,
-            command_q_addr + RESP_Q_ADDR + 4 * RD_PTR_OFFSET,
-            next_rptr,
-        )?;
-
-        data[buffer_pos as usize..][..block_len]
-            .copy_from_slice(&dma_buffer.buffer[dma_offset as usize..][..block_len]);
-
-        buffer_pos += buffer_slice_len;
-        coord.offset += buffer_slice_len;
     }
 
-    Ok(())
+    Ok(EthTransferStats {
+        bytes: data.len() as u64,
+        requests,
+        elapsed: start.elapsed(),
+    })
 }
 
 // These functions are used in one place.
@@ -371,6 +475,15 @@ pub fn eth_write32<D>(
     Ok(())
 }
 
+/// Once part of a write has been handed to the erisc, a timeout must not lead to the write being
+/// replayed from the start.
+fn queued_write_timeout(err: LuwenError, queued: bool) -> LuwenError {
+    match err {
+        LuwenError::EthernetTimeout(err) if queued => LuwenError::EthernetWriteTimeout(err),
+        err => err,
+    }
+}
+
 // These functions are used in one place.
 // Should be fixed if more calls are added.
 #[allow(clippy::too_many_arguments)]
@@ -378,18 +491,39 @@ pub fn block_write<D>(
     user_data: &mut D,
     mut read32: impl FnMut(&mut D, u32) -> Result<u32, PciError>,
     mut write32: impl FnMut(&mut D, u32, u32) -> Result<(), PciError>,
-    dma_buffer: &mut ttkmd_if::DmaBuffer,
+    dma_buffer: Option<&mut ttkmd_if::DmaBuffer>,
     command_q_addr: u32,
     timeout: std::time::Duration,
     fake_it: bool,
     mut coord: EthCommCoord,
     data: &[u8],
-) -> Result<(), LuwenError> {
-    if fake_it {
-        assert_eq!(data.len() % 4, 0);
+) -> Result<EthTransferStats, LuwenError> {
+    let start = std::time::Instant::now();
+
+    // Without a dma buffer fall back to issuing one word at a time
+    let Some(dma_buffer) = dma_buffer.filter(|_| !fake_it) else {
+        let mut requests = 0;
+        for chunk in data.chunks(4) {
+            let value = if let Ok(word) = <[u8; 4]>::try_from(chunk) {
+                u32::from_le_bytes(word)
+            } else {
+                // Read-modify-write the trailing partial word so the bytes past the end of
+                // the buffer are left alone
+                let mut word = eth_read32(
+                    user_data,
+                    &mut read32,
+                    &mut write32,
+                    command_q_addr,
+                    coord.clone(),
+                    timeout,
+                )
+                .map_err(|err| queued_write_timeout(err, requests > 0))?
+                .to_le_bytes();
+                word[..chunk.len()].copy_from_slice(chunk);
+                requests += 1;
+                u32::from_le_bytes(word)
+            };
 
-        let data = unsafe { std::mem::transmute::<_, &[u32]>(data) };
-        for d in data {
             eth_write32(
                 user_data,
                 &mut read32,
@@ -397,149 +531,538 @@ pub fn block_write<D>(
                 command_q_addr,
                 coord.clone(),
                 timeout,
-                *d,
-            )?;
+                value,
+            )
+            .map_err(|err| queued_write_timeout(err, requests > 0))?;
+            requests += 1;
             coord.offset += 4;
         }
 
-        return Ok(());
-    }
+        return Ok(EthTransferStats {
+            bytes: data.len() as u64,
+            requests,
+            elapsed: start.elapsed(),
+        });
+    };
 
-    let rack_addr = get_rack_addr(&coord);
+    let chunk_len = dma_chunk_len(dma_buffer);
+
+    // A chunk of the dma buffer is free again once the erisc has consumed the request which
+    // used it. Requests are handed out round-robin over the chunks, so waiting for a free
+    // command slot also guarantees that the chunk for the next request is free.
+    let mut chunk_index = 0;
+    let mut requests = 0;
 
     let mut buffer_pos = 0;
+    while buffer_pos < data.len() {
+        let dma_offset = chunk_len * (chunk_index % CMD_BUF_SIZE as u64);
+        let block_len = (data.len() - buffer_pos).min(chunk_len as usize);
 
-    let number_of_slices = 4;
-    let buffer_slice_len = dma_buffer.size / number_of_slices;
-    while buffer_pos < data.len() as u64 {
-        let sys_addr = get_sys_addr(&coord);
+        // Make sure the chunk we are about to overwrite is no longer in use
+        wait_for_idle(user_data, &mut read32, command_q_addr, timeout)
+            .map_err(|err| queued_write_timeout(err, requests > 0))?;
 
-        let curr_wptr = wait_for_idle(user_data, &mut read32, command_q_addr, timeout)?;
+        dma_buffer.buffer[dma_offset as usize..][..block_len]
+            .copy_from_slice(&data[buffer_pos..][..block_len]);
 
-        let cmd_addr = command_q_addr
-            + REQ_Q_ADDR
-            + 4 * CMD_OFFSET
-            + (curr_wptr % CMD_BUF_SIZE) * Q_ENTRY_BYTES;
+        issue_block_request(
+            user_data,
+            &mut read32,
+            &mut write32,
+            command_q_addr,
+            timeout,
+            &coord,
+            CMD_WR_REQ,
+            dma_buffer.physical_address + dma_offset,
+            block_len as u32,
+        )
+        .map_err(|err| queued_write_timeout(err, requests > 0))?;
+
+        chunk_index += 1;
+        requests += 1;
+        buffer_pos += block_len;
+        coord.offset += block_len as u64;
+    }
 
-        let dma_offset = buffer_slice_len * (curr_wptr as u64 % number_of_slices);
+    // Don't hand the dma buffer back until the erisc has picked up all of the data
+    let wr_ptr = read32(user_data, command_q_addr + REQ_Q_ADDR + 4 * WR_PTR_OFFSET)?;
+    wait_for(
+        user_data,
+        &mut read32,
+        command_q_addr + REQ_Q_ADDR + 4 * RD_PTR_OFFSET,
+        "write queue to drain",
+        timeout,
+        |&rd_ptr| rd_ptr == wr_ptr,
+    )
+    .map_err(|err| queued_write_timeout(err, true))?;
+
+    Ok(EthTransferStats {
+        bytes: data.len() as u64,
+        requests,
+        elapsed: start.elapsed(),
+    })
+}
 
-        let dma_phys_pointer = dma_buffer.physical_address + dma_offset;
-        let block_len = (data.len() as u64 - buffer_pos).min(buffer_slice_len) as usize;
+/// A single decoded command slot from one of the erisc command queues.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct EthQueueEntry {
+    pub addr: u64,
+    pub data: u32,
+    pub flags: u32,
+    pub src_resp_buf_index: u32,
+    pub local_buf_index: u32,
+    pub src_resp_q_id: u32,
+    pub src_addr_tag: u32,
+}
 
-        dma_buffer.buffer[dma_offset as usize..][..block_len]
-            .copy_from_slice(&data[buffer_pos as usize..][..block_len]);
+impl EthQueueEntry {
+    fn from_words(words: &[u32]) -> Self {
+        EthQueueEntry {
+            addr: ((words[ADDR_H_OFFSET as usize] as u64) << 32)
+                | words[ADDR_L_OFFSET as usize] as u64,
+            data: words[DATA_OFFSET as usize],
+            flags: words[FLAGS_OFFSET as usize],
+            src_resp_buf_index: words[SRC_RESP_BUF_INDEX_OFFSET as usize],
+            local_buf_index: words[LCL_BUF_INDEX_OFFSET as usize],
+            src_resp_q_id: words[SRC_RESP_Q_ID_OFFSET as usize],
+            src_addr_tag: words[SRC_ADDR_TAG_OFFSET as usize],
+        }
+    }
 
-        write32(user_data, cmd_addr, (sys_addr & 0xFFFFFFFF) as u32)?;
-        write32(user_data, cmd_addr + 4, (sys_addr >> 32) as u32)?;
-        write32(user_data, cmd_addr + 16, rack_addr as u32)?;
+    pub fn is_write_request(&self) -> bool {
+        self.flags & CMD_WR_REQ != 0
+    }
 
-        let mut flags = CMD_WR_REQ;
-        flags |= ((coord.noc_id as u32) & NOC_ID_MASK) << NOC_ID_SHIFT;
-        flags |= CMD_DATA_BLOCK | CMD_DATA_BLOCK_DRAM;
-        write32(user_data, cmd_addr + 8, block_len as u32)?;
-        write32(user_data, cmd_addr + 28, dma_phys_pointer as u32)?;
-        write32(user_data, cmd_addr + 12, flags)?;
+    pub fn is_read_request(&self) -> bool {
+        self.flags & CMD_RD_REQ != 0
+    }
 
-        let next_wptr = (curr_wptr + 1) % (2 * CMD_BUF_SIZE);
-        write32(
-            user_data,
-            command_q_addr + REQ_Q_ADDR + 4 * WR_PTR_OFFSET,
-            next_wptr,
-        )?;
+    pub fn is_read_data(&self) -> bool {
+        self.flags & CMD_RD_DATA != 0
+    }
 
-        buffer_pos += buffer_slice_len;
-        coord.offset += buffer_slice_len;
+    pub fn is_block(&self) -> bool {
+        self.flags & CMD_DATA_BLOCK != 0
     }
 
-    Ok(())
+    pub fn is_dram_block(&self) -> bool {
+        self.flags & CMD_DATA_BLOCK_DRAM != 0
+    }
+
+    pub fn noc_id(&self) -> u8 {
+        ((self.flags >> NOC_ID_SHIFT) & NOC_ID_MASK) as u8
+    }
+
+    pub fn dest_unreachable(&self) -> bool {
+        self.flags & CMD_DEST_UNREACHABLE != 0
+    }
+
+    pub fn block_unavailable(&self) -> bool {
+        self.flags & CMD_DATA_BLOCK_UNAVAILABLE != 0
+    }
+}
+
+/// The pointers and command slots of a single erisc command queue.
+///
+/// The pointers wrap at `2 * CMD_BUF_SIZE` so that a full queue can be told apart from an empty one.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EthQueue {
+    pub name: &'static str,
+    pub wr_ptr: u32,
+    pub rd_ptr: u32,
+    pub entries: [EthQueueEntry; CMD_BUF_SIZE as usize],
 }
 
+impl EthQueue {
+    /// Both pointers are within the range the firmware will ever produce.
+    pub fn pointers_valid(&self) -> bool {
+        self.wr_ptr < 2 * CMD_BUF_SIZE && self.rd_ptr < 2 * CMD_BUF_SIZE
+    }
+
+    /// Number of entries which have been written but not yet consumed.
+    pub fn pending(&self) -> u32 {
+        self.wr_ptr.wrapping_sub(self.rd_ptr) % (2 * CMD_BUF_SIZE)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.wr_ptr == self.rd_ptr
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.wr_ptr != self.rd_ptr
+            && (self.wr_ptr & CMD_BUF_SIZE_MASK) == (self.rd_ptr & CMD_BUF_SIZE_MASK)
+    }
+
+    /// The entries between rd_ptr and wr_ptr, oldest first.
+    pub fn pending_entries(&self) -> impl Iterator<Item = &EthQueueEntry> {
+        let start = self.rd_ptr;
+        (0..self.pending()).map(move |i| &self.entries[((start + i) % CMD_BUF_SIZE) as usize])
+    }
+}
+
+impl std::fmt::Display for EthQueue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "{}", self.name)?;
+        writeln!(f, "Wr Ptr = {}", self.wr_ptr)?;
+        writeln!(f, "Rd Ptr = {}", self.rd_ptr)?;
+        for (cmd, entry) in self.entries.iter().enumerate() {
+            writeln!(f, "Address [{cmd}] = 0x{:016x}", entry.addr)?;
+            writeln!(f, "Data    [{cmd}] = 0x{:08x}", entry.data)?;
+            writeln!(f, "Flags   [{cmd}] = 0x{:02x}", entry.flags)?;
+            writeln!(f, "Src Buf [{cmd}] = {}", entry.src_resp_buf_index)?;
+            writeln!(f, "Lcl Buf [{cmd}] = {}", entry.local_buf_index)?;
+            writeln!(f, "Src QID [{cmd}] = {}", entry.src_resp_q_id)?;
+            writeln!(f, "Src Tag [{cmd}] = 0x{:08x}\n", entry.src_addr_tag)?;
+        }
+        writeln!(f, "==============================")
+    }
+}
+
+/// Snapshot of all command queues of an erisc core.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EthQueueState {
+    pub command_q_addr: u32,
+    pub queues: [EthQueue; 4],
+}
+
+impl EthQueueState {
+    /// Host -> erisc request queue.
+    pub fn request(&self) -> &EthQueue {
+        &self.queues[0]
+    }
+
+    pub fn eth_in_request(&self) -> &EthQueue {
+        &self.queues[1]
+    }
+
+    /// Erisc -> host response queue.
+    pub fn response(&self) -> &EthQueue {
+        &self.queues[2]
+    }
+
+    pub fn eth_out_request(&self) -> &EthQueue {
+        &self.queues[3]
+    }
+
+    pub fn is_idle(&self) -> bool {
+        self.queues.iter().all(|q| q.is_empty())
+    }
+}
+
+impl std::fmt::Display for EthQueueState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for q in &self.queues {
+            write!(f, "{q}")?;
+        }
+
+        Ok(())
+    }
+}
+
+pub fn get_queue_state<D>(
+    user_data: &mut D,
+    mut read32: impl FnMut(&mut D, u32) -> Result<u32, PciError>,
+    command_q_addr: u32,
+) -> Result<EthQueueState, PciError> {
+    let mut q_data = Vec::with_capacity(Q_NAME.len() * Q_SIZE_WORDS as usize);
+    let mut rd_addr = command_q_addr + REQ_Q_ADDR;
+    for _ in 0..Q_NAME.len() as u32 * Q_SIZE_WORDS {
+        q_data.push(read32(user_data, rd_addr)?);
+        rd_addr += 4;
+    }
+
+    let queues = std::array::from_fn(|i| {
+        let base = i * Q_SIZE_WORDS as usize;
+        EthQueue {
+            name: Q_NAME[i],
+            wr_ptr: q_data[base + WR_PTR_OFFSET as usize],
+            rd_ptr: q_data[base + RD_PTR_OFFSET as usize],
+            entries: std::array::from_fn(|cmd| {
+                let cmd_base = base + CMD_OFFSET as usize + cmd * Q_ENTRY_WORDS as usize;
+                EthQueueEntry::from_words(&q_data[cmd_base..][..Q_ENTRY_WORDS as usize])
+            }),
+        }
+    });
+
+    Ok(EthQueueState {
+        command_q_addr,
+        queues,
+    })
+}
+
+/// What had to be done to get the command queues back into a usable state.
+#[derive(Clone, Debug, Default)]
+pub struct EthQueueRecovery {
+    /// Number of requests the erisc consumed while we were waiting for it to drain.
+    pub drained_requests: u32,
+    /// Response entries which were never read by the host and have been thrown away.
+    pub discarded_responses: Vec<EthQueueEntry>,
+    /// The response pointers were outside of the valid range and have been reset.
+    pub reset_response_pointers: bool,
+}
+
+impl EthQueueRecovery {
+    pub fn is_clean(&self) -> bool {
+        self.drained_requests == 0
+            && self.discarded_responses.is_empty()
+            && !self.reset_response_pointers
+    }
+}
+
+/// Controls how the ethernet transport recovers from a timeout.
+#[derive(Clone, Debug)]
+pub struct EthRecoveryPolicy {
+    /// Timeout for a single ethernet transaction.
+    pub timeout: std::time::Duration,
+    /// How long to wait for outstanding requests to be consumed before giving up on the core.
+    pub drain_timeout: std::time::Duration,
+    /// Number of times a timed out transaction is retried after recovering the queues.
+    pub retries: u32,
+}
+
+impl Default for EthRecoveryPolicy {
+    fn default() -> Self {
+        EthRecoveryPolicy {
+            timeout: std::time::Duration::from_secs(5 * 60),
+            drain_timeout: std::time::Duration::from_secs(1),
+            retries: 1,
+        }
+    }
+}
+
+/// Bring the response queue back in sync with the erisc by discarding any responses left behind
+/// by an interrupted transaction.
 pub fn fixup_queues<D>(
     user_data: &mut D,
     mut read32: impl FnMut(&mut D, u32) -> Result<u32, PciError>,
     mut write32: impl FnMut(&mut D, u32, u32) -> Result<(), PciError>,
     command_q_addr: u32,
-) -> Result<(), PciError> {
-    let i = 2;
-    let wr_ptr_addr = command_q_addr + REQ_Q_ADDR + 4 * (i * Q_SIZE_WORDS + WR_PTR_OFFSET);
-    let rd_ptr_addr = command_q_addr + REQ_Q_ADDR + 4 * (i * Q_SIZE_WORDS + RD_PTR_OFFSET);
+) -> Result<EthQueueRecovery, PciError> {
+    let mut recovery = EthQueueRecovery::default();
+
+    let wr_ptr_addr = command_q_addr + RESP_Q_ADDR + 4 * WR_PTR_OFFSET;
+    let rd_ptr_addr = command_q_addr + RESP_Q_ADDR + 4 * RD_PTR_OFFSET;
     let wr_ptr = read32(user_data, wr_ptr_addr)?;
     let rd_ptr = read32(user_data, rd_ptr_addr)?;
 
     if wr_ptr != rd_ptr {
-        println!("RESPONSE_Q out of sync - wr_ptr: {wr_ptr}, rd_ptr: {rd_ptr}");
-        println!("Setting rd_ptr = wr_ptr for the RESP CMD Q");
-        write32(user_data, rd_ptr_addr, wr_ptr)?;
+        let state = get_queue_state(user_data, &mut read32, command_q_addr)?;
+        let response = state.response();
+        if response.pointers_valid() {
+            recovery.discarded_responses = response.pending_entries().copied().collect();
+            tracing::warn!(
+                "RESP CMD Q out of sync (wr_ptr: {wr_ptr}, rd_ptr: {rd_ptr}); discarding {} responses",
+                recovery.discarded_responses.len()
+            );
+            write32(user_data, rd_ptr_addr, wr_ptr)?;
+        } else {
+            tracing::warn!(
+                "RESP CMD Q pointers corrupted (wr_ptr: {wr_ptr}, rd_ptr: {rd_ptr}); resetting"
+            );
+            recovery.reset_response_pointers = true;
+            write32(user_data, rd_ptr_addr, wr_ptr % (2 * CMD_BUF_SIZE))?;
+        }
     }
 
-    Ok(())
+    Ok(recovery)
+}
+
+/// Inspect the queues after a transport timeout; waits for the erisc to drain any outstanding
+/// requests and then resyncs the response queue.
+pub fn recover_queues<D>(
+    user_data: &mut D,
+    mut read32: impl FnMut(&mut D, u32) -> Result<u32, PciError>,
+    write32: impl FnMut(&mut D, u32, u32) -> Result<(), PciError>,
+    command_q_addr: u32,
+    drain_timeout: std::time::Duration,
+) -> Result<EthQueueRecovery, LuwenError> {
+    let state = get_queue_state(user_data, &mut read32, command_q_addr)?;
+    let request = state.request();
+    if !request.pointers_valid() {
+        return Err(LuwenError::Custom(format!(
+            "Ethernet request queue pointers are corrupted (wr_ptr: {}, rd_ptr: {}); the erisc needs to be reset",
+            request.wr_ptr, request.rd_ptr
+        )));
+    }
+
+    let initially_pending = request.pending();
+    if initially_pending != 0 {
+        wait_for(
+            user_data,
+            &mut read32,
+            command_q_addr + REQ_Q_ADDR + 4 * RD_PTR_OFFSET,
+            "request queue to drain",
+            drain_timeout,
+            |&rd_ptr| request.wr_ptr.wrapping_sub(rd_ptr) % (2 * CMD_BUF_SIZE) == 0,
+        )?;
+    }
+
+    let mut recovery = fixup_queues(user_data, read32, write32, command_q_addr)?;
+    recovery.drained_requests = initially_pending;
+
+    Ok(recovery)
 }
 
 #[allow(dead_code)]
 pub fn print_queue_state<D>(
     user_data: &mut D,
-    mut read32: impl FnMut(&mut D, u32) -> Result<u32, PciError>,
+    read32: impl FnMut(&mut D, u32) -> Result<u32, PciError>,
     command_q_addr: u32,
     skip_aligned_queues: bool,
 ) -> Result<(), PciError> {
-    let mut rd_addr = command_q_addr + REQ_Q_ADDR;
-    let mut q_data = Vec::new();
-
-    for _ in 0..14 {
-        let mut j = 0;
-        while j < Q_SIZE {
-            q_data.push(read32(user_data, rd_addr)?);
-            j += j;
-            rd_addr += rd_addr;
+    let state = get_queue_state(user_data, read32, command_q_addr)?;
+
+    for (i, q) in state.queues.iter().enumerate() {
+        if skip_aligned_queues && q.is_empty() {
+            tracing::debug!("{}: {i} Wptr == Rptr, skipping...", q.name);
+            continue;
         }
+
+        tracing::debug!("{q}");
     }
 
-    for i in 0..Q_NAME.len() as u32 {
-        println!("{}", Q_NAME[i as usize]);
-        let wptr = q_data[(i * Q_SIZE_WORDS + WR_PTR_OFFSET) as usize];
-        let rptr = q_data[(i * Q_SIZE_WORDS + RD_PTR_OFFSET) as usize];
+    Ok(())
+}
 
-        if skip_aligned_queues && wptr == rptr {
-            println!("{i} Wptr == Rptr, skipping...");
-            continue;
-        }
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
 
-        println!("Wr Ptr = {wptr}");
-        println!("Rd Ptr = {rptr}");
-        for cmd in 0..4 {
-            let cmd_base = i * Q_SIZE_WORDS + CMD_OFFSET + cmd * Q_ENTRY_WORDS;
-            println!(
-                "Address [{cmd}] = 0x{:08x}{:08x}",
-                q_data[(cmd_base + ADDR_H_OFFSET) as usize],
-                q_data[(cmd_base + ADDR_L_OFFSET) as usize]
-            );
-            println!(
-                "Data    [{cmd}] = 0x{:08x}",
-                q_data[(cmd_base + DATA_OFFSET) as usize]
-            );
-            println!(
-                "Flags   [{cmd}] = 0x{:02x}",
-                q_data[(cmd_base + FLAGS_OFFSET) as usize]
-            );
-            println!(
-                "Src Buf [{cmd}] = {}",
-                q_data[(cmd_base + SRC_RESP_BUF_INDEX_OFFSET) as usize]
-            );
-            println!(
-                "Lcl Buf [{cmd}] = {}",
-                q_data[(cmd_base + LCL_BUF_INDEX_OFFSET) as usize]
-            );
-            println!(
-                "Src QID [{cmd}] = {}",
-                q_data[(cmd_base + SRC_RESP_Q_ID_OFFSET) as usize]
-            );
-            println!(
-                "Src Tag [{cmd}] = 0x{:08x}\n",
-                q_data[(cmd_base + SRC_ADDR_TAG_OFFSET) as usize]
-            );
+    use super::*;
+
+    const COMMAND_Q_ADDR: u32 = 0x1000;
+
+    fn read32(mem: &mut HashMap<u32, u32>, addr: u32) -> Result<u32, PciError> {
+        Ok(mem.get(&addr).copied().unwrap_or(0))
+    }
+
+    fn write32(mem: &mut HashMap<u32, u32>, addr: u32, value: u32) -> Result<(), PciError> {
+        mem.insert(addr, value);
+        Ok(())
+    }
+
+    fn queue_word(queue: u32, word: u32) -> u32 {
+        COMMAND_Q_ADDR + REQ_Q_ADDR + 4 * (queue * Q_SIZE_WORDS + word)
+    }
+
+    #[test]
+    fn queue_state_decode() {
+        let mut mem = HashMap::new();
+        mem.insert(queue_word(2, WR_PTR_OFFSET), 5);
+        mem.insert(queue_word(2, RD_PTR_OFFSET), 3);
+        let entry = CMD_OFFSET + 3 * Q_ENTRY_WORDS;
+        mem.insert(queue_word(2, entry + ADDR_L_OFFSET), 0x100);
+        mem.insert(queue_word(2, entry + ADDR_H_OFFSET), 0x1);
+        mem.insert(queue_word(2, entry + DATA_OFFSET), 0xdead);
+        mem.insert(
+            queue_word(2, entry + FLAGS_OFFSET),
+            CMD_RD_DATA | CMD_DEST_UNREACHABLE | (1 << NOC_ID_SHIFT),
+        );
+
+        let state = get_queue_state(&mut mem, read32, COMMAND_Q_ADDR).unwrap();
+        assert!(state.request().is_empty());
+        assert!(!state.is_idle());
+
+        let response = state.response();
+        assert_eq!(response.name, "RESP CMD Q");
+        assert_eq!(response.pending(), 2);
+
+        let pending = response.pending_entries().collect::<Vec<_>>();
+        assert_eq!(pending[0].addr, 0x1_0000_0100);
+        assert_eq!(pending[0].data, 0xdead);
+        assert!(pending[0].is_read_data());
+        assert!(pending[0].dest_unreachable());
+        assert!(!pending[0].block_unavailable());
+        assert_eq!(pending[0].noc_id(), 1);
+    }
+
+    #[test]
+    fn fixup_discards_stale_responses() {
+        let mut mem = HashMap::new();
+        mem.insert(queue_word(2, WR_PTR_OFFSET), 1);
+        mem.insert(queue_word(2, RD_PTR_OFFSET), 7);
+
+        let recovery = fixup_queues(&mut mem, read32, write32, COMMAND_Q_ADDR).unwrap();
+        assert_eq!(recovery.discarded_responses.len(), 2);
+        assert!(!recovery.reset_response_pointers);
+        assert_eq!(mem[&queue_word(2, RD_PTR_OFFSET)], 1);
+
+        let recovery = fixup_queues(&mut mem, read32, write32, COMMAND_Q_ADDR).unwrap();
+        assert!(recovery.is_clean());
+    }
+
+    fn coord() -> EthCommCoord {
+        EthCommCoord {
+            coord: EthAddr {
+                shelf_x: 0,
+                shelf_y: 0,
+                rack_x: 0,
+                rack_y: 0,
+            },
+            noc_id: 0,
+            noc_x: 0,
+            noc_y: 0,
+            offset: 0,
         }
-        println!("==============================");
     }
 
-    Ok(())
+    #[test]
+    fn partially_queued_write_is_not_retryable() {
+        // The erisc never consumes anything, so the queue fills up after CMD_BUF_SIZE words.
+        let mut mem = HashMap::new();
+        let data = [0u8; 4 * (CMD_BUF_SIZE as usize + 1)];
+        let result = block_write(
+            &mut mem,
+            read32,
+            write32,
+            None,
+            COMMAND_Q_ADDR,
+            std::time::Duration::from_millis(1),
+            false,
+            coord(),
+            &data,
+        );
+        assert!(matches!(result, Err(LuwenError::EthernetWriteTimeout(_))));
+
+        // Nothing from this write made it into the queue, so it is safe to retry
+        let result = block_write(
+            &mut mem,
+            read32,
+            write32,
+            None,
+            COMMAND_Q_ADDR,
+            std::time::Duration::from_millis(1),
+            false,
+            coord(),
+            &data[..4],
+        );
+        assert!(matches!(result, Err(LuwenError::EthernetTimeout(_))));
+    }
+
+    #[test]
+    fn recover_fails_on_stuck_requests() {
+        let mut mem = HashMap::new();
+        mem.insert(queue_word(0, WR_PTR_OFFSET), 2);
+        mem.insert(queue_word(0, RD_PTR_OFFSET), 1);
+
+        let result = recover_queues(
+            &mut mem,
+            read32,
+            write32,
+            COMMAND_Q_ADDR,
+            std::time::Duration::from_millis(1),
+        );
+        assert!(result.is_err());
+
+        mem.insert(queue_word(0, RD_PTR_OFFSET), 2);
+        let recovery = recover_queues(
+            &mut mem,
+            read32,
+            write32,
+            COMMAND_Q_ADDR,
+            std::time::Duration::from_millis(1),
+        )
+        .unwrap();
+        assert!(recovery.is_clean());
+    }
 }
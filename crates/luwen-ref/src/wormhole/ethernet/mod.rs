@@ -2,5 +2,7 @@
 // SPDX-License-Identifier: Apache-2.0
 
 mod ethernet_6;
+mod routing;
 
 pub use ethernet_6::*;
+pub use routing::*;
@@ -0,0 +1,236 @@
+// SPDX-FileCopyrightText: © 2024 Tenstorrent Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use std::sync::{
+    atomic::{AtomicBool, AtomicUsize, Ordering},
+    Arc, Mutex, MutexGuard,
+};
+
+use luwen_if::{chip::NeighbouringChip, EthAddr};
+use ttkmd_if::DmaBuffer;
+
+use super::EthTransferStats;
+
+/// An erisc core on the mmio chip which can be used to issue remote transactions.
+///
+/// Each core has its own command queue, so transactions through different cores may run
+/// in parallel. Access to a single core is serialized by its lock.
+pub struct EthCore {
+    pub x: u8,
+    pub y: u8,
+    healthy: AtomicBool,
+    state: Mutex<EthCoreState>,
+}
+
+#[derive(Default)]
+pub struct EthCoreState {
+    /// Host buffer used for block transfers through this core, allocated on first use.
+    pub dma_buffer: Option<DmaBuffer>,
+    /// Set when the dma buffer could not be allocated, block transfers fall back to word accesses.
+    pub dma_unavailable: bool,
+    /// Totals over all block transfers issued through this core.
+    pub transfer_stats: EthTransferStats,
+}
+
+impl EthCore {
+    pub fn new(x: u8, y: u8) -> Self {
+        EthCore {
+            x,
+            y,
+            healthy: AtomicBool::new(true),
+            state: Mutex::new(EthCoreState::default()),
+        }
+    }
+
+    /// False if the last transaction through this core timed out.
+    pub fn is_healthy(&self) -> bool {
+        self.healthy.load(Ordering::Relaxed)
+    }
+
+    pub fn set_healthy(&self, healthy: bool) {
+        self.healthy.store(healthy, Ordering::Relaxed);
+    }
+
+    pub fn lock(&self) -> MutexGuard<'_, EthCoreState> {
+        self.state.lock().unwrap()
+    }
+}
+
+/// A usable erisc core together with the chip on the far side of its link.
+#[derive(Clone)]
+pub struct EthRoute {
+    pub core: Arc<EthCore>,
+    pub remote: Option<EthAddr>,
+}
+
+/// Picks which erisc core to use for a remote transaction.
+pub struct EthRouter {
+    routes: Vec<EthRoute>,
+    next: AtomicUsize,
+}
+
+fn distance(a: &EthAddr, b: &EthAddr) -> u32 {
+    a.shelf_x.abs_diff(b.shelf_x) as u32
+        + a.shelf_y.abs_diff(b.shelf_y) as u32
+        + a.rack_x.abs_diff(b.rack_x) as u32
+        + a.rack_y.abs_diff(b.rack_y) as u32
+}
+
+impl EthRouter {
+    pub fn new(routes: Vec<EthRoute>) -> Self {
+        EthRouter {
+            routes,
+            next: AtomicUsize::new(0),
+        }
+    }
+
+    /// Route everything through a single core, used until the real routes are known.
+    pub fn single(x: u8, y: u8) -> Self {
+        Self::new(vec![EthRoute {
+            core: Arc::new(EthCore::new(x, y)),
+            remote: None,
+        }])
+    }
+
+    /// Build a new router from the neighbours reported by the chip; cores which are already
+    /// known keep their state. If none of the links can route, the current routes are kept.
+    pub fn with_routes(&self, neighbours: &[NeighbouringChip]) -> Self {
+        let mut routes: Vec<EthRoute> = Vec::with_capacity(neighbours.len());
+        for neighbour in neighbours.iter().filter(|n| n.routing_enabled) {
+            let (x, y) = neighbour.local_noc_addr;
+            if routes.iter().any(|r| r.core.x == x && r.core.y == y) {
+                continue;
+            }
+
+            let core = self
+                .routes
+                .iter()
+                .find(|r| r.core.x == x && r.core.y == y)
+                .map(|r| r.core.clone())
+                .unwrap_or_else(|| Arc::new(EthCore::new(x, y)));
+
+            routes.push(EthRoute {
+                core,
+                remote: Some(neighbour.eth_addr),
+            });
+        }
+
+        if routes.is_empty() {
+            routes = self.routes.clone();
+        }
+
+        Self::new(routes)
+    }
+
+    /// The same routes with fresh cores, used when the device was reopened and the state of the
+    /// old cores (i.e. their dma buffers) belongs to the old device.
+    pub fn with_new_cores(&self) -> Self {
+        Self::new(
+            self.routes
+                .iter()
+                .map(|route| EthRoute {
+                    core: Arc::new(EthCore::new(route.core.x, route.core.y)),
+                    remote: route.remote,
+                })
+                .collect(),
+        )
+    }
+
+    pub fn routes(&self) -> &[EthRoute] {
+        &self.routes
+    }
+
+    /// The order in which cores should be tried to reach `dest`.
+    ///
+    /// Healthy cores come first, ordered by how close their link partner is to the destination.
+    /// Cores which are equally close are handed out round-robin.
+    pub fn candidates(&self, dest: &EthAddr) -> Vec<Arc<EthCore>> {
+        if self.routes.is_empty() {
+            return Vec::new();
+        }
+
+        let start = self.next.fetch_add(1, Ordering::Relaxed) % self.routes.len();
+
+        let mut ordered = self.routes[start..]
+            .iter()
+            .chain(self.routes[..start].iter())
+            .collect::<Vec<_>>();
+        // Stable sort so that the rotation is kept among equals
+        ordered.sort_by_key(|route| {
+            (
+                !route.core.is_healthy(),
+                route.remote.map(|r| distance(&r, dest)).unwrap_or(0),
+            )
+        });
+
+        ordered.into_iter().map(|r| r.core.clone()).collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn addr(shelf_x: u8, shelf_y: u8) -> EthAddr {
+        EthAddr {
+            shelf_x,
+            shelf_y,
+            rack_x: 0,
+            rack_y: 0,
+        }
+    }
+
+    fn neighbour(x: u8, y: u8, remote: EthAddr, routing_enabled: bool) -> NeighbouringChip {
+        NeighbouringChip {
+            routing_enabled,
+            local_noc_addr: (x, y),
+            remote_noc_addr: (0, 0),
+            eth_addr: remote,
+        }
+    }
+
+    #[test]
+    fn route_selection() {
+        let router = EthRouter::single(4, 6).with_routes(&[
+            neighbour(9, 0, addr(1, 0), true),
+            neighbour(1, 0, addr(0, 1), true),
+            neighbour(8, 0, addr(0, 1), true),
+            neighbour(2, 0, addr(5, 5), false),
+        ]);
+        assert_eq!(router.routes().len(), 3);
+
+        // Closest core is always picked first
+        for _ in 0..3 {
+            let first = &router.candidates(&addr(3, 0))[0];
+            assert_eq!((first.x, first.y), (9, 0));
+        }
+
+        // Equally close cores are rotated
+        let firsts = (0..3)
+            .map(|_| router.candidates(&addr(0, 2))[0].x)
+            .collect::<Vec<_>>();
+        assert!(firsts.contains(&1) && firsts.contains(&8));
+
+        // Unhealthy cores are only used as a last resort
+        router.routes()[0].core.set_healthy(false);
+        let candidates = router.candidates(&addr(3, 0));
+        assert_eq!(candidates.len(), 3);
+        assert_eq!(candidates[2].x, 9);
+    }
+
+    #[test]
+    fn routes_keep_state() {
+        let router = EthRouter::single(4, 6);
+        assert_eq!(router.with_routes(&[]).routes()[0].core.x, 4);
+
+        let router = router.with_routes(&[neighbour(9, 0, addr(1, 0), true)]);
+        router.routes()[0].core.set_healthy(false);
+
+        let router = router.with_routes(&[
+            neighbour(9, 0, addr(1, 0), true),
+            neighbour(1, 0, addr(0, 1), true),
+        ]);
+        assert!(!router.routes()[0].core.is_healthy());
+        assert!(router.routes()[1].core.is_healthy());
+    }
+}
@@ -30,13 +30,31 @@ fn get_cargo_target_dir() -> Result<std::path::PathBuf, Box<dyn std::error::Erro
 }
 
 fn main() {
-    let result = cbindgen::Builder::new()
+    // The C++ header keeps everything in the luwen namespace, this is what existing consumers
+    // include. include/luwen.hpp builds its RAII wrapper on top of it.
+    let cpp = cbindgen::Builder::new()
         .with_pragma_once(true)
         .with_namespace("luwen")
         .with_crate(std::env::var("CARGO_MANIFEST_DIR").unwrap())
         .generate()
         .expect("Unable to generate bindings");
 
+    // A plain C header so that the library can also be used from C.
+    let mut config = cbindgen::Config {
+        language: cbindgen::Language::C,
+        pragma_once: true,
+        cpp_compat: true,
+        after_includes: Some("typedef struct Chip Chip;".to_string()),
+        ..Default::default()
+    };
+    config.enumeration.prefix_with_name = true;
+
+    let c = cbindgen::Builder::new()
+        .with_config(config)
+        .with_crate(std::env::var("CARGO_MANIFEST_DIR").unwrap())
+        .generate()
+        .expect("Unable to generate C bindings");
+
     // Cargo deb can't access the OUT_DIR variable, so we need to copy it to the directory
     // that this file will end up in.
     // let include_dir = std::env::var("OUT_DIR").unwrap();
@@ -49,7 +67,9 @@ fn main() {
     });
     let include_dir = std::path::Path::new(&include_dir);
 
-    result.write_to_file(include_dir.join("luwen.h"));
+    cpp.write_to_file(include_dir.join("luwen.h"));
+    c.write_to_file(include_dir.join("luwen_c.h"));
 
     println!("cargo:rerun-if-changed=build.rs");
+    println!("cargo:rerun-if-changed=src");
 }
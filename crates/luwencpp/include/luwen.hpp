@@ -0,0 +1,195 @@
+// SPDX-FileCopyrightText: © 2024 Tenstorrent Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+// Thin C++ wrapper around the generated luwen.h. Chips are closed when they go out of scope and
+// failed calls throw luwen::raii::Error. The wrapper lives in its own namespace so that the raw
+// bindings in luwen:: keep their names.
+
+#pragma once
+
+#include <algorithm>
+#include <cstdint>
+#include <stdexcept>
+#include <string>
+#include <utility>
+#include <vector>
+
+#include "luwen.h"
+
+namespace luwen {
+namespace raii {
+
+class Error : public std::runtime_error {
+  public:
+    Error(ErrorKind kind, const std::string &message) : std::runtime_error(message), kind_(kind) {}
+
+    ErrorKind kind() const { return kind_; }
+
+  private:
+    ErrorKind kind_;
+};
+
+// Take ownership of the result, throwing if it holds an error.
+inline uint32_t check(CResult result) {
+    if (result.tag == CResultTag::Err) {
+        std::string message = result.err != nullptr ? result.err : "";
+        luwen_free_error(result.err);
+        throw Error(result.kind, message);
+    }
+    return result.ok;
+}
+
+class Chip {
+  public:
+    explicit Chip(::luwen::Chip *chip) : chip_(chip) {}
+
+    Chip(const Chip &) = delete;
+    Chip &operator=(const Chip &) = delete;
+
+    Chip(Chip &&other) noexcept : chip_(std::exchange(other.chip_, nullptr)) {}
+
+    Chip &operator=(Chip &&other) noexcept {
+        if (this != &other) {
+            reset();
+            chip_ = std::exchange(other.chip_, nullptr);
+        }
+        return *this;
+    }
+
+    ~Chip() { reset(); }
+
+    // Open /dev/tenstorrent/{interface_id} using the built in pci backend.
+    static Chip open_pci(uint32_t interface_id) {
+        ::luwen::Chip *chip = nullptr;
+        check(luwen_open_pci(interface_id, &chip));
+        return Chip(chip);
+    }
+
+    Chip open_remote(EthAddr addr) const {
+        ::luwen::Chip *chip = luwen_open_remote(handle(), addr);
+        if (chip == nullptr) {
+            throw Error(ErrorKind::Generic, "Could not open remote chip");
+        }
+        return Chip(chip);
+    }
+
+    ::luwen::Chip *get() const { return chip_; }
+
+    Arch arch() const { return chip_arch(*handle()); }
+
+    DeviceInfo device_info() const {
+        DeviceInfo info{};
+        check(chip_device_info(*handle(), &info));
+        return info;
+    }
+
+    // Returns the return code of the message, arg is set to the value returned by ARC.
+    uint32_t arc_msg(uint32_t msg, bool wait_for_done = true, uint16_t arg0 = 0, uint16_t arg1 = 0,
+                     int32_t timeout = 1, uint32_t *arg = nullptr) const {
+        return check(chip_arc_msg(*handle(), msg, wait_for_done, arg0, arg1, timeout, arg));
+    }
+
+    void noc_read(uint8_t noc_id, uint8_t x, uint8_t y, uint64_t addr, uint8_t *data,
+                  size_t len) const {
+        check(chip_noc_read(*handle(), noc_id, x, y, addr, data, len));
+    }
+
+    void noc_write(uint8_t noc_id, uint8_t x, uint8_t y, uint64_t addr, const uint8_t *data,
+                   size_t len) const {
+        check(chip_noc_write(*handle(), noc_id, x, y, addr, data, len));
+    }
+
+    void noc_broadcast(uint8_t noc_id, uint64_t addr, const uint8_t *data, size_t len) const {
+        check(chip_noc_broadcast(*handle(), noc_id, addr, data, len));
+    }
+
+    uint32_t noc_read32(uint8_t noc_id, uint8_t x, uint8_t y, uint64_t addr) const {
+        return check(chip_noc_read32(*handle(), noc_id, x, y, addr));
+    }
+
+    void noc_write32(uint8_t noc_id, uint8_t x, uint8_t y, uint64_t addr, uint32_t value) const {
+        check(chip_noc_write32(*handle(), noc_id, x, y, addr, value));
+    }
+
+    void axi_read(uint64_t addr, uint8_t *data, size_t len) const {
+        check(chip_axi_read(*handle(), addr, data, len));
+    }
+
+    void axi_write(uint64_t addr, const uint8_t *data, size_t len) const {
+        check(chip_axi_write(*handle(), addr, data, len));
+    }
+
+    uint32_t axi_read32(uint64_t addr) const { return check(chip_axi_read32(*handle(), addr)); }
+
+    void axi_write32(uint64_t addr, uint32_t value) const {
+        check(chip_axi_write32(*handle(), addr, value));
+    }
+
+    void spi_read(uint32_t addr, uint8_t *data, size_t len) const {
+        check(chip_spi_read(*handle(), addr, data, len));
+    }
+
+    void spi_write(uint32_t addr, const uint8_t *data, size_t len) const {
+        check(chip_spi_write(*handle(), addr, data, len));
+    }
+
+    Telemetry telemetry() const {
+        Telemetry telemetry{};
+        check(chip_telemetry(*handle(), &telemetry));
+        return telemetry;
+    }
+
+    std::vector<Neighbour> neighbours() const {
+        std::vector<Neighbour> neighbours(check(chip_neighbours(*handle(), nullptr, 0)));
+        size_t count = check(chip_neighbours(*handle(), neighbours.data(), neighbours.size()));
+        neighbours.resize(std::min(count, neighbours.size()));
+        return neighbours;
+    }
+
+  private:
+    // The raw bindings take a reference to the chip, so never hand them a moved-from handle.
+    ::luwen::Chip *handle() const {
+        if (chip_ == nullptr) {
+            throw Error(ErrorKind::InvalidArgument, "luwen chip used after it was moved from");
+        }
+        return chip_;
+    }
+
+    void reset() {
+        if (chip_ != nullptr) {
+            luwen_close(chip_);
+            chip_ = nullptr;
+        }
+    }
+
+    ::luwen::Chip *chip_;
+};
+
+struct DetectedChip {
+    Chip chip;
+    InitStatus status;
+    // Empty if the chip initialized without errors.
+    std::string status_message;
+};
+
+inline std::vector<DetectedChip> detect_chips(const DetectOptions &options = {}) {
+    DetectedChips detected{};
+    check(luwen_detect_chips(&options, &detected));
+
+    std::vector<DetectedChip> chips;
+    chips.reserve(detected.count);
+    for (size_t i = 0; i < detected.count; ++i) {
+        const ::luwen::DetectedChip &chip = detected.chips[i];
+        chips.push_back(DetectedChip{
+            Chip(chip.chip),
+            chip.status,
+            chip.status_message != nullptr ? chip.status_message : "",
+        });
+    }
+    luwen_free_detected(detected);
+
+    return chips;
+}
+
+}  // namespace raii
+}  // namespace luwen
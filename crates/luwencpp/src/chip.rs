@@ -0,0 +1,444 @@
+// SPDX-FileCopyrightText: © 2024 Tenstorrent Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Access to an opened chip. Buffers are passed as a pointer and a length, a null pointer is
+//! only accepted when the length is 0.
+
+use luwen_if::chip::{Chip, HlComms};
+use luwen_if::ChipImpl;
+
+use crate::error::{ffi, CResult, Error, ErrorKind};
+use crate::{Arch, DeviceInfo, EthAddr};
+
+fn slice<'a>(data: *const u8, len: usize) -> Result<&'a [u8], Error> {
+    if len == 0 {
+        Ok(&[])
+    } else if data.is_null() {
+        Err(Error::null("data"))
+    } else {
+        Ok(unsafe { std::slice::from_raw_parts(data, len) })
+    }
+}
+
+fn slice_mut<'a>(data: *mut u8, len: usize) -> Result<&'a mut [u8], Error> {
+    if len == 0 {
+        Ok(&mut [])
+    } else if data.is_null() {
+        Err(Error::null("data"))
+    } else {
+        Ok(unsafe { std::slice::from_raw_parts_mut(data, len) })
+    }
+}
+
+fn out<'a, T>(value: *mut T, name: &str) -> Result<&'a mut T, Error> {
+    unsafe { value.as_mut() }.ok_or_else(|| Error::null(name))
+}
+
+#[no_mangle]
+pub extern "C" fn chip_arch(chip: &Chip) -> Arch {
+    match chip.get_arch() {
+        luwen_core::Arch::Grayskull => Arch::GRAYSKULL,
+        luwen_core::Arch::Wormhole => Arch::WORMHOLE,
+        luwen_core::Arch::Blackhole => Arch::BLACKHOLE,
+        luwen_core::Arch::Unknown(_) => Arch::UNKNOWN,
+    }
+}
+
+#[no_mangle]
+/// # Safety
+///
+/// info must be a valid pointer
+pub unsafe extern "C" fn chip_device_info(chip: &Chip, info: *mut DeviceInfo) -> CResult {
+    ffi(|| {
+        let info = out(info, "info")?;
+        match chip.get_device_info()? {
+            Some(value) => {
+                *info = value.into();
+                Ok(0)
+            }
+            None => Err(Error::new(
+                ErrorKind::Unsupported,
+                "Device info is not available for this chip",
+            )),
+        }
+    })
+}
+
+#[no_mangle]
+/// # Safety
+///
+/// data must point to at least len bytes
+pub unsafe extern "C" fn chip_noc_read(
+    chip: &Chip,
+    noc_id: u8,
+    x: u8,
+    y: u8,
+    addr: u64,
+    data: *mut u8,
+    len: usize,
+) -> CResult {
+    ffi(|| {
+        chip.noc_read(noc_id, x, y, addr, slice_mut(data, len)?)?;
+        Ok(0)
+    })
+}
+
+#[no_mangle]
+/// # Safety
+///
+/// data must point to at least len bytes
+pub unsafe extern "C" fn chip_noc_write(
+    chip: &Chip,
+    noc_id: u8,
+    x: u8,
+    y: u8,
+    addr: u64,
+    data: *const u8,
+    len: usize,
+) -> CResult {
+    ffi(|| {
+        chip.noc_write(noc_id, x, y, addr, slice(data, len)?)?;
+        Ok(0)
+    })
+}
+
+#[no_mangle]
+/// # Safety
+///
+/// data must point to at least len bytes
+pub unsafe extern "C" fn chip_noc_broadcast(
+    chip: &Chip,
+    noc_id: u8,
+    addr: u64,
+    data: *const u8,
+    len: usize,
+) -> CResult {
+    ffi(|| {
+        chip.noc_broadcast(noc_id, addr, slice(data, len)?)?;
+        Ok(0)
+    })
+}
+
+/// Returns the value read in ok.
+#[no_mangle]
+pub extern "C" fn chip_noc_read32(chip: &Chip, noc_id: u8, x: u8, y: u8, addr: u64) -> CResult {
+    ffi(|| Ok(chip.noc_read32(noc_id, x, y, addr)?))
+}
+
+#[no_mangle]
+pub extern "C" fn chip_noc_write32(
+    chip: &Chip,
+    noc_id: u8,
+    x: u8,
+    y: u8,
+    addr: u64,
+    value: u32,
+) -> CResult {
+    ffi(|| {
+        chip.noc_write32(noc_id, x, y, addr, value)?;
+        Ok(0)
+    })
+}
+
+#[no_mangle]
+/// # Safety
+///
+/// data must point to at least len bytes
+pub unsafe extern "C" fn chip_axi_read(
+    chip: &Chip,
+    addr: u64,
+    data: *mut u8,
+    len: usize,
+) -> CResult {
+    ffi(|| {
+        chip.axi_read(addr, slice_mut(data, len)?)?;
+        Ok(0)
+    })
+}
+
+#[no_mangle]
+/// # Safety
+///
+/// data must point to at least len bytes
+pub unsafe extern "C" fn chip_axi_write(
+    chip: &Chip,
+    addr: u64,
+    data: *const u8,
+    len: usize,
+) -> CResult {
+    ffi(|| {
+        chip.axi_write(addr, slice(data, len)?)?;
+        Ok(0)
+    })
+}
+
+/// Returns the value read in ok.
+#[no_mangle]
+pub extern "C" fn chip_axi_read32(chip: &Chip, addr: u64) -> CResult {
+    ffi(|| Ok(chip.axi_read32(addr)?))
+}
+
+#[no_mangle]
+pub extern "C" fn chip_axi_write32(chip: &Chip, addr: u64, value: u32) -> CResult {
+    ffi(|| {
+        chip.axi_write32(addr, value)?;
+        Ok(0)
+    })
+}
+
+#[no_mangle]
+/// # Safety
+///
+/// data must point to at least len bytes
+pub unsafe extern "C" fn chip_spi_read(
+    chip: &Chip,
+    addr: u32,
+    data: *mut u8,
+    len: usize,
+) -> CResult {
+    ffi(|| {
+        let data = slice_mut(data, len)?;
+        if let Some(wh) = chip.as_wh() {
+            wh.spi_read(addr, data)?;
+        } else if let Some(gs) = chip.as_gs() {
+            gs.spi_read(addr, data)?;
+        } else if let Some(bh) = chip.as_bh() {
+            bh.spi_read(addr, data)?;
+        } else {
+            return Err(Error::new(
+                ErrorKind::Unsupported,
+                "SPI is not supported on this chip",
+            ));
+        }
+        Ok(0)
+    })
+}
+
+#[no_mangle]
+/// # Safety
+///
+/// data must point to at least len bytes
+pub unsafe extern "C" fn chip_spi_write(
+    chip: &Chip,
+    addr: u32,
+    data: *const u8,
+    len: usize,
+) -> CResult {
+    ffi(|| {
+        let data = slice(data, len)?;
+        if let Some(wh) = chip.as_wh() {
+            wh.spi_write(addr, data)?;
+        } else if let Some(gs) = chip.as_gs() {
+            gs.spi_write(addr, data)?;
+        } else if let Some(bh) = chip.as_bh() {
+            bh.spi_write(addr, data)?;
+        } else {
+            return Err(Error::new(
+                ErrorKind::Unsupported,
+                "SPI is not supported on this chip",
+            ));
+        }
+        Ok(0)
+    })
+}
+
+/// The full telemetry of a chip. Values the firmware does not report are 0.
+#[repr(C)]
+pub struct Telemetry {
+    pub board_id: u64,
+    pub enum_version: u32,
+    pub entry_count: u32,
+    pub device_id: u32,
+    pub asic_id: u32,
+    pub asic_ro: u32,
+    pub asic_idd: u32,
+    pub board_id_high: u32,
+    pub board_id_low: u32,
+    pub harvesting_state: u32,
+    pub update_telem_speed: u32,
+    pub arc0_fw_version: u32,
+    pub arc1_fw_version: u32,
+    pub arc2_fw_version: u32,
+    pub arc3_fw_version: u32,
+    pub spibootrom_fw_version: u32,
+    pub eth_fw_version: u32,
+    pub ddr_fw_version: u32,
+    pub l2cpu_fw_version: u32,
+    pub m3_bl_fw_version: u32,
+    pub m3_app_fw_version: u32,
+    pub ddr_speed: u32,
+    pub ddr_status: u32,
+    pub eth_status0: u32,
+    pub eth_status1: u32,
+    pub pcie_status: u32,
+    pub faults: u32,
+    pub arc0_health: u32,
+    pub arc1_health: u32,
+    pub arc2_health: u32,
+    pub arc3_health: u32,
+    pub fan_speed: u32,
+    pub aiclk: u32,
+    pub axiclk: u32,
+    pub arcclk: u32,
+    pub l2cpuclk0: u32,
+    pub l2cpuclk1: u32,
+    pub l2cpuclk2: u32,
+    pub l2cpuclk3: u32,
+    pub throttler: u32,
+    pub vcore: u32,
+    pub asic_temperature: u32,
+    pub vreg_temperature: u32,
+    pub board_temperature: u32,
+    pub tdp: u32,
+    pub tdc: u32,
+    pub vdd_limits: u32,
+    pub thm_limits: u32,
+    pub wh_fw_date: u32,
+    pub asic_tmon0: u32,
+    pub asic_tmon1: u32,
+    pub mvddq_power: u32,
+    pub gddr_train_temp0: u32,
+    pub gddr_train_temp1: u32,
+    pub asic_power: u32,
+    pub aux_status: u32,
+    pub boot_date: u32,
+    pub rt_seconds: u32,
+    pub eth_debug_status0: u32,
+    pub eth_debug_status1: u32,
+    pub tt_flash_version: u32,
+    pub fw_bundle_version: u32,
+    pub timer_heartbeat: u32,
+}
+
+impl From<luwen_if::chip::Telemetry> for Telemetry {
+    fn from(value: luwen_if::chip::Telemetry) -> Self {
+        Telemetry {
+            board_id: value.board_id,
+            enum_version: value.enum_version,
+            entry_count: value.entry_count,
+            device_id: value.device_id,
+            asic_id: value.asic_id,
+            asic_ro: value.asic_ro,
+            asic_idd: value.asic_idd,
+            board_id_high: value.board_id_high,
+            board_id_low: value.board_id_low,
+            harvesting_state: value.harvesting_state,
+            update_telem_speed: value.update_telem_speed,
+            arc0_fw_version: value.arc0_fw_version,
+            arc1_fw_version: value.arc1_fw_version,
+            arc2_fw_version: value.arc2_fw_version,
+            arc3_fw_version: value.arc3_fw_version,
+            spibootrom_fw_version: value.spibootrom_fw_version,
+            eth_fw_version: value.eth_fw_version,
+            ddr_fw_version: value.ddr_fw_version,
+            l2cpu_fw_version: value.l2cpu_fw_version,
+            m3_bl_fw_version: value.m3_bl_fw_version,
+            m3_app_fw_version: value.m3_app_fw_version,
+            ddr_speed: value.ddr_speed.unwrap_or(0),
+            ddr_status: value.ddr_status,
+            eth_status0: value.eth_status0,
+            eth_status1: value.eth_status1,
+            pcie_status: value.pcie_status,
+            faults: value.faults,
+            arc0_health: value.arc0_health,
+            arc1_health: value.arc1_health,
+            arc2_health: value.arc2_health,
+            arc3_health: value.arc3_health,
+            fan_speed: value.fan_speed,
+            aiclk: value.aiclk,
+            axiclk: value.axiclk,
+            arcclk: value.arcclk,
+            l2cpuclk0: value.l2cpuclk0,
+            l2cpuclk1: value.l2cpuclk1,
+            l2cpuclk2: value.l2cpuclk2,
+            l2cpuclk3: value.l2cpuclk3,
+            throttler: value.throttler,
+            vcore: value.vcore,
+            asic_temperature: value.asic_temperature,
+            vreg_temperature: value.vreg_temperature,
+            board_temperature: value.board_temperature,
+            tdp: value.tdp,
+            tdc: value.tdc,
+            vdd_limits: value.vdd_limits,
+            thm_limits: value.thm_limits,
+            wh_fw_date: value.wh_fw_date,
+            asic_tmon0: value.asic_tmon0,
+            asic_tmon1: value.asic_tmon1,
+            mvddq_power: value.mvddq_power,
+            gddr_train_temp0: value.gddr_train_temp0,
+            gddr_train_temp1: value.gddr_train_temp1,
+            asic_power: value.asic_power.unwrap_or(0),
+            aux_status: value.aux_status.unwrap_or(0),
+            boot_date: value.boot_date,
+            rt_seconds: value.rt_seconds,
+            eth_debug_status0: value.eth_debug_status0,
+            eth_debug_status1: value.eth_debug_status1,
+            tt_flash_version: value.tt_flash_version,
+            fw_bundle_version: value.fw_bundle_version,
+            timer_heartbeat: value.timer_heartbeat,
+        }
+    }
+}
+
+#[no_mangle]
+/// # Safety
+///
+/// telemetry must be a valid pointer
+pub unsafe extern "C" fn chip_telemetry(chip: &Chip, telemetry: *mut Telemetry) -> CResult {
+    ffi(|| {
+        let telemetry = out(telemetry, "telemetry")?;
+        *telemetry = chip.get_telemetry()?.into();
+        Ok(0)
+    })
+}
+
+/// An ethernet link to another chip.
+#[repr(C)]
+pub struct Neighbour {
+    pub routing_enabled: bool,
+    pub local_noc_x: u8,
+    pub local_noc_y: u8,
+    pub remote_noc_x: u8,
+    pub remote_noc_y: u8,
+    pub eth_addr: EthAddr,
+}
+
+impl From<luwen_if::chip::NeighbouringChip> for Neighbour {
+    fn from(value: luwen_if::chip::NeighbouringChip) -> Self {
+        Neighbour {
+            routing_enabled: value.routing_enabled,
+            local_noc_x: value.local_noc_addr.0,
+            local_noc_y: value.local_noc_addr.1,
+            remote_noc_x: value.remote_noc_addr.0,
+            remote_noc_y: value.remote_noc_addr.1,
+            eth_addr: value.eth_addr.into(),
+        }
+    }
+}
+
+#[no_mangle]
+/// Fill neighbours with up to capacity of the chips connected over ethernet, the total number
+/// of neighbours is returned in ok.
+///
+/// # Safety
+///
+/// neighbours must point to at least capacity values
+pub unsafe extern "C" fn chip_neighbours(
+    chip: &Chip,
+    neighbours: *mut Neighbour,
+    capacity: usize,
+) -> CResult {
+    ffi(|| {
+        if neighbours.is_null() && capacity > 0 {
+            return Err(Error::null("neighbours"));
+        }
+
+        let found = chip.get_neighbouring_chips()?;
+        let count = found.len();
+        for (index, neighbour) in found.into_iter().take(capacity).enumerate() {
+            unsafe { neighbours.add(index).write(neighbour.into()) };
+        }
+
+        Ok(count as u32)
+    })
+}
@@ -0,0 +1,234 @@
+// SPDX-FileCopyrightText: © 2024 Tenstorrent Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use std::ffi::{c_char, CString};
+use std::panic::{catch_unwind, AssertUnwindSafe};
+
+use luwen_if::{
+    chip::PowerError,
+    error::{AccessError, PlatformError},
+    ArcMsgError, ArcMsgProtocolError,
+};
+use luwen_ref::error::LuwenError;
+
+#[repr(u8)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CResultTag {
+    Ok,
+    Err,
+}
+
+/// The class of error held by a CResult, so that callers can react to specific failures
+/// without parsing the message.
+#[repr(u8)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ErrorKind {
+    None,
+    Generic,
+    /// A null pointer or out of range value was passed in.
+    InvalidArgument,
+    /// The operation is not available on this chip architecture.
+    Unsupported,
+    /// luwen panicked, the chip may be left in an inconsistent state.
+    Panic,
+    DeviceOpen,
+    ArcTimeout,
+    ArcError,
+    AxiError,
+    WaitTimeout,
+    WrongChipArch,
+    UnsupportedFwVersion,
+    EthernetTraining,
+    InitFailed,
+}
+
+/// The result of a fallible call. When tag is Err, err holds a message which must be released
+/// with luwen_free_error.
+#[repr(C)]
+pub struct CResult {
+    pub tag: CResultTag,
+    pub ok: u32,
+    pub err: *const c_char,
+    pub kind: ErrorKind,
+}
+
+impl CResult {
+    pub fn ok(value: u32) -> CResult {
+        CResult {
+            tag: CResultTag::Ok,
+            ok: value,
+            err: std::ptr::null(),
+            kind: ErrorKind::None,
+        }
+    }
+
+    pub fn err(value: &str) -> CResult {
+        Error::new(ErrorKind::Generic, value).into()
+    }
+}
+
+pub struct Error {
+    pub kind: ErrorKind,
+    pub message: String,
+}
+
+impl Error {
+    pub fn new(kind: ErrorKind, message: impl Into<String>) -> Self {
+        Error {
+            kind,
+            message: message.into(),
+        }
+    }
+
+    pub fn null(name: &str) -> Self {
+        Error::new(
+            ErrorKind::InvalidArgument,
+            format!("{name} must not be null"),
+        )
+    }
+}
+
+impl From<Error> for CResult {
+    fn from(value: Error) -> Self {
+        // Interior nuls would truncate the message on the C side anyway.
+        let message = value.message.replace('\0', " ");
+        CResult {
+            tag: CResultTag::Err,
+            ok: 0,
+            err: CString::new(message).unwrap_or_default().into_raw(),
+            kind: value.kind,
+        }
+    }
+}
+
+impl From<PlatformError> for Error {
+    fn from(value: PlatformError) -> Self {
+        Error::new(platform_kind(&value), value.to_string())
+    }
+}
+
+fn platform_kind(err: &PlatformError) -> ErrorKind {
+    match err {
+        PlatformError::WrongChipArch { .. } | PlatformError::WrongChipArchs { .. } => {
+            ErrorKind::WrongChipArch
+        }
+        PlatformError::UnsupportedFwVersion { .. } => ErrorKind::UnsupportedFwVersion,
+        PlatformError::ArcNotReady(..) => ErrorKind::ArcError,
+        PlatformError::ArcMsgError(err) => arc_msg_kind(err),
+        PlatformError::MessageError(luwen_if::chip::MessageError::Timeout(..)) => {
+            ErrorKind::ArcTimeout
+        }
+        PlatformError::MessageError(..) => ErrorKind::ArcError,
+        PlatformError::PowerError(err) => match err {
+            PowerError::AiclkOutOfRange { .. }
+            | PowerError::UnknownLimit(..)
+            | PowerError::FanSpeedOutOfRange { .. }
+            | PowerError::InvalidFanCurve(..) => ErrorKind::InvalidArgument,
+            PowerError::Unsupported { .. } => ErrorKind::Unsupported,
+            PowerError::Rejected { .. } => ErrorKind::ArcError,
+            PowerError::OverTemperature { .. } => ErrorKind::Generic,
+        },
+        PlatformError::EthernetTrainingNotComplete(..) => ErrorKind::EthernetTraining,
+        PlatformError::AxiError(..) => ErrorKind::AxiError,
+        PlatformError::WaitError(..) => ErrorKind::WaitTimeout,
+        // The access context is already part of the message, classify by what went wrong
+        PlatformError::AccessError(err) => error_kind(err.inner()),
+        PlatformError::Generic(..) | PlatformError::GenericError(..) => ErrorKind::Generic,
+    }
+}
+
+fn arc_msg_kind(err: &ArcMsgError) -> ErrorKind {
+    match err {
+        ArcMsgError::ProtocolError {
+            source: ArcMsgProtocolError::Timeout(..),
+            ..
+        } => ErrorKind::ArcTimeout,
+        ArcMsgError::ProtocolError { .. } => ErrorKind::ArcError,
+        ArcMsgError::AxiError(..) => ErrorKind::AxiError,
+    }
+}
+
+fn error_kind(err: &(dyn std::error::Error + 'static)) -> ErrorKind {
+    if let Some(err) = err.downcast_ref::<PlatformError>() {
+        platform_kind(err)
+    } else if let Some(err) = err.downcast_ref::<ArcMsgError>() {
+        arc_msg_kind(err)
+    } else if let Some(err) = err.downcast_ref::<AccessError>() {
+        error_kind(err.inner())
+    } else {
+        ErrorKind::Generic
+    }
+}
+
+impl From<Box<dyn std::error::Error>> for Error {
+    fn from(value: Box<dyn std::error::Error>) -> Self {
+        Error::new(error_kind(value.as_ref()), value.to_string())
+    }
+}
+
+impl From<LuwenError> for Error {
+    fn from(value: LuwenError) -> Self {
+        match value {
+            LuwenError::PlatformError(err) => err.into(),
+            LuwenError::PciOpenError(err) => Error::new(ErrorKind::DeviceOpen, err.to_string()),
+            LuwenError::EthernetTimeout(..) | LuwenError::EthernetWriteTimeout(..) => {
+                Error::new(ErrorKind::WaitTimeout, value.to_string())
+            }
+            err => Error::new(ErrorKind::Generic, err.to_string()),
+        }
+    }
+}
+
+/// Run the body of an exported function, converting errors and panics into a CResult so that
+/// nothing unwinds across the ffi boundary.
+pub fn ffi(f: impl FnOnce() -> Result<u32, Error>) -> CResult {
+    match catch_unwind(AssertUnwindSafe(f)) {
+        Ok(Ok(value)) => CResult::ok(value),
+        Ok(Err(err)) => err.into(),
+        Err(panic) => {
+            let message = if let Some(message) = panic.downcast_ref::<&str>() {
+                message.to_string()
+            } else if let Some(message) = panic.downcast_ref::<String>() {
+                message.clone()
+            } else {
+                "luwen panicked".to_string()
+            };
+            Error::new(ErrorKind::Panic, message).into()
+        }
+    }
+}
+
+#[no_mangle]
+/// Release the error message of a CResult.
+///
+/// # Safety
+///
+/// err must be null or a message returned in a CResult which has not already been freed.
+pub unsafe extern "C" fn luwen_free_error(err: *const c_char) {
+    if !err.is_null() {
+        unsafe {
+            drop(CString::from_raw(err as *mut c_char));
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn ffi_catches_panics() {
+        let result = ffi(|| panic!("oh no"));
+        assert_eq!(result.tag, CResultTag::Err);
+        assert_eq!(result.kind, ErrorKind::Panic);
+
+        let message = unsafe { std::ffi::CStr::from_ptr(result.err) };
+        assert_eq!(message.to_str().unwrap(), "oh no");
+        unsafe { luwen_free_error(result.err) };
+
+        let result = ffi(|| Ok(7));
+        assert_eq!(result.tag, CResultTag::Ok);
+        assert_eq!(result.ok, 7);
+        assert!(result.err.is_null());
+    }
+}
@@ -7,12 +7,20 @@ use luwen_if::{
     ArcMsg, ArcMsgError, ArcMsgProtocolError, CallbackStorage, ChipImpl, FnOptions,
 };
 
+mod chip;
+mod error;
+mod pci;
+
+pub use chip::{Neighbour, Telemetry};
+pub use error::{CResult, CResultTag, ErrorKind};
+pub use pci::{ComponentStatus, DetectOptions, DetectedChip, DetectedChips, InitStatus};
+
 #[repr(C)]
 pub struct EthAddr {
-    shelf_x: u8,
-    shelf_y: u8,
-    rack_x: u8,
-    rack_y: u8,
+    pub shelf_x: u8,
+    pub shelf_y: u8,
+    pub rack_x: u8,
+    pub rack_y: u8,
 }
 
 impl From<EthAddr> for luwen_if::EthAddr {
@@ -26,10 +34,23 @@ impl From<EthAddr> for luwen_if::EthAddr {
     }
 }
 
+impl From<luwen_if::EthAddr> for EthAddr {
+    fn from(value: luwen_if::EthAddr) -> Self {
+        EthAddr {
+            shelf_x: value.shelf_x,
+            shelf_y: value.shelf_y,
+            rack_x: value.rack_x,
+            rack_y: value.rack_y,
+        }
+    }
+}
+
 #[repr(u8)]
 pub enum Arch {
     GRAYSKULL,
     WORMHOLE,
+    BLACKHOLE,
+    UNKNOWN,
 }
 
 #[repr(C)]
@@ -63,6 +84,22 @@ impl From<DeviceInfo> for luwen_if::DeviceInfo {
     }
 }
 
+impl From<luwen_if::DeviceInfo> for DeviceInfo {
+    fn from(value: luwen_if::DeviceInfo) -> Self {
+        DeviceInfo {
+            interface_id: value.interface_id,
+            domain: value.domain,
+            bus: value.bus,
+            slot: value.slot,
+            function: value.function,
+            vendor: value.vendor,
+            device_id: value.device_id,
+            bar_size: value.bar_size,
+            board_id: value.board_id,
+        }
+    }
+}
+
 #[repr(C)]
 #[derive(Clone)]
 pub struct LuwenGlue {
@@ -154,6 +191,15 @@ pub fn callback_glue(
                 }
                 Ok(())
             }
+            // The glue always routes through its own ethernet core, so there is nothing to configure.
+            luwen_if::FnDriver::EthRoutes { .. } => Ok(()),
+            // The glue has no way to tell that the device went away, so it is always reported as connected.
+            luwen_if::FnDriver::ConnectionState(_) => Ok(()),
+            luwen_if::FnDriver::Reopen => {
+                Err("The callback interface does not support reopening the device".into())
+            }
+            // Serializing accesses to the device is left to the caller of the glue.
+            luwen_if::FnDriver::Acquire | luwen_if::FnDriver::Release => Ok(()),
         },
         FnOptions::Axi(op) => match op {
             luwen_if::FnAxi::Read { addr, data, len } => {
@@ -279,6 +325,8 @@ pub extern "C" fn luwen_open(arch: Arch, glue: LuwenGlue) -> *mut Chip {
     let arch = match arch {
         Arch::GRAYSKULL => luwen_core::Arch::Grayskull,
         Arch::WORMHOLE => luwen_core::Arch::Wormhole,
+        Arch::BLACKHOLE => luwen_core::Arch::Blackhole,
+        Arch::UNKNOWN => return std::ptr::null_mut(),
     };
 
     if let Ok(chip) = Chip::open(
@@ -297,12 +345,14 @@ pub extern "C" fn luwen_open(arch: Arch, glue: LuwenGlue) -> *mut Chip {
 #[no_mangle]
 /// # Safety
 ///
-/// local_chip must be a valid pointer
+/// local_chip must be a valid pointer, null is returned if the remote chip could not be opened.
 pub unsafe extern "C" fn luwen_open_remote(local_chip: *mut Chip, addr: EthAddr) -> *mut Chip {
     let local_chip = unsafe { &*local_chip };
 
-    if let Some(wh) = local_chip.as_wh() {
-        let remote = wh.open_remote(luwen_if::EthAddr::from(addr)).unwrap();
+    if let Some(Ok(remote)) = local_chip
+        .as_wh()
+        .map(|wh| wh.open_remote(luwen_if::EthAddr::from(addr)))
+    {
         Box::leak(Box::new(Chip::from(Box::new(remote) as Box<_>)))
     } else {
         std::ptr::null_mut()
@@ -312,40 +362,11 @@ pub unsafe extern "C" fn luwen_open_remote(local_chip: *mut Chip, addr: EthAddr)
 #[no_mangle]
 /// # Safety
 ///
-/// chip must be a valid pointer
+/// chip must be null or a chip which has not already been closed
 pub unsafe extern "C" fn luwen_close(chip: *mut Chip) {
-    unsafe {
-        let _ = Box::from_raw(chip);
-    }
-}
-
-#[repr(u8)]
-pub enum CResultTag {
-    Ok,
-    Err,
-}
-
-#[repr(C)]
-pub struct CResult {
-    pub tag: CResultTag,
-    pub ok: u32,
-    pub err: *const std::ffi::c_char,
-}
-
-impl CResult {
-    pub fn ok(value: u32) -> CResult {
-        CResult {
-            tag: CResultTag::Ok,
-            ok: value,
-            err: std::ptr::null(),
-        }
-    }
-
-    pub fn err(value: &str) -> CResult {
-        CResult {
-            tag: CResultTag::Err,
-            ok: 0,
-            err: std::ffi::CString::new(value).unwrap().into_raw(),
+    if !chip.is_null() {
+        unsafe {
+            let _ = Box::from_raw(chip);
         }
     }
 }
@@ -361,51 +382,29 @@ pub extern "C" fn chip_arc_msg(
     timeout: i32,
     return_3: *mut u32,
 ) -> CResult {
-    match chip.arc_msg(ArcMsgOptions {
-        msg: ArcMsg::from_values(msg, arg0, arg1),
-        wait_for_done,
-        timeout: std::time::Duration::from_secs(timeout as u64),
-        ..Default::default()
-    }) {
-        Ok(value) => match value {
-            luwen_if::ArcMsgOk::Ok { rc, arg } => {
-                if !return_3.is_null() {
-                    unsafe {
-                        *return_3 = arg;
+    error::ffi(|| {
+        match chip.arc_msg(ArcMsgOptions {
+            msg: ArcMsg::from_values(msg, arg0, arg1),
+            wait_for_done,
+            timeout: std::time::Duration::from_secs(timeout as u64),
+            ..Default::default()
+        }) {
+            Ok(value) => match value {
+                luwen_if::ArcMsgOk::Ok { rc, arg } => {
+                    if !return_3.is_null() {
+                        unsafe {
+                            *return_3 = arg;
+                        }
                     }
+                    Ok(rc)
                 }
-                CResult::ok(rc)
-            }
-            luwen_if::ArcMsgOk::OkNoWait => CResult::ok(0),
-        },
-        Err(err) => {
-            if let PlatformError::ArcMsgError(ArcMsgError::ProtocolError {
+                luwen_if::ArcMsgOk::OkNoWait => Ok(0),
+            },
+            Err(PlatformError::ArcMsgError(ArcMsgError::ProtocolError {
                 source: ArcMsgProtocolError::MsgNotRecognized(arc),
                 ..
-            }) = err
-            {
-                CResult::ok(arc as u32)
-            } else {
-                CResult::err(&err.to_string())
-            }
+            })) => Ok(arc as u32),
+            Err(err) => Err(err.into()),
         }
-    }
-}
-
-#[repr(C)]
-pub struct Telemetry {
-    board_id: u64,
-}
-
-impl From<luwen_if::chip::Telemetry> for Telemetry {
-    fn from(value: luwen_if::chip::Telemetry) -> Self {
-        Telemetry {
-            board_id: value.board_id,
-        }
-    }
-}
-
-#[no_mangle]
-pub extern "C" fn chip_telemetry(chip: &Chip) -> Telemetry {
-    chip.get_telemetry().unwrap().into()
+    })
 }
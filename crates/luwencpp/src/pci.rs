@@ -0,0 +1,252 @@
+// SPDX-FileCopyrightText: © 2024 Tenstorrent Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Entry points which use the pci backend from luwen-ref, so callers don't have to provide their
+//! own LuwenGlue.
+
+use std::ffi::{c_char, CString};
+
+use luwen_if::{
+    chip::{Chip, ComponentStatusInfo, WaitStatus},
+    ChipDetectOptions, UninitChip,
+};
+
+use crate::error::{ffi, luwen_free_error, CResult, Error, ErrorKind};
+
+#[no_mangle]
+/// Open the chip at /dev/tenstorrent/{interface_id} without waiting for it to initialize.
+///
+/// # Safety
+///
+/// chip must be a valid pointer, on success it is set to a chip which must be released with
+/// luwen_close.
+pub unsafe extern "C" fn luwen_open_pci(interface_id: u32, chip: *mut *mut Chip) -> CResult {
+    ffi(|| {
+        if chip.is_null() {
+            return Err(Error::null("chip"));
+        }
+
+        let opened = luwen_ref::open(interface_id as usize)?;
+        unsafe {
+            *chip = Box::into_raw(Box::new(opened));
+        }
+
+        Ok(0)
+    })
+}
+
+#[repr(C)]
+pub struct DetectOptions {
+    /// The interfaces to detect chips on, if null all interfaces are used.
+    pub interfaces: *const u32,
+    pub interface_count: usize,
+    /// Skip remote chips.
+    pub local_only: bool,
+    /// Return chips that failed to initialize instead of failing the detection.
+    pub continue_on_failure: bool,
+    /// Don't initialize anything that requires talking over the NOC.
+    pub noc_safe: bool,
+}
+
+/// A summary of the init status of one component type on a chip.
+#[repr(C)]
+#[derive(Default)]
+pub struct ComponentStatus {
+    pub present: bool,
+    pub waiting: bool,
+    pub has_error: bool,
+    /// Number of instances of the component (i.e. DRAM channels or ethernet ports).
+    pub count: u32,
+    pub done: u32,
+    pub errors: u32,
+    pub timeouts: u32,
+}
+
+impl<P, E> From<&ComponentStatusInfo<P, E>> for ComponentStatus {
+    fn from(value: &ComponentStatusInfo<P, E>) -> Self {
+        let count = |f: fn(&WaitStatus<P, E>) -> bool| {
+            value.wait_status.iter().filter(|v| f(v)).count() as u32
+        };
+        ComponentStatus {
+            present: value.is_present(),
+            waiting: value.is_waiting(),
+            has_error: value.has_error(),
+            count: value.wait_status.len() as u32,
+            done: count(|v| {
+                matches!(
+                    v,
+                    WaitStatus::Done | WaitStatus::JustFinished | WaitStatus::NoCheck
+                )
+            }),
+            errors: count(|v| matches!(v, WaitStatus::Error(_))),
+            timeouts: count(|v| matches!(v, WaitStatus::Timeout(_))),
+        }
+    }
+}
+
+#[repr(C)]
+pub struct InitStatus {
+    /// False if the chip initialized without errors, the status below is then left empty.
+    pub partially_initialized: bool,
+    pub can_communicate: bool,
+    pub unknown_state: bool,
+    pub has_error: bool,
+    pub arc: ComponentStatus,
+    pub dram: ComponentStatus,
+    pub eth: ComponentStatus,
+    pub cpu: ComponentStatus,
+}
+
+impl From<Option<&luwen_if::chip::InitStatus>> for InitStatus {
+    fn from(value: Option<&luwen_if::chip::InitStatus>) -> Self {
+        match value {
+            Some(status) => InitStatus {
+                partially_initialized: true,
+                can_communicate: status.can_communicate(),
+                unknown_state: status.unknown_state,
+                has_error: status.has_error(),
+                arc: (&status.arc_status).into(),
+                dram: (&status.dram_status).into(),
+                eth: (&status.eth_status).into(),
+                cpu: (&status.cpu_status).into(),
+            },
+            None => InitStatus {
+                partially_initialized: false,
+                can_communicate: true,
+                unknown_state: false,
+                has_error: false,
+                arc: Default::default(),
+                dram: Default::default(),
+                eth: Default::default(),
+                cpu: Default::default(),
+            },
+        }
+    }
+}
+
+#[repr(C)]
+pub struct DetectedChip {
+    /// Owned by the caller, release with luwen_close.
+    pub chip: *mut Chip,
+    pub status: InitStatus,
+    /// A human readable description of the init status, null if the chip initialized without
+    /// errors. Released by luwen_free_detected.
+    pub status_message: *const c_char,
+}
+
+#[repr(C)]
+pub struct DetectedChips {
+    pub chips: *mut DetectedChip,
+    pub count: usize,
+}
+
+fn detect(options: &DetectOptions) -> Result<Vec<UninitChip>, Error> {
+    let all_devices = luwen_ref::PciDevice::scan();
+    let interfaces = if options.interfaces.is_null() {
+        all_devices
+    } else {
+        let interfaces =
+            unsafe { std::slice::from_raw_parts(options.interfaces, options.interface_count) };
+        let mut selected = Vec::with_capacity(interfaces.len());
+        for interface in interfaces.iter().map(|v| *v as usize) {
+            if !all_devices.contains(&interface) {
+                return Err(Error::new(
+                    ErrorKind::DeviceOpen,
+                    format!(
+                        "Could not open TT-PCI device: {interface}; expected one of {all_devices:?}"
+                    ),
+                ));
+            }
+            selected.push(interface);
+        }
+        selected
+    };
+
+    let options = ChipDetectOptions {
+        continue_on_failure: options.continue_on_failure,
+        local_only: options.local_only,
+        chip_filter: vec![],
+        noc_safe: options.noc_safe,
+        chip_selectors: vec![],
+    };
+    luwen_ref::detect_interfaces_silent(&interfaces, options).map_err(|err| {
+        match Error::from(err) {
+            // A chip failed to initialize and continue_on_failure was not set
+            err if err.kind == ErrorKind::Generic => Error {
+                kind: ErrorKind::InitFailed,
+                ..err
+            },
+            err => err,
+        }
+    })
+}
+
+#[no_mangle]
+/// Find all chips and wait for them to initialize.
+///
+/// # Safety
+///
+/// options and chips must be valid pointers, if options.interfaces is not null it must point to
+/// options.interface_count values. On success chips must be released with luwen_free_detected.
+pub unsafe extern "C" fn luwen_detect_chips(
+    options: *const DetectOptions,
+    chips: *mut DetectedChips,
+) -> CResult {
+    ffi(|| {
+        if options.is_null() {
+            return Err(Error::null("options"));
+        }
+        if chips.is_null() {
+            return Err(Error::null("chips"));
+        }
+
+        let detected: Box<[DetectedChip]> = detect(unsafe { &*options })?
+            .into_iter()
+            .map(|chip| {
+                let status = chip.status();
+                let status_message = status
+                    .filter(|status| status.has_error())
+                    .map(|status| {
+                        CString::new(status.to_string().replace('\0', " "))
+                            .unwrap_or_default()
+                            .into_raw() as *const c_char
+                    })
+                    .unwrap_or(std::ptr::null());
+                DetectedChip {
+                    status: status.into(),
+                    status_message,
+                    chip: Box::into_raw(Box::new(chip.upgrade())),
+                }
+            })
+            .collect();
+
+        let count = detected.len();
+        unsafe {
+            *chips = DetectedChips {
+                chips: Box::into_raw(detected) as *mut DetectedChip,
+                count,
+            };
+        }
+
+        Ok(count as u32)
+    })
+}
+
+#[no_mangle]
+/// Release the list returned by luwen_detect_chips, the chips themselves are owned by the caller
+/// and are not closed.
+///
+/// # Safety
+///
+/// chips must have been filled in by luwen_detect_chips and not already freed.
+pub unsafe extern "C" fn luwen_free_detected(chips: DetectedChips) {
+    if chips.chips.is_null() {
+        return;
+    }
+
+    let detected =
+        unsafe { Box::from_raw(std::ptr::slice_from_raw_parts_mut(chips.chips, chips.count)) };
+    for chip in detected.iter() {
+        unsafe { luwen_free_error(chip.status_message) };
+    }
+}
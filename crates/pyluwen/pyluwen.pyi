@@ -1,6 +1,31 @@
 # Generated content DO NOT EDIT
 @staticmethod
-def detect_chips():
+def detect_chips(interfaces=None, local_only=False, continue_on_failure=False, chip_filter=None, noc_safe=False, callback=None, chips=None):
+    """
+    """
+    pass
+
+@staticmethod
+def detect_chips_async(interfaces=None, local_only=False, continue_on_failure=False, chip_filter=None, noc_safe=False, callback=None, chips=None):
+    """
+    Awaitable version of `detect_chips` for use from asyncio code.
+    """
+    pass
+
+@staticmethod
+def detect_chips_fallible(interfaces=None, local_only=False, continue_on_failure=False, chip_filter=None, noc_safe=False, callback=None, chips=None):
+    """
+    Detect all chips without requiring them to finish initializing.
+    
+    `chips` limits the result to the chips matching at least one of the given selectors, these
+    stay the same across reboots unlike interface ids: "bdf:0000:41:00.0", "board:100014511abcd",
+    "board:100014511abcd/1" (the second chip on the board), "eth:0,0,1,0" (rack_x, rack_y,
+    shelf_x, shelf_y) or "if:3".
+    """
+    pass
+
+@staticmethod
+def pci_scan():
     """
     """
     pass
@@ -20,131 +45,260 @@ class AxiData:
 
     pass
 
-class DmaBuffer:
-    def get_physical_address(self):
+class ChipId:
+    """
+    Identifies a chip across reboots, see `detect_chips_fallible` for the selector syntax.
+    """
+    @property
+    def asic_location(self):
         """
+        The position of the chip on its board, only known for chips from `detect_chips_fallible`.
         """
         pass
 
-    def get_user_address(self):
+    @property
+    def board_id(self):
+        """
+        """
+        pass
+
+    @property
+    def eth_addr(self):
+        """
+        """
+        pass
+
+    @property
+    def interface_id(self):
+        """
+        """
+        pass
+
+    def matches(self, selector):
+        """
+        True if the chip is matched by the selector, i.e. "board:100014511abcd/1".
+        """
+        pass
+
+    @property
+    def pci_bdf(self):
         """
         """
         pass
 
     pass
 
-class PciChip:
-    def __init__(self, pci_interface=None):
+class ComponentError:
+    """
+    Details of why a single component instance is not initialized.
+    """
+    @property
+    def channel_status(self):
+        """
+        """
         pass
 
-    def arc_msg(self, msg, wait_for_done=True, use_second_mailbox=False, arg0=65535, arg1=65535, timeout=1.0):
+    @property
+    def kind(self):
         """
+        The kind of error, i.e. "fw_corrupted" or "waiting_for_init".
         """
         pass
 
-    def as_gs(self):
+    @property
+    def message(self):
         """
         """
         pass
 
-    def as_wh(self):
+    @property
+    def msg(self):
         """
+        The message ARC is busy with.
         """
         pass
 
-    def axi_read(self, addr, data):
+    @property
+    def post_code(self):
         """
         """
         pass
 
-    def axi_read32(self, addr):
+    @property
+    def reason(self):
         """
+        Why ARC is not ready when kind is "waiting_for_init".
         """
         pass
 
-    def axi_translate(self, addr):
+    @property
+    def required(self):
         """
         """
         pass
 
-    def axi_write(self, addr, data):
+    @property
+    def version(self):
         """
         """
         pass
 
-    def axi_write32(self, addr, data):
+    pass
+
+class ComponentStatus:
+    """
+    The init status of one component type on a chip (ARC, DRAM, ETH or CPU).
+    """
+    @property
+    def elapsed(self):
         """
+        How long we have been waiting for the component in seconds.
         """
         pass
 
-    def bar_size(self):
+    def has_error(self):
         """
         """
         pass
 
-    def board_id(self):
+    def is_present(self):
         """
         """
         pass
 
-    def device_id(self):
+    def is_waiting(self):
         """
         """
         pass
 
-    def get_pci_bdf(self):
+    @property
+    def items(self):
         """
+        The state of each instance of the component.
         """
         pass
 
-    def get_telemetry(self):
+    @property
+    def name(self):
         """
         """
         pass
 
-    def init(self):
+    @property
+    def timeout(self):
         """
+        How long to wait for the component to initialize in seconds.
         """
         pass
 
-    def noc_broadcast(self, noc_id, addr, data):
+    pass
+
+class DmaBuffer:
+    def get_physical_address(self):
         """
         """
         pass
 
-    def noc_broadcast32(self, noc_id, addr, data):
+    def get_user_address(self):
         """
         """
         pass
 
-    def noc_read(self, noc_id, x, y, addr, data):
+    pass
+
+class InitStatus:
+    """
+    The init status of a chip.
+    """
+    @property
+    def arc(self):
         """
         """
         pass
 
-    def noc_read32(self, noc_id, x, y, addr):
+    def can_communicate(self):
         """
         """
         pass
 
-    def noc_write(self, noc_id, x, y, addr, data):
+    @property
+    def comms_error(self):
         """
+        None if we can communicate with the chip, otherwise the reason we cannot.
         """
         pass
 
-    def noc_write32(self, noc_id, x, y, addr, data):
+    def components(self):
+        """
+        All component statuses in the order they are initialized.
+        """
+        pass
+
+    @property
+    def cpu(self):
+        """
+        """
+        pass
+
+    @property
+    def dram(self):
+        """
+        """
+        pass
+
+    @property
+    def eth(self):
+        """
+        """
+        pass
+
+    def has_error(self):
+        """
+        """
+        pass
+
+    def init_complete(self):
+        """
+        """
+        pass
+
+    def is_waiting(self):
+        """
+        """
+        pass
+
+    @property
+    def noc_safe(self):
+        """
         """
+        pass
+
+    @property
+    def unknown_state(self):
+        """
+        True until we have been able to check the state of the chip.
         """
         pass
 
     pass
 
-class PciGrayskull:
+class PciBlackhole:
+    def allocate_dma_buffer(self, size):
+        """
+        """
+        pass
+
     def arc_msg(self, msg, wait_for_done=True, use_second_mailbox=False, arg0=65535, arg1=65535, timeout=1.0):
         """
         """
         pass
 
+    def assert_risc_reset(self, cores, riscs):
+        """
+        Hold the named RISC cores (brisc, ncrisc, trisc0-2 or erisc) of each core in soft reset.
+        """
+        pass
+
     def axi_read(self, addr, data):
         """
         """
@@ -170,106 +324,132 @@ class PciGrayskull:
         """
         pass
 
-    def noc_broadcast(self, noc_id, addr, data):
+    def clear_fan_speed(self):
         """
+        Hand the fan back to the firmware curve.
         """
         pass
 
-    def noc_broadcast32(self, noc_id, addr, data):
+    def clear_forced_aiclk(self):
         """
+        Hand AICLK back to the firmware.
         """
         pass
 
-    def noc_read(self, noc_id, x, y, addr, data):
+    def clear_throttle(self):
         """
+        Stop throttling and hand AICLK back to the firmware.
         """
         pass
 
-    def noc_read32(self, noc_id, x, y, addr):
+    def config_dma(self, dma_64_bit_addr, csm_pcie_ctrl_dma_request_offset, arc_misc_cntl_addr, msi, read_threshold, write_threshold):
         """
         """
         pass
 
-    def noc_write(self, noc_id, x, y, addr, data):
+    def deassert_risc_reset(self, cores, riscs):
         """
         """
         pass
 
-    def noc_write32(self, noc_id, x, y, addr, data):
+    def decode_boot_fs_table(self, tag_name):
         """
         """
         pass
 
-    def pci_axi_read32(self, addr):
+    def dma_transfer_turbo(self, addr, physical_dma_buffer, size, write):
         """
         """
         pass
 
-    def pci_axi_write32(self, addr, data):
+    def force_aiclk(self, mhz):
         """
+        Pin AICLK to `mhz`, overriding the throttlers. Raises PowerError if it is above the
+        board limit, the limit is unknown or the firmware rejects it.
         """
         pass
 
-    def pci_board_type(self):
+    def force_fan_speed(self, speed):
         """
+        Run the fan at `speed` percent until clear_fan_speed is called. Raises PowerError if
+        the speed is below the allowed minimum. Nothing clears the speed if the process exits
+        first, unless the chip was opened through luwen-broker.
         """
         pass
 
-    def set_default_tlb(self, index):
+    def force_throttle(self):
         """
+        Throttle the chip by pinning AICLK to the board minimum. Only supported on blackhole,
+        raises PowerError on other chips or if the firmware does not report a minimum.
         """
         pass
 
-    def setup_tlb(self, index, addr, x_start, y_start, x_end, y_end, noc_sel, mcast, ordering, linked):
+    def get_aiclk(self):
         """
+        The current AICLK in MHz, as reported by the firmware.
         """
         pass
 
-    pass
+    def get_fan_curve(self):
+        """
+        The firmware fan curve as a list of (temperature, speed percent) points. Fan control
+        is only supported on blackhole, other chips raise PowerError.
+        """
+        pass
 
-class PciWormhole:
-    def allocate_dma_buffer(self, size):
+    def get_fan_status(self):
         """
+        The fan speed, ASIC temperature and the thermal limit as a dict.
         """
         pass
 
-    def arc_msg(self, msg, wait_for_done=True, use_second_mailbox=False, arg0=65535, arg1=65535, timeout=1.0):
+    def get_harvesting(self):
         """
+        The harvested tensix rows or columns and the disabled DRAM channels, ethernet ports
+        and L2CPUs as a dict.
         """
         pass
 
-    def axi_read(self, addr, data):
+    def get_local_coord(self):
         """
         """
         pass
 
-    def axi_read32(self, addr):
+    def get_neighbouring_chips(self):
         """
         """
         pass
 
-    def axi_translate(self, addr):
+    def get_power_status(self):
         """
+        The current clock, power, current and temperature along with the board limits as a dict.
         """
         pass
 
-    def axi_write(self, addr, data):
+    def get_spirom_table_image_size(self, tag_name):
         """
         """
         pass
 
-    def axi_write32(self, addr, data):
+    def get_spirom_table_spi_addr(self, tag_name):
         """
         """
         pass
 
-    def config_dma(self, dma_64_bit_addr, csm_pcie_ctrl_dma_request_offset, arc_misc_cntl_addr, msi, read_threshold, write_threshold):
+    def get_telemetry(self):
         """
         """
         pass
 
-    def dma_transfer_turbo(self, addr, physical_dma_buffer, size, write):
+    def get_telemetry_async(self):
+        """
+        Awaitable version of `get_telemetry` for use from asyncio code.
+        """
+        pass
+
+    def load_elf(self, cores, elf, broadcast=False):
         """
+        Write the segments of an ELF to each core, returns its entry point.
         """
         pass
 
@@ -303,8 +483,39 @@ class PciWormhole:
         """
         pass
 
-    def open_remote(self, rack_x=None, rack_y=None, shelf_x=None, shelf_y=None):
+    def pci_board_type(self):
+        """
+        """
+        pass
+
+    def pci_interface_id(self):
+        """
+        """
+        pass
+
+    def read_mailbox(self, cores, addr, count):
+        """
+        Read `count` words at `addr` in the L1 of each core, i.e. a mailbox a program writes its results to.
+        """
+        pass
+
+    def risc_in_reset(self, x, y, risc):
+        """
+        """
+        pass
+
+    def run_elf(self, cores, risc, elf, broadcast=False):
         """
+        Load an ELF onto each core and start it on the named RISC core.
+        """
+        pass
+
+    def run_fan_curve(self, curve, interval=1.0, duration=None):
+        """
+        Drive the fan from a list of (temperature, speed percent) points for `duration`
+        seconds, or until interrupted. The fan is handed back to the firmware when this
+        returns, and early with a PowerError if the ASIC reaches its thermal limit. If the
+        process is killed while this runs the fan stays forced, there is no watchdog.
         """
         pass
 
@@ -313,19 +524,76 @@ class PciWormhole:
         """
         pass
 
+    def set_power_state(self, state):
+        """
+        Request the busy, short_idle or long_idle power state.
+        """
+        pass
+
+    def set_risc_entry(self, x, y, risc, entry):
+        """
+        """
+        pass
+
     def setup_tlb(self, index, addr, x_start, y_start, x_end, y_end, noc_sel, mcast, ordering, linked):
         """
         """
         pass
 
+    def spi_read(self, addr, data):
+        """
+        """
+        pass
+
+    def spi_read_async(self, addr, data):
+        """
+        Awaitable version of `spi_read`, `data` must not be resized until it completes.
+        """
+        pass
+
+    def spi_write(self, addr, data):
+        """
+        """
+        pass
+
+    def spi_write_async(self, addr, data):
+        """
+        Awaitable version of `spi_write`.
+        """
+        pass
+
     pass
 
-class RemoteWormhole:
+class PciChip:
+    def __init__(self, pci_interface=None):
+        pass
+
     def arc_msg(self, msg, wait_for_done=True, use_second_mailbox=False, arg0=65535, arg1=65535, timeout=1.0):
         """
         """
         pass
 
+    def as_bh(self):
+        """
+        """
+        pass
+
+    def as_gs(self):
+        """
+        """
+        pass
+
+    def as_wh(self):
+        """
+        """
+        pass
+
+    def assert_risc_reset(self, cores, riscs):
+        """
+        Hold the named RISC cores (brisc, ncrisc, trisc0-2 or erisc) of each core in soft reset.
+        """
+        pass
+
     def axi_read(self, addr, data):
         """
         """
@@ -351,34 +619,1602 @@ class RemoteWormhole:
         """
         pass
 
-    def noc_broadcast(self, noc_id, addr, data):
+    def bar_size(self):
         """
         """
         pass
 
-    def noc_broadcast32(self, noc_id, addr, data):
+    def board_id(self):
         """
         """
         pass
 
-    def noc_read(self, noc_id, x, y, addr, data):
+    def chip_id(self):
         """
+        Identify the chip, the asic location is not known for a chip on its own.
         """
         pass
 
-    def noc_read32(self, noc_id, x, y, addr):
+    def clear_fan_speed(self):
         """
+        Hand the fan back to the firmware curve.
         """
         pass
 
-    def noc_write(self, noc_id, x, y, addr, data):
+    def clear_forced_aiclk(self):
         """
+        Hand AICLK back to the firmware.
         """
         pass
 
-    def noc_write32(self, noc_id, x, y, addr, data):
+    def clear_throttle(self):
         """
+        Stop throttling and hand AICLK back to the firmware.
         """
         pass
 
-    pass
+    def connection_state(self):
+        """
+        "connected", or "lost" and "resetting" when accesses will raise ConnectionLost until the
+        chip is reopened.
+        """
+        pass
+
+    def deassert_risc_reset(self, cores, riscs):
+        """
+        """
+        pass
+
+    def device_id(self):
+        """
+        """
+        pass
+
+    def force_aiclk(self, mhz):
+        """
+        Pin AICLK to `mhz`, overriding the throttlers. Raises PowerError if it is above the
+        board limit, the limit is unknown or the firmware rejects it.
+        """
+        pass
+
+    def force_fan_speed(self, speed):
+        """
+        Run the fan at `speed` percent until clear_fan_speed is called. Raises PowerError if
+        the speed is below the allowed minimum. Nothing clears the speed if the process exits
+        first, unless the chip was opened through luwen-broker.
+        """
+        pass
+
+    def force_throttle(self):
+        """
+        Throttle the chip by pinning AICLK to the board minimum. Only supported on blackhole,
+        raises PowerError on other chips or if the firmware does not report a minimum.
+        """
+        pass
+
+    def get_aiclk(self):
+        """
+        The current AICLK in MHz, as reported by the firmware.
+        """
+        pass
+
+    def get_fan_curve(self):
+        """
+        The firmware fan curve as a list of (temperature, speed percent) points. Fan control
+        is only supported on blackhole, other chips raise PowerError.
+        """
+        pass
+
+    def get_fan_status(self):
+        """
+        The fan speed, ASIC temperature and the thermal limit as a dict.
+        """
+        pass
+
+    def get_harvesting(self):
+        """
+        The harvested tensix rows or columns and the disabled DRAM channels, ethernet ports
+        and L2CPUs as a dict.
+        """
+        pass
+
+    def get_neighbouring_chips(self):
+        """
+        """
+        pass
+
+    def get_pci_bdf(self):
+        """
+        """
+        pass
+
+    def get_pci_interface_id(self):
+        """
+        """
+        pass
+
+    def get_power_status(self):
+        """
+        The current clock, power, current and temperature along with the board limits as a dict.
+        """
+        pass
+
+    def get_telemetry(self):
+        """
+        """
+        pass
+
+    def get_telemetry_async(self):
+        """
+        Awaitable version of `get_telemetry` for use from asyncio code.
+        """
+        pass
+
+    def init(self, callback=None):
+        """
+        """
+        pass
+
+    def is_remote(self):
+        """
+        """
+        pass
+
+    def load_elf(self, cores, elf, broadcast=False):
+        """
+        Write the segments of an ELF to each core, returns its entry point.
+        """
+        pass
+
+    def noc_broadcast(self, noc_id, addr, data):
+        """
+        """
+        pass
+
+    def noc_broadcast32(self, noc_id, addr, data):
+        """
+        """
+        pass
+
+    def noc_read(self, noc_id, x, y, addr, data):
+        """
+        """
+        pass
+
+    def noc_read32(self, noc_id, x, y, addr):
+        """
+        """
+        pass
+
+    def noc_write(self, noc_id, x, y, addr, data):
+        """
+        """
+        pass
+
+    def noc_write32(self, noc_id, x, y, addr, data):
+        """
+        """
+        pass
+
+    def on_connection_lost(self, callback):
+        """
+        Call `callback` with the interface id when the connection to the chip is lost.
+        Exceptions raised by the callback are printed and otherwise ignored.
+        """
+        pass
+
+    def read_mailbox(self, cores, addr, count):
+        """
+        Read `count` words at `addr` in the L1 of each core, i.e. a mailbox a program writes its results to.
+        """
+        pass
+
+    def reopen(self):
+        """
+        Open the device again after the connection was lost or the chip was reset.
+        The chip should be initialized again with init once this returns.
+        """
+        pass
+
+    def risc_in_reset(self, x, y, risc):
+        """
+        """
+        pass
+
+    def run_elf(self, cores, risc, elf, broadcast=False):
+        """
+        Load an ELF onto each core and start it on the named RISC core.
+        """
+        pass
+
+    def run_fan_curve(self, curve, interval=1.0, duration=None):
+        """
+        Drive the fan from a list of (temperature, speed percent) points for `duration`
+        seconds, or until interrupted. The fan is handed back to the firmware when this
+        returns, and early with a PowerError if the ASIC reaches its thermal limit. If the
+        process is killed while this runs the fan stays forced, there is no watchdog.
+        """
+        pass
+
+    def set_power_state(self, state):
+        """
+        Request the busy, short_idle or long_idle power state.
+        """
+        pass
+
+    def set_risc_entry(self, x, y, risc, entry):
+        """
+        """
+        pass
+
+    pass
+
+class PciGrayskull:
+    def arc_msg(self, msg, wait_for_done=True, use_second_mailbox=False, arg0=65535, arg1=65535, timeout=1.0):
+        """
+        """
+        pass
+
+    def assert_risc_reset(self, cores, riscs):
+        """
+        Hold the named RISC cores (brisc, ncrisc, trisc0-2 or erisc) of each core in soft reset.
+        """
+        pass
+
+    def axi_read(self, addr, data):
+        """
+        """
+        pass
+
+    def axi_read32(self, addr):
+        """
+        """
+        pass
+
+    def axi_translate(self, addr):
+        """
+        """
+        pass
+
+    def axi_write(self, addr, data):
+        """
+        """
+        pass
+
+    def axi_write32(self, addr, data):
+        """
+        """
+        pass
+
+    def clear_fan_speed(self):
+        """
+        Hand the fan back to the firmware curve.
+        """
+        pass
+
+    def clear_forced_aiclk(self):
+        """
+        Hand AICLK back to the firmware.
+        """
+        pass
+
+    def clear_throttle(self):
+        """
+        Stop throttling and hand AICLK back to the firmware.
+        """
+        pass
+
+    def deassert_risc_reset(self, cores, riscs):
+        """
+        """
+        pass
+
+    def force_aiclk(self, mhz):
+        """
+        Pin AICLK to `mhz`, overriding the throttlers. Raises PowerError if it is above the
+        board limit, the limit is unknown or the firmware rejects it.
+        """
+        pass
+
+    def force_fan_speed(self, speed):
+        """
+        Run the fan at `speed` percent until clear_fan_speed is called. Raises PowerError if
+        the speed is below the allowed minimum. Nothing clears the speed if the process exits
+        first, unless the chip was opened through luwen-broker.
+        """
+        pass
+
+    def force_throttle(self):
+        """
+        Throttle the chip by pinning AICLK to the board minimum. Only supported on blackhole,
+        raises PowerError on other chips or if the firmware does not report a minimum.
+        """
+        pass
+
+    def get_aiclk(self):
+        """
+        The current AICLK in MHz, as reported by the firmware.
+        """
+        pass
+
+    def get_fan_curve(self):
+        """
+        The firmware fan curve as a list of (temperature, speed percent) points. Fan control
+        is only supported on blackhole, other chips raise PowerError.
+        """
+        pass
+
+    def get_fan_status(self):
+        """
+        The fan speed, ASIC temperature and the thermal limit as a dict.
+        """
+        pass
+
+    def get_harvesting(self):
+        """
+        The harvested tensix rows or columns and the disabled DRAM channels, ethernet ports
+        and L2CPUs as a dict.
+        """
+        pass
+
+    def get_neighbouring_chips(self):
+        """
+        """
+        pass
+
+    def get_power_status(self):
+        """
+        The current clock, power, current and temperature along with the board limits as a dict.
+        """
+        pass
+
+    def get_telemetry(self):
+        """
+        """
+        pass
+
+    def get_telemetry_async(self):
+        """
+        Awaitable version of `get_telemetry` for use from asyncio code.
+        """
+        pass
+
+    def load_elf(self, cores, elf, broadcast=False):
+        """
+        Write the segments of an ELF to each core, returns its entry point.
+        """
+        pass
+
+    def noc_broadcast(self, noc_id, addr, data):
+        """
+        """
+        pass
+
+    def noc_broadcast32(self, noc_id, addr, data):
+        """
+        """
+        pass
+
+    def noc_read(self, noc_id, x, y, addr, data):
+        """
+        """
+        pass
+
+    def noc_read32(self, noc_id, x, y, addr):
+        """
+        """
+        pass
+
+    def noc_write(self, noc_id, x, y, addr, data):
+        """
+        """
+        pass
+
+    def noc_write32(self, noc_id, x, y, addr, data):
+        """
+        """
+        pass
+
+    def pci_axi_read32(self, addr):
+        """
+        """
+        pass
+
+    def pci_axi_write32(self, addr, data):
+        """
+        """
+        pass
+
+    def pci_board_type(self):
+        """
+        """
+        pass
+
+    def pci_interface_id(self):
+        """
+        """
+        pass
+
+    def read_mailbox(self, cores, addr, count):
+        """
+        Read `count` words at `addr` in the L1 of each core, i.e. a mailbox a program writes its results to.
+        """
+        pass
+
+    def risc_in_reset(self, x, y, risc):
+        """
+        """
+        pass
+
+    def run_elf(self, cores, risc, elf, broadcast=False):
+        """
+        Load an ELF onto each core and start it on the named RISC core.
+        """
+        pass
+
+    def run_fan_curve(self, curve, interval=1.0, duration=None):
+        """
+        Drive the fan from a list of (temperature, speed percent) points for `duration`
+        seconds, or until interrupted. The fan is handed back to the firmware when this
+        returns, and early with a PowerError if the ASIC reaches its thermal limit. If the
+        process is killed while this runs the fan stays forced, there is no watchdog.
+        """
+        pass
+
+    def set_default_tlb(self, index):
+        """
+        """
+        pass
+
+    def set_power_state(self, state):
+        """
+        Request the busy, short_idle or long_idle power state.
+        """
+        pass
+
+    def set_risc_entry(self, x, y, risc, entry):
+        """
+        """
+        pass
+
+    def setup_tlb(self, index, addr, x_start, y_start, x_end, y_end, noc_sel, mcast, ordering, linked):
+        """
+        """
+        pass
+
+    def spi_read(self, addr, data):
+        """
+        """
+        pass
+
+    def spi_read_async(self, addr, data):
+        """
+        Awaitable version of `spi_read`, `data` must not be resized until it completes.
+        """
+        pass
+
+    def spi_write(self, addr, data):
+        """
+        """
+        pass
+
+    def spi_write_async(self, addr, data):
+        """
+        Awaitable version of `spi_write`.
+        """
+        pass
+
+    pass
+
+class PciWormhole:
+    def allocate_dma_buffer(self, size):
+        """
+        """
+        pass
+
+    def arc_msg(self, msg, wait_for_done=True, use_second_mailbox=False, arg0=65535, arg1=65535, timeout=1.0):
+        """
+        """
+        pass
+
+    def assert_risc_reset(self, cores, riscs):
+        """
+        Hold the named RISC cores (brisc, ncrisc, trisc0-2 or erisc) of each core in soft reset.
+        """
+        pass
+
+    def axi_read(self, addr, data):
+        """
+        """
+        pass
+
+    def axi_read32(self, addr):
+        """
+        """
+        pass
+
+    def axi_translate(self, addr):
+        """
+        """
+        pass
+
+    def axi_write(self, addr, data):
+        """
+        """
+        pass
+
+    def axi_write32(self, addr, data):
+        """
+        """
+        pass
+
+    def clear_fan_speed(self):
+        """
+        Hand the fan back to the firmware curve.
+        """
+        pass
+
+    def clear_forced_aiclk(self):
+        """
+        Hand AICLK back to the firmware.
+        """
+        pass
+
+    def clear_throttle(self):
+        """
+        Stop throttling and hand AICLK back to the firmware.
+        """
+        pass
+
+    def config_dma(self, dma_64_bit_addr, csm_pcie_ctrl_dma_request_offset, arc_misc_cntl_addr, msi, read_threshold, write_threshold):
+        """
+        """
+        pass
+
+    def deassert_risc_reset(self, cores, riscs):
+        """
+        """
+        pass
+
+    def dma_transfer_turbo(self, addr, physical_dma_buffer, size, write):
+        """
+        """
+        pass
+
+    def eth_port_status(self, heartbeat_interval=None):
+        """
+        The link state and firmware health of every ethernet port as a list of
+        dicts. With a `heartbeat_interval` (in seconds) the heartbeats are checked for progress.
+        """
+        pass
+
+    def force_aiclk(self, mhz):
+        """
+        Pin AICLK to `mhz`, overriding the throttlers. Raises PowerError if it is above the
+        board limit, the limit is unknown or the firmware rejects it.
+        """
+        pass
+
+    def force_fan_speed(self, speed):
+        """
+        Run the fan at `speed` percent until clear_fan_speed is called. Raises PowerError if
+        the speed is below the allowed minimum. Nothing clears the speed if the process exits
+        first, unless the chip was opened through luwen-broker.
+        """
+        pass
+
+    def force_throttle(self):
+        """
+        Throttle the chip by pinning AICLK to the board minimum. Only supported on blackhole,
+        raises PowerError on other chips or if the firmware does not report a minimum.
+        """
+        pass
+
+    def get_aiclk(self):
+        """
+        The current AICLK in MHz, as reported by the firmware.
+        """
+        pass
+
+    def get_fan_curve(self):
+        """
+        The firmware fan curve as a list of (temperature, speed percent) points. Fan control
+        is only supported on blackhole, other chips raise PowerError.
+        """
+        pass
+
+    def get_fan_status(self):
+        """
+        The fan speed, ASIC temperature and the thermal limit as a dict.
+        """
+        pass
+
+    def get_harvesting(self):
+        """
+        The harvested tensix rows or columns and the disabled DRAM channels, ethernet ports
+        and L2CPUs as a dict.
+        """
+        pass
+
+    def get_local_coord(self):
+        """
+        """
+        pass
+
+    def get_neighbouring_chips(self):
+        """
+        """
+        pass
+
+    def get_power_status(self):
+        """
+        The current clock, power, current and temperature along with the board limits as a dict.
+        """
+        pass
+
+    def get_telemetry(self):
+        """
+        """
+        pass
+
+    def get_telemetry_async(self):
+        """
+        Awaitable version of `get_telemetry` for use from asyncio code.
+        """
+        pass
+
+    def load_elf(self, cores, elf, broadcast=False):
+        """
+        Write the segments of an ELF to each core, returns its entry point.
+        """
+        pass
+
+    def noc_broadcast(self, noc_id, addr, data):
+        """
+        """
+        pass
+
+    def noc_broadcast32(self, noc_id, addr, data):
+        """
+        """
+        pass
+
+    def noc_read(self, noc_id, x, y, addr, data):
+        """
+        """
+        pass
+
+    def noc_read32(self, noc_id, x, y, addr):
+        """
+        """
+        pass
+
+    def noc_write(self, noc_id, x, y, addr, data):
+        """
+        """
+        pass
+
+    def noc_write32(self, noc_id, x, y, addr, data):
+        """
+        """
+        pass
+
+    def open_remote(self, rack_x=None, rack_y=None, shelf_x=None, shelf_y=None):
+        """
+        """
+        pass
+
+    def pci_board_type(self):
+        """
+        """
+        pass
+
+    def pci_interface_id(self):
+        """
+        """
+        pass
+
+    def read_mailbox(self, cores, addr, count):
+        """
+        Read `count` words at `addr` in the L1 of each core, i.e. a mailbox a program writes its results to.
+        """
+        pass
+
+    def risc_in_reset(self, x, y, risc):
+        """
+        """
+        pass
+
+    def run_elf(self, cores, risc, elf, broadcast=False):
+        """
+        Load an ELF onto each core and start it on the named RISC core.
+        """
+        pass
+
+    def run_fan_curve(self, curve, interval=1.0, duration=None):
+        """
+        Drive the fan from a list of (temperature, speed percent) points for `duration`
+        seconds, or until interrupted. The fan is handed back to the firmware when this
+        returns, and early with a PowerError if the ASIC reaches its thermal limit. If the
+        process is killed while this runs the fan stays forced, there is no watchdog.
+        """
+        pass
+
+    def set_default_tlb(self, index):
+        """
+        """
+        pass
+
+    def set_power_state(self, state):
+        """
+        Request the busy, short_idle or long_idle power state.
+        """
+        pass
+
+    def set_risc_entry(self, x, y, risc, entry):
+        """
+        """
+        pass
+
+    def setup_tlb(self, index, addr, x_start, y_start, x_end, y_end, noc_sel, mcast, ordering, linked):
+        """
+        """
+        pass
+
+    def spi_read(self, addr, data):
+        """
+        """
+        pass
+
+    def spi_read_async(self, addr, data):
+        """
+        Awaitable version of `spi_read`, `data` must not be resized until it completes.
+        """
+        pass
+
+    def spi_write(self, addr, data):
+        """
+        """
+        pass
+
+    def spi_write_async(self, addr, data):
+        """
+        Awaitable version of `spi_write`.
+        """
+        pass
+
+    pass
+
+class RemoteWormhole:
+    def arc_msg(self, msg, wait_for_done=True, use_second_mailbox=False, arg0=65535, arg1=65535, timeout=1.0):
+        """
+        """
+        pass
+
+    def assert_risc_reset(self, cores, riscs):
+        """
+        Hold the named RISC cores (brisc, ncrisc, trisc0-2 or erisc) of each core in soft reset.
+        """
+        pass
+
+    def axi_read(self, addr, data):
+        """
+        """
+        pass
+
+    def axi_read32(self, addr):
+        """
+        """
+        pass
+
+    def axi_translate(self, addr):
+        """
+        """
+        pass
+
+    def axi_write(self, addr, data):
+        """
+        """
+        pass
+
+    def axi_write32(self, addr, data):
+        """
+        """
+        pass
+
+    def clear_fan_speed(self):
+        """
+        Hand the fan back to the firmware curve.
+        """
+        pass
+
+    def clear_forced_aiclk(self):
+        """
+        Hand AICLK back to the firmware.
+        """
+        pass
+
+    def clear_throttle(self):
+        """
+        Stop throttling and hand AICLK back to the firmware.
+        """
+        pass
+
+    def deassert_risc_reset(self, cores, riscs):
+        """
+        """
+        pass
+
+    def force_aiclk(self, mhz):
+        """
+        Pin AICLK to `mhz`, overriding the throttlers. Raises PowerError if it is above the
+        board limit, the limit is unknown or the firmware rejects it.
+        """
+        pass
+
+    def force_fan_speed(self, speed):
+        """
+        Run the fan at `speed` percent until clear_fan_speed is called. Raises PowerError if
+        the speed is below the allowed minimum. Nothing clears the speed if the process exits
+        first, unless the chip was opened through luwen-broker.
+        """
+        pass
+
+    def force_throttle(self):
+        """
+        Throttle the chip by pinning AICLK to the board minimum. Only supported on blackhole,
+        raises PowerError on other chips or if the firmware does not report a minimum.
+        """
+        pass
+
+    def get_aiclk(self):
+        """
+        The current AICLK in MHz, as reported by the firmware.
+        """
+        pass
+
+    def get_fan_curve(self):
+        """
+        The firmware fan curve as a list of (temperature, speed percent) points. Fan control
+        is only supported on blackhole, other chips raise PowerError.
+        """
+        pass
+
+    def get_fan_status(self):
+        """
+        The fan speed, ASIC temperature and the thermal limit as a dict.
+        """
+        pass
+
+    def get_harvesting(self):
+        """
+        The harvested tensix rows or columns and the disabled DRAM channels, ethernet ports
+        and L2CPUs as a dict.
+        """
+        pass
+
+    def get_neighbouring_chips(self):
+        """
+        """
+        pass
+
+    def get_power_status(self):
+        """
+        The current clock, power, current and temperature along with the board limits as a dict.
+        """
+        pass
+
+    def get_telemetry(self):
+        """
+        """
+        pass
+
+    def get_telemetry_async(self):
+        """
+        Awaitable version of `get_telemetry` for use from asyncio code.
+        """
+        pass
+
+    def load_elf(self, cores, elf, broadcast=False):
+        """
+        Write the segments of an ELF to each core, returns its entry point.
+        """
+        pass
+
+    def noc_broadcast(self, noc_id, addr, data):
+        """
+        """
+        pass
+
+    def noc_broadcast32(self, noc_id, addr, data):
+        """
+        """
+        pass
+
+    def noc_read(self, noc_id, x, y, addr, data):
+        """
+        """
+        pass
+
+    def noc_read32(self, noc_id, x, y, addr):
+        """
+        """
+        pass
+
+    def noc_write(self, noc_id, x, y, addr, data):
+        """
+        """
+        pass
+
+    def noc_write32(self, noc_id, x, y, addr, data):
+        """
+        """
+        pass
+
+    def read_mailbox(self, cores, addr, count):
+        """
+        Read `count` words at `addr` in the L1 of each core, i.e. a mailbox a program writes its results to.
+        """
+        pass
+
+    def risc_in_reset(self, x, y, risc):
+        """
+        """
+        pass
+
+    def run_elf(self, cores, risc, elf, broadcast=False):
+        """
+        Load an ELF onto each core and start it on the named RISC core.
+        """
+        pass
+
+    def run_fan_curve(self, curve, interval=1.0, duration=None):
+        """
+        Drive the fan from a list of (temperature, speed percent) points for `duration`
+        seconds, or until interrupted. The fan is handed back to the firmware when this
+        returns, and early with a PowerError if the ASIC reaches its thermal limit. If the
+        process is killed while this runs the fan stays forced, there is no watchdog.
+        """
+        pass
+
+    def set_power_state(self, state):
+        """
+        Request the busy, short_idle or long_idle power state.
+        """
+        pass
+
+    def set_risc_entry(self, x, y, risc, entry):
+        """
+        """
+        pass
+
+    def spi_read(self, addr, data):
+        """
+        """
+        pass
+
+    def spi_read_async(self, addr, data):
+        """
+        Awaitable version of `spi_read`, `data` must not be resized until it completes.
+        """
+        pass
+
+    def spi_write(self, addr, data):
+        """
+        """
+        pass
+
+    def spi_write_async(self, addr, data):
+        """
+        Awaitable version of `spi_write`.
+        """
+        pass
+
+    pass
+
+class Telemetry:
+    @property
+    def aiclk(self):
+        """
+        """
+        pass
+
+    @property
+    def arc0_fw_version(self):
+        """
+        """
+        pass
+
+    @property
+    def arc0_health(self):
+        """
+        """
+        pass
+
+    @property
+    def arc1_fw_version(self):
+        """
+        """
+        pass
+
+    @property
+    def arc1_health(self):
+        """
+        """
+        pass
+
+    @property
+    def arc2_fw_version(self):
+        """
+        """
+        pass
+
+    @property
+    def arc2_health(self):
+        """
+        """
+        pass
+
+    @property
+    def arc3_fw_version(self):
+        """
+        """
+        pass
+
+    @property
+    def arc3_health(self):
+        """
+        """
+        pass
+
+    @property
+    def arcclk(self):
+        """
+        """
+        pass
+
+    @property
+    def asic_id(self):
+        """
+        """
+        pass
+
+    @property
+    def asic_idd(self):
+        """
+        """
+        pass
+
+    @property
+    def asic_ro(self):
+        """
+        """
+        pass
+
+    @property
+    def asic_temperature(self):
+        """
+        """
+        pass
+
+    @property
+    def asic_tmon0(self):
+        """
+        """
+        pass
+
+    @property
+    def asic_tmon1(self):
+        """
+        """
+        pass
+
+    @property
+    def axiclk(self):
+        """
+        """
+        pass
+
+    @property
+    def board_id(self):
+        """
+        """
+        pass
+
+    @property
+    def board_id_high(self):
+        """
+        """
+        pass
+
+    @property
+    def board_id_low(self):
+        """
+        """
+        pass
+
+    @property
+    def board_temperature(self):
+        """
+        """
+        pass
+
+    @property
+    def boot_date(self):
+        """
+        """
+        pass
+
+    @property
+    def ddr_speed(self):
+        """
+        """
+        pass
+
+    @property
+    def ddr_status(self):
+        """
+        """
+        pass
+
+    @property
+    def device_id(self):
+        """
+        """
+        pass
+
+    @property
+    def entry_count(self):
+        """
+        """
+        pass
+
+    @property
+    def enum_version(self):
+        """
+        """
+        pass
+
+    @property
+    def eth_debug_status0(self):
+        """
+        """
+        pass
+
+    @property
+    def eth_debug_status1(self):
+        """
+        """
+        pass
+
+    @property
+    def eth_fw_version(self):
+        """
+        """
+        pass
+
+    @property
+    def eth_status0(self):
+        """
+        """
+        pass
+
+    @property
+    def eth_status1(self):
+        """
+        """
+        pass
+
+    @property
+    def fan_speed(self):
+        """
+        """
+        pass
+
+    @property
+    def faults(self):
+        """
+        """
+        pass
+
+    @property
+    def fw_bundle_version(self):
+        """
+        """
+        pass
+
+    @property
+    def gddr_train_temp0(self):
+        """
+        """
+        pass
+
+    @property
+    def gddr_train_temp1(self):
+        """
+        """
+        pass
+
+    @property
+    def l2cpuclk0(self):
+        """
+        """
+        pass
+
+    @property
+    def l2cpuclk1(self):
+        """
+        """
+        pass
+
+    @property
+    def l2cpuclk2(self):
+        """
+        """
+        pass
+
+    @property
+    def l2cpuclk3(self):
+        """
+        """
+        pass
+
+    @property
+    def m3_app_fw_version(self):
+        """
+        """
+        pass
+
+    @property
+    def m3_bl_fw_version(self):
+        """
+        """
+        pass
+
+    @property
+    def mvddq_power(self):
+        """
+        """
+        pass
+
+    @property
+    def pcie_status(self):
+        """
+        """
+        pass
+
+    @property
+    def rt_seconds(self):
+        """
+        """
+        pass
+
+    @property
+    def spibootrom_fw_version(self):
+        """
+        """
+        pass
+
+    @property
+    def tdc(self):
+        """
+        """
+        pass
+
+    @property
+    def tdp(self):
+        """
+        """
+        pass
+
+    @property
+    def thm_limits(self):
+        """
+        """
+        pass
+
+    @property
+    def throttler(self):
+        """
+        """
+        pass
+
+    @property
+    def timer_heartbeat(self):
+        """
+        """
+        pass
+
+    @property
+    def tt_flash_version(self):
+        """
+        """
+        pass
+
+    @property
+    def vcore(self):
+        """
+        """
+        pass
+
+    @property
+    def vdd_limits(self):
+        """
+        """
+        pass
+
+    @property
+    def vreg_temperature(self):
+        """
+        """
+        pass
+
+    @property
+    def wh_fw_date(self):
+        """
+        """
+        pass
+
+    pass
+
+class UninitPciChip:
+    def arc_alive(self):
+        """
+        """
+        pass
+
+    def chip_id(self):
+        """
+        The identity of the chip, read during detection.
+        """
+        pass
+
+    def cpu_safe(self):
+        """
+        """
+        pass
+
+    def dram_safe(self):
+        """
+        """
+        pass
+
+    def eth_safe(self):
+        """
+        """
+        pass
+
+    def force_upgrade(self):
+        """
+        """
+        pass
+
+    def have_comms(self):
+        """
+        """
+        pass
+
+    def init(self):
+        """
+        """
+        pass
+
+    def init_errors(self):
+        """
+        The errors recorded for each component during init, as exception objects which can be
+        inspected or raised.
+        """
+        pass
+
+    def status(self):
+        """
+        The status recorded when the chip was initialized.
+        """
+        pass
+
+    pass
+
+class WaitStatus:
+    """
+    The state of a single instance of a component, i.e. one DRAM channel or ethernet port.
+    """
+    @property
+    def error(self):
+        """
+        """
+        pass
+
+    def is_done(self):
+        """
+        """
+        pass
+
+    @property
+    def message(self):
+        """
+        What is being waited on, or the error message.
+        """
+        pass
+
+    @property
+    def state(self):
+        """
+        One of "not_present", "waiting", "just_finished", "done", "no_check", "timeout",
+        "not_initialized" or "error".
+        """
+        pass
+
+    @property
+    def timeout(self):
+        """
+        The timeout in seconds when state is "timeout".
+        """
+        pass
+
+    pass
+
+class LuwenError(Exception):
+    """
+    Base class of all luwen errors.
+    
+    `interface` is the pci interface id of the chip that raised it, or None.
+    Errors raised by a chip access also set the `eth_addr` ([rack_x, rack_y, shelf_x, shelf_y]),
+    `arch` and `board_id` of the chip when known, and the `operation`, `noc_id`, `x`, `y`, `address`
+    and `length` of the access.
+    """
+    pass
+
+class ArcError(LuwenError):
+    """
+    Sending a message to ARC failed.
+    
+    `msg` and `timeout` are the message that was sent, `mailbox` or `code` are set when the
+    mailbox or the returned error code was invalid.
+    """
+    pass
+
+class AxiError(LuwenError):
+    """
+    An AXI register access failed.
+    
+    `address`, or the register `key` and the `path` it failed to resolve.
+    """
+    pass
+
+class ChipInitError(LuwenError):
+    """
+    A chip component failed to initialize.
+    
+    `component` and the `index` of the failing instance.
+    """
+    pass
+
+class ConnectionLost(LuwenError):
+    """
+    The connection to the chip was lost, it must be reopened before it can be used again.
+    
+    `state` is the connection state, "lost" or "resetting".
+    """
+    pass
+
+class EthernetTrainingError(LuwenError):
+    """
+    Ethernet training has not completed.
+    
+    `ports` is the training state of each port.
+    """
+    pass
+
+class MessageError(LuwenError):
+    """
+    The blackhole message queue failed.
+    
+    `index` and `queue_count` are set when the queue index was out of range.
+    """
+    pass
+
+class PowerError(LuwenError):
+    """
+    A clock, power state or fan request could not be made.
+    
+    `requested` and `max` (or `min` for the fan speed) are set when the request is out of range,
+    `request` and `rc` when the firmware rejected it and `temperature` and `limit` when the ASIC
+    overheated.
+    """
+    pass
+
+class UnsupportedFwVersion(LuwenError):
+    """
+    The firmware on the chip is too old.
+    
+    `version` and the `required` firmware version.
+    """
+    pass
+
+class WaitTimeout(LuwenError):
+    """
+    Waiting on the chip timed out.
+    
+    `what` was waited on for `elapsed` seconds over `polls` polls, the `last` observed state and
+    whether the wait was `cancelled`.
+    """
+    pass
+
+class WrongChipArch(LuwenError):
+    """
+    The chip has a different architecture than expected.
+    
+    `actual` and the `expected` architectures.
+    """
+    pass
+
+class ArcAsleep(ArcError):
+    """
+    ARC is asleep.
+    """
+    pass
+
+class ArcInitError(ChipInitError):
+    """
+    ARC failed to initialize.
+    
+    `version` and `required` are set when the firmware is too old.
+    """
+    pass
+
+class ArcMsgNotRecognized(ArcError):
+    """
+    ARC did not recognize the message.
+    
+    `msg` is the unrecognized message.
+    """
+    pass
+
+class ArcNotReady(ArcError):
+    """
+    ARC is not in a state to handle messages.
+    
+    `reason` it is not ready.
+    """
+    pass
+
+class ArcTimeout(ArcError):
+    """
+    ARC did not respond to a message in time.
+    """
+    pass
+
+class CommsError(ChipInitError):
+    """
+    Could not communicate with the chip.
+    """
+    pass
+
+class DramInitError(ChipInitError):
+    """
+    A DRAM channel failed to train.
+    
+    `channel_status` is the reported training state.
+    """
+    pass
+
+class EthernetInitError(ChipInitError):
+    """
+    An ethernet core failed to initialize.
+    """
+    pass
+
+class InitTimeout(ChipInitError):
+    """
+    A chip component did not initialize in time.
+    
+    `timeout` in seconds.
+    """
+    pass
+
+class MessageTimeout(MessageError):
+    """
+    The blackhole message queue did not respond in time.
+    """
+    pass
+
@@ -0,0 +1,45 @@
+import asyncio
+import time
+from concurrent.futures import ThreadPoolExecutor
+
+from pyluwen import detect_chips, detect_chips_async
+
+
+def read_telemetry(chip, count=100):
+    for _ in range(count):
+        chip.get_telemetry()
+
+
+def threaded(devices):
+    start = time.time()
+    for dev in devices:
+        read_telemetry(dev)
+    serial = time.time() - start
+
+    start = time.time()
+    with ThreadPoolExecutor(max_workers=len(devices)) as pool:
+        list(pool.map(read_telemetry, devices))
+    parallel = time.time() - start
+
+    print(f"telemetry on {len(devices)} chips: serial {serial:.2f}s, threaded {parallel:.2f}s")
+
+
+async def with_asyncio():
+    devices = await detect_chips_async()
+    telemetry = await asyncio.gather(*(dev.get_telemetry_async() for dev in devices))
+    for dev, telem in zip(devices, telemetry):
+        print(f"{dev.get_pci_interface_id()}: board id {telem.board_id:x}")
+
+
+def main():
+    devices = detect_chips(local_only=True)
+    if len(devices) == 0:
+        print("No chips found")
+        return
+
+    threaded(devices)
+    asyncio.run(with_asyncio())
+
+
+if __name__ == "__main__":
+    main()
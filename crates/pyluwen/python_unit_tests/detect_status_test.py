@@ -0,0 +1,39 @@
+from pyluwen import detect_chips_fallible
+
+
+def show_status(state):
+    if state.new_chip():
+        print("Found chip")
+        return
+
+    status = state.status()
+    if status is None:
+        return
+
+    for component in status.components():
+        if not component.is_present():
+            continue
+        print(f"  {component.name} ({component.elapsed:.1f}/{component.timeout:.0f}s)")
+        for index, item in enumerate(component.items):
+            line = f"    [{index}] {item.state}"
+            if item.message is not None:
+                line += f": {item.message}"
+            if item.error is not None and item.error.post_code is not None:
+                line += f" (post code {item.error.post_code:08x})"
+            print(line)
+
+    if state.init_completed():
+        print("Chip initialization " + ("failed" if status.has_error() else "complete"))
+
+
+def main():
+    chips = detect_chips_fallible(continue_on_failure=True, callback=show_status)
+    for chip in chips:
+        status = chip.status()
+        if status is not None and status.has_error():
+            for err in chip.init_errors():
+                print(f"{type(err).__name__}: {err}")
+
+
+if __name__ == "__main__":
+    main()
@@ -0,0 +1,616 @@
+// SPDX-FileCopyrightText: © 2024 Tenstorrent Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Maps the luwen errors onto a python exception hierarchy so that callers can catch specific
+//! failures instead of matching on the message.
+
+use luwen_if::chip::{
+    ArcInitError as RsArcInitError, AxiError as RsAxiError, ChipImpl, CommsStatus,
+    ComponentStatusInfo, InitError, InitStatus, MessageError as RsMessageError,
+    PowerError as RsPowerError, WaitError, WaitStatus,
+};
+use luwen_if::error::{AccessError, ChipIdentity, Operation, OperationKind, PlatformError};
+use luwen_if::{ArcMsgError, ArcMsgProtocolError, ConnectionState};
+use luwen_ref::error::LuwenError as RefError;
+use pyo3::create_exception;
+use pyo3::exceptions::PyException;
+use pyo3::prelude::*;
+
+create_exception!(
+    pyluwen,
+    LuwenError,
+    PyException,
+    "Base class of all luwen errors.\n\n`interface` is the pci interface id of the chip that raised it, or None.\nErrors raised by a chip access also set the `eth_addr` ([rack_x, rack_y, shelf_x, shelf_y]),\n`arch` and `board_id` of the chip when known, and the `operation`, `noc_id`, `x`, `y`, `address`\nand `length` of the access."
+);
+
+create_exception!(
+    pyluwen,
+    ConnectionLost,
+    LuwenError,
+    "The connection to the chip was lost, it must be reopened before it can be used again.\n\n`state` is the connection state, \"lost\" or \"resetting\"."
+);
+
+create_exception!(
+    pyluwen,
+    ArcError,
+    LuwenError,
+    "Sending a message to ARC failed.\n\n`msg` and `timeout` are the message that was sent, `mailbox` or `code` are set when the\nmailbox or the returned error code was invalid."
+);
+create_exception!(
+    pyluwen,
+    ArcTimeout,
+    ArcError,
+    "ARC did not respond to a message in time."
+);
+create_exception!(
+    pyluwen,
+    ArcNotReady,
+    ArcError,
+    "ARC is not in a state to handle messages.\n\n`reason` it is not ready."
+);
+create_exception!(pyluwen, ArcAsleep, ArcError, "ARC is asleep.");
+create_exception!(
+    pyluwen,
+    ArcMsgNotRecognized,
+    ArcError,
+    "ARC did not recognize the message.\n\n`msg` is the unrecognized message."
+);
+
+create_exception!(
+    pyluwen,
+    AxiError,
+    LuwenError,
+    "An AXI register access failed.\n\n`address`, or the register `key` and the `path` it failed to resolve."
+);
+
+create_exception!(
+    pyluwen,
+    MessageError,
+    LuwenError,
+    "The blackhole message queue failed.\n\n`index` and `queue_count` are set when the queue index was out of range."
+);
+create_exception!(
+    pyluwen,
+    MessageTimeout,
+    MessageError,
+    "The blackhole message queue did not respond in time."
+);
+
+create_exception!(
+    pyluwen,
+    WaitTimeout,
+    LuwenError,
+    "Waiting on the chip timed out.\n\n`what` was waited on for `elapsed` seconds over `polls` polls, the `last` observed state and\nwhether the wait was `cancelled`."
+);
+create_exception!(
+    pyluwen,
+    EthernetTrainingError,
+    LuwenError,
+    "Ethernet training has not completed.\n\n`ports` is the training state of each port."
+);
+create_exception!(
+    pyluwen,
+    WrongChipArch,
+    LuwenError,
+    "The chip has a different architecture than expected.\n\n`actual` and the `expected` architectures."
+);
+create_exception!(
+    pyluwen,
+    UnsupportedFwVersion,
+    LuwenError,
+    "The firmware on the chip is too old.\n\n`version` and the `required` firmware version."
+);
+
+create_exception!(
+    pyluwen,
+    PowerError,
+    LuwenError,
+    "A clock, power state or fan request could not be made.\n\n`requested` and `max` (or `min` for the fan speed) are set when the request is out of range,\n`request` and `rc` when the firmware rejected it and `temperature` and `limit` when the ASIC\noverheated."
+);
+
+create_exception!(
+    pyluwen,
+    ChipInitError,
+    LuwenError,
+    "A chip component failed to initialize.\n\n`component` and the `index` of the failing instance."
+);
+create_exception!(
+    pyluwen,
+    CommsError,
+    ChipInitError,
+    "Could not communicate with the chip."
+);
+create_exception!(
+    pyluwen,
+    ArcInitError,
+    ChipInitError,
+    "ARC failed to initialize.\n\n`version` and `required` are set when the firmware is too old."
+);
+create_exception!(
+    pyluwen,
+    EthernetInitError,
+    ChipInitError,
+    "An ethernet core failed to initialize."
+);
+create_exception!(
+    pyluwen,
+    DramInitError,
+    ChipInitError,
+    "A DRAM channel failed to train.\n\n`channel_status` is the reported training state."
+);
+create_exception!(
+    pyluwen,
+    InitTimeout,
+    ChipInitError,
+    "A chip component did not initialize in time.\n\n`timeout` in seconds."
+);
+
+pub fn register(py: Python, m: &PyModule) -> PyResult<()> {
+    m.add("LuwenError", py.get_type::<LuwenError>())?;
+    m.add("ConnectionLost", py.get_type::<ConnectionLost>())?;
+    m.add("ArcError", py.get_type::<ArcError>())?;
+    m.add("ArcTimeout", py.get_type::<ArcTimeout>())?;
+    m.add("ArcNotReady", py.get_type::<ArcNotReady>())?;
+    m.add("ArcAsleep", py.get_type::<ArcAsleep>())?;
+    m.add("ArcMsgNotRecognized", py.get_type::<ArcMsgNotRecognized>())?;
+    m.add("AxiError", py.get_type::<AxiError>())?;
+    m.add("MessageError", py.get_type::<MessageError>())?;
+    m.add("MessageTimeout", py.get_type::<MessageTimeout>())?;
+    m.add("WaitTimeout", py.get_type::<WaitTimeout>())?;
+    m.add(
+        "EthernetTrainingError",
+        py.get_type::<EthernetTrainingError>(),
+    )?;
+    m.add("WrongChipArch", py.get_type::<WrongChipArch>())?;
+    m.add(
+        "UnsupportedFwVersion",
+        py.get_type::<UnsupportedFwVersion>(),
+    )?;
+    m.add("PowerError", py.get_type::<PowerError>())?;
+    m.add("ChipInitError", py.get_type::<ChipInitError>())?;
+    m.add("CommsError", py.get_type::<CommsError>())?;
+    m.add("ArcInitError", py.get_type::<ArcInitError>())?;
+    m.add("EthernetInitError", py.get_type::<EthernetInitError>())?;
+    m.add("DramInitError", py.get_type::<DramInitError>())?;
+    m.add("InitTimeout", py.get_type::<InitTimeout>())?;
+
+    Ok(())
+}
+
+/// A value attached to an exception as an attribute.
+pub enum Attr {
+    None,
+    Bool(bool),
+    Int(u64),
+    Float(f64),
+    Str(String),
+    Strs(Vec<String>),
+    Bools(Vec<bool>),
+    Ints(Vec<u64>),
+}
+
+impl IntoPy<PyObject> for Attr {
+    fn into_py(self, py: Python<'_>) -> PyObject {
+        match self {
+            Attr::None => py.None(),
+            Attr::Bool(value) => value.into_py(py),
+            Attr::Int(value) => value.into_py(py),
+            Attr::Float(value) => value.into_py(py),
+            Attr::Str(value) => value.into_py(py),
+            Attr::Strs(value) => value.into_py(py),
+            Attr::Bools(value) => value.into_py(py),
+            Attr::Ints(value) => value.into_py(py),
+        }
+    }
+}
+
+macro_rules! attr_from {
+    ($($ty:ty => $variant:ident),*) => {
+        $(impl From<$ty> for Attr {
+            fn from(value: $ty) -> Self {
+                Attr::$variant(value.into())
+            }
+        })*
+    };
+}
+
+attr_from!(bool => Bool, u8 => Int, u16 => Int, u32 => Int, u64 => Int, f64 => Float, String => Str, &str => Str);
+
+impl From<usize> for Attr {
+    fn from(value: usize) -> Self {
+        Attr::Int(value as u64)
+    }
+}
+
+impl<T: Into<Attr>> From<Option<T>> for Attr {
+    fn from(value: Option<T>) -> Self {
+        value.map(Into::into).unwrap_or(Attr::None)
+    }
+}
+
+/// A luwen error along with the python exception it will be raised as.
+///
+/// This doesn't need the GIL so it can be built while it is released, the exception object is
+/// only created once this is converted into a `PyErr`. Every exception has an `interface`
+/// attribute, the other attributes depend on the exception type.
+pub struct Error {
+    new: fn(String) -> PyErr,
+    message: String,
+    attrs: Vec<(&'static str, Attr)>,
+}
+
+impl Error {
+    pub fn new(new: fn(String) -> PyErr, message: impl ToString) -> Self {
+        Self {
+            new,
+            message: message.to_string(),
+            attrs: vec![("interface", Attr::None)],
+        }
+    }
+
+    pub fn attr(mut self, name: &'static str, value: impl Into<Attr>) -> Self {
+        let value = value.into();
+        if let Some(attr) = self.attrs.iter_mut().find(|(key, _)| *key == name) {
+            attr.1 = value;
+        } else {
+            self.attrs.push((name, value));
+        }
+        self
+    }
+
+    /// Record which pci interface the failing chip is on, remote chips don't have one.
+    pub fn chip(self, chip: &dyn ChipImpl) -> Self {
+        match chip.get_device_info() {
+            Ok(Some(info)) => self.attr("interface", info.interface_id),
+            _ => self,
+        }
+    }
+
+    /// Record the chip and the access that failed.
+    fn access(self, chip: &ChipIdentity, operation: &Operation) -> Self {
+        let eth_addr = chip.eth_addr.map(|addr| {
+            Attr::Ints(vec![
+                addr.rack_x.into(),
+                addr.rack_y.into(),
+                addr.shelf_x.into(),
+                addr.shelf_y.into(),
+            ])
+        });
+        let kind = match operation.kind {
+            OperationKind::AxiRead => "axi_read",
+            OperationKind::AxiWrite => "axi_write",
+            OperationKind::NocRead => "noc_read",
+            OperationKind::NocWrite => "noc_write",
+            OperationKind::NocBroadcast => "noc_broadcast",
+        };
+        self.attr("interface", chip.interface_id)
+            .attr("eth_addr", eth_addr.unwrap_or(Attr::None))
+            .attr("arch", chip.arch.map(|arch| arch.to_string()))
+            .attr("board_id", chip.board_id)
+            .attr("operation", kind)
+            .attr("noc_id", operation.noc_id)
+            .attr("x", operation.x)
+            .attr("y", operation.y)
+            .attr("address", operation.addr)
+            .attr("length", operation.len)
+    }
+
+    pub fn context(mut self, context: &str) -> Self {
+        self.message = format!("{context}: {}", self.message);
+        self
+    }
+
+    fn wait(new: fn(String) -> PyErr, message: String, err: &WaitError) -> Self {
+        let (what, elapsed, polls, last, cancelled) = match err {
+            WaitError::Timeout {
+                what,
+                elapsed,
+                polls,
+                last,
+            } => (what, elapsed, polls, last, false),
+            WaitError::Cancelled {
+                what,
+                elapsed,
+                polls,
+                last,
+            } => (what, elapsed, polls, last, true),
+        };
+        Self::new(new, message)
+            .attr("what", what.as_str())
+            .attr("elapsed", elapsed.as_secs_f64())
+            .attr("polls", *polls)
+            .attr("last", last.as_str())
+            .attr("cancelled", cancelled)
+    }
+}
+
+impl From<Error> for PyErr {
+    fn from(err: Error) -> PyErr {
+        Python::with_gil(|py| {
+            let pyerr = (err.new)(err.message);
+            let value = pyerr.value(py);
+            for (name, attr) in err.attrs {
+                if let Err(err) = value.setattr(name, attr.into_py(py)) {
+                    return err;
+                }
+            }
+            pyerr
+        })
+    }
+}
+
+impl From<WaitError> for Error {
+    fn from(err: WaitError) -> Self {
+        Error::wait(WaitTimeout::new_err, err.to_string(), &err)
+    }
+}
+
+impl From<RsAxiError> for Error {
+    fn from(err: RsAxiError) -> Self {
+        let message = err.to_string();
+        match err {
+            RsAxiError::InvalidPath { key, path } | RsAxiError::InvalidArrayPath { key, path } => {
+                Error::new(AxiError::new_err, message)
+                    .attr("key", key)
+                    .attr("path", path)
+            }
+            RsAxiError::NoAxiData
+            | RsAxiError::ReadBufferTooSmall
+            | RsAxiError::WriteBufferMismatch => Error::new(AxiError::new_err, message),
+        }
+    }
+}
+
+impl From<ArcMsgError> for Error {
+    fn from(err: ArcMsgError) -> Self {
+        let message = err.to_string();
+        match err {
+            ArcMsgError::ProtocolError { source, .. } => match source {
+                ArcMsgProtocolError::Timeout(wait) => {
+                    Error::wait(ArcTimeout::new_err, message, &wait)
+                }
+                ArcMsgProtocolError::MsgNotRecognized(msg) => {
+                    Error::new(ArcMsgNotRecognized::new_err, message).attr("msg", msg)
+                }
+                ArcMsgProtocolError::ArcAsleep => Error::new(ArcAsleep::new_err, message),
+                ArcMsgProtocolError::InvalidMailbox(mailbox) => {
+                    Error::new(ArcError::new_err, message).attr("mailbox", mailbox)
+                }
+                ArcMsgProtocolError::UnknownErrorCode(code) => {
+                    Error::new(ArcError::new_err, message).attr("code", code)
+                }
+                ArcMsgProtocolError::FwIntFailed => Error::new(ArcError::new_err, message),
+            },
+            ArcMsgError::AxiError(err) => err.into(),
+        }
+    }
+}
+
+impl From<RsMessageError> for Error {
+    fn from(err: RsMessageError) -> Self {
+        let message = err.to_string();
+        match err {
+            RsMessageError::Timeout(wait) => Error::wait(MessageTimeout::new_err, message, &wait),
+            RsMessageError::QueueIndexOutOfRange { index, queue_count } => {
+                Error::new(MessageError::new_err, message)
+                    .attr("index", index)
+                    .attr("queue_count", queue_count)
+            }
+        }
+    }
+}
+
+impl From<RsPowerError> for Error {
+    fn from(err: RsPowerError) -> Self {
+        let message = err.to_string();
+        match err {
+            RsPowerError::AiclkOutOfRange { requested, max } => {
+                Error::new(PowerError::new_err, message)
+                    .attr("requested", requested)
+                    .attr("max", max)
+            }
+            RsPowerError::FanSpeedOutOfRange { requested, min } => {
+                Error::new(PowerError::new_err, message)
+                    .attr("requested", requested)
+                    .attr("min", min)
+            }
+            RsPowerError::OverTemperature { temperature, limit } => {
+                Error::new(PowerError::new_err, message)
+                    .attr("temperature", temperature)
+                    .attr("limit", limit)
+            }
+            RsPowerError::Unsupported { .. }
+            | RsPowerError::UnknownLimit(..)
+            | RsPowerError::InvalidFanCurve(..) => Error::new(PowerError::new_err, message),
+            RsPowerError::Rejected { request, rc } => Error::new(PowerError::new_err, message)
+                .attr("request", request)
+                .attr("rc", rc),
+        }
+    }
+}
+
+impl From<PlatformError> for Error {
+    fn from(err: PlatformError) -> Self {
+        let message = err.to_string();
+        match err {
+            PlatformError::WrongChipArch {
+                actual, expected, ..
+            } => Error::new(WrongChipArch::new_err, message)
+                .attr("actual", actual.to_string())
+                .attr("expected", Attr::Strs(vec![expected.to_string()])),
+            PlatformError::WrongChipArchs {
+                actual, expected, ..
+            } => Error::new(WrongChipArch::new_err, message)
+                .attr("actual", actual.to_string())
+                .attr(
+                    "expected",
+                    Attr::Strs(expected.iter().map(|arch| arch.to_string()).collect()),
+                ),
+            PlatformError::UnsupportedFwVersion { version, required } => {
+                Error::new(UnsupportedFwVersion::new_err, message)
+                    .attr("version", version)
+                    .attr("required", required)
+            }
+            PlatformError::ArcNotReady(reason, _) => {
+                Error::new(ArcNotReady::new_err, message).attr("reason", reason.to_string())
+            }
+            PlatformError::ArcMsgError(err) => err.into(),
+            PlatformError::MessageError(err) => err.into(),
+            PlatformError::PowerError(err) => err.into(),
+            PlatformError::EthernetTrainingNotComplete(ports) => {
+                Error::new(EthernetTrainingError::new_err, message)
+                    .attr("ports", Attr::Bools(ports))
+            }
+            PlatformError::AxiError(err) => err.into(),
+            PlatformError::WaitError(err) => (*err).into(),
+            PlatformError::AccessError(err) => (*err).into(),
+            PlatformError::GenericError(err, _) => {
+                let mut inner = Error::from(err);
+                inner.message = message;
+                inner
+            }
+            PlatformError::Generic(..) => Error::new(LuwenError::new_err, message),
+        }
+    }
+}
+
+impl From<AccessError> for Error {
+    fn from(err: AccessError) -> Self {
+        // Raise the exception of the underlying error, with the access context attached
+        let message = err.to_string();
+        let (chip, operation) = (err.chip().clone(), *err.operation());
+        let mut inner = Error::from(err.into_inner());
+        inner.message = message;
+        inner.access(&chip, &operation)
+    }
+}
+
+impl From<RefError> for Error {
+    fn from(err: RefError) -> Self {
+        let message = err.to_string();
+        match err {
+            RefError::PlatformError(err) => err.into(),
+            RefError::ConnectionLost { interface, state } => {
+                Error::new(ConnectionLost::new_err, message)
+                    .attr("interface", interface)
+                    .attr("state", connection_state(state))
+            }
+            RefError::DeviceMissing { interface, .. } => {
+                Error::new(LuwenError::new_err, message).attr("interface", interface)
+            }
+            _ => Error::new(LuwenError::new_err, message),
+        }
+    }
+}
+
+pub fn connection_state(state: ConnectionState) -> &'static str {
+    match state {
+        ConnectionState::Connected => "connected",
+        ConnectionState::Lost => "lost",
+        ConnectionState::Resetting => "resetting",
+    }
+}
+
+impl From<Box<dyn std::error::Error>> for Error {
+    fn from(err: Box<dyn std::error::Error>) -> Self {
+        match err.downcast::<PlatformError>() {
+            Ok(err) => (*err).into(),
+            Err(err) => match err.downcast::<AccessError>() {
+                Ok(err) => (*err).into(),
+                Err(err) => match err.downcast::<ArcMsgError>() {
+                    Ok(err) => (*err).into(),
+                    Err(err) => match err.downcast::<RefError>() {
+                        Ok(err) => (*err).into(),
+                        Err(err) => Error::new(LuwenError::new_err, err),
+                    },
+                },
+            },
+        }
+    }
+}
+
+impl From<String> for Error {
+    fn from(err: String) -> Self {
+        Error::new(LuwenError::new_err, err)
+    }
+}
+
+/// Convert the result of chip init, errors raised by the python callback are passed through.
+pub fn init_error(err: InitError<PyErr>) -> PyErr {
+    match err {
+        InitError::PlatformError(err) => Error::from(err).into(),
+        InitError::CallbackError(err) => err,
+    }
+}
+
+fn component_errors<P, E: std::fmt::Display>(
+    status: &ComponentStatusInfo<P, E>,
+    new: fn(String) -> PyErr,
+    error: impl Fn(Error, &E) -> Error,
+) -> Vec<Error> {
+    let mut errors = Vec::new();
+    for (index, wait_status) in status.wait_status.iter().enumerate() {
+        let err = match wait_status {
+            WaitStatus::Error(err) => {
+                let base = Error::new(new, format!("{} {index}: {err}", status.name));
+                error(base, err)
+            }
+            WaitStatus::Timeout(timeout) => Error::new(
+                InitTimeout::new_err,
+                format!(
+                    "{} {index} did not initialize within {timeout:?}",
+                    status.name
+                ),
+            )
+            .attr("timeout", timeout.as_secs_f64()),
+            _ => continue,
+        };
+        errors.push(
+            err.attr("component", status.name.as_str())
+                .attr("index", index),
+        );
+    }
+    errors
+}
+
+/// The errors recorded for each component while the chip was initialized.
+pub fn init_status_errors(status: &InitStatus) -> Vec<Error> {
+    let mut errors = Vec::new();
+    if let CommsStatus::CommunicationError(err) = &status.comms_status {
+        errors.push(
+            Error::new(CommsError::new_err, err)
+                .attr("component", "comms")
+                .attr("index", Attr::None),
+        );
+    }
+    errors.extend(component_errors(
+        &status.arc_status,
+        ArcInitError::new_err,
+        |base, err| match err {
+            RsArcInitError::FwVersionTooOld { version, required } => {
+                base.attr("version", *version).attr("required", *required)
+            }
+            _ => base,
+        },
+    ));
+    errors.extend(component_errors(
+        &status.dram_status,
+        DramInitError::new_err,
+        |base, err| match err {
+            luwen_if::chip::DramInitError::NotTrained(channel) => {
+                base.attr("channel_status", channel.to_string())
+            }
+        },
+    ));
+    errors.extend(component_errors(
+        &status.eth_status,
+        EthernetInitError::new_err,
+        |base, _| base,
+    ));
+    errors.extend(component_errors(
+        &status.cpu_status,
+        ChipInitError::new_err,
+        |base, _| base,
+    ));
+    errors
+}
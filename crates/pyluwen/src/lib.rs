@@ -6,16 +6,22 @@ use std::str::FromStr;
 
 use luwen_core::Arch;
 use luwen_if::chip::{
-    wait_for_init, ArcMsg, ArcMsgOk, ArcMsgOptions, ChipImpl, HlComms, HlCommsInterface, InitError,
-    NocInterface,
+    wait_for_init, ArcMsg, ArcMsgOk, ArcMsgOptions, ChipImpl, FanControl, FanCurve, HlComms,
+    HlCommsInterface, InitError, NocInterface, PowerControl, RiscCore, RiscElf,
+};
+use luwen_if::{
+    CallbackStorage, ChipDetectOptions, ChipSelector, DeviceInfo, PowerState, UninitChip,
 };
-use luwen_if::{CallbackStorage, ChipDetectOptions, DeviceInfo, UninitChip};
 use luwen_ref::{DmaConfig, ExtendedPciDeviceWrapper};
-use pyo3::exceptions::PyException;
 use pyo3::prelude::*;
-use pyo3::types::{PyDict, PyList};
+use pyo3::types::{PyDict, PyList, PyTuple};
 use serde_json::Value;
 
+use error::{Error, LuwenError};
+
+mod error;
+mod status;
+
 #[pyclass]
 pub struct PciChip(luwen_if::chip::Chip);
 
@@ -316,176 +322,440 @@ impl From<luwen_if::chip::AxiData> for AxiData {
     }
 }
 
+/// Run a hardware access with the GIL released so that other python threads can make progress
+/// in the meantime.
+fn unlocked<T: Send, E: Into<Error>>(
+    py: Python,
+    chip: &dyn ChipImpl,
+    f: impl FnOnce() -> Result<T, E> + Send,
+) -> Result<T, Error> {
+    py.allow_threads(|| f().map_err(|err| err.into().chip(chip)))
+}
+
+/// Flashing a full image takes minutes, so spi transfers are split up with a check for Ctrl-C
+/// between each chunk.
+const SPI_CHUNK_SIZE: usize = 64 * 1024;
+
+fn spi_read_chunked<E: Into<Error>>(
+    py: Python,
+    chip: &dyn ChipImpl,
+    addr: u32,
+    data: &mut [u8],
+    read: impl Fn(u32, &mut [u8]) -> Result<(), E> + Sync,
+) -> PyResult<()> {
+    for (index, chunk) in data.chunks_mut(SPI_CHUNK_SIZE).enumerate() {
+        let addr = addr + (index * SPI_CHUNK_SIZE) as u32;
+        unlocked(py, chip, || read(addr, chunk)).map_err(|err| err.attr("address", addr))?;
+        py.check_signals()?;
+    }
+
+    Ok(())
+}
+
+fn spi_write_chunked<E: Into<Error>>(
+    py: Python,
+    chip: &dyn ChipImpl,
+    addr: u32,
+    data: &[u8],
+    write: impl Fn(u32, &[u8]) -> Result<(), E> + Sync,
+) -> PyResult<()> {
+    for (index, chunk) in data.chunks(SPI_CHUNK_SIZE).enumerate() {
+        let addr = addr + (index * SPI_CHUNK_SIZE) as u32;
+        unlocked(py, chip, || write(addr, chunk)).map_err(|err| err.attr("address", addr))?;
+        py.check_signals()?;
+    }
+
+    Ok(())
+}
+
+/// Schedule `func(*args)` on the default executor of the running asyncio loop and return the
+/// resulting future. The functions passed in here release the GIL so they run alongside the loop.
+fn run_in_executor<'py>(
+    py: Python<'py>,
+    func: &'py PyAny,
+    args: impl IntoPy<Py<PyTuple>>,
+) -> PyResult<&'py PyAny> {
+    let mut partial_args = vec![func.into_py(py)];
+    partial_args.extend(
+        args.into_py(py)
+            .as_ref(py)
+            .iter()
+            .map(|arg| arg.into_py(py)),
+    );
+    let call = py
+        .import("functools")?
+        .call_method1("partial", PyTuple::new(py, partial_args))?;
+    py.import("asyncio")?
+        .call_method0("get_running_loop")?
+        .call_method1("run_in_executor", (py.None(), call))
+}
+
 macro_rules! common_chip_comms_impls {
     ($name:ty) => {
         #[pymethods]
         impl $name {
             pub fn noc_read(
                 &self,
+                py: Python,
                 noc_id: u8,
                 x: u8,
                 y: u8,
                 addr: u64,
                 data: pyo3::buffer::PyBuffer<u8>,
             ) -> PyResult<()> {
-                Python::with_gil(|_py| {
-                    let ptr: *mut u8 = data.buf_ptr().cast();
-                    let len = data.len_bytes();
-
-                    let data = unsafe { std::slice::from_raw_parts_mut(ptr, len) };
-                    self.0
-                        .noc_read(noc_id, x, y, addr, data)
-                        .map_err(|v| PyException::new_err(v.to_string()))
-                })
+                let ptr: *mut u8 = data.buf_ptr().cast();
+                let len = data.len_bytes();
+
+                let data = unsafe { std::slice::from_raw_parts_mut(ptr, len) };
+                Ok(unlocked(py, &self.0, || self.0.noc_read(noc_id, x, y, addr, data))
+                    .map_err(|err| err.attr("address", addr))?)
             }
 
-            pub fn noc_read32(&self, noc_id: u8, x: u8, y: u8, addr: u64) -> PyResult<u32> {
+            pub fn noc_read32(&self, py: Python, noc_id: u8, x: u8, y: u8, addr: u64) -> PyResult<u32> {
                 let mut data = [0u8; 4];
-                self.0
-                    .noc_read(noc_id, x, y, addr, &mut data)
-                    .map_err(|v| PyException::new_err(v.to_string()))?;
+                unlocked(py, &self.0, || self.0.noc_read(noc_id, x, y, addr, &mut data))
+                    .map_err(|err| err.attr("address", addr))?;
 
                 Ok(u32::from_le_bytes(data))
             }
 
             pub fn noc_write(
                 &self,
+                py: Python,
                 noc_id: u8,
                 x: u8,
                 y: u8,
                 addr: u64,
                 data: pyo3::buffer::PyBuffer<u8>,
             ) -> PyResult<()> {
-                Python::with_gil(|_py| {
-                    let ptr: *mut u8 = data.buf_ptr().cast();
-                    let len = data.len_bytes();
-
-                    let data = unsafe { std::slice::from_raw_parts(ptr, len) };
-                    self.0
-                        .noc_write(noc_id, x, y, addr, data)
-                        .map_err(|v| PyException::new_err(v.to_string()))
-                })
+                let ptr: *mut u8 = data.buf_ptr().cast();
+                let len = data.len_bytes();
+
+                let data = unsafe { std::slice::from_raw_parts(ptr, len) };
+                Ok(unlocked(py, &self.0, || self.0.noc_write(noc_id, x, y, addr, data))
+                    .map_err(|err| err.attr("address", addr))?)
             }
 
             pub fn noc_write32(
                 &self,
+                py: Python,
                 noc_id: u8,
                 x: u8,
                 y: u8,
                 addr: u64,
                 data: u32,
             ) -> PyResult<()> {
-                self.0
-                    .noc_write(noc_id, x, y, addr, &data.to_le_bytes())
-                    .map_err(|v| PyException::new_err(v.to_string()))
+                Ok(unlocked(py, &self.0, || self.0.noc_write(noc_id, x, y, addr, &data.to_le_bytes()))
+                    .map_err(|err| err.attr("address", addr))?)
             }
 
             pub fn noc_broadcast(
                 &self,
+                py: Python,
                 noc_id: u8,
                 addr: u64,
                 data: pyo3::buffer::PyBuffer<u8>,
             ) -> PyResult<()> {
-                Python::with_gil(|_py| {
-                    let ptr: *mut u8 = data.buf_ptr().cast();
-                    let len = data.len_bytes();
-
-                    let data = unsafe { std::slice::from_raw_parts(ptr, len) };
-                    self.0
-                        .noc_broadcast(noc_id, addr, data)
-                        .map_err(|v| PyException::new_err(v.to_string()))
-                })
+                let ptr: *mut u8 = data.buf_ptr().cast();
+                let len = data.len_bytes();
+
+                let data = unsafe { std::slice::from_raw_parts(ptr, len) };
+                Ok(unlocked(py, &self.0, || self.0.noc_broadcast(noc_id, addr, data))
+                    .map_err(|err| err.attr("address", addr))?)
             }
 
-            pub fn noc_broadcast32(&self, noc_id: u8, addr: u64, data: u32) -> PyResult<()> {
-                self.0
-                    .noc_broadcast(noc_id, addr, &data.to_le_bytes())
-                    .map_err(|v| PyException::new_err(v.to_string()))
+            pub fn noc_broadcast32(&self, py: Python, noc_id: u8, addr: u64, data: u32) -> PyResult<()> {
+                Ok(unlocked(py, &self.0, || self.0.noc_broadcast(noc_id, addr, &data.to_le_bytes()))
+                    .map_err(|err| err.attr("address", addr))?)
             }
 
             pub fn axi_translate(&self, addr: &str) -> PyResult<AxiData> {
-                match self.0.axi_translate(addr).map_err(|err| err.to_string()) {
-                    Ok(v) => Ok(v.into()),
-                    Err(err) => Err(PyException::new_err(err)),
-                }
+                Ok(self.0.axi_translate(addr).map_err(|err| Error::from(err).chip(&self.0))?.into())
             }
 
-            pub fn axi_read(&self, addr: u64, data: pyo3::buffer::PyBuffer<u8>) -> PyResult<()> {
-                Python::with_gil(|_py| {
-                    let ptr: *mut u8 = data.buf_ptr().cast();
-                    let len = data.len_bytes();
+            pub fn axi_read(&self, py: Python, addr: u64, data: pyo3::buffer::PyBuffer<u8>) -> PyResult<()> {
+                let ptr: *mut u8 = data.buf_ptr().cast();
+                let len = data.len_bytes();
 
-                    let data = unsafe { std::slice::from_raw_parts_mut(ptr, len) };
-                    self.0
-                        .axi_read(addr, data)
-                        .map_err(|v| PyException::new_err(v.to_string()))
-                })
+                let data = unsafe { std::slice::from_raw_parts_mut(ptr, len) };
+                Ok(unlocked(py, &self.0, || self.0.axi_read(addr, data))
+                    .map_err(|err| err.attr("address", addr))?)
             }
 
-            pub fn axi_read32(&self, addr: u64) -> PyResult<u32> {
+            pub fn axi_read32(&self, py: Python, addr: u64) -> PyResult<u32> {
                 let mut data = [0u8; 4];
-                self.0
-                    .axi_read(addr, &mut data)
-                    .map_err(|v| PyException::new_err(v.to_string()))?;
+                unlocked(py, &self.0, || self.0.axi_read(addr, &mut data))
+                    .map_err(|err| err.attr("address", addr))?;
 
                 Ok(u32::from_le_bytes(data))
             }
 
-            pub fn axi_write(&self, addr: u64, data: pyo3::buffer::PyBuffer<u8>) -> PyResult<()> {
-                Python::with_gil(|_py| {
-                    let ptr: *mut u8 = data.buf_ptr().cast();
-                    let len = data.len_bytes();
+            pub fn axi_write(&self, py: Python, addr: u64, data: pyo3::buffer::PyBuffer<u8>) -> PyResult<()> {
+                let ptr: *mut u8 = data.buf_ptr().cast();
+                let len = data.len_bytes();
 
-                    let data = unsafe { std::slice::from_raw_parts_mut(ptr, len) };
-                    self.0
-                        .axi_write(addr, data)
-                        .map_err(|v| PyException::new_err(v.to_string()))
-                })
+                let data = unsafe { std::slice::from_raw_parts(ptr, len) };
+                Ok(unlocked(py, &self.0, || self.0.axi_write(addr, data))
+                    .map_err(|err| err.attr("address", addr))?)
             }
 
-            pub fn axi_write32(&self, addr: u64, data: u32) -> PyResult<()> {
-                self.0
-                    .axi_write(addr, &data.to_le_bytes())
-                    .map_err(|v| PyException::new_err(v.to_string()))
+            pub fn axi_write32(&self, py: Python, addr: u64, data: u32) -> PyResult<()> {
+                Ok(unlocked(py, &self.0, || self.0.axi_write(addr, &data.to_le_bytes()))
+                    .map_err(|err| err.attr("address", addr))?)
             }
 
+            #[allow(clippy::too_many_arguments)]
             #[pyo3(signature = (msg, wait_for_done = true, use_second_mailbox = false, arg0 = 0xffff, arg1 = 0xffff, timeout = 1.0))]
-            pub fn arc_msg(&self, msg: u16, wait_for_done: bool, use_second_mailbox: bool, arg0: u16, arg1: u16, timeout: f64) -> PyResult<Option<(u32, u32)>> {
-                match self.0
+            pub fn arc_msg(&self, py: Python, msg: u16, wait_for_done: bool, use_second_mailbox: bool, arg0: u16, arg1: u16, timeout: f64) -> PyResult<Option<(u32, u32)>> {
+                let result = unlocked(py, &self.0, || self.0
                     .arc_msg(ArcMsgOptions {
                         addrs: None,
                         msg: ArcMsg::Raw{ msg, arg0, arg1 },
                         wait_for_done,
                         use_second_mailbox,
                         timeout: std::time::Duration::from_secs_f64(timeout),
-                    }) {
-                        Ok(ArcMsgOk::Ok {rc, arg}) => {
-                            Ok(Some((arg, rc)))
-                        }
-                        Ok(ArcMsgOk::OkNoWait) => {
-                            Ok(None)
-                        }
-                        Err(err) => {
-                            Err(PyException::new_err(err.to_string()))
-                        }
+                    }))
+                    .map_err(|err| err.attr("msg", msg).attr("timeout", timeout))?;
+                match result {
+                    ArcMsgOk::Ok {rc, arg} => {
+                        Ok(Some((arg, rc)))
                     }
+                    ArcMsgOk::OkNoWait => {
+                        Ok(None)
+                    }
+                }
+            }
+
+            pub fn get_telemetry(&self, py: Python) -> PyResult<Telemetry> {
+                Ok(unlocked(py, &self.0, || self.0.get_telemetry())?.into())
+            }
+
+            /// Awaitable version of `get_telemetry` for use from asyncio code.
+            pub fn get_telemetry_async<'py>(slf: &'py PyCell<Self>, py: Python<'py>) -> PyResult<&'py PyAny> {
+                run_in_executor(py, slf.getattr("get_telemetry")?, ())
+            }
+
+            pub fn get_neighbouring_chips(&self, py: Python) -> PyResult<Vec<NeighbouringChip>> {
+                Ok(unlocked(py, &self.0, || self.0.get_neighbouring_chips())?
+                    .into_iter()
+                    .map(|v| v.into())
+                    .collect())
+            }
+
+            /// Hold the named RISC cores (brisc, ncrisc, trisc0-2 or erisc) of each core in soft reset.
+            pub fn assert_risc_reset(&self, py: Python, cores: Vec<(u8, u8)>, riscs: Vec<&str>) -> PyResult<()> {
+                let riscs = riscs.into_iter().map(parse_risc).collect::<PyResult<Vec<_>>>()?;
+                Ok(unlocked(py, &self.0, || luwen_if::chip::assert_risc_reset(&self.0, &cores, &riscs))?)
+            }
+
+            pub fn deassert_risc_reset(&self, py: Python, cores: Vec<(u8, u8)>, riscs: Vec<&str>) -> PyResult<()> {
+                let riscs = riscs.into_iter().map(parse_risc).collect::<PyResult<Vec<_>>>()?;
+                Ok(unlocked(py, &self.0, || luwen_if::chip::deassert_risc_reset(&self.0, &cores, &riscs))?)
+            }
+
+            pub fn risc_in_reset(&self, py: Python, x: u8, y: u8, risc: &str) -> PyResult<bool> {
+                let risc = parse_risc(risc)?;
+                Ok(unlocked(py, &self.0, || luwen_if::chip::risc_in_reset(&self.0, (x, y), risc))?)
+            }
+
+            pub fn set_risc_entry(&self, py: Python, x: u8, y: u8, risc: &str, entry: u32) -> PyResult<()> {
+                let risc = parse_risc(risc)?;
+                Ok(unlocked(py, &self.0, || luwen_if::chip::set_risc_entry(&self.0, (x, y), risc, entry))?)
+            }
+
+            /// Write the segments of an ELF to each core, returns its entry point.
+            #[pyo3(signature = (cores, elf, broadcast = false))]
+            pub fn load_elf(&self, py: Python, cores: Vec<(u8, u8)>, elf: &[u8], broadcast: bool) -> PyResult<u32> {
+                let elf = parse_elf(elf)?;
+                unlocked(py, &self.0, || luwen_if::chip::load_elf(&self.0, &cores, &elf, broadcast))?;
+
+                Ok(elf.entry)
+            }
+
+            /// Load an ELF onto each core and start it on the named RISC core.
+            #[pyo3(signature = (cores, risc, elf, broadcast = false))]
+            pub fn run_elf(&self, py: Python, cores: Vec<(u8, u8)>, risc: &str, elf: &[u8], broadcast: bool) -> PyResult<()> {
+                let risc = parse_risc(risc)?;
+                let elf = parse_elf(elf)?;
+                Ok(unlocked(py, &self.0, || luwen_if::chip::run_elf(&self.0, &cores, risc, &elf, broadcast))?)
+            }
+
+            /// Read `count` words at `addr` in the L1 of each core, i.e. a mailbox a program writes its results to.
+            pub fn read_mailbox(&self, py: Python, cores: Vec<(u8, u8)>, addr: u64, count: usize) -> PyResult<Vec<Vec<u32>>> {
+                Ok(unlocked(py, &self.0, || luwen_if::chip::read_mailbox(&self.0, &cores, addr, count))?)
+            }
+
+            /// Pin AICLK to `mhz`, overriding the throttlers. Raises PowerError if it is above the
+            /// board limit, the limit is unknown or the firmware rejects it.
+            pub fn force_aiclk(&self, py: Python, mhz: u32) -> PyResult<()> {
+                Ok(unlocked(py, &self.0, || PowerControl::new(&self.0).force_aiclk(mhz))?)
+            }
+
+            /// Hand AICLK back to the firmware.
+            pub fn clear_forced_aiclk(&self, py: Python) -> PyResult<()> {
+                Ok(unlocked(py, &self.0, || PowerControl::new(&self.0).clear_forced_aiclk())?)
+            }
+
+            /// Throttle the chip by pinning AICLK to the board minimum. Only supported on blackhole,
+            /// raises PowerError on other chips or if the firmware does not report a minimum.
+            pub fn force_throttle(&self, py: Python) -> PyResult<()> {
+                Ok(unlocked(py, &self.0, || PowerControl::new(&self.0).force_throttle())?)
+            }
+
+            /// Stop throttling and hand AICLK back to the firmware.
+            pub fn clear_throttle(&self, py: Python) -> PyResult<()> {
+                Ok(unlocked(py, &self.0, || PowerControl::new(&self.0).clear_throttle())?)
+            }
+
+            /// Request the busy, short_idle or long_idle power state.
+            pub fn set_power_state(&self, py: Python, state: &str) -> PyResult<()> {
+                let state = parse_power_state(state)?;
+                Ok(unlocked(py, &self.0, || PowerControl::new(&self.0).set_power_state(state))?)
+            }
+
+            /// The current AICLK in MHz, as reported by the firmware.
+            pub fn get_aiclk(&self, py: Python) -> PyResult<u32> {
+                Ok(unlocked(py, &self.0, || PowerControl::new(&self.0).aiclk())?)
+            }
+
+            /// The current clock, power, current and temperature along with the board limits as a dict.
+            pub fn get_power_status(&self, py: Python) -> PyResult<PyObject> {
+                let status = unlocked(py, &self.0, || PowerControl::new(&self.0).status())?;
+                let status = serde_json::to_value(status)
+                    .map_err(|err| LuwenError::new_err(format!("Could not convert power status: {err}")))?;
+
+                serde_json_value_to_pyobject(py, &status)
             }
 
-            pub fn get_telemetry(&self) -> PyResult<Telemetry> {
-                self.0.get_telemetry().map(|v| v.into()).map_err(|v| PyException::new_err(v.to_string()))
+            /// The harvested tensix rows or columns and the disabled DRAM channels, ethernet ports
+            /// and L2CPUs as a dict.
+            pub fn get_harvesting(&self, py: Python) -> PyResult<PyObject> {
+                let harvesting = unlocked(py, &self.0, || self.0.harvesting())?;
+                let harvesting = serde_json::to_value(harvesting)
+                    .map_err(|err| LuwenError::new_err(format!("Could not convert harvesting: {err}")))?;
+
+                serde_json_value_to_pyobject(py, &harvesting)
+            }
+
+            /// The fan speed, ASIC temperature and the thermal limit as a dict.
+            pub fn get_fan_status(&self, py: Python) -> PyResult<PyObject> {
+                let status = unlocked(py, &self.0, || FanControl::new(&self.0).status())?;
+                let status = serde_json::to_value(status)
+                    .map_err(|err| LuwenError::new_err(format!("Could not convert fan status: {err}")))?;
+
+                serde_json_value_to_pyobject(py, &status)
+            }
+
+            /// The firmware fan curve as a list of (temperature, speed percent) points. Fan control
+            /// is only supported on blackhole, other chips raise PowerError.
+            pub fn get_fan_curve(&self, py: Python) -> PyResult<Vec<(f64, u32)>> {
+                Ok(unlocked(py, &self.0, || FanControl::new(&self.0).curve())?.points)
+            }
+
+            /// Run the fan at `speed` percent until clear_fan_speed is called. Raises PowerError if
+            /// the speed is below the allowed minimum. Nothing clears the speed if the process exits
+            /// first, unless the chip was opened through luwen-broker.
+            pub fn force_fan_speed(&self, py: Python, speed: u32) -> PyResult<()> {
+                Ok(unlocked(py, &self.0, || FanControl::new(&self.0).force_speed(speed))?)
+            }
+
+            /// Hand the fan back to the firmware curve.
+            pub fn clear_fan_speed(&self, py: Python) -> PyResult<()> {
+                Ok(unlocked(py, &self.0, || FanControl::new(&self.0).clear_forced_speed())?)
             }
 
-            pub fn get_neighbouring_chips(&self) -> PyResult<Vec<NeighbouringChip>> {
-                self.0
-                    .get_neighbouring_chips()
-                    .map(|v| v.into_iter().map(|v| v.into()).collect())
-                    .map_err(|v| PyException::new_err(v.to_string()))
+            /// Drive the fan from a list of (temperature, speed percent) points for `duration`
+            /// seconds, or until interrupted. The fan is handed back to the firmware when this
+            /// returns, and early with a PowerError if the ASIC reaches its thermal limit. If the
+            /// process is killed while this runs the fan stays forced, there is no watchdog.
+            #[pyo3(signature = (curve, interval = 1.0, duration = None))]
+            pub fn run_fan_curve(&self, py: Python, curve: Vec<(f64, u32)>, interval: f64, duration: Option<f64>) -> PyResult<()> {
+                let curve = FanCurve::new(curve);
+                let start = std::time::Instant::now();
+                let interrupt = std::sync::Mutex::new(None);
+                let stop = || {
+                    if duration.is_some_and(|duration| start.elapsed().as_secs_f64() >= duration) {
+                        return true;
+                    }
+                    match Python::with_gil(|py| py.check_signals()) {
+                        Ok(()) => false,
+                        Err(err) => {
+                            *interrupt.lock().unwrap() = Some(err);
+                            true
+                        }
+                    }
+                };
+                unlocked(py, &self.0, || {
+                    FanControl::new(&self.0).run_curve(&curve, std::time::Duration::from_secs_f64(interval), stop)
+                })?;
+
+                match interrupt.into_inner().unwrap() {
+                    Some(err) => Err(err),
+                    None => Ok(()),
+                }
             }
 
     }
 }
 }
 
+macro_rules! spi_impls {
+    ($name:ty) => {
+        #[pymethods]
+        impl $name {
+            pub fn spi_read(
+                &self,
+                py: Python,
+                addr: u32,
+                data: pyo3::buffer::PyBuffer<u8>,
+            ) -> PyResult<()> {
+                let ptr: *mut u8 = data.buf_ptr().cast();
+                let len = data.len_bytes();
+
+                let data = unsafe { std::slice::from_raw_parts_mut(ptr, len) };
+                spi_read_chunked(py, &self.0, addr, data, |addr, data| {
+                    self.0.spi_read(addr, data)
+                })
+            }
+
+            pub fn spi_write(
+                &self,
+                py: Python,
+                addr: u32,
+                data: pyo3::buffer::PyBuffer<u8>,
+            ) -> PyResult<()> {
+                let ptr: *mut u8 = data.buf_ptr().cast();
+                let len = data.len_bytes();
+
+                let data = unsafe { std::slice::from_raw_parts(ptr, len) };
+                spi_write_chunked(py, &self.0, addr, data, |addr, data| {
+                    self.0.spi_write(addr, data)
+                })
+            }
+
+            /// Awaitable version of `spi_read`, `data` must not be resized until it completes.
+            pub fn spi_read_async<'py>(
+                slf: &'py PyCell<Self>,
+                py: Python<'py>,
+                addr: u32,
+                data: &'py PyAny,
+            ) -> PyResult<&'py PyAny> {
+                run_in_executor(py, slf.getattr("spi_read")?, (addr, data))
+            }
+
+            /// Awaitable version of `spi_write`.
+            pub fn spi_write_async<'py>(
+                slf: &'py PyCell<Self>,
+                py: Python<'py>,
+                addr: u32,
+                data: &'py PyAny,
+            ) -> PyResult<&'py PyAny> {
+                run_in_executor(py, slf.getattr("spi_write")?, (addr, data))
+            }
+        }
+    };
+}
+
 #[pyclass]
 struct PyChipDetectState(luwen_if::chip::ChipDetectState<'static>);
 
@@ -499,6 +769,23 @@ impl PyChipDetectState {
         matches!(self.0.call, luwen_if::chip::CallReason::NotNew)
     }
 
+    /// A snapshot of the init status, None when the chip was just found.
+    pub fn status(&self) -> Option<status::InitStatus> {
+        match self.0.call {
+            luwen_if::chip::CallReason::NewChip | luwen_if::chip::CallReason::NotNew => None,
+            luwen_if::chip::CallReason::ChipInitCompleted(status)
+            | luwen_if::chip::CallReason::InitWait(status) => Some(status.into()),
+        }
+    }
+
+    /// True once the chip has finished initializing, successfully or not.
+    pub fn init_completed(&self) -> bool {
+        matches!(
+            self.0.call,
+            luwen_if::chip::CallReason::ChipInitCompleted(_)
+        )
+    }
+
     pub fn status_string(&self) -> Option<String> {
         match self.0.call {
             luwen_if::chip::CallReason::NewChip | luwen_if::chip::CallReason::NotNew => None,
@@ -511,6 +798,30 @@ impl PyChipDetectState {
     }
 }
 
+/// The callback passed to detection and init. Without a user callback we still check for Ctrl-C
+/// since detection can take a long time. The GIL is only reacquired for the duration of the call.
+#[allow(clippy::type_complexity)]
+fn detect_callback(
+    callback: Option<PyObject>,
+) -> Box<dyn FnMut(luwen_if::chip::ChipDetectState) -> Result<(), PyErr> + Send> {
+    if let Some(callback) = callback {
+        Box::new(move |status| {
+            // Safety: This is extremly unsafe, the alternative would be to copy the status for
+            // every invocation.
+            let status = unsafe { std::mem::transmute(status) };
+            if let Err(err) =
+                Python::with_gil(|py| callback.call1(py, (PyChipDetectState(status),)))
+            {
+                Err(err)
+            } else {
+                Ok(())
+            }
+        })
+    } else {
+        Box::new(|_| Python::with_gil(|py| py.check_signals()))
+    }
+}
+
 impl PciChip {
     fn device_info(&self) -> PyResult<DeviceInfo> {
         match self.0.inner.get_device_info() {
@@ -518,16 +829,34 @@ impl PciChip {
                 if let Some(info) = info {
                     Ok(info)
                 } else {
-                    Err(PyException::new_err(
+                    Err(LuwenError::new_err(
                         "Could not get device info: info unavailable",
                     ))
                 }
             }
-            Err(err) => Err(PyException::new_err(format!(
+            Err(err) => Err(LuwenError::new_err(format!(
                 "Could not get device info: {err}"
             ))),
         }
     }
+
+    fn pci_interface(&self) -> Option<&ExtendedPciDeviceWrapper> {
+        let storage = if let Some(wh) = self.0.as_wh() {
+            wh.get_if::<CallbackStorage<ExtendedPciDeviceWrapper>>()
+        } else if let Some(gs) = self.0.as_gs() {
+            gs.get_if::<CallbackStorage<ExtendedPciDeviceWrapper>>()
+        } else {
+            self.0
+                .as_bh()
+                .and_then(|bh| bh.get_if::<NocInterface>())
+                .and_then(|v| {
+                    v.backing
+                        .as_any()
+                        .downcast_ref::<CallbackStorage<ExtendedPciDeviceWrapper>>()
+                })
+        };
+        storage.map(|v| &v.user_data)
+    }
 }
 
 #[pymethods]
@@ -556,7 +885,9 @@ impl PciChip {
     pub fn new(pci_interface: Option<usize>) -> PyResult<Self> {
         let pci_interface = pci_interface.unwrap();
 
-        let chip = luwen_ref::ExtendedPciDevice::open(pci_interface).unwrap();
+        let chip = luwen_ref::ExtendedPciDevice::open(pci_interface).map_err(|err| {
+            Error::from(format!("Could not open chip: {err}")).attr("interface", pci_interface)
+        })?;
 
         let arch = chip.borrow().device.arch;
 
@@ -568,41 +899,28 @@ impl PciChip {
                     user_data: chip,
                 },
             )
-            .map_err(|v| PyException::new_err(format!("Could not initialize chip: {v}")))?,
+            .map_err(|err| {
+                Error::from(err)
+                    .context("Could not initialize chip")
+                    .attr("interface", pci_interface)
+            })?,
         ))
     }
 
     #[pyo3(signature = (callback = None))]
-    pub fn init(&mut self, callback: Option<PyObject>) -> PyResult<()> {
-        #[allow(clippy::type_complexity)]
-        let mut callback: Box<
-            dyn FnMut(luwen_if::chip::ChipDetectState) -> Result<(), PyErr>,
-        > = if let Some(callback) = callback {
-            Box::new(move |status| {
-                // Safety: This is extremly unsafe, the alternative would be to copy the status for
-                // every invocation.
-                let status = unsafe { std::mem::transmute(status) };
-                if let Err(err) =
-                    Python::with_gil(|py| callback.call1(py, (PyChipDetectState(status),)))
-                {
-                    Err(err)
-                } else {
-                    Ok(())
-                }
-            })
-        } else {
-            Box::new(|_| Python::with_gil(|py| py.check_signals()))
-        };
-
-        match wait_for_init(&mut self.0, &mut callback, false, false) {
-            Err(InitError::PlatformError(err)) => Err(PyException::new_err(format!(
-                "Could not initialize chip: {err}"
-            ))),
-            Err(InitError::CallbackError(err)) => Err(err),
-            Ok(status) => Ok(status),
-        }?;
-
-        Ok(())
+    pub fn init(&mut self, py: Python, callback: Option<PyObject>) -> PyResult<()> {
+        let mut callback = detect_callback(callback);
+
+        py.allow_threads(
+            || match wait_for_init(&mut self.0, &mut callback, false, false) {
+                Err(InitError::PlatformError(err)) => Err(Error::from(err)
+                    .context("Could not initialize chip")
+                    .chip(&self.0)
+                    .into()),
+                Err(InitError::CallbackError(err)) => Err(err),
+                Ok(_) => Ok(()),
+            },
+        )
     }
 
     pub fn board_id(&self) -> u64 {
@@ -631,6 +949,49 @@ impl PciChip {
         let info = self.device_info()?;
         Ok(info.interface_id)
     }
+
+    /// Identify the chip, the asic location is not known for a chip on its own.
+    pub fn chip_id(&self, py: Python) -> ChipId {
+        py.allow_threads(|| luwen_if::ChipId::read(&self.0)).into()
+    }
+
+    /// "connected", or "lost" and "resetting" when accesses will raise ConnectionLost until the
+    /// chip is reopened.
+    pub fn connection_state(&self) -> &'static str {
+        error::connection_state(self.0.connection_state())
+    }
+
+    /// Open the device again after the connection was lost or the chip was reset.
+    /// The chip should be initialized again with init once this returns.
+    pub fn reopen(&self, py: Python) -> PyResult<()> {
+        py.allow_threads(|| {
+            self.0.reopen().map_err(|err| {
+                Error::from(err)
+                    .context("Could not reopen chip")
+                    .chip(&self.0)
+                    .into()
+            })
+        })
+    }
+
+    /// Call `callback` with the interface id when the connection to the chip is lost.
+    /// Exceptions raised by the callback are printed and otherwise ignored.
+    pub fn on_connection_lost(&self, callback: PyObject) -> PyResult<()> {
+        let Some(pci_interface) = self.pci_interface() else {
+            return Err(LuwenError::new_err(
+                "The chip is not backed by a pci device",
+            ));
+        };
+        pci_interface.on_connection_lost(move |interface| {
+            Python::with_gil(|py| {
+                if let Err(err) = callback.call1(py, (interface,)) {
+                    err.print(py);
+                }
+            })
+        });
+
+        Ok(())
+    }
 }
 
 common_chip_comms_impls!(PciChip);
@@ -639,7 +1000,7 @@ common_chip_comms_impls!(PciChip);
 impl PciGrayskull {
     #[allow(clippy::too_many_arguments)]
     pub fn setup_tlb(
-        &mut self,
+        &self,
         index: u32,
         addr: u64,
         x_start: u8,
@@ -655,15 +1016,15 @@ impl PciGrayskull {
 
         if let Some(value) = value {
             match ttkmd_if::tlb::Ordering::from(ordering) {
-                ttkmd_if::tlb::Ordering::UNKNOWN(ordering) => Err(PyException::new_err(format!(
-                    "Invalid ordering {ordering}."
-                ))),
+                ttkmd_if::tlb::Ordering::UNKNOWN(ordering) => {
+                    Err(LuwenError::new_err(format!("Invalid ordering {ordering}.")))
+                }
                 ordering => Ok(value.setup_tlb(
                     index, addr, x_start, y_start, x_end, y_end, noc_sel, mcast, ordering, linked,
                 )),
             }
         } else {
-            Err(PyException::new_err(
+            Err(LuwenError::new_err(
                 "Could not get PCI interface for this chip.",
             ))
         }
@@ -676,7 +1037,7 @@ impl PciGrayskull {
             value.pci_interface.borrow_mut().default_tlb = index;
             Ok(())
         } else {
-            Err(PyException::new_err(
+            Err(LuwenError::new_err(
                 "Could not get PCI interface for this chip.",
             ))
         }
@@ -687,9 +1048,9 @@ impl PciGrayskull {
         if let Some(value) = value {
             value
                 .axi_read32(addr)
-                .map_err(|v| PyException::new_err(v.to_string()))
+                .map_err(|v| LuwenError::new_err(v.to_string()))
         } else {
-            Err(PyException::new_err(
+            Err(LuwenError::new_err(
                 "Could not get PCI interface for this chip.",
             ))
         }
@@ -700,9 +1061,9 @@ impl PciGrayskull {
         if let Some(value) = value {
             value
                 .axi_write32(addr, data)
-                .map_err(|v| PyException::new_err(v.to_string()))
+                .map_err(|v| LuwenError::new_err(v.to_string()))
         } else {
-            Err(PyException::new_err(
+            Err(LuwenError::new_err(
                 "Could not get PCI interface for this chip.",
             ))
         }
@@ -713,7 +1074,7 @@ impl PciGrayskull {
         if let Some(value) = value {
             Ok(value.pci_interface.borrow().device.physical.subsystem_id)
         } else {
-            Err(PyException::new_err(
+            Err(LuwenError::new_err(
                 "Could not get PCI interface for this chip.",
             ))
         }
@@ -724,38 +1085,15 @@ impl PciGrayskull {
         if let Some(value) = value {
             Ok(value.pci_interface.borrow().device.id)
         } else {
-            Err(PyException::new_err(
+            Err(LuwenError::new_err(
                 "Could not get PCI interface for this chip.",
             ))
         }
     }
-
-    pub fn spi_read(&self, addr: u32, data: pyo3::buffer::PyBuffer<u8>) -> PyResult<()> {
-        Python::with_gil(|_py| {
-            let ptr: *mut u8 = data.buf_ptr().cast();
-            let len = data.len_bytes();
-
-            let data = unsafe { std::slice::from_raw_parts_mut(ptr, len) };
-            self.0
-                .spi_read(addr, data)
-                .map_err(|v| PyException::new_err(v.to_string()))
-        })
-    }
-
-    pub fn spi_write(&self, addr: u32, data: pyo3::buffer::PyBuffer<u8>) -> PyResult<()> {
-        Python::with_gil(|_py| {
-            let ptr: *mut u8 = data.buf_ptr().cast();
-            let len = data.len_bytes();
-
-            let data = unsafe { std::slice::from_raw_parts(ptr, len) };
-            self.0
-                .spi_write(addr, data)
-                .map_err(|v| PyException::new_err(v.to_string()))
-        })
-    }
 }
 
 common_chip_comms_impls!(PciGrayskull);
+spi_impls!(PciGrayskull);
 
 pub struct PciInterface<'a> {
     pub pci_interface: &'a ExtendedPciDeviceWrapper,
@@ -922,6 +1260,77 @@ impl From<luwen_if::EthAddr> for EthAddr {
     }
 }
 
+/// Identifies a chip across reboots, see `detect_chips_fallible` for the selector syntax.
+#[pyclass]
+#[derive(Clone)]
+pub struct ChipId {
+    #[pyo3(get)]
+    pub board_id: Option<u64>,
+    /// The position of the chip on its board, only known for chips from `detect_chips_fallible`.
+    #[pyo3(get)]
+    pub asic_location: Option<u8>,
+    #[pyo3(get)]
+    pub pci_bdf: Option<String>,
+    #[pyo3(get)]
+    pub interface_id: Option<u32>,
+    #[pyo3(get)]
+    pub eth_addr: Option<EthAddr>,
+    id: luwen_if::ChipId,
+}
+
+impl From<luwen_if::ChipId> for ChipId {
+    fn from(value: luwen_if::ChipId) -> Self {
+        Self {
+            board_id: value.board_id,
+            asic_location: value.asic_location,
+            pci_bdf: value.pci_bdf.map(|bdf| bdf.to_string()),
+            interface_id: value.interface_id,
+            eth_addr: value.eth_addr.map(EthAddr::from),
+            id: value,
+        }
+    }
+}
+
+#[pymethods]
+impl ChipId {
+    /// True if the chip is matched by the selector, i.e. "board:100014511abcd/1".
+    pub fn matches(&self, selector: &str) -> PyResult<bool> {
+        Ok(self.id.matches(&parse_chip_selector(selector)?))
+    }
+
+    pub fn __str__(&self) -> String {
+        self.id.to_string()
+    }
+
+    pub fn __repr__(&self) -> String {
+        format!("ChipId({})", self.id)
+    }
+}
+
+fn parse_chip_selector(selector: &str) -> PyResult<ChipSelector> {
+    ChipSelector::from_str(selector)
+        .map_err(|err| LuwenError::new_err(format!("Could not parse chip selector: {err}")))
+}
+
+fn parse_risc(risc: &str) -> PyResult<RiscCore> {
+    RiscCore::from_str(risc).map_err(|err| LuwenError::new_err(err.to_string()))
+}
+
+fn parse_elf(elf: &[u8]) -> PyResult<RiscElf> {
+    RiscElf::parse(elf).map_err(|err| LuwenError::new_err(format!("Could not load ELF: {err}")))
+}
+
+fn parse_power_state(state: &str) -> PyResult<PowerState> {
+    match state {
+        "busy" => Ok(PowerState::Busy),
+        "short_idle" => Ok(PowerState::ShortIdle),
+        "long_idle" => Ok(PowerState::LongIdle),
+        _ => Err(LuwenError::new_err(format!(
+            "Unknown power state {state}, expected busy, short_idle or long_idle"
+        ))),
+    }
+}
+
 #[pymethods]
 impl PciWormhole {
     pub fn open_remote(
@@ -934,13 +1343,13 @@ impl PciWormhole {
         Ok(RemoteWormhole(
             self.0
                 .open_remote((rack_x, rack_y, shelf_x, shelf_y))
-                .map_err(|v| PyException::new_err(format!("Could not open remote: {}", v)))?,
+                .map_err(|v| LuwenError::new_err(format!("Could not open remote: {}", v)))?,
         ))
     }
 
     #[allow(clippy::too_many_arguments)]
     pub fn setup_tlb(
-        &mut self,
+        &self,
         index: u32,
         addr: u64,
         x_start: u8,
@@ -956,15 +1365,15 @@ impl PciWormhole {
 
         if let Some(value) = value {
             match ttkmd_if::tlb::Ordering::from(ordering) {
-                ttkmd_if::tlb::Ordering::UNKNOWN(ordering) => Err(PyException::new_err(format!(
-                    "Invalid ordering {ordering}."
-                ))),
+                ttkmd_if::tlb::Ordering::UNKNOWN(ordering) => {
+                    Err(LuwenError::new_err(format!("Invalid ordering {ordering}.")))
+                }
                 ordering => Ok(value.setup_tlb(
                     index, addr, x_start, y_start, x_end, y_end, noc_sel, mcast, ordering, linked,
                 )),
             }
         } else {
-            Err(PyException::new_err(
+            Err(LuwenError::new_err(
                 "Could not get PCI interface for this chip.",
             ))
         }
@@ -977,7 +1386,7 @@ impl PciWormhole {
             value.pci_interface.borrow_mut().default_tlb = index;
             Ok(())
         } else {
-            Err(PyException::new_err(
+            Err(LuwenError::new_err(
                 "Could not get PCI interface for this chip.",
             ))
         }
@@ -988,10 +1397,10 @@ impl PciWormhole {
 
         if let Some(value) = value {
             Ok(value.allocate_dma_buffer(size).map_err(|v| {
-                PyException::new_err(format!("Could not allocate DMA buffer: {}", v))
+                LuwenError::new_err(format!("Could not allocate DMA buffer: {}", v))
             })?)
         } else {
-            Err(PyException::new_err(
+            Err(LuwenError::new_err(
                 "Could not get PCI interface for this chip.",
             ))
         }
@@ -1019,9 +1428,9 @@ impl PciWormhole {
                     read_threshold,
                     write_threshold,
                 )
-                .map_err(|v| PyException::new_err(format!("Could perform dma config: {}", v)))?)
+                .map_err(|v| LuwenError::new_err(format!("Could perform dma config: {}", v)))?)
         } else {
-            Err(PyException::new_err(
+            Err(LuwenError::new_err(
                 "Could not get PCI interface for this chip.",
             ))
         }
@@ -1039,9 +1448,9 @@ impl PciWormhole {
         if let Some(value) = value {
             Ok(value
                 .dma_transfer_turbo(addr, physical_dma_buffer, size, write)
-                .map_err(|v| PyException::new_err(format!("Could perform dma transfer: {}", v)))?)
+                .map_err(|v| LuwenError::new_err(format!("Could perform dma transfer: {}", v)))?)
         } else {
-            Err(PyException::new_err(
+            Err(LuwenError::new_err(
                 "Could not get PCI interface for this chip.",
             ))
         }
@@ -1052,7 +1461,7 @@ impl PciWormhole {
         if let Some(value) = value {
             Ok(value.pci_interface.borrow().device.physical.subsystem_id)
         } else {
-            Err(PyException::new_err(
+            Err(LuwenError::new_err(
                 "Could not get PCI interface for this chip.",
             ))
         }
@@ -1063,81 +1472,55 @@ impl PciWormhole {
         if let Some(value) = value {
             Ok(value.pci_interface.borrow().device.id)
         } else {
-            Err(PyException::new_err(
+            Err(LuwenError::new_err(
                 "Could not get PCI interface for this chip.",
             ))
         }
     }
 
-    pub fn spi_read(&self, addr: u32, data: pyo3::buffer::PyBuffer<u8>) -> PyResult<()> {
-        Python::with_gil(|_py| {
-            let ptr: *mut u8 = data.buf_ptr().cast();
-            let len = data.len_bytes();
-
-            let data = unsafe { std::slice::from_raw_parts_mut(ptr, len) };
-            self.0
-                .spi_read(addr, data)
-                .map_err(|v| PyException::new_err(v.to_string()))
-        })
+    pub fn get_local_coord(&self) -> PyResult<EthAddr> {
+        Ok(self
+            .0
+            .get_local_chip_coord()
+            .map_err(|err| Error::from(err).chip(&self.0))?
+            .into())
     }
 
-    pub fn spi_write(&self, addr: u32, data: pyo3::buffer::PyBuffer<u8>) -> PyResult<()> {
-        Python::with_gil(|_py| {
-            let ptr: *mut u8 = data.buf_ptr().cast();
-            let len = data.len_bytes();
-
-            let data = unsafe { std::slice::from_raw_parts(ptr, len) };
+    /// The link state and firmware health of every ethernet port as a list of
+    /// dicts. With a `heartbeat_interval` (in seconds) the heartbeats are checked for progress.
+    #[pyo3(signature = (heartbeat_interval = None))]
+    pub fn eth_port_status(
+        &self,
+        py: Python,
+        heartbeat_interval: Option<f64>,
+    ) -> PyResult<PyObject> {
+        let ports = unlocked(py, &self.0, || {
             self.0
-                .spi_write(addr, data)
-                .map_err(|v| PyException::new_err(v.to_string()))
-        })
-    }
+                .eth_port_status(heartbeat_interval.map(std::time::Duration::from_secs_f64))
+        })?;
+        let ports = serde_json::to_value(ports)
+            .map_err(|err| LuwenError::new_err(format!("Could not convert port status: {err}")))?;
 
-    pub fn get_local_coord(&self) -> PyResult<EthAddr> {
-        self.0
-            .get_local_chip_coord()
-            .map(|v| v.into())
-            .map_err(|v| PyException::new_err(v.to_string()))
+        serde_json_value_to_pyobject(py, &ports)
     }
 }
 
 common_chip_comms_impls!(PciWormhole);
+spi_impls!(PciWormhole);
 
 #[pyclass]
 pub struct RemoteWormhole(luwen_if::chip::Wormhole);
 
 common_chip_comms_impls!(RemoteWormhole);
+spi_impls!(RemoteWormhole);
 
 impl RemoteWormhole {
-    pub fn spi_read(&self, addr: u32, data: pyo3::buffer::PyBuffer<u8>) -> PyResult<()> {
-        Python::with_gil(|_py| {
-            let ptr: *mut u8 = data.buf_ptr().cast();
-            let len = data.len_bytes();
-
-            let data = unsafe { std::slice::from_raw_parts_mut(ptr, len) };
-            self.0
-                .spi_read(addr, data)
-                .map_err(|v| PyException::new_err(v.to_string()))
-        })
-    }
-
-    pub fn spi_write(&self, addr: u32, data: pyo3::buffer::PyBuffer<u8>) -> PyResult<()> {
-        Python::with_gil(|_py| {
-            let ptr: *mut u8 = data.buf_ptr().cast();
-            let len = data.len_bytes();
-
-            let data = unsafe { std::slice::from_raw_parts(ptr, len) };
-            self.0
-                .spi_write(addr, data)
-                .map_err(|v| PyException::new_err(v.to_string()))
-        })
-    }
-
     pub fn get_local_coord(&self) -> PyResult<EthAddr> {
-        self.0
+        Ok(self
+            .0
             .get_local_chip_coord()
-            .map(|v| v.into())
-            .map_err(|v| PyException::new_err(v.to_string()))
+            .map_err(|err| Error::from(err).chip(&self.0))?
+            .into())
     }
 }
 
@@ -1145,7 +1528,7 @@ impl RemoteWormhole {
 impl PciBlackhole {
     #[allow(clippy::too_many_arguments)]
     pub fn setup_tlb(
-        &mut self,
+        &self,
         index: u32,
         addr: u64,
         x_start: u8,
@@ -1161,15 +1544,15 @@ impl PciBlackhole {
 
         if let Some(value) = value {
             match ttkmd_if::tlb::Ordering::from(ordering) {
-                ttkmd_if::tlb::Ordering::UNKNOWN(ordering) => Err(PyException::new_err(format!(
-                    "Invalid ordering {ordering}."
-                ))),
+                ttkmd_if::tlb::Ordering::UNKNOWN(ordering) => {
+                    Err(LuwenError::new_err(format!("Invalid ordering {ordering}.")))
+                }
                 ordering => Ok(value.setup_tlb(
                     index, addr, x_start, y_start, x_end, y_end, noc_sel, mcast, ordering, linked,
                 )),
             }
         } else {
-            Err(PyException::new_err(
+            Err(LuwenError::new_err(
                 "Could not get PCI interface for this chip.",
             ))
         }
@@ -1182,7 +1565,7 @@ impl PciBlackhole {
             value.pci_interface.borrow_mut().default_tlb = index;
             Ok(())
         } else {
-            Err(PyException::new_err(
+            Err(LuwenError::new_err(
                 "Could not get PCI interface for this chip.",
             ))
         }
@@ -1193,10 +1576,10 @@ impl PciBlackhole {
 
         if let Some(value) = value {
             Ok(value.allocate_dma_buffer(size).map_err(|v| {
-                PyException::new_err(format!("Could not allocate DMA buffer: {}", v))
+                LuwenError::new_err(format!("Could not allocate DMA buffer: {}", v))
             })?)
         } else {
-            Err(PyException::new_err(
+            Err(LuwenError::new_err(
                 "Could not get PCI interface for this chip.",
             ))
         }
@@ -1224,9 +1607,9 @@ impl PciBlackhole {
                     read_threshold,
                     write_threshold,
                 )
-                .map_err(|v| PyException::new_err(format!("Could perform dma config: {}", v)))?)
+                .map_err(|v| LuwenError::new_err(format!("Could perform dma config: {}", v)))?)
         } else {
-            Err(PyException::new_err(
+            Err(LuwenError::new_err(
                 "Could not get PCI interface for this chip.",
             ))
         }
@@ -1244,9 +1627,9 @@ impl PciBlackhole {
         if let Some(value) = value {
             Ok(value
                 .dma_transfer_turbo(addr, physical_dma_buffer, size, write)
-                .map_err(|v| PyException::new_err(format!("Could perform dma transfer: {}", v)))?)
+                .map_err(|v| LuwenError::new_err(format!("Could perform dma transfer: {}", v)))?)
         } else {
-            Err(PyException::new_err(
+            Err(LuwenError::new_err(
                 "Could not get PCI interface for this chip.",
             ))
         }
@@ -1257,7 +1640,7 @@ impl PciBlackhole {
         if let Some(value) = value {
             Ok(value.pci_interface.borrow().device.physical.subsystem_id)
         } else {
-            Err(PyException::new_err(
+            Err(LuwenError::new_err(
                 "Could not get PCI interface for this chip.",
             ))
         }
@@ -1268,41 +1651,18 @@ impl PciBlackhole {
         if let Some(value) = value {
             Ok(value.pci_interface.borrow().device.id)
         } else {
-            Err(PyException::new_err(
+            Err(LuwenError::new_err(
                 "Could not get PCI interface for this chip.",
             ))
         }
     }
 
-    pub fn spi_read(&self, addr: u32, data: pyo3::buffer::PyBuffer<u8>) -> PyResult<()> {
-        Python::with_gil(|_py| {
-            let ptr: *mut u8 = data.buf_ptr().cast();
-            let len = data.len_bytes();
-
-            let data = unsafe { std::slice::from_raw_parts_mut(ptr, len) };
-            self.0
-                .spi_read(addr, data)
-                .map_err(|v| PyException::new_err(v.to_string()))
-        })
-    }
-
-    pub fn spi_write(&self, addr: u32, data: pyo3::buffer::PyBuffer<u8>) -> PyResult<()> {
-        Python::with_gil(|_py| {
-            let ptr: *mut u8 = data.buf_ptr().cast();
-            let len = data.len_bytes();
-
-            let data = unsafe { std::slice::from_raw_parts(ptr, len) };
-            self.0
-                .spi_write(addr, data)
-                .map_err(|v| PyException::new_err(v.to_string()))
-        })
-    }
-
     pub fn get_local_coord(&self) -> PyResult<EthAddr> {
-        self.0
+        Ok(self
+            .0
             .get_local_chip_coord()
-            .map(|v| v.into())
-            .map_err(|v| PyException::new_err(v.to_string()))
+            .map_err(|err| Error::from(err).chip(&self.0))?
+            .into())
     }
 
     pub fn decode_boot_fs_table(&self, tag_name: &str) -> PyResult<Py<PyDict>> {
@@ -1311,7 +1671,7 @@ impl PciBlackhole {
             let result = self
                 .0
                 .decode_boot_fs_table(tag_name)
-                .map_err(|v| PyException::new_err(v.to_string()))?;
+                .map_err(|err| Error::from(err).chip(&self.0))?;
             let py_dict = PyDict::new(py);
             // Convert the HashMap<String, Value> to a pydict
             for (key, value) in result {
@@ -1328,7 +1688,7 @@ impl PciBlackhole {
         let result = self
             .0
             .get_boot_fs_tables_spi_read(tag_name)
-            .map_err(|v| PyException::new_err(v.to_string()))?;
+            .map_err(|err| Error::from(err).chip(&self.0))?;
         Ok(result.unwrap().1.spi_addr)
     }
 
@@ -1337,30 +1697,56 @@ impl PciBlackhole {
         let result = self
             .0
             .get_boot_fs_tables_spi_read(tag_name)
-            .map_err(|v| PyException::new_err(v.to_string()))?;
+            .map_err(|err| Error::from(err).chip(&self.0))?;
         Ok(unsafe { result.unwrap().1.flags.f.image_size() })
     }
 }
 
 common_chip_comms_impls!(PciBlackhole);
+spi_impls!(PciBlackhole);
 
 #[pyclass]
 pub struct UninitPciChip {
     pub chip: UninitChip,
+    pub id: luwen_if::ChipId,
 }
 
 #[pymethods]
 impl UninitPciChip {
-    pub fn init(&self) -> PyResult<PciChip> {
-        let chip = self
-            .chip
-            .clone()
-            .init(&mut |_| Python::with_gil(|py| py.check_signals()));
-        match chip {
-            Ok(chip) => Ok(PciChip(chip)),
-            Err(InitError::PlatformError(err)) => Err(PyException::new_err(err.to_string())),
-            Err(InitError::CallbackError(err)) => Err(err),
-        }
+    pub fn init(&self, py: Python) -> PyResult<PciChip> {
+        let chip = self.chip.clone();
+        py.allow_threads(
+            || match chip.init(&mut |_| Python::with_gil(|py| py.check_signals())) {
+                Ok(chip) => Ok(PciChip(chip)),
+                Err(err) => Err(error::init_error(err)),
+            },
+        )
+    }
+
+    /// The errors recorded for each component during init, as exception objects which can be
+    /// inspected or raised.
+    pub fn init_errors(&self) -> Vec<PyErr> {
+        let chip = match &self.chip {
+            UninitChip::Partially { underlying, .. } => underlying,
+            UninitChip::Initialized(chip) => chip,
+        };
+        self.chip
+            .status()
+            .map(error::init_status_errors)
+            .unwrap_or_default()
+            .into_iter()
+            .map(|err| err.chip(chip).into())
+            .collect()
+    }
+
+    /// The identity of the chip, read during detection.
+    pub fn chip_id(&self) -> ChipId {
+        self.id.into()
+    }
+
+    /// The status recorded when the chip was initialized.
+    pub fn status(&self) -> Option<status::InitStatus> {
+        self.chip.status().map(status::InitStatus::from)
     }
 
     pub fn have_comms(&self) -> bool {
@@ -1395,16 +1781,53 @@ impl UninitPciChip {
 //add arguments, (own or from luwen)
 //from luwen, multiple points to different callback functions
 
+/// Detect all chips without requiring them to finish initializing.
+///
+/// `chips` limits the result to the chips matching at least one of the given selectors, these
+/// stay the same across reboots unlike interface ids: "bdf:0000:41:00.0", "board:100014511abcd",
+/// "board:100014511abcd/1" (the second chip on the board), "eth:0,0,1,0" (rack_x, rack_y,
+/// shelf_x, shelf_y) or "if:3".
 #[pyfunction]
-#[pyo3(signature = (interfaces = None, local_only = false, continue_on_failure = false, chip_filter = None, noc_safe = false, callback = None))]
+#[pyo3(signature = (interfaces = None, local_only = false, continue_on_failure = false, chip_filter = None, noc_safe = false, callback = None, chips = None))]
+#[allow(clippy::too_many_arguments)]
 pub fn detect_chips_fallible(
+    py: Python,
     interfaces: Option<Vec<usize>>,
     local_only: bool,
     continue_on_failure: bool,
     chip_filter: Option<Vec<String>>,
     noc_safe: bool,
     callback: Option<PyObject>,
+    chips: Option<Vec<String>>,
 ) -> PyResult<Vec<UninitPciChip>> {
+    // Detection can take several seconds per chip, let other python threads run in the meantime
+    py.allow_threads(|| {
+        detect_chips_unlocked(
+            interfaces,
+            local_only,
+            continue_on_failure,
+            chip_filter,
+            noc_safe,
+            callback,
+            chips,
+        )
+    })
+}
+
+fn detect_chips_unlocked(
+    interfaces: Option<Vec<usize>>,
+    local_only: bool,
+    continue_on_failure: bool,
+    chip_filter: Option<Vec<String>>,
+    noc_safe: bool,
+    callback: Option<PyObject>,
+    chips: Option<Vec<String>>,
+) -> PyResult<Vec<UninitPciChip>> {
+    let selectors = chips
+        .unwrap_or_default()
+        .iter()
+        .map(|selector| parse_chip_selector(selector))
+        .collect::<PyResult<Vec<_>>>()?;
     let interfaces = interfaces.unwrap_or_default();
 
     let all_devices = luwen_ref::PciDevice::scan();
@@ -1419,7 +1842,7 @@ pub fn detect_chips_fallible(
         }
 
         if !error_interfaces.is_empty() {
-            return Err(PyException::new_err(format!(
+            return Err(LuwenError::new_err(format!(
                 "Could not open TT-PCI device: {:?}; expected one of {:?}",
                 error_interfaces, all_devices
             )));
@@ -1455,7 +1878,7 @@ pub fn detect_chips_fallible(
     let mut converted_chip_filter = Vec::with_capacity(chip_filter.len());
     for filter in chip_filter {
         converted_chip_filter.push(Arch::from_str(&filter).map_err(|value| {
-            PyException::new_err(format!("Could not parse chip arch: {}", value))
+            LuwenError::new_err(format!("Could not parse chip arch: {}", value))
         })?);
     }
     let options = ChipDetectOptions {
@@ -1463,35 +1886,13 @@ pub fn detect_chips_fallible(
         local_only,
         chip_filter: converted_chip_filter,
         noc_safe,
+        // Applied below, once the chips which failed to communicate have been added
+        chip_selectors: Vec::new(),
     };
 
-    #[allow(clippy::type_complexity)]
-    let mut callback: Box<dyn FnMut(luwen_if::chip::ChipDetectState) -> Result<(), PyErr>> =
-        if let Some(callback) = callback {
-            Box::new(move |status| {
-                // Safety: This is extremly unsafe, the alternative would be to copy the status for
-                // every invocation.
-                let status = unsafe { std::mem::transmute(status) };
-                if let Err(err) =
-                    Python::with_gil(|py| callback.call1(py, (PyChipDetectState(status),)))
-                {
-                    Err(err)
-                } else {
-                    Ok(())
-                }
-            })
-        } else {
-            Box::new(|_| Python::with_gil(|py| py.check_signals()))
-        };
-    let mut chips = match luwen_if::detect_chips(root_chips, &mut callback, options) {
-        Ok(chips) => chips,
-        Err(InitError::PlatformError(err)) => {
-            return Err(PyException::new_err(err.to_string()))?;
-        }
-        Err(InitError::CallbackError(err)) => {
-            return Err(err)?;
-        }
-    };
+    let mut callback = detect_callback(callback);
+    let mut chips =
+        luwen_if::detect_chips(root_chips, &mut callback, options).map_err(error::init_error)?;
     for (id, chip, err) in failed_chips.into_iter() {
         let mut status = luwen_if::chip::InitStatus::new_unknown();
         status.comms_status = luwen_if::chip::CommsStatus::CommunicationError(err.to_string());
@@ -1505,44 +1906,81 @@ pub fn detect_chips_fallible(
         );
     }
 
+    let ids = luwen_if::chip_ids(&chips);
     Ok(chips
         .into_iter()
-        .map(|chip| UninitPciChip { chip })
+        .zip(ids)
+        .filter(|(_, id)| id.matches_any(&selectors))
+        .map(|(chip, id)| UninitPciChip { chip, id })
         .collect())
 }
 
 #[pyfunction]
-#[pyo3(signature = (interfaces = None, local_only = false, continue_on_failure = false, chip_filter = None, noc_safe = false, callback = None))]
+#[pyo3(signature = (interfaces = None, local_only = false, continue_on_failure = false, chip_filter = None, noc_safe = false, callback = None, chips = None))]
+#[allow(clippy::too_many_arguments)]
 pub fn detect_chips(
+    py: Python,
     interfaces: Option<Vec<usize>>,
     local_only: bool,
     continue_on_failure: bool,
     chip_filter: Option<Vec<String>>,
     noc_safe: bool,
     callback: Option<PyObject>,
+    chips: Option<Vec<String>>,
 ) -> PyResult<Vec<PciChip>> {
     let chips = detect_chips_fallible(
+        py,
         interfaces,
         local_only,
         continue_on_failure,
         chip_filter,
         noc_safe,
         callback,
+        chips,
     )?;
     let mut output = Vec::with_capacity(chips.len());
     for chip in chips {
-        output.push(chip.init()?);
+        output.push(chip.init(py)?);
     }
     Ok(output)
 }
 
+/// Awaitable version of `detect_chips` for use from asyncio code.
+#[pyfunction]
+#[pyo3(signature = (interfaces = None, local_only = false, continue_on_failure = false, chip_filter = None, noc_safe = false, callback = None, chips = None))]
+#[allow(clippy::too_many_arguments)]
+pub fn detect_chips_async(
+    py: Python,
+    interfaces: Option<Vec<usize>>,
+    local_only: bool,
+    continue_on_failure: bool,
+    chip_filter: Option<Vec<String>>,
+    noc_safe: bool,
+    callback: Option<PyObject>,
+    chips: Option<Vec<String>>,
+) -> PyResult<&PyAny> {
+    run_in_executor(
+        py,
+        wrap_pyfunction!(detect_chips, py)?,
+        (
+            interfaces,
+            local_only,
+            continue_on_failure,
+            chip_filter,
+            noc_safe,
+            callback,
+            chips,
+        ),
+    )
+}
+
 #[pyfunction]
 pub fn pci_scan() -> Vec<usize> {
     luwen_ref::PciDevice::scan()
 }
 
 #[pymodule]
-fn pyluwen(_py: Python, m: &PyModule) -> PyResult<()> {
+fn pyluwen(py: Python, m: &PyModule) -> PyResult<()> {
     m.add_class::<PciChip>()?;
     m.add_class::<UninitPciChip>()?;
     m.add_class::<PciWormhole>()?;
@@ -1551,13 +1989,18 @@ fn pyluwen(_py: Python, m: &PyModule) -> PyResult<()> {
     m.add_class::<DmaBuffer>()?;
     m.add_class::<AxiData>()?;
     m.add_class::<Telemetry>()?;
+    m.add_class::<ChipId>()?;
 
     m.add_class::<PciBlackhole>()?;
 
     m.add_wrapped(wrap_pyfunction!(detect_chips))?;
     m.add_wrapped(wrap_pyfunction!(detect_chips_fallible))?;
+    m.add_wrapped(wrap_pyfunction!(detect_chips_async))?;
     m.add_wrapped(wrap_pyfunction!(pci_scan))?;
 
+    error::register(py, m)?;
+    status::register(m)?;
+
     Ok(())
 }
 
@@ -0,0 +1,389 @@
+// SPDX-FileCopyrightText: © 2024 Tenstorrent Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Python views of the chip initialization status.
+//!
+//! These are snapshots, the status objects handed to the detect callback are only valid for the
+//! duration of the call so everything is copied out when the view is created.
+
+use std::convert::Infallible;
+
+use luwen_if::chip::{
+    ArcInitError, CommsStatus, ComponentStatusInfo, CpuInitError, DramInitError, EthernetInitError,
+    EthernetPartialInitError,
+};
+use luwen_if::error::ArcReadyError;
+use pyo3::prelude::*;
+
+/// Details of why a single component instance is not initialized.
+#[pyclass]
+#[derive(Clone, Default)]
+pub struct ComponentError {
+    /// The kind of error, i.e. "fw_corrupted" or "waiting_for_init".
+    #[pyo3(get)]
+    pub kind: String,
+    #[pyo3(get)]
+    pub message: String,
+    /// Why ARC is not ready when kind is "waiting_for_init".
+    #[pyo3(get)]
+    pub reason: Option<String>,
+    #[pyo3(get)]
+    pub post_code: Option<u32>,
+    /// The message ARC is busy with.
+    #[pyo3(get)]
+    pub msg: Option<u32>,
+    #[pyo3(get)]
+    pub version: Option<u32>,
+    #[pyo3(get)]
+    pub required: Option<u32>,
+    #[pyo3(get)]
+    pub channel_status: Option<String>,
+}
+
+#[pymethods]
+impl ComponentError {
+    fn __str__(&self) -> String {
+        self.message.clone()
+    }
+
+    fn __repr__(&self) -> String {
+        format!("ComponentError({}: {})", self.kind, self.message)
+    }
+}
+
+impl ComponentError {
+    fn new(kind: &str, message: String) -> Self {
+        ComponentError {
+            kind: kind.to_string(),
+            message,
+            ..Default::default()
+        }
+    }
+}
+
+trait IntoComponentError {
+    fn component_error(&self) -> ComponentError;
+}
+
+impl IntoComponentError for Infallible {
+    fn component_error(&self) -> ComponentError {
+        match *self {}
+    }
+}
+
+impl IntoComponentError for CpuInitError {
+    fn component_error(&self) -> ComponentError {
+        match *self {}
+    }
+}
+
+impl IntoComponentError for ArcInitError {
+    fn component_error(&self) -> ComponentError {
+        let message = self.to_string();
+        match self {
+            ArcInitError::FwCorrupted => ComponentError::new("fw_corrupted", message),
+            ArcInitError::NoAccess => ComponentError::new("no_access", message),
+            ArcInitError::Hung => ComponentError::new("hung", message),
+            ArcInitError::FwVersionTooOld { version, required } => ComponentError {
+                version: *version,
+                required: Some(*required),
+                ..ComponentError::new("fw_version_too_old", message)
+            },
+            ArcInitError::WaitingForInit(err) => {
+                let base = ComponentError::new("waiting_for_init", message);
+                let reason = |reason: &str| Some(reason.to_string());
+                match err {
+                    ArcReadyError::NoAccess => ComponentError {
+                        reason: reason("no_access"),
+                        ..base
+                    },
+                    ArcReadyError::WatchdogTriggered => ComponentError {
+                        reason: reason("watchdog_triggered"),
+                        ..base
+                    },
+                    ArcReadyError::BootIncomplete => ComponentError {
+                        reason: reason("boot_incomplete"),
+                        ..base
+                    },
+                    ArcReadyError::Asleep => ComponentError {
+                        reason: reason("asleep"),
+                        ..base
+                    },
+                    ArcReadyError::OutstandingPcieDMA => ComponentError {
+                        reason: reason("outstanding_pcie_dma"),
+                        ..base
+                    },
+                    ArcReadyError::MessageQueued(msg) => ComponentError {
+                        reason: reason("message_queued"),
+                        msg: Some(*msg),
+                        ..base
+                    },
+                    ArcReadyError::HandlingMessage(msg) => ComponentError {
+                        reason: reason("handling_message"),
+                        msg: Some(*msg),
+                        ..base
+                    },
+                    ArcReadyError::OldPostCode(post_code) => ComponentError {
+                        reason: reason("old_post_code"),
+                        post_code: Some(*post_code),
+                        ..base
+                    },
+                }
+            }
+        }
+    }
+}
+
+impl IntoComponentError for DramInitError {
+    fn component_error(&self) -> ComponentError {
+        let message = self.to_string();
+        match self {
+            DramInitError::NotTrained(channel) => ComponentError {
+                channel_status: Some(channel.to_string()),
+                ..ComponentError::new("not_trained", message)
+            },
+        }
+    }
+}
+
+impl IntoComponentError for EthernetInitError {
+    fn component_error(&self) -> ComponentError {
+        let message = self.to_string();
+        match self {
+            EthernetInitError::FwCorrupted => ComponentError::new("fw_corrupted", message),
+            EthernetInitError::NotTrained => ComponentError::new("not_trained", message),
+        }
+    }
+}
+
+impl IntoComponentError for EthernetPartialInitError {
+    fn component_error(&self) -> ComponentError {
+        let message = self.to_string();
+        match self {
+            EthernetPartialInitError::FwOverwritten => {
+                ComponentError::new("fw_overwritten", message)
+            }
+        }
+    }
+}
+
+/// The state of a single instance of a component, i.e. one DRAM channel or ethernet port.
+#[pyclass]
+#[derive(Clone)]
+pub struct WaitStatus {
+    /// One of "not_present", "waiting", "just_finished", "done", "no_check", "timeout",
+    /// "not_initialized" or "error".
+    #[pyo3(get)]
+    pub state: &'static str,
+    /// What is being waited on, or the error message.
+    #[pyo3(get)]
+    pub message: Option<String>,
+    /// The timeout in seconds when state is "timeout".
+    #[pyo3(get)]
+    pub timeout: Option<f64>,
+    #[pyo3(get)]
+    pub error: Option<ComponentError>,
+}
+
+#[pymethods]
+impl WaitStatus {
+    pub fn is_done(&self) -> bool {
+        self.state == "done"
+    }
+
+    fn __repr__(&self) -> String {
+        match &self.message {
+            Some(message) => format!("WaitStatus({}: {message})", self.state),
+            None => format!("WaitStatus({})", self.state),
+        }
+    }
+}
+
+impl<P: IntoComponentError, E: IntoComponentError> From<&luwen_if::chip::WaitStatus<P, E>>
+    for WaitStatus
+{
+    fn from(value: &luwen_if::chip::WaitStatus<P, E>) -> Self {
+        use luwen_if::chip::WaitStatus as Status;
+
+        fn state(state: &'static str) -> WaitStatus {
+            WaitStatus {
+                state,
+                message: None,
+                timeout: None,
+                error: None,
+            }
+        }
+        let error = |name, error: ComponentError| WaitStatus {
+            message: Some(error.message.clone()),
+            error: Some(error),
+            ..state(name)
+        };
+
+        match value {
+            Status::NotPresent => state("not_present"),
+            Status::Waiting(message) => WaitStatus {
+                message: message.clone(),
+                ..state("waiting")
+            },
+            Status::JustFinished => state("just_finished"),
+            Status::Done => state("done"),
+            Status::NoCheck => state("no_check"),
+            Status::Timeout(timeout) => WaitStatus {
+                timeout: Some(timeout.as_secs_f64()),
+                ..state("timeout")
+            },
+            Status::NotInitialized(err) => error("not_initialized", err.component_error()),
+            Status::Error(err) => error("error", err.component_error()),
+        }
+    }
+}
+
+/// The init status of one component type on a chip (ARC, DRAM, ETH or CPU).
+#[pyclass]
+#[derive(Clone)]
+pub struct ComponentStatus {
+    #[pyo3(get)]
+    pub name: String,
+    /// The state of each instance of the component.
+    #[pyo3(get)]
+    pub items: Vec<WaitStatus>,
+    /// How long to wait for the component to initialize in seconds.
+    #[pyo3(get)]
+    pub timeout: f64,
+    /// How long we have been waiting for the component in seconds.
+    #[pyo3(get)]
+    pub elapsed: f64,
+    summary: String,
+    waiting: bool,
+    present: bool,
+    error: bool,
+}
+
+#[pymethods]
+impl ComponentStatus {
+    pub fn is_waiting(&self) -> bool {
+        self.waiting
+    }
+
+    pub fn is_present(&self) -> bool {
+        self.present
+    }
+
+    pub fn has_error(&self) -> bool {
+        self.error
+    }
+
+    fn __len__(&self) -> usize {
+        self.items.len()
+    }
+
+    fn __str__(&self) -> String {
+        self.summary.clone()
+    }
+}
+
+impl<P, E> From<&ComponentStatusInfo<P, E>> for ComponentStatus
+where
+    P: IntoComponentError + std::fmt::Display,
+    E: IntoComponentError + std::fmt::Display,
+{
+    fn from(value: &ComponentStatusInfo<P, E>) -> Self {
+        ComponentStatus {
+            name: value.name.clone(),
+            items: value.wait_status.iter().map(WaitStatus::from).collect(),
+            timeout: value.timeout.as_secs_f64(),
+            elapsed: value.start_time.elapsed().as_secs_f64(),
+            summary: value.to_string(),
+            waiting: value.is_waiting(),
+            present: value.is_present(),
+            error: value.has_error(),
+        }
+    }
+}
+
+/// The init status of a chip.
+#[pyclass]
+#[derive(Clone)]
+pub struct InitStatus {
+    /// None if we can communicate with the chip, otherwise the reason we cannot.
+    #[pyo3(get)]
+    pub comms_error: Option<String>,
+    #[pyo3(get)]
+    pub arc: ComponentStatus,
+    #[pyo3(get)]
+    pub dram: ComponentStatus,
+    #[pyo3(get)]
+    pub eth: ComponentStatus,
+    #[pyo3(get)]
+    pub cpu: ComponentStatus,
+    #[pyo3(get)]
+    pub noc_safe: bool,
+    /// True until we have been able to check the state of the chip.
+    #[pyo3(get)]
+    pub unknown_state: bool,
+    summary: String,
+    waiting: bool,
+    error: bool,
+}
+
+#[pymethods]
+impl InitStatus {
+    pub fn can_communicate(&self) -> bool {
+        self.comms_error.is_none()
+    }
+
+    pub fn is_waiting(&self) -> bool {
+        self.waiting
+    }
+
+    pub fn init_complete(&self) -> bool {
+        !self.waiting
+    }
+
+    pub fn has_error(&self) -> bool {
+        self.error
+    }
+
+    /// All component statuses in the order they are initialized.
+    pub fn components(&self) -> Vec<ComponentStatus> {
+        vec![
+            self.arc.clone(),
+            self.dram.clone(),
+            self.eth.clone(),
+            self.cpu.clone(),
+        ]
+    }
+
+    fn __str__(&self) -> String {
+        self.summary.clone()
+    }
+}
+
+impl From<&luwen_if::chip::InitStatus> for InitStatus {
+    fn from(value: &luwen_if::chip::InitStatus) -> Self {
+        InitStatus {
+            comms_error: match &value.comms_status {
+                CommsStatus::CanCommunicate => None,
+                CommsStatus::CommunicationError(err) => Some(err.clone()),
+            },
+            arc: (&value.arc_status).into(),
+            dram: (&value.dram_status).into(),
+            eth: (&value.eth_status).into(),
+            cpu: (&value.cpu_status).into(),
+            noc_safe: value.init_options.noc_safe,
+            unknown_state: value.unknown_state,
+            summary: value.to_string(),
+            waiting: value.is_waiting(),
+            error: value.has_error(),
+        }
+    }
+}
+
+pub fn register(m: &PyModule) -> PyResult<()> {
+    m.add_class::<InitStatus>()?;
+    m.add_class::<ComponentStatus>()?;
+    m.add_class::<WaitStatus>()?;
+    m.add_class::<ComponentError>()?;
+
+    Ok(())
+}
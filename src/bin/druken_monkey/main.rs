@@ -1,5 +1,5 @@
 use luwen_if::{
-    chip::{Chip, HlComms, HlCommsInterface, InitStatus},
+    chip::{Chip, HlComms, HlCommsInterface, InitStatus, WaitError},
     error::{BtWrapper, PlatformError},
     ArcMsgError, ArcMsgProtocolError, ArcState, ChipImpl,
 };
@@ -261,7 +261,12 @@ fn main() {
                 };
                 let duration = Duration::from_secs(1);
                 let expected = PlatformError::ArcMsgError(ArcMsgError::ProtocolError {
-                    source: ArcMsgProtocolError::Timeout(duration),
+                    source: ArcMsgProtocolError::Timeout(Box::new(WaitError::Timeout {
+                        what: "ARC to respond".to_string(),
+                        elapsed: duration,
+                        polls: 0,
+                        last: String::new(),
+                    })),
                     backtrace: BtWrapper(Backtrace::capture()),
                 });
 
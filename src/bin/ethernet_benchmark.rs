@@ -78,30 +78,71 @@ fn read_write_test(
     Ok((write_time, read_time))
 }
 
+fn eth_stats(chip: &impl HlComms) -> luwen_ref::EthTransferStats {
+    let mut total = luwen_ref::EthTransferStats::default();
+    if let Some(pci) = chip
+        .comms_obj()
+        .1
+        .as_any()
+        .downcast_ref::<CallbackStorage<luwen_ref::ExtendedPciDeviceWrapper>>()
+    {
+        for (_, stats) in pci.user_data.eth_transfer_stats() {
+            total.accumulate(&stats);
+        }
+    }
+
+    total
+}
+
+fn mb_per_s(bytes: u64, secs: f64) -> f64 {
+    (bytes as f64 / 1_048_576.0) / secs
+}
+
 pub fn main() -> Result<(), LuwenError> {
     let chips = luwen_ref::detect_chips()?;
 
     for (chip_index, chip) in chips.into_iter().enumerate() {
-        println!("Running on {chip_index}");
-        // let size = 1 << 31;
-        let size = 1 << 19;
-        // let size = 1000;
-        let (write_time, read_time) = if let Some(wh) = chip.as_wh() {
-            read_write_test(wh, 0, 0, size, true).unwrap()
-        } else if let Some(gs) = chip.as_gs() {
-            read_write_test(gs, 1, 0, size, true).unwrap()
-        } else if let Some(bh) = chip.as_bh() {
-            read_write_test(bh, 1, 11, size, false).unwrap()
-        } else {
-            unimplemented!("Chip of arch {:?} not supported", chip.get_arch());
-        };
-
+        let is_remote = chip.as_wh().map(|wh| wh.is_remote).unwrap_or(false);
         println!(
-            "Chip {} write: {:.1} MB/s, read: {:.1} MB/s",
-            chip_index,
-            (size as f64 / 1_048_576.0) / write_time,
-            (size as f64 / 1_048_576.0) / read_time
+            "Running on {chip_index} ({})",
+            if is_remote { "remote" } else { "local" }
         );
+
+        for size in [1 << 12, 1 << 16, 1 << 19, 1 << 22] {
+            let before = eth_stats(&chip);
+
+            let (write_time, read_time) = if let Some(wh) = chip.as_wh() {
+                read_write_test(wh, 0, 0, size, true).unwrap()
+            } else if let Some(gs) = chip.as_gs() {
+                read_write_test(gs, 1, 0, size, true).unwrap()
+            } else if let Some(bh) = chip.as_bh() {
+                read_write_test(bh, 1, 11, size, false).unwrap()
+            } else {
+                unimplemented!("Chip of arch {:?} not supported", chip.get_arch());
+            };
+
+            println!(
+                "Chip {} {:>8} bytes write: {:.1} MB/s, read: {:.1} MB/s",
+                chip_index,
+                size,
+                mb_per_s(size as u64, write_time),
+                mb_per_s(size as u64, read_time)
+            );
+
+            if is_remote {
+                let after = eth_stats(&chip);
+                let bytes = after.bytes - before.bytes;
+                let requests = after.requests - before.requests;
+                let elapsed = after.elapsed - before.elapsed;
+                if requests > 0 {
+                    println!(
+                        "    ethernet: {} requests, {:.1} MB/s on the link",
+                        requests,
+                        mb_per_s(bytes, elapsed.as_secs_f64())
+                    );
+                }
+            }
+        }
     }
 
     Ok(())
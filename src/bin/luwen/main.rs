@@ -0,0 +1,194 @@
+// SPDX-FileCopyrightText: © 2024 Tenstorrent Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Command line tool for inspecting and debugging chips.
+
+mod mem;
+mod pcie;
+mod reset;
+mod select;
+mod spi;
+mod telemetry;
+mod topology;
+
+use std::time::Duration;
+
+use clap::{Parser, Subcommand};
+use luwen_if::{chip::ArcMsgOptions, ArcMsg, ArcMsgOk, ChipImpl, ChipSelector};
+
+use select::{parse_num, DetectedChip};
+
+pub type Result<T> = std::result::Result<T, Box<dyn std::error::Error>>;
+
+#[derive(Parser)]
+#[command(name = "luwen", about = "Inspect and debug Tenstorrent chips")]
+struct Args {
+    /// Only operate on the matching chips, can be given more than once.
+    /// Accepts a pci address (bdf:0000:41:00.0), a board id (board:<hex>, board:<hex>/<location>),
+    /// an ethernet coordinate (eth:rack_x,rack_y,shelf_x,shelf_y) or an interface id (if:3 or 3).
+    #[arg(long, short, global = true)]
+    chip: Vec<ChipSelector>,
+
+    /// Don't search for chips over ethernet.
+    #[arg(long, global = true)]
+    local_only: bool,
+
+    /// Also operate on chips which did not finish initializing.
+    #[arg(long, global = true)]
+    force: bool,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// List all chips along with their initialization status.
+    List,
+    /// Show chip telemetry.
+    Telemetry(telemetry::TelemetryArgs),
+    /// Send an arc message to a single chip and print the response.
+    ArcMsg {
+        #[arg(value_parser = parse_num::<u16>)]
+        msg: u16,
+        #[arg(value_parser = parse_num::<u16>, default_value = "0")]
+        arg0: u16,
+        #[arg(value_parser = parse_num::<u16>, default_value = "0")]
+        arg1: u16,
+        /// Don't wait for the arc to respond.
+        #[arg(long)]
+        no_wait: bool,
+        /// Seconds to wait for the response.
+        #[arg(long, default_value_t = 1.0)]
+        timeout: f64,
+    },
+    /// Read or write a register by its axi path (i.e. ARC_RESET.SCRATCH[0]), writes need a single
+    /// chip.
+    #[command(subcommand)]
+    Reg(mem::RegCommand),
+    /// Read or write memory over the noc, writes need a single chip.
+    #[command(subcommand)]
+    Noc(mem::NocCommand),
+    /// Read or write the spi flash.
+    #[command(subcommand)]
+    Spi(spi::SpiCommand),
+    /// Inspect the blackhole boot filesystem.
+    #[command(subcommand)]
+    Bootfs(spi::BootfsCommand),
+    /// Show the ethernet connections between chips.
+    Topology,
+    /// Show the pcie link, error counters and upstream bridges of local chips.
+    Pcie(pcie::PcieArgs),
+    /// Reset chips.
+    Reset(reset::ResetArgs),
+}
+
+fn list(chips: &[DetectedChip]) {
+    println!(
+        "{:<5} {:<10} {:<9} {:<18} {:<16} STATUS",
+        "CHIP", "ARCH", "INTERFACE", "BOARD ID", "COORD"
+    );
+    for chip in chips {
+        let interface = chip
+            .interface
+            .map(|id| id.to_string())
+            .unwrap_or_else(|| "remote".to_string());
+        let board_id = chip
+            .board_id
+            .map(|id| format!("{id:x}"))
+            .unwrap_or_else(|| "-".to_string());
+        let coord = chip
+            .coord
+            .map(|coord| coord.to_string())
+            .unwrap_or_else(|| "-".to_string());
+
+        let status = match &chip.status {
+            None => "ready".to_string(),
+            Some(status) if !status.init_complete() => "init incomplete".to_string(),
+            Some(_) => "init failed".to_string(),
+        };
+
+        println!(
+            "{:<5} {:<10} {:<9} {:<18} {:<16} {status}",
+            chip.index,
+            format!("{:?}", chip.arch()),
+            interface,
+            board_id,
+            coord
+        );
+        if let Some(status) = &chip.status {
+            print!("{status}");
+        }
+    }
+}
+
+fn arc_msg(chip: &DetectedChip, msg: ArcMsg, wait_for_done: bool, timeout: Duration) -> Result<()> {
+    let result = chip.chip.arc_msg(ArcMsgOptions {
+        msg,
+        wait_for_done,
+        timeout,
+        ..Default::default()
+    })?;
+
+    match result {
+        ArcMsgOk::Ok { rc, arg } => println!("{}: rc: {rc:#x}, arg: {arg:#x}", chip.label()),
+        ArcMsgOk::OkNoWait => println!("{}: sent", chip.label()),
+    }
+
+    Ok(())
+}
+
+fn run(args: Args) -> Result<()> {
+    // Reset works on the pci interfaces directly so that it can be used on chips which are
+    // too broken to detect.
+    if let Command::Reset(reset_args) = &args.command {
+        return reset::run(reset_args, &args.chip, args.local_only);
+    }
+
+    let chips = select::detect(args.local_only)?;
+    if let Command::List = args.command {
+        // Listing is how you find out why a chip is broken, so always show them
+        list(&select::select(chips, &args.chip, true)?);
+        return Ok(());
+    }
+
+    let chips = select::select(chips, &args.chip, args.force)?;
+    if chips.is_empty() {
+        return Err("No chips to operate on".into());
+    }
+
+    match args.command {
+        Command::List | Command::Reset(_) => unreachable!(),
+        Command::Telemetry(telemetry_args) => telemetry::run(&telemetry_args, &chips)?,
+        Command::ArcMsg {
+            msg,
+            arg0,
+            arg1,
+            no_wait,
+            timeout,
+        } => {
+            // Arbitrary messages can change the chip state, so they are only sent to one chip
+            arc_msg(
+                select::single(&chips)?,
+                ArcMsg::Raw { msg, arg0, arg1 },
+                !no_wait,
+                Duration::from_secs_f64(timeout),
+            )?;
+        }
+        Command::Reg(command) => mem::run_reg(&command, &chips)?,
+        Command::Noc(command) => mem::run_noc(&command, &chips)?,
+        Command::Spi(command) => spi::run_spi(&command, &chips)?,
+        Command::Bootfs(command) => spi::run_bootfs(&command, &chips)?,
+        Command::Topology => topology::run(&chips)?,
+        Command::Pcie(pcie_args) => pcie::run(&pcie_args, &chips)?,
+    }
+
+    Ok(())
+}
+
+fn main() {
+    if let Err(err) = run(Args::parse()) {
+        eprintln!("Error: {err}");
+        std::process::exit(1);
+    }
+}
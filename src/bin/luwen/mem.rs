@@ -0,0 +1,127 @@
+// SPDX-FileCopyrightText: © 2024 Tenstorrent Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use clap::Subcommand;
+use luwen_if::chip::{HlComms, HlCommsInterface};
+
+use crate::{
+    select::{parse_num, single, DetectedChip},
+    Result,
+};
+
+#[derive(Subcommand)]
+pub enum RegCommand {
+    Read {
+        path: String,
+    },
+    Write {
+        path: String,
+        #[arg(value_parser = parse_num::<u32>)]
+        value: u32,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum NocCommand {
+    Read {
+        #[arg(value_parser = parse_num::<u8>)]
+        x: u8,
+        #[arg(value_parser = parse_num::<u8>)]
+        y: u8,
+        #[arg(value_parser = parse_num::<u64>)]
+        addr: u64,
+        /// Number of bytes to read.
+        #[arg(long, value_parser = parse_num::<usize>, default_value = "4")]
+        len: usize,
+        #[arg(long, default_value_t = 0)]
+        noc_id: u8,
+    },
+    Write {
+        #[arg(value_parser = parse_num::<u8>)]
+        x: u8,
+        #[arg(value_parser = parse_num::<u8>)]
+        y: u8,
+        #[arg(value_parser = parse_num::<u64>)]
+        addr: u64,
+        /// 32 bit words to write starting at addr.
+        #[arg(value_parser = parse_num::<u32>, required = true)]
+        values: Vec<u32>,
+        #[arg(long, default_value_t = 0)]
+        noc_id: u8,
+    },
+}
+
+/// Print data as rows of 16 bytes, prefixed with the address of the first byte.
+pub fn hexdump(addr: u64, data: &[u8]) {
+    for (row, chunk) in data.chunks(16).enumerate() {
+        let bytes = chunk
+            .iter()
+            .map(|b| format!("{b:02x}"))
+            .collect::<Vec<_>>()
+            .join(" ");
+        let ascii = chunk
+            .iter()
+            .map(|&b| {
+                if b.is_ascii_graphic() || b == b' ' {
+                    b as char
+                } else {
+                    '.'
+                }
+            })
+            .collect::<String>();
+        println!("{:#010x}: {bytes:<47}  {ascii}", addr + (row * 16) as u64);
+    }
+}
+
+pub fn run_reg(command: &RegCommand, chips: &[DetectedChip]) -> Result<()> {
+    match command {
+        RegCommand::Read { path } => {
+            for chip in chips {
+                let value = chip.chip.axi_sread32(path)?;
+                println!("{}: {value:#010x}", chip.label());
+            }
+        }
+        RegCommand::Write { path, value } => {
+            // Writes need exactly one chip, so that a missing --chip can't write to the whole host
+            single(chips)?.chip.axi_swrite32(path, *value)?;
+        }
+    }
+
+    Ok(())
+}
+
+pub fn run_noc(command: &NocCommand, chips: &[DetectedChip]) -> Result<()> {
+    match command {
+        NocCommand::Read {
+            x,
+            y,
+            addr,
+            len,
+            noc_id,
+        } => {
+            for chip in chips {
+                let mut data = vec![0; *len];
+                chip.chip.noc_read(*noc_id, *x, *y, *addr, &mut data)?;
+
+                println!("{}:", chip.label());
+                hexdump(*addr, &data);
+            }
+        }
+        NocCommand::Write {
+            x,
+            y,
+            addr,
+            values,
+            noc_id,
+        } => {
+            let chip = single(chips)?;
+            let data = values
+                .iter()
+                .flat_map(|v| v.to_le_bytes())
+                .collect::<Vec<_>>();
+            chip.chip.noc_write(*noc_id, *x, *y, *addr, &data)?;
+        }
+    }
+
+    Ok(())
+}
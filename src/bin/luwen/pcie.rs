@@ -0,0 +1,97 @@
+// SPDX-FileCopyrightText: © 2024 Tenstorrent Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use std::path::PathBuf;
+
+use clap::Args;
+use luwen_if::{AerCounters, ChipImpl, PcieAer, PcieLink};
+
+use crate::{select::DetectedChip, Result};
+
+#[derive(Args)]
+pub struct PcieArgs {
+    /// Where sysfs is mounted.
+    #[arg(long, default_value = luwen_if::SYSFS_ROOT)]
+    sysfs_root: PathBuf,
+}
+
+fn format_link(link: &PcieLink) -> String {
+    format!(
+        "gen{} x{} (max gen{} x{}){}",
+        link.current_gen,
+        link.current_width,
+        link.max_gen,
+        link.max_width,
+        if link.is_degraded() { " DEGRADED" } else { "" }
+    )
+}
+
+fn format_aer(aer: &PcieAer) -> String {
+    let total = |counters: &Option<AerCounters>| {
+        counters
+            .as_ref()
+            .map_or("-".to_string(), |counters| counters.total.to_string())
+    };
+    format!(
+        "correctable {}, nonfatal {}, fatal {}",
+        total(&aer.correctable),
+        total(&aer.nonfatal),
+        total(&aer.fatal)
+    )
+}
+
+pub fn run(args: &PcieArgs, chips: &[DetectedChip]) -> Result<()> {
+    for chip in chips {
+        let Some(info) = chip.chip.get_device_info()? else {
+            continue;
+        };
+
+        let health = match info.pcie_health_from(&args.sysfs_root) {
+            Ok(health) => health,
+            Err(err) => {
+                println!("{}: {err}", chip.label());
+                continue;
+            }
+        };
+
+        println!("{} at {}", chip.label(), health.bdf);
+        println!("    link: {}", format_link(&health.link));
+        println!("    aer: {}", format_aer(&health.aer));
+        println!(
+            "    link down events: {}, retrain events: {}",
+            health.link_down_events(),
+            health.retrain_events()
+        );
+        println!(
+            "    numa node: {}, iommu group: {}",
+            health
+                .numa_node
+                .map_or("-".to_string(), |node| node.to_string()),
+            health
+                .iommu_group
+                .map_or("-".to_string(), |group| group.to_string())
+        );
+        for bar in &health.bars {
+            println!(
+                "    bar{}: {:#x} size {:#x} flags {:#x}",
+                bar.index,
+                bar.start,
+                bar.size(),
+                bar.flags
+            );
+        }
+        for bridge in &health.upstream {
+            println!(
+                "    upstream {}: {}, aer: {}",
+                bridge.bdf,
+                bridge
+                    .link
+                    .as_ref()
+                    .map_or("no link".to_string(), format_link),
+                format_aer(&bridge.aer)
+            );
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,73 @@
+// SPDX-FileCopyrightText: © 2024 Tenstorrent Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use std::time::Duration;
+
+use clap::Args;
+use luwen_if::ChipSelector;
+use luwen_ref::reset::ResetOptions;
+
+use crate::Result;
+
+#[derive(Args)]
+pub struct ResetArgs {
+    /// Don't retrain the pcie link before the reset.
+    #[arg(long)]
+    no_link_reset: bool,
+    /// Seconds to wait for the chips to come out of reset.
+    #[arg(long, default_value_t = 2.0)]
+    timeout: f64,
+    /// Don't rerun chip detection after the reset.
+    #[arg(long)]
+    no_verify: bool,
+}
+
+pub fn run(args: &ResetArgs, selectors: &[ChipSelector], local_only: bool) -> Result<()> {
+    let options = ResetOptions {
+        link_reset: !args.no_link_reset,
+        reset_remote: !local_only,
+        timeout: Duration::from_secs_f64(args.timeout),
+        verify: !args.no_verify,
+        ..Default::default()
+    };
+
+    // Remote chips resolve to the interface they are reached through, same as the reset tool
+    let interfaces = luwen_ref::resolve_interfaces(selectors)?;
+    println!("Resetting interfaces {interfaces:?}");
+
+    let report = luwen_ref::reset::reset_interfaces(&interfaces, &options)?;
+
+    for remote in &report.remote {
+        println!(
+            "Remote chip {} ({} hops): {}",
+            remote.coord,
+            remote
+                .hops
+                .map(|h| h.to_string())
+                .unwrap_or_else(|| "?".to_string()),
+            remote.status
+        );
+    }
+    for interface in &report.interfaces {
+        println!("Interface {}: {}", interface.interface, interface.status);
+        if let Some(Err(err)) = &interface.link {
+            println!("    link reset failed: {err}");
+        }
+        if let Some(Err(err)) = &interface.restore {
+            println!("    restore failed: {err}");
+        }
+    }
+    match &report.verification {
+        Some(Ok(verification)) if !verification.is_ok() => {
+            println!("Chips did not come back as expected: {verification:?}")
+        }
+        Some(Err(err)) => println!("Failed to redetect chips: {err}"),
+        _ => {}
+    }
+
+    if !report.is_ok() {
+        return Err("Reset did not complete cleanly".into());
+    }
+
+    Ok(())
+}
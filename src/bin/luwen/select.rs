@@ -0,0 +1,151 @@
+// SPDX-FileCopyrightText: © 2024 Tenstorrent Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use luwen_core::Arch;
+use luwen_if::{
+    chip::{Chip, InitStatus},
+    ChipDetectOptions, ChipId, ChipImpl, ChipSelector, EthAddr,
+};
+use luwen_ref::error::LuwenError;
+
+/// Parse a number which may be given in hex (0x prefix) or decimal.
+pub fn parse_num<T: TryFrom<u64>>(value: &str) -> Result<T, String> {
+    let parsed = if let Some(hex) = value.strip_prefix("0x") {
+        u64::from_str_radix(&hex.replace('_', ""), 16)
+    } else {
+        value.replace('_', "").parse()
+    };
+
+    parsed
+        .map_err(|err| format!("{value:?} is not a number: {err}"))?
+        .try_into()
+        .map_err(|_| format!("{value} is out of range"))
+}
+
+pub struct DetectedChip {
+    /// Position in the detection order, this is the chip id that other tools use.
+    pub index: usize,
+    pub chip: Chip,
+    /// The init status of chips which did not finish initializing cleanly.
+    pub status: Option<InitStatus>,
+    /// What the chip is matched against when selecting chips.
+    pub id: ChipId,
+
+    pub interface: Option<u32>,
+    pub board_id: Option<u64>,
+    pub coord: Option<EthAddr>,
+}
+
+impl DetectedChip {
+    pub fn arch(&self) -> Arch {
+        self.chip.get_arch()
+    }
+
+    pub fn matches(&self, selector: &ChipSelector) -> bool {
+        self.id.matches(selector)
+    }
+
+    pub fn is_ready(&self) -> bool {
+        self.status.is_none()
+    }
+
+    pub fn label(&self) -> String {
+        let mut label = format!("{} ({:?}", self.index, self.arch());
+        if let Some(board_id) = self.board_id {
+            label.push_str(&format!(" {board_id:x}"));
+        }
+        label.push(')');
+        label
+    }
+}
+
+pub fn detect(local_only: bool) -> Result<Vec<DetectedChip>, LuwenError> {
+    let chips = luwen_ref::detect_chips_options(
+        ChipDetectOptions::default()
+            .continue_on_failure(true)
+            .local_only(local_only),
+    )?;
+
+    let ids = luwen_if::chip_ids(&chips);
+
+    let mut output = Vec::with_capacity(chips.len());
+    for (index, (chip, id)) in chips.into_iter().zip(ids).enumerate() {
+        let status = chip.status().cloned();
+        let arc_alive = chip.arc_alive();
+        let chip = chip.upgrade();
+
+        let interface = chip
+            .get_device_info()
+            .ok()
+            .flatten()
+            .map(|info| info.interface_id);
+        let board_id = if arc_alive {
+            chip.get_telemetry().ok().map(|t| t.board_id)
+        } else {
+            None
+        };
+        let coord = if let Some(wh) = chip.as_wh() {
+            wh.get_local_chip_coord().ok()
+        } else if let Some(bh) = chip.as_bh() {
+            bh.get_local_chip_coord().ok()
+        } else {
+            None
+        };
+
+        output.push(DetectedChip {
+            index,
+            chip,
+            status,
+            id,
+            interface,
+            board_id,
+            coord,
+        });
+    }
+
+    Ok(output)
+}
+
+/// Filter the detected chips down to the ones picked by the selectors (all of them if there are
+/// none). Chips which didn't finish init are only kept when `force` is set.
+pub fn select(
+    chips: Vec<DetectedChip>,
+    selectors: &[ChipSelector],
+    force: bool,
+) -> Result<Vec<DetectedChip>, String> {
+    for selector in selectors {
+        if !chips.iter().any(|chip| chip.matches(selector)) {
+            return Err(format!("No chip matches {selector}"));
+        }
+    }
+
+    let mut output = Vec::new();
+    for chip in chips {
+        if !chip.id.matches_any(selectors) {
+            continue;
+        }
+
+        if !chip.is_ready() && !force {
+            eprintln!(
+                "Skipping chip {} because it did not finish initializing (use --force to include it)",
+                chip.label()
+            );
+            continue;
+        }
+
+        output.push(chip);
+    }
+
+    Ok(output)
+}
+
+/// For commands which are too dangerous or verbose to run on every chip at once.
+pub fn single(chips: &[DetectedChip]) -> Result<&DetectedChip, String> {
+    match chips {
+        [chip] => Ok(chip),
+        _ => Err(format!(
+            "This command needs exactly one chip, but {} were selected (use --chip)",
+            chips.len()
+        )),
+    }
+}
@@ -0,0 +1,157 @@
+// SPDX-FileCopyrightText: © 2024 Tenstorrent Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use std::path::PathBuf;
+
+use clap::Subcommand;
+use luwen_if::chip::Chip;
+
+use crate::{
+    mem::hexdump,
+    select::{parse_num, single, DetectedChip},
+    Result,
+};
+
+#[derive(Subcommand)]
+pub enum SpiCommand {
+    /// Print the flash contents, or save them to a file.
+    Dump {
+        #[arg(value_parser = parse_num::<u32>)]
+        addr: u32,
+        #[arg(value_parser = parse_num::<usize>)]
+        len: usize,
+        #[arg(long, short)]
+        output: Option<PathBuf>,
+    },
+    /// Write a file to the flash and read it back to check that it stuck.
+    Flash {
+        #[arg(value_parser = parse_num::<u32>)]
+        addr: u32,
+        file: PathBuf,
+        /// Skip reading back the written data.
+        #[arg(long)]
+        no_verify: bool,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum BootfsCommand {
+    /// List the tables in the boot filesystem.
+    Ls,
+    /// Decode a table (i.e. boardcfg or cmfwcfg) and print it as json.
+    Decode { tag: String },
+}
+
+fn spi_read(chip: &Chip, addr: u32, data: &mut [u8]) -> Result<()> {
+    if let Some(wh) = chip.as_wh() {
+        wh.spi_read(addr, data)
+    } else if let Some(gs) = chip.as_gs() {
+        gs.spi_read(addr, data)
+    } else if let Some(bh) = chip.as_bh() {
+        bh.spi_read(addr, data)
+    } else {
+        Err("Spi access is not supported on this chip".into())
+    }
+}
+
+fn spi_write(chip: &Chip, addr: u32, data: &[u8]) -> Result<()> {
+    if let Some(wh) = chip.as_wh() {
+        wh.spi_write(addr, data)
+    } else if let Some(gs) = chip.as_gs() {
+        gs.spi_write(addr, data)
+    } else if let Some(bh) = chip.as_bh() {
+        bh.spi_write(addr, data)
+    } else {
+        Err("Spi access is not supported on this chip".into())
+    }
+}
+
+pub fn run_spi(command: &SpiCommand, chips: &[DetectedChip]) -> Result<()> {
+    match command {
+        SpiCommand::Dump { addr, len, output } => {
+            if let Some(output) = output {
+                let chip = single(chips)?;
+                let mut data = vec![0; *len];
+                spi_read(&chip.chip, *addr, &mut data)?;
+                std::fs::write(output, data)?;
+            } else {
+                for chip in chips {
+                    let mut data = vec![0; *len];
+                    spi_read(&chip.chip, *addr, &mut data)?;
+
+                    println!("{}:", chip.label());
+                    hexdump(*addr as u64, &data);
+                }
+            }
+        }
+        SpiCommand::Flash {
+            addr,
+            file,
+            no_verify,
+        } => {
+            let chip = single(chips)?;
+            let data = std::fs::read(file)?;
+            spi_write(&chip.chip, *addr, &data)?;
+            println!(
+                "Wrote {} bytes at {addr:#x} to {}",
+                data.len(),
+                chip.label()
+            );
+
+            if !no_verify {
+                let mut readback = vec![0; data.len()];
+                spi_read(&chip.chip, *addr, &mut readback)?;
+                if let Some(offset) = data.iter().zip(&readback).position(|(a, b)| a != b) {
+                    return Err(format!(
+                        "Readback mismatch at {:#x}: wrote {:#04x}, read {:#04x}",
+                        *addr as usize + offset,
+                        data[offset],
+                        readback[offset]
+                    )
+                    .into());
+                }
+                println!("Verified");
+            }
+        }
+    }
+
+    Ok(())
+}
+
+pub fn run_bootfs(command: &BootfsCommand, chips: &[DetectedChip]) -> Result<()> {
+    for chip in chips {
+        let Some(bh) = chip.chip.as_bh() else {
+            eprintln!("Skipping {}, only blackhole has a boot fs", chip.label());
+            continue;
+        };
+
+        println!("{}:", chip.label());
+        match command {
+            BootfsCommand::Ls => {
+                println!(
+                    "{:<10} {:<10} {:<10} {:<10} EXECUTABLE",
+                    "TAG", "SPI ADDR", "SIZE", "COPY DEST"
+                );
+                for (_, fd) in bh.get_boot_fs_tables()? {
+                    println!(
+                        "{:<10} {:<#10x} {:<#10x} {:<#10x} {}",
+                        fd.image_tag_str(),
+                        fd.spi_addr,
+                        fd.image_size(),
+                        fd.copy_dest,
+                        fd.executable()
+                    );
+                }
+            }
+            BootfsCommand::Decode { tag } => {
+                if bh.get_boot_fs_tables_spi_read(tag)?.is_none() {
+                    return Err(format!("No table named {tag:?} in the boot fs").into());
+                }
+                let table = bh.decode_boot_fs_table(tag)?;
+                println!("{}", serde_json::to_string_pretty(&table)?);
+            }
+        }
+    }
+
+    Ok(())
+}
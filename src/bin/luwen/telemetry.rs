@@ -0,0 +1,113 @@
+// SPDX-FileCopyrightText: © 2024 Tenstorrent Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use std::time::Duration;
+
+use clap::Args;
+use luwen_if::{chip::Telemetry, ChipImpl};
+use serde_json::{json, Value};
+
+use crate::{select::DetectedChip, Result};
+
+#[derive(Args)]
+pub struct TelemetryArgs {
+    /// Print a json object per chip instead of a table.
+    #[arg(long)]
+    json: bool,
+
+    /// Keep refreshing every N seconds. With --json one line is printed per refresh.
+    #[arg(long, short)]
+    watch: Option<f64>,
+}
+
+fn to_json(chip: &DetectedChip, telemetry: &Telemetry) -> Value {
+    json!({
+        "chip": chip.index,
+        "arch": format!("{:?}", chip.arch()),
+        "interface": chip.interface,
+        "coord": chip.coord.map(|coord| coord.to_string()),
+        "board_id": format!("{:x}", telemetry.board_id),
+        "board_type": telemetry.try_board_type(),
+        "arc_fw_version": telemetry.arc_fw_version(),
+        "eth_fw_version": telemetry.eth_fw_version(),
+        "fw_date": telemetry.firmware_date(),
+        "aiclk_mhz": telemetry.ai_clk(),
+        "axiclk_mhz": telemetry.axi_clk(),
+        "arcclk_mhz": telemetry.arc_clk(),
+        "voltage_v": telemetry.voltage(),
+        "power_w": telemetry.power(),
+        "current_a": telemetry.current(),
+        "asic_temperature_c": telemetry.asic_temperature_for(chip.arch()),
+        "vreg_temperature_c": telemetry.vreg_temperature(),
+        "fan_speed": telemetry.fan_speed,
+        "throttler": telemetry.throttler,
+        "faults": telemetry.faults,
+        "heartbeat": telemetry.timer_heartbeat,
+    })
+}
+
+fn print_table(rows: &[(&DetectedChip, std::result::Result<Telemetry, String>)]) {
+    println!(
+        "{:<5} {:<10} {:<18} {:<10} {:>6} {:>8} {:>7} {:>7} {:>5} {:<10}",
+        "CHIP", "ARCH", "BOARD ID", "TYPE", "AICLK", "VOLTAGE", "POWER", "TEMP", "FAN", "ARC FW"
+    );
+    for (chip, telemetry) in rows {
+        match telemetry {
+            Ok(t) => println!(
+                "{:<5} {:<10} {:<18} {:<10} {:>6} {:>8.3} {:>7.1} {:>7.1} {:>5} {:<10}",
+                chip.index,
+                format!("{:?}", chip.arch()),
+                format!("{:x}", t.board_id),
+                t.board_type(),
+                t.ai_clk(),
+                t.voltage(),
+                t.power(),
+                t.asic_temperature_for(chip.arch()),
+                t.fan_speed,
+                t.arc_fw_version()
+            ),
+            Err(err) => println!(
+                "{:<5} {:<10} failed to read telemetry: {err}",
+                chip.index,
+                format!("{:?}", chip.arch())
+            ),
+        }
+    }
+}
+
+pub fn run(args: &TelemetryArgs, chips: &[DetectedChip]) -> Result<()> {
+    loop {
+        let rows = chips
+            .iter()
+            .map(|chip| (chip, chip.chip.get_telemetry().map_err(|e| e.to_string())))
+            .collect::<Vec<_>>();
+
+        if args.json {
+            let values = rows
+                .iter()
+                .map(|(chip, telemetry)| match telemetry {
+                    Ok(telemetry) => to_json(chip, telemetry),
+                    Err(err) => json!({ "chip": chip.index, "error": err }),
+                })
+                .collect::<Vec<_>>();
+            if args.watch.is_some() {
+                println!("{}", Value::Array(values));
+            } else {
+                println!("{}", serde_json::to_string_pretty(&values)?);
+            }
+        } else {
+            if args.watch.is_some() {
+                // Clear the screen and move the cursor back to the top
+                print!("\x1b[2J\x1b[H");
+            }
+            print_table(&rows);
+        }
+
+        let Some(interval) = args.watch else {
+            break;
+        };
+        std::thread::sleep(Duration::from_secs_f64(interval));
+    }
+
+    Ok(())
+}
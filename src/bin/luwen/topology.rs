@@ -0,0 +1,42 @@
+// SPDX-FileCopyrightText: © 2024 Tenstorrent Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use luwen_if::ChipImpl;
+
+use crate::{select::DetectedChip, Result};
+
+pub fn run(chips: &[DetectedChip]) -> Result<()> {
+    for chip in chips {
+        let coord = chip
+            .coord
+            .map(|coord| coord.to_string())
+            .unwrap_or_else(|| "-".to_string());
+        let connection = match chip.interface {
+            Some(id) => format!("pci {id}"),
+            None => "remote".to_string(),
+        };
+        println!("{} at {coord} via {connection}", chip.label());
+
+        for neighbour in chip.chip.get_neighbouring_chips()? {
+            // Only chips which were selected are known, so the neighbour may not have a name
+            let name = chips
+                .iter()
+                .find(|other| other.coord == Some(neighbour.eth_addr))
+                .map(|other| format!(" chip {}", other.label()))
+                .unwrap_or_default();
+            println!(
+                "    eth {:?} -> {}{name} eth {:?}{}",
+                neighbour.local_noc_addr,
+                neighbour.eth_addr,
+                neighbour.remote_noc_addr,
+                if neighbour.routing_enabled {
+                    ""
+                } else {
+                    " (routing disabled)"
+                }
+            );
+        }
+    }
+
+    Ok(())
+}